@@ -1,29 +1,14 @@
 //! JQ JSON Utilities
 //!
-//! Shell-based JSON parsing using jq (if available).
+//! Back-compat names for the native `rsb::json` engine (no `jq` binary
+//! needed). Errors are logged and yield an empty string.
 
-use crate::hosts::command::{is_command, run_cmd};
-
-/// Extract a value from JSON using jq (if available).
+/// Extract a value from JSON with a jq-style query.
 pub fn jq_get(json_str: &str, path: &str) -> String {
-    if !is_command("jq") {
-        return String::new();
-    }
-
-    let cmd = format!(
-        "echo '{}' | jq -r '{}'",
-        json_str.replace("'", "'\"'\"'"),
-        path
-    );
-    run_cmd(&cmd).trim().to_string()
+    crate::json_get!(json_str, path)
 }
 
-/// Extract a value from JSON file using jq (if available).
+/// Extract a value from a JSON file with a jq-style query.
 pub fn jq_get_file(json_file: &str, path: &str) -> String {
-    if !is_command("jq") {
-        return String::new();
-    }
-
-    let cmd = format!("jq -r '{}' '{}'", path, json_file);
-    run_cmd(&cmd).trim().to_string()
+    crate::json_get_file!(json_file, path)
 }
@@ -5,8 +5,13 @@ use crate::hosts::command::{run_cmd_with_status, CmdResult};
 
 // --- Network ---
 
-/// Simple HTTP GET using curl.
+/// Simple HTTP GET using curl (the native client for `http://` URLs when
+/// the `http` feature is enabled).
 pub fn curl_get(url: &str) -> CmdResult {
+    #[cfg(feature = "http")]
+    if url.starts_with("http://") {
+        return crate::http::to_cmd_result(crate::http::get(url).send());
+    }
     let url_q = crate::string::utils::shell_single_quote(url);
     let cmd = format!("curl -s {}", url_q);
     run_cmd_with_status(&cmd)
@@ -19,8 +24,16 @@ pub fn curl_get_with_options(url: &str, options: &str) -> CmdResult {
     run_cmd_with_status(&cmd)
 }
 
-/// Simple HTTP POST using curl.
+/// Simple HTTP POST using curl (native for `http://` URLs with the `http`
+/// feature; the body is sent form-encoded like `curl -d`).
 pub fn curl_post(url: &str, data: &str) -> CmdResult {
+    #[cfg(feature = "http")]
+    if url.starts_with("http://") {
+        let request = crate::http::post(url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(data);
+        return crate::http::to_cmd_result(request.send());
+    }
     let url_q = crate::string::utils::shell_single_quote(url);
     let data_q = crate::string::utils::shell_single_quote(data);
     let cmd = format!("curl -s -X POST -d {} {}", data_q, url_q);
@@ -53,56 +66,153 @@ pub fn http_post(url: &str, data: &str) -> CmdResult {
 }
 
 // --- Archive Operations ---
+//
+// With the `archive` feature these run natively (`fs::archive`); otherwise
+// they shell out to tar/zip/unzip with every path single-quoted.
+
+fn quote_paths(paths: &[&str]) -> String {
+    paths
+        .iter()
+        .map(|p| crate::string::utils::shell_single_quote(p))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-/// Creates a tar archive using system tar command.
+/// Creates a tar archive (native with `archive`, else system tar).
 pub fn create_tar(archive_path: &str, source_paths: &[&str]) -> CmdResult {
-    let paths = source_paths.join(" ");
-    let cmd = format!("tar -cf '{}' {}", archive_path, paths);
-    run_cmd_with_status(&cmd)
+    #[cfg(feature = "archive")]
+    {
+        use crate::fs::archive::{create_archive, to_cmd_result, ArchiveFormat, ArchiveOptions};
+        let opts = ArchiveOptions::new().format(ArchiveFormat::Tar);
+        return to_cmd_result(create_archive(archive_path, source_paths, &opts));
+    }
+    #[allow(unreachable_code)]
+    {
+        let archive_q = crate::string::utils::shell_single_quote(archive_path);
+        let cmd = format!("tar -cf {} {}", archive_q, quote_paths(source_paths));
+        run_cmd_with_status(&cmd)
+    }
 }
 
-/// Creates a compressed tar.gz archive using system tar command.
+/// Creates a compressed tar.gz archive (native with `archive`, else system tar).
 pub fn create_tar_gz(archive_path: &str, source_paths: &[&str]) -> CmdResult {
-    let paths = source_paths.join(" ");
-    let cmd = format!("tar -czf '{}' {}", archive_path, paths);
-    run_cmd_with_status(&cmd)
+    #[cfg(feature = "archive")]
+    {
+        use crate::fs::archive::{create_archive, to_cmd_result, ArchiveFormat, ArchiveOptions};
+        let opts = ArchiveOptions::new().format(ArchiveFormat::TarGz);
+        return to_cmd_result(create_archive(archive_path, source_paths, &opts));
+    }
+    #[allow(unreachable_code)]
+    {
+        let archive_q = crate::string::utils::shell_single_quote(archive_path);
+        let cmd = format!("tar -czf {} {}", archive_q, quote_paths(source_paths));
+        run_cmd_with_status(&cmd)
+    }
 }
 
-/// Extracts a tar archive using system tar command.
+/// Extracts a tar or tar.gz archive (native with `archive`, else system tar).
 pub fn extract_tar(archive_path: &str, dest_dir: Option<&str>) -> CmdResult {
-    let cmd = if let Some(dir) = dest_dir {
-        format!("tar -xf '{}' -C '{}'", archive_path, dir)
-    } else {
-        format!("tar -xf '{}'", archive_path)
-    };
-    run_cmd_with_status(&cmd)
+    #[cfg(feature = "archive")]
+    {
+        use crate::fs::archive::{extract_archive, to_cmd_result, ArchiveOptions};
+        return to_cmd_result(extract_archive(
+            archive_path,
+            dest_dir.unwrap_or("."),
+            &ArchiveOptions::new(),
+        ));
+    }
+    #[allow(unreachable_code)]
+    {
+        let archive_q = crate::string::utils::shell_single_quote(archive_path);
+        let cmd = if let Some(dir) = dest_dir {
+            format!(
+                "tar -xf {} -C {}",
+                archive_q,
+                crate::string::utils::shell_single_quote(dir)
+            )
+        } else {
+            format!("tar -xf {}", archive_q)
+        };
+        run_cmd_with_status(&cmd)
+    }
 }
 
-/// Lists contents of a tar archive using system tar command.
+/// Lists contents of a tar or tar.gz archive (native with `archive`, else system tar).
 pub fn list_tar(archive_path: &str) -> CmdResult {
-    let cmd = format!("tar -tf '{}'", archive_path);
-    run_cmd_with_status(&cmd)
+    #[cfg(feature = "archive")]
+    {
+        use crate::fs::archive::{list_archive, to_cmd_result, ArchiveOptions};
+        return to_cmd_result(list_archive(archive_path, &ArchiveOptions::new()));
+    }
+    #[allow(unreachable_code)]
+    {
+        let cmd = format!(
+            "tar -tf {}",
+            crate::string::utils::shell_single_quote(archive_path)
+        );
+        run_cmd_with_status(&cmd)
+    }
 }
 
-/// Creates a zip archive using system zip command.
+/// Creates a zip archive (native with `archive`, else system zip).
 pub fn create_zip(archive_path: &str, source_paths: &[&str]) -> CmdResult {
-    let paths = source_paths.join(" ");
-    let cmd = format!("zip -r '{}' {}", archive_path, paths);
-    run_cmd_with_status(&cmd)
+    #[cfg(feature = "archive")]
+    {
+        use crate::fs::archive::{create_archive, to_cmd_result, ArchiveFormat, ArchiveOptions};
+        let opts = ArchiveOptions::new().format(ArchiveFormat::Zip);
+        return to_cmd_result(create_archive(archive_path, source_paths, &opts));
+    }
+    #[allow(unreachable_code)]
+    {
+        let archive_q = crate::string::utils::shell_single_quote(archive_path);
+        let cmd = format!("zip -r {} {}", archive_q, quote_paths(source_paths));
+        run_cmd_with_status(&cmd)
+    }
 }
 
-/// Extracts a zip archive using system unzip command.
+/// Extracts a zip archive (native with `archive`, else system unzip).
 pub fn extract_zip(archive_path: &str, dest_dir: Option<&str>) -> CmdResult {
-    let cmd = if let Some(dir) = dest_dir {
-        format!("unzip '{}' -d '{}'", archive_path, dir)
-    } else {
-        format!("unzip '{}'", archive_path)
-    };
-    run_cmd_with_status(&cmd)
+    #[cfg(feature = "archive")]
+    {
+        use crate::fs::archive::{extract_archive, to_cmd_result, ArchiveFormat, ArchiveOptions};
+        let opts = ArchiveOptions::new().format(ArchiveFormat::Zip);
+        return to_cmd_result(extract_archive(
+            archive_path,
+            dest_dir.unwrap_or("."),
+            &opts,
+        ));
+    }
+    #[allow(unreachable_code)]
+    {
+        let archive_q = crate::string::utils::shell_single_quote(archive_path);
+        let cmd = if let Some(dir) = dest_dir {
+            format!(
+                "unzip {} -d {}",
+                archive_q,
+                crate::string::utils::shell_single_quote(dir)
+            )
+        } else {
+            format!("unzip {}", archive_q)
+        };
+        run_cmd_with_status(&cmd)
+    }
 }
 
-/// Lists contents of a zip archive using system unzip command.
+/// Lists contents of a zip archive (native with `archive`: one path per
+/// line; else the `unzip -l` table).
 pub fn list_zip(archive_path: &str) -> CmdResult {
-    let cmd = format!("unzip -l '{}'", archive_path);
-    run_cmd_with_status(&cmd)
+    #[cfg(feature = "archive")]
+    {
+        use crate::fs::archive::{list_archive, to_cmd_result, ArchiveFormat, ArchiveOptions};
+        let opts = ArchiveOptions::new().format(ArchiveFormat::Zip);
+        return to_cmd_result(list_archive(archive_path, &opts));
+    }
+    #[allow(unreachable_code)]
+    {
+        let cmd = format!(
+            "unzip -l {}",
+            crate::string::utils::shell_single_quote(archive_path)
+        );
+        run_cmd_with_status(&cmd)
+    }
 }
@@ -0,0 +1,136 @@
+//! Command aliases
+//!
+//! Aliases are user-level shortcuts for command lines: `co` runs `checkout`,
+//! `ship` runs `deploy --env prod`. They are registered in code
+//! (`register_alias`) or read from an RC file, where each
+//! `alias_<name>="<expansion>"` assignment defines one. `dispatch!` loads
+//! the tool's own RC file (`hosts::rsb_config_path(program_name())`) when
+//! `RSB_ETC_HOME` is set.
+//!
+//! Only the command word is expanded, and only once; the words after it
+//! are kept. Real commands (handlers, specs and their aliases, built-ins)
+//! always win over an alias with the same name.
+
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+use crate::cli::help::BUILT_INS;
+use crate::cli::Args;
+use crate::global::{self, ConfigDoc, ConfigError};
+
+/// RC file keys starting with this prefix define aliases.
+pub const ALIAS_KEY_PREFIX: &str = "alias_";
+
+lazy_static! {
+    static ref COMMAND_ALIASES: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+}
+
+/// Registers (or replaces) an alias. The expansion is split like a REPL
+/// line, so double-quoted words stay together.
+pub fn register_alias(name: &str, expansion: &str) {
+    let mut aliases = COMMAND_ALIASES.lock().unwrap();
+    aliases.retain(|(n, _)| n != name);
+    aliases.push((name.to_string(), expansion.to_string()));
+}
+
+pub fn unregister_alias(name: &str) {
+    COMMAND_ALIASES.lock().unwrap().retain(|(n, _)| n != name);
+}
+
+/// The expansion registered for `name`, if any.
+pub fn find_alias(name: &str) -> Option<String> {
+    let aliases = COMMAND_ALIASES.lock().unwrap();
+    aliases
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, expansion)| expansion.clone())
+}
+
+/// All aliases as `(name, expansion)`, sorted by name.
+pub fn list_aliases() -> Vec<(String, String)> {
+    let mut aliases = COMMAND_ALIASES.lock().unwrap().clone();
+    aliases.sort();
+    aliases
+}
+
+/// Registers the `alias_<name>` assignments in an RC file, returning how
+/// many were found. The file is parsed, not applied: nothing else in it
+/// touches Global. Values must be literal (no `$VAR` references).
+pub fn try_load_aliases(path: &str) -> Result<usize, ConfigError> {
+    let path = global::expand_vars(path);
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| ConfigError::new(0, format!("cannot read aliases: {}", e)).in_file(&path))?;
+    let doc = ConfigDoc::parse(&content).map_err(|e| e.in_file(&path))?;
+
+    let mut count = 0;
+    for key in doc.keys() {
+        let Some(name) = key.strip_prefix(ALIAS_KEY_PREFIX) else {
+            continue;
+        };
+        match doc.get(&key) {
+            Some(expansion) if !name.is_empty() && !expansion.trim().is_empty() => {
+                register_alias(name, &expansion);
+                count += 1;
+            }
+            _ => crate::utils::stderrx(
+                "warn",
+                &format!("{}: alias '{}' needs a literal command", path, key),
+            ),
+        }
+    }
+    Ok(count)
+}
+
+/// Loads aliases from an RC file if it exists, best effort (errors go to
+/// stderr).
+pub fn load_aliases(path: &str) {
+    if !std::path::Path::new(&global::expand_vars(path)).exists() {
+        return;
+    }
+    if let Err(err) = try_load_aliases(path) {
+        crate::utils::stderrx("warn", &format!("aliases: {}", err));
+    }
+}
+
+/// Loads aliases from the tool's RC file under `RSB_ETC_HOME`. Does nothing
+/// when `RSB_ETC_HOME` isn't set, so a stray file in the working directory
+/// is never picked up.
+pub fn load_rc_aliases() {
+    if global::get_var("RSB_ETC_HOME").is_empty() {
+        return;
+    }
+    load_aliases(&crate::hosts::rsb_config_path(&crate::cli::program_name()));
+}
+
+/// Whether `word` names a real command: a registered handler or function,
+/// a spec path or spec alias, or a dispatch built-in.
+fn is_command(word: &str) -> bool {
+    BUILT_INS.iter().any(|(name, _)| *name == word)
+        || global::has_handler(word)
+        || crate::cli::subcommands("").iter().any(|name| name == word)
+        || crate::cli::find_command(word).is_some()
+}
+
+/// Replaces an alias in command position (the first word after the program
+/// name) with its expansion. Args without an alias come back unchanged.
+pub fn expand_alias(args: &Args) -> Args {
+    let all = args.all();
+    let start = match args.is_program_index(0) {
+        true => 1,
+        false => 0,
+    };
+    let Some(word) = all.get(start) else {
+        return args.clone();
+    };
+    if is_command(word) {
+        return args.clone();
+    }
+    let Some(expansion) = find_alias(word) else {
+        return args.clone();
+    };
+
+    let mut words = all[..start].to_vec();
+    words.extend(Args::from_line(&expansion).all().iter().cloned());
+    words.extend(all[start + 1..].iter().cloned());
+    Args::new(&words)
+}
@@ -47,7 +47,7 @@ impl Args {
         }
     }
 
-    fn is_program_index(&self, index: usize) -> bool {
+    pub(crate) fn is_program_index(&self, index: usize) -> bool {
         if self.args.is_empty() || index != 0 {
             return false;
         }
@@ -186,40 +186,36 @@ impl Args {
 
     /// Apply options strategy after processing options
     pub fn apply_options_strategy(&mut self, strategy: OptionsStrategy, context: &OptionsContext) {
-        // First validate we haven't consumed positional args as flag values
-        if context.has_boundary_issues {
-            eprintln!("Warning: Potential flag/value boundary issue detected");
-        }
-
         match strategy {
-            OptionsStrategy::Sort => self.sort_flags_last(),
-            OptionsStrategy::Remove => self.remove_flags(&context.processed_flags),
+            OptionsStrategy::Sort => self.sort_flags_last(context),
+            OptionsStrategy::Remove => self.remove_flags(context),
             OptionsStrategy::Default => {},
         }
     }
 
-    /// Sort flags to the end of the argument list
-    fn sort_flags_last(&mut self) {
-        let (mut positionals, mut flags): (Vec<_>, Vec<_>) = self.args
+    /// Sort flags (with the values they consumed) after the positionals;
+    /// a `--` and the words after it stay last so they remain positional.
+    fn sort_flags_last(&mut self, context: &OptionsContext) {
+        let end = context.end_of_options.unwrap_or(self.args.len()).min(self.args.len());
+        let tail = self.args.split_off(end);
+        let (mut flags, positionals): (Vec<_>, Vec<_>) = self.args
             .drain(..)
-            .partition(|arg| !arg.starts_with('-'));
-
-        // Keep program name at the beginning if present
-        if !positionals.is_empty() && self.is_program_index(0) {
-            let prog = positionals.remove(0);
-            self.args.push(prog);
-        }
+            .enumerate()
+            .partition(|(i, _)| context.option_indices.contains(i));
 
-        self.args.append(&mut positionals);
-        self.args.append(&mut flags);
+        self.args = positionals.into_iter().map(|(_, arg)| arg).collect();
+        self.args.extend(flags.drain(..).map(|(_, arg)| arg));
+        self.args.extend(tail);
     }
 
-    /// Remove processed flags from the argument list
-    fn remove_flags(&mut self, processed: &[String]) {
-        self.args.retain(|arg| {
-            !processed.iter().any(|flag| {
-                arg == flag || arg.starts_with(&format!("{}=", flag))
-            })
+    /// Remove processed flags, their values and the `--` terminator
+    fn remove_flags(&mut self, context: &OptionsContext) {
+        let mut index = 0;
+        self.args.retain(|_| {
+            let keep = !context.option_indices.contains(&index)
+                && context.end_of_options != Some(index);
+            index += 1;
+            keep
         });
     }
 }
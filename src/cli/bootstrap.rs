@@ -58,6 +58,9 @@ pub fn cli_bootstrap(args: &[String]) {
     // Store CLI args in global store (new in v0.7.0+)
     cli_to_global(args);
 
+    // Apply schema defaults and report invalid keys (no-op without a schema)
+    crate::global::validate_schema();
+
     // CLI‑specific extensions could go here (help registry, interactive checks, etc.)
 }
 
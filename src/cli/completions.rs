@@ -0,0 +1,271 @@
+//! Shell completions for dispatch-based tools
+//!
+//! `completions <bash|zsh|fish>` prints a small script that asks the tool
+//! itself for candidates: the shell runs `prog __complete <words...>` and
+//! `complete_words` answers from the registered commands, aliases and
+//! `CommandSpec` options at that moment, so completions never go stale.
+//!
+//! Values come from, in order: a spec's `complete_with` hook (for dynamic
+//! data like profile names from config), enum choices, `true`/`false` for
+//! bools, and the filesystem for paths.
+
+use super::help::BUILT_INS;
+use crate::cli::{find_command, is_group, resolve_path, subcommands, CommandSpec};
+use crate::global::ValueType;
+
+/// Shells we can generate scripts for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+}
+
+/// Completion script for `prog` in `shell`.
+pub fn render_completions(shell: Shell, prog: &str) -> String {
+    let func: String = prog
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match shell {
+        Shell::Bash => format!(
+            r#"# bash completion for {prog}
+_{func}_complete() {{
+    local line words typed="${{COMP_LINE:0:COMP_POINT}}"
+    read -ra words <<< "$typed"
+    [[ "$typed" == *[[:space:]] ]] && words+=("")
+    local last="${{words[${{#words[@]}}-1]}}"
+    COMPREPLY=()
+    while IFS= read -r line; do
+        line="${{line%%$'\t'*}}"
+        # bash completes `--opt=value` after the '='
+        [[ "$last" == --*=* ]] && line="${{line#*=}}"
+        [[ -n "$line" ]] && COMPREPLY+=("$line")
+    done < <("{prog}" __complete "${{words[@]:1}}" 2>/dev/null)
+}}
+complete -o default -F _{func}_complete {prog}
+"#
+        ),
+        Shell::Zsh => format!(
+            r#"#compdef {prog}
+_{func}() {{
+    local -a candidates
+    local line
+    for line in "${{(@f)$("{prog}" __complete "${{(@)words[2,CURRENT-1]}}" "${{words[CURRENT]}}" 2>/dev/null)}}"; do
+        [[ -n "$line" ]] && candidates+=(${{${{line//:/\\:}}/$'\t'/:}})
+    done
+    if (( ${{#candidates}} )); then
+        _describe 'values' candidates
+    else
+        _files
+    fi
+}}
+compdef _{func} {prog}
+"#
+        ),
+        Shell::Fish => format!(
+            r#"# fish completion for {prog}
+function __{func}_complete
+    set -l tokens (commandline -opc)
+    "{prog}" __complete $tokens[2..-1] (commandline -ct) 2>/dev/null
+end
+complete -c {prog} -f -a '(__{func}_complete)'
+"#
+        ),
+    }
+}
+
+/// Candidates for the last of `words` (the words after the program name;
+/// the last one is the partial word being completed, possibly empty).
+/// Returns `(value, description)` pairs, filtered by the partial word.
+pub fn complete_words(words: &[String]) -> Vec<(String, String)> {
+    let (current, before) = match words.split_last() {
+        Some((current, before)) => (current.as_str(), before),
+        None => ("", words),
+    };
+    match before.first().map(String::as_str) {
+        Some("help") => {
+            let mut candidates = complete_words(&words[1..]);
+            candidates.retain(|(value, _)| !value.starts_with('-'));
+            return candidates;
+        }
+        Some("completions") if before.len() == 1 => {
+            return ["bash", "fish", "zsh"]
+                .iter()
+                .filter(|shell| shell.starts_with(current))
+                .map(|shell| (shell.to_string(), String::new()))
+                .collect();
+        }
+        _ => {}
+    }
+    let path = resolve_path(before);
+    let name = path.join(" ");
+    let rest = &before[path.len()..];
+
+    let mut candidates = if rest.is_empty() && (path.is_empty() || is_group(&name)) {
+        command_candidates(&name)
+    } else {
+        match find_command(&name) {
+            Some(spec) => spec_candidates(&spec, rest, current),
+            None => Vec::new(),
+        }
+    };
+    candidates.retain(|(value, _)| value.starts_with(current));
+    candidates
+}
+
+/// Subcommands of `path` (with aliases), plus built-ins at the top level.
+fn command_candidates(path: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    for sub in subcommands(path) {
+        let full = match path.is_empty() {
+            true => sub.clone(),
+            false => format!("{} {}", path, sub),
+        };
+        let spec = find_command(&full);
+        let about = spec.as_ref().map(|s| s.about.clone()).unwrap_or_default();
+        for alias in spec.iter().flat_map(|s| s.aliases.iter()) {
+            out.push((alias.clone(), about.clone()));
+        }
+        out.push((sub, about));
+    }
+    if path.is_empty() {
+        for (built_in, about) in BUILT_INS {
+            if !out.iter().any(|(value, _)| value == built_in) {
+                out.push((built_in.to_string(), about.to_string()));
+            }
+        }
+    }
+    out.sort();
+    out
+}
+
+/// Options, option values or positional values for a spec'd command.
+fn spec_candidates(spec: &CommandSpec, rest: &[String], current: &str) -> Vec<(String, String)> {
+    // --opt=partial
+    if let Some((long, _)) = current.strip_prefix("--").and_then(|c| c.split_once('=')) {
+        return match spec.find_long(long).filter(|opt| opt.takes_value()) {
+            Some(opt) => {
+                let prefix = format!("--{}=", long);
+                let partial = &current[prefix.len()..];
+                value_candidates(opt.ty.as_ref(), opt.completer, partial)
+                    .into_iter()
+                    .map(|(value, about)| (format!("{}{}", prefix, value), about))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+    }
+
+    // Value for the option just before the cursor
+    if let Some(opt) = rest.last().and_then(|prev| pending_option(spec, prev)) {
+        return value_candidates(opt.ty.as_ref(), opt.completer, current);
+    }
+
+    if current.starts_with('-') {
+        let mut out: Vec<(String, String)> = spec
+            .opts
+            .iter()
+            .map(|opt| (format!("--{}", opt.long), opt.about.clone()))
+            .collect();
+        out.push(("--help".to_string(), "Show this help".to_string()));
+        return out;
+    }
+
+    // Positional: count the positionals already typed
+    let mut index = 0;
+    let mut skip_value = false;
+    for word in rest {
+        if skip_value {
+            skip_value = false;
+        } else if pending_option(spec, word).is_some() {
+            skip_value = true;
+        } else if !word.starts_with('-') {
+            index += 1;
+        }
+    }
+    let arg = spec
+        .args
+        .get(index)
+        .or_else(|| spec.args.last().filter(|arg| arg.repeatable));
+    match arg {
+        Some(arg) => value_candidates(Some(&arg.ty), arg.completer, current),
+        None => Vec::new(),
+    }
+}
+
+/// The option `word` names if it still needs its value from the next word.
+fn pending_option<'a>(spec: &'a CommandSpec, word: &str) -> Option<&'a crate::cli::OptSpec> {
+    let opt = if let Some(long) = word.strip_prefix("--") {
+        spec.find_long(long)?
+    } else {
+        let mut chars = word.strip_prefix('-')?.chars();
+        let short = chars.next()?;
+        if chars.next().is_some() {
+            return None;
+        }
+        spec.find_short(short)?
+    };
+    opt.takes_value().then_some(opt)
+}
+
+fn value_candidates(
+    ty: Option<&ValueType>,
+    completer: Option<crate::cli::Completer>,
+    current: &str,
+) -> Vec<(String, String)> {
+    let values = match (completer, ty) {
+        (Some(completer), _) => completer(current),
+        (None, Some(ValueType::Enum(choices))) => choices.clone(),
+        (None, Some(ValueType::Bool)) => vec!["true".to_string(), "false".to_string()],
+        (None, Some(ValueType::Path)) => path_candidates(current),
+        _ => Vec::new(),
+    };
+    values.into_iter().map(|v| (v, String::new())).collect()
+}
+
+/// Filesystem entries matching `prefix`; directories end in `/`.
+fn path_candidates(prefix: &str) -> Vec<String> {
+    let (dir, file) = match prefix.rfind('/') {
+        Some(i) => (&prefix[..=i], &prefix[i + 1..]),
+        None => ("", prefix),
+    };
+    let Ok(entries) = std::fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+    let mut out: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(file) || (name.starts_with('.') && !file.starts_with('.')) {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}", dir, name, slash))
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+/// Prints candidates for `words` as `value<TAB>description` lines (the
+/// protocol the generated scripts read).
+pub fn print_completions(words: &[String]) {
+    for (value, about) in complete_words(words) {
+        match about.is_empty() {
+            true => println!("{}", value),
+            false => println!("{}\t{}", value, about),
+        }
+    }
+}
@@ -3,11 +3,25 @@
 //! Provides dispatch functions that work with dispatch! and pre_dispatch! macros
 //! for command routing and execution following MODULE_SPEC pattern.
 
+use crate::cli::help::BUILT_INS;
 use crate::cli::Args;
+use crate::com::ExitKind;
 use crate::global;
 
 /// Type alias for command handler functions that take Args and return exit code.
-pub type CommandHandler = fn(Args) -> i32;
+/// Same type as the shared `global` handler table.
+pub type CommandHandler = global::CommandFn;
+
+static RC_ALIASES: std::sync::Once = std::sync::Once::new();
+
+/// Where a command line routes to.
+enum Route {
+    /// A handler ran and returned this exit code
+    Ran(i32),
+    /// The path names a group; the word after it (if any) isn't a subcommand
+    Group(String, Option<String>),
+    Unknown,
+}
 
 /// Internal helper for dispatch macro - handles all dispatch logic.
 ///
@@ -15,46 +29,88 @@ pub type CommandHandler = fn(Args) -> i32;
 /// - Flag command checking (--help, --version) - NEW in v2.0
 /// - Command extraction and argument processing
 /// - Handler registration for introspection
-/// - Built-in command handling (help, inspect, stack)
-/// - User command routing and execution with call stack management
+/// - Built-in command handling (help, inspect, stack, completions, and the
+///   hidden `__complete` used by completion scripts); a user handler with
+///   the same name takes precedence over a built-in
+/// - Commands missing from the dispatch table fall back to the shared
+///   `global` handler table (`global::register_handler`)
+/// - Command aliases (`cli::register_alias`, or the tool's RC file) are
+///   expanded before anything else looks at the command word
+/// - User command routing (including nested `group sub` paths) and execution
+///   with call stack management
+/// - Unknown commands get "did you mean" suggestions and exit with
+///   `ExitKind::UserFailure`
 /// - Process exit with appropriate codes
 pub fn execute_dispatch<F>(args: &Args, handler_lookup: F)
 where
     F: Fn(&str) -> Option<CommandHandler>,
 {
+    RC_ALIASES.call_once(crate::cli::load_rc_aliases);
+    let args = &crate::cli::expand_alias(args);
+    // Completion requests carry partial words like `-h`; answer them first
+    if args.get(1) == "__complete" {
+        let mut words = args.clone();
+        words.has_pop("__complete");
+        crate::cli::print_completions(&words.remaining());
+        std::process::exit(0);
+    }
     // NEW: Check flag commands first (--help, --version)
     if let Some(exit_code) = crate::cli::check_flag_commands(args) {
         std::process::exit(exit_code);
     }
+    let lookup = |command: &str| handler_lookup(command).or_else(|| global::get_handler(command));
     let command = args.get_or(1, "help");
     let mut cmd_args = args.clone();
     cmd_args.has_pop(&command);
+    let built_in = lookup(&command).is_none();
 
     // Handle built-in commands first
     match command.as_str() {
-        "help" | "--help" | "-h" => {
-            global::show_help();
+        "help" | "--help" | "-h" if built_in => {
+            let path = crate::cli::resolve_path(&cmd_args.remaining()).join(" ");
+            if !crate::cli::show_command_help(&path) && !crate::cli::show_group_help(&path) {
+                crate::cli::show_help();
+            }
             std::process::exit(0);
         }
-        "inspect" => {
+        "inspect" if built_in => {
             global::show_functions();
             std::process::exit(0);
         }
-        "stack" => {
+        "stack" if built_in => {
             global::show_call_stack();
             std::process::exit(0);
         }
-        _ => {
-            // Try to find user command handler
-            if let Some(handler) = handler_lookup(&command) {
-                global::push_call(&command, cmd_args.all());
-                let result = handler(cmd_args);
-                global::pop_call();
-                std::process::exit(result);
-            } else {
-                handle_unknown_command(&command, &handler_lookup);
+        "completions" if built_in => match crate::cli::Shell::from_name(&cmd_args.get(1)) {
+            Some(shell) => {
+                let prog = crate::cli::program_name();
+                print!("{}", crate::cli::render_completions(shell, &prog));
+                std::process::exit(0);
             }
-        }
+            None => {
+                eprintln!("Usage: completions <bash|zsh|fish>");
+                std::process::exit(ExitKind::UserFailure.code() as i32);
+            }
+        },
+        _ => match route(args, &lookup) {
+            Route::Ran(code) => std::process::exit(code),
+            Route::Group(group, None) => {
+                crate::cli::show_group_help(&group);
+                std::process::exit(ExitKind::UserFailure.code() as i32);
+            }
+            Route::Group(group, Some(sub)) => handle_unknown_subcommand(&group, &sub),
+            Route::Unknown => handle_unknown_command(&command),
+        },
+    }
+}
+
+/// Runs the command named by `args` from the shared handler table (with
+/// spec validation, aliases and call frames, as `dispatch!` does). Returns
+/// `None` if no registered command matches. `args[0]` is the program name.
+pub fn run_command(args: &Args) -> Option<i32> {
+    match route(&crate::cli::expand_alias(args), &global::get_handler) {
+        Route::Ran(code) => Some(code),
+        _ => None,
     }
 }
 
@@ -66,110 +122,209 @@ pub fn execute_pre_dispatch<F>(args: &Args, handler_lookup: F) -> bool
 where
     F: Fn(&str) -> Option<CommandHandler>,
 {
-    let command = args.get_or(1, "");
     let is_test = std::env::var("CARGO_TEST").is_ok()
         || std::thread::current()
             .name()
             .map_or(false, |n| n.contains("test"));
 
-    if let Some(handler) = handler_lookup(&command) {
-        let mut cmd_args = args.clone();
-        cmd_args.has_pop(&command);
-        global::push_call(&command, cmd_args.all());
-        let result = handler(cmd_args);
+    RC_ALIASES.call_once(crate::cli::load_rc_aliases);
+    match route(&crate::cli::expand_alias(args), &handler_lookup) {
+        Route::Ran(_) if is_test => true,
+        Route::Ran(result) => std::process::exit(result),
+        _ => false,
+    }
+}
+
+/// Resolves the command path from the leading words of `args` and runs its
+/// handler. Single words fall back to `handler_lookup` directly so handlers
+/// that were never registered still dispatch.
+fn route<F>(args: &Args, handler_lookup: &F) -> Route
+where
+    F: Fn(&str) -> Option<CommandHandler>,
+{
+    let words = args.remaining();
+    let mut path = crate::cli::resolve_path(&words);
+    if path.is_empty() {
+        match words.first() {
+            Some(first) => path.push(first.clone()),
+            None => return Route::Unknown,
+        }
+    }
+    let name = path.join(" ");
+    let mut cmd_args = args.clone();
+    for word in &words[..path.len()] {
+        cmd_args.has_pop(word);
+    }
+
+    if let Some((name, handler)) = resolve_handler(&name, handler_lookup) {
+        return Route::Ran(run_path(&name, cmd_args, handler));
+    }
+    if crate::cli::is_group(&name) {
+        let next = words.get(path.len()).filter(|w| !w.starts_with('-'));
+        return Route::Group(name, next.cloned());
+    }
+    Route::Unknown
+}
+
+/// Finds the handler for `command`, resolving spec aliases to the command
+/// name. Returns the canonical name with the handler.
+fn resolve_handler<F>(command: &str, handler_lookup: &F) -> Option<(String, CommandHandler)>
+where
+    F: Fn(&str) -> Option<CommandHandler>,
+{
+    if let Some(handler) = handler_lookup(command) {
+        return Some((command.to_string(), handler));
+    }
+    let spec = crate::cli::find_command(command)?;
+    handler_lookup(&spec.name).map(|handler| (spec.name, handler))
+}
+
+/// Runs a (possibly nested) command: each enclosing group gets a call frame
+/// and its pre-hook runs inside it, then the handler runs in its own frame.
+fn run_path(command: &str, cmd_args: Args, handler: CommandHandler) -> i32 {
+    let words: Vec<&str> = command.split(' ').collect();
+    let mut frames = 0;
+    let mut result = None;
+    for depth in 1..words.len() {
+        let group = words[..depth].join(" ");
+        global::push_call(&group, cmd_args.all());
+        frames += 1;
+        let hook = crate::cli::find_command(&group).and_then(|spec| spec.pre);
+        match hook.map(|hook| hook(&cmd_args)) {
+            Some(code) if code != 0 => {
+                result = Some(code);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let result = result.unwrap_or_else(|| run_handler(command, cmd_args, handler));
+    for _ in 0..frames {
         global::pop_call();
+    }
+    result
+}
 
-        if is_test {
-            true
-        } else {
-            std::process::exit(result);
+/// Runs a handler inside a call frame. When a spec is registered for the
+/// command, `--help` shows its help page and the arguments are validated
+/// first: problems are reported together and the handler is not called.
+fn run_handler(command: &str, cmd_args: Args, handler: CommandHandler) -> i32 {
+    if let Some(spec) = crate::cli::find_command(command) {
+        let wants_help =
+            cmd_args.has("--help") || (cmd_args.has("-h") && spec.find_short('h').is_none());
+        if wants_help && spec.find_long("help").is_none() {
+            crate::cli::show_command_help(command);
+            return 0;
+        }
+        match spec.parse(&cmd_args) {
+            Ok(matches) => spec.apply(&matches),
+            Err(errors) => {
+                crate::cli::show_usage_errors(&spec, &errors);
+                return ExitKind::UserFailure.code() as i32;
+            }
+        }
+        if let Some(hook) = spec.pre {
+            let code = hook(&cmd_args);
+            if code != 0 {
+                return code;
+            }
         }
-    } else {
-        false
     }
+    global::push_call(command, cmd_args.all());
+    let result = handler(cmd_args);
+    global::pop_call();
+    result
 }
 
 /// Register command handlers for introspection (used by macros).
 ///
 /// This is called by the dispatch macros to register all available commands
-/// for the built-in "inspect" command functionality.
+/// for the built-in "inspect" command functionality, and to put them in the
+/// shared handler table used by `--help`/`--version` and the REPL.
 pub fn register_handlers(handlers: &[(&str, CommandHandler)]) {
-    for (cmd, _handler) in handlers {
+    for (cmd, handler) in handlers {
         // Store a clean, human-readable entry without leaking pointer debug strings.
         // Description can be enriched by higher-level helpers later.
         global::register_function(cmd, "");
+        // Make the handler callable from flags and the REPL too
+        global::register_handler(cmd, *handler);
     }
 }
 
-/// Enhanced error handling for unknown commands with helpful suggestions.
-fn handle_unknown_command<F>(command: &str, handler_lookup: &F)
-where
-    F: Fn(&str) -> Option<CommandHandler>,
-{
+/// Unknown command: suggest similar commands and aliases, then exit.
+fn handle_unknown_command(command: &str) -> ! {
     eprintln!("Error: Unknown command '{}'", command);
-
-    // Try to find similar commands (simple suggestion algorithm)
-    let suggestions = find_command_suggestions(command, handler_lookup);
-
-    if !suggestions.is_empty() {
-        eprintln!();
-        eprintln!("Did you mean one of these?");
-        for suggestion in suggestions {
-            eprintln!("  {}", suggestion);
-        }
-        eprintln!();
-    }
+    show_suggestions("", command);
 
     eprintln!("Use 'help' to see all available commands.");
     eprintln!("Use 'inspect' to see registered command handlers.");
-
-    std::process::exit(1);
+    std::process::exit(ExitKind::UserFailure.code() as i32);
 }
 
-/// Find command suggestions using simple string distance algorithm.
-fn find_command_suggestions<F>(target: &str, handler_lookup: &F) -> Vec<String>
-where
-    F: Fn(&str) -> Option<CommandHandler>,
-{
-    // Common commands to check for suggestions
-    let common_commands = [
-        "help", "version", "status", "init", "build", "test", "run", "start", "stop", "create",
-        "delete", "list", "show", "get", "set", "config", "install", "update",
-    ];
-
-    let mut suggestions = Vec::new();
-
-    // Check if any common commands are available and similar
-    for cmd in &common_commands {
-        if handler_lookup(cmd).is_some() && is_similar(target, cmd) {
-            suggestions.push(cmd.to_string());
-        }
-    }
+/// Unknown word after a command group: suggest similar subcommands.
+fn handle_unknown_subcommand(group: &str, sub: &str) -> ! {
+    eprintln!("Error: Unknown command '{} {}'", group, sub);
+    show_suggestions(group, sub);
 
-    // Limit suggestions to avoid clutter
-    suggestions.truncate(3);
-    suggestions
+    eprintln!("Use 'help {}' to see its commands.", group);
+    std::process::exit(ExitKind::UserFailure.code() as i32);
 }
 
-/// Simple similarity check for command suggestions.
-fn is_similar(a: &str, b: &str) -> bool {
-    if a.is_empty() || b.is_empty() {
-        return false;
+fn show_suggestions(path: &str, word: &str) {
+    let suggestions = suggest_commands(path, word);
+    if suggestions.is_empty() {
+        return;
     }
+    eprintln!();
+    eprintln!("Did you mean one of these?");
+    for suggestion in suggestions {
+        match path.is_empty() {
+            true => eprintln!("  {}", suggestion),
+            false => eprintln!("  {} {}", path, suggestion),
+        }
+    }
+    eprintln!();
+}
 
-    // Check for common prefixes
-    if a.len() >= 2 && b.len() >= 2 {
-        if a[..2] == b[..2] {
-            return true;
+/// Up to three commands under `path` (`""` for the top level) that `word`
+/// may have been meant as, closest first. Candidates are the subcommands
+/// and their spec aliases; at the top level also the built-ins and user
+/// aliases (`cli::register_alias`).
+pub fn suggest_commands(path: &str, word: &str) -> Vec<String> {
+    let mut candidates = crate::cli::subcommands(path);
+    for spec in crate::cli::list_commands() {
+        if spec.parent() == path {
+            candidates.extend(spec.aliases.iter().cloned());
         }
     }
+    if path.is_empty() {
+        candidates.extend(BUILT_INS.iter().map(|(name, _)| name.to_string()));
+        candidates.extend(crate::cli::list_aliases().into_iter().map(|(name, _)| name));
+    }
 
-    // Check for one character difference (simple edit distance)
-    if (a.len() as i32 - b.len() as i32).abs() <= 1 {
-        let distance = edit_distance(a, b);
-        distance <= 2
-    } else {
-        false
+    let mut ranked: Vec<(usize, String)> = candidates
+        .into_iter()
+        .filter(|candidate| candidate != word)
+        .filter_map(|candidate| similarity(word, &candidate).map(|d| (d, candidate)))
+        .collect();
+    ranked.sort();
+    ranked.dedup();
+    ranked.into_iter().take(3).map(|(_, name)| name).collect()
+}
+
+/// Edit distance from `typed` to `candidate` if it's close enough to
+/// suggest: within a third of the word's length (at least 2), or the typed
+/// word is a prefix of the candidate.
+fn similarity(typed: &str, candidate: &str) -> Option<usize> {
+    if typed.is_empty() || candidate.is_empty() {
+        return None;
+    }
+    let distance = edit_distance(typed, candidate);
+    let limit = std::cmp::max(2, typed.chars().count() / 3);
+    let prefix = typed.chars().count() >= 2 && candidate.starts_with(typed);
+    match (distance <= limit && distance < candidate.chars().count()) || prefix {
+        true => Some(distance),
+        false => None,
     }
 }
 
@@ -10,55 +10,46 @@ use crate::global;
 /// Handle --help / -h flag with topic-aware re-routing
 ///
 /// Behavior:
-/// - `prog --help` → generic help (calls cmd_help or show_help)
-/// - `prog <topic> --help` → routes to help dispatcher if exists: `prog help <topic>`
-/// - Falls back to generic help if help dispatcher or topic doesn't exist
+/// - `prog --help` → the registered `help` handler, else generic help
+/// - `prog <topic> --help` → the `help` handler as `prog help <topic>`, else
+///   the command's spec page or group page
+/// - Falls back to generic help if neither exists
 fn rsb_handle_help_flag(args: &Args) -> Option<i32> {
     // Check if --help or -h is present
     if !args.has("--help") && !args.has("-h") {
         return None;
     }
-
-    let first_arg = args.get(1);
-
-    // Case 1: prog --help (flag is first argument, no topic)
-    if first_arg == "--help" || first_arg == "-h" {
-        // Try cmd_help handler
-        if global::has_var("fn_cmd_help") {
-            // Handler exists, construct minimal args
-            let _help_args = Args::new(&[args.get(0)]);
-            // Call via global registry would require function pointer storage
-            // For now, fall through to show_help
-            // TODO: Store function pointers in registry for dynamic dispatch
+    let path = crate::cli::resolve_path(&args.remaining());
+    if let Some(spec) = crate::cli::find_command(&path.join(" ")) {
+        if !args.has("--help") && spec.find_short('h').is_some() {
+            return None;
         }
-        global::show_help();
-        return Some(0);
     }
 
-    // Case 2: prog <topic> --help (topic before flag)
-    if !first_arg.is_empty() && !first_arg.starts_with('-') {
-        // Check if help command is registered
-        let functions = global::list_functions();
-        let help_exists = functions.iter().any(|(name, _)| name == "help");
+    // A user-defined help command owns all help output
+    if global::has_handler("help") {
+        let mut words = vec![program_arg(args), "help".to_string()];
+        words.extend(path.iter().cloned());
+        let mut help_args = Args::new(&words);
+        help_args.has_pop("help");
+        return global::call_handler("help", help_args);
+    }
 
-        if help_exists {
-            // Help command exists - suggest using it for topic-specific help
-            // Note: We can't call it dynamically here (would need help router system)
-            // For now, show hint and fall through to generic help
-            eprintln!("Tip: For detailed help on '{}', try: {} help {}",
-                     first_arg, args.get(0), first_arg);
-        }
+    // prog <topic> [<sub>...] --help
+    let path = path.join(" ");
+    if crate::cli::show_command_help(&path) || crate::cli::show_group_help(&path) {
+        return Some(0);
     }
 
     // Fallback: generic help
-    global::show_help();
+    crate::cli::show_help();
     Some(0)
 }
 
 /// Handle --version / -v flag with smart defaults
 ///
 /// Behavior:
-/// - Tries to call cmd_version() handler if it exists
+/// - Calls the registered `version` handler if there is one
 /// - Falls back to default version info extracted from Cargo.toml
 /// - Format matches boxy style:
 ///   [ASCII ART if RSB_LOGO_ART exists]
@@ -70,11 +61,19 @@ fn rsb_handle_version_flag(args: &Args) -> Option<i32> {
         return None;
     }
 
-    // Try cmd_version handler first
-    if global::has_var("fn_cmd_version") {
-        // Handler exists
-        // TODO: Dynamic dispatch through function registry
-        // For now, fall through to default
+    // Commands with a spec may claim -v/--version for themselves
+    let path = crate::cli::resolve_path(&args.remaining()).join(" ");
+    if let Some(spec) = crate::cli::find_command(&path) {
+        if spec.find_long("version").is_some() || spec.find_short('v').is_some() {
+            return None;
+        }
+    }
+
+    // User-defined version command
+    let mut version_args = Args::new(&[program_arg(args), "version".to_string()]);
+    version_args.has_pop("version");
+    if let Some(code) = global::call_handler("version", version_args) {
+        return Some(code);
     }
 
     // Default version output
@@ -82,6 +81,14 @@ fn rsb_handle_version_flag(args: &Args) -> Option<i32> {
     Some(0)
 }
 
+/// argv[0] as given (empty if there is none).
+fn program_arg(args: &Args) -> String {
+    match args.is_program_index(0) {
+        true => args.all()[0].clone(),
+        false => String::new(),
+    }
+}
+
 /// Show default version info extracted from package metadata
 ///
 /// Format matches boxy style output. Sources (in priority order):
@@ -1,106 +1,240 @@
-// Help System and Function Registry Display
-
-// FUNCTIONS TO MOVE FROM context.rs:
-
-// show_help() -> ()
-// - Display complete help with script name, commands, built-ins
-// - Uses SCRIPT_NAME from global context, formats with colors
-
-// show_functions() -> ()
-// - Display all registered functions
-// - Gets data from global function registry
-
-// show_call_stack() -> ()
-// - Display current call stack with timing
-// - Gets data from global call stack
-
-// HELP SYSTEM OPERATIONS:
-
-// show_help() -> ()
-// - Complete help display with:
-//   - Script name and usage
-//   - Registered commands
-//   - Built-in commands (help, inspect, stack)
-// - Example output: "USAGE: backup <command> [options]"
-
-// show_usage() -> ()
-// - Show just usage line without full help
-// - Example: "USAGE: backup <command> [options]"
-
-// show_commands() -> ()
-// - Show just registered commands without built-ins
-// - Example: "  backup      Create system backup"
-
-// show_functions() -> ()
-// - Alias for show_commands() (from function registry)
-// - Shows all registered function descriptions
-
-// HELP FORMATTING:
-
-// format_help_header() -> String
-// - Format script name and usage header with colors
-// - Example: "{bold}{blue}backup{reset}\n\nUSAGE:\n  backup <command> [options]"
-
-// format_command_section(title: &str, commands: &[(String, String)]) -> String
-// - Format section of commands with title
-// - Example: "{bold}COMMANDS:{reset}\n  backup    Create backup"
-
-// format_command_entry(name: &str, description: &str, width: usize) -> String
-// - Format single command entry with alignment
-// - Example: "  {cyan}backup{reset}         Create system backup"
-
-// BUILT-IN COMMANDS:
-
-// show_built_in_commands() -> ()
-// - Display built-in RSB commands
-// - help, inspect, stack, etc.
-
-// handle_built_in_command(command: &str) -> bool
-// - Handle built-in commands, return true if handled
-// - Example: handle_built_in_command("help") -> true (shows help)
-
-// is_built_in_command(command: &str) -> bool
-// - Check if command is built-in
-// - Example: is_built_in_command("help") -> true
-
-// HELP CUSTOMIZATION:
-
-// set_help_header(header: &str) -> ()
-// - Customize help header text
-// - Example: set_help_header("My Custom Tool v1.0")
-
-// add_help_section(title: &str, content: &str) -> ()
-// - Add custom section to help output
-// - Example: add_help_section("EXAMPLES", "  backup --full /home")
-
-// set_usage_template(template: &str) -> ()
-// - Set custom usage template
-// - Example: set_usage_template("{script} [OPTIONS] <COMMAND>")
-
-// INTERACTIVE HELP:
-
-// interactive_help() -> ()
-// - Interactive help system with command search
-// - Example: Type command name to get specific help
-
-// search_commands(query: &str) -> Vec<(String, String)>
-// - Search commands by name or description
-// - Example: search_commands("back") -> [("backup", "Create backup")]
-
-// suggest_command(input: &str) -> Option<String>
-// - Suggest similar command for typos
-// - Example: suggest_command("bakup") -> Some("backup")
-
-// HELP INTEGRATION:
-
-// auto_help_on_error() -> ()
-// - Automatically show help when command fails
-// - Example: Unknown command shows help
-
-// context_sensitive_help(command: &str) -> ()
-// - Show help specific to command context
-// - Example: context_sensitive_help("backup") shows backup options
-
-// help_with_examples(command: &str) -> ()
-// - Show help with usage examples for specific command
-// - Example: help_with_examples("backup") shows backup examples
+//! Help System — general and per-command help pages
+//!
+//! The general page lists commands from the spec registry (`cli::spec`)
+//! plus any functions registered without a spec, then the built-ins.
+//! Per-command pages render a `CommandSpec`: usage line, description,
+//! arguments, options (with types and defaults), aliases and examples.
+//! Groups of nested commands (`remote add`, `remote remove`) get a page
+//! listing their subcommands; the general page shows top-level entries only,
+//! followed by any user aliases (`cli::register_alias`).
+//! Output uses inline color tags, stripped when colors are disabled.
+
+use crate::cli::{find_command, is_group, subcommands, CommandSpec};
+use crate::global;
+use crate::utils::expand_colors_unified;
+
+/// Built-in commands handled by `execute_dispatch`.
+pub(crate) const BUILT_INS: &[(&str, &str)] = &[
+    (
+        "help",
+        "Show this help message (help <command> for details)",
+    ),
+    ("inspect", "List all available functions"),
+    ("stack", "Show the current call stack"),
+    (
+        "completions",
+        "Print a shell completion script (bash, zsh, fish)",
+    ),
+];
+
+/// Program name for usage lines: `SCRIPT_NAME`, else the basename of argv[0].
+pub fn program_name() -> String {
+    let name = global::get_var("SCRIPT_NAME");
+    if !name.is_empty() {
+        return name;
+    }
+    let prog = global::get_var("cli_prog");
+    match std::path::Path::new(&prog).file_name() {
+        Some(base) => base.to_string_lossy().to_string(),
+        None => "app".to_string(),
+    }
+}
+
+/// `prog build [OPTIONS] <target> [files]...`
+pub fn render_usage(spec: &CommandSpec) -> String {
+    let mut usage = format!("{} {}", program_name(), spec.name);
+    if !spec.opts.is_empty() {
+        usage.push_str(" [OPTIONS]");
+    }
+    for arg in &spec.args {
+        usage.push(' ');
+        usage.push_str(&arg.usage());
+    }
+    usage
+}
+
+fn section(out: &mut String, title: &str, rows: &[(String, String)]) {
+    if rows.is_empty() {
+        return;
+    }
+    let width = rows.iter().map(|(l, _)| l.len()).max().unwrap_or(0).max(15);
+    out.push_str(&format!("\n{{bold}}{}:{{reset}}\n", title));
+    for (label, about) in rows {
+        out.push_str(&format!(
+            "  {{cyan}}{:<w$}{{reset}}  {}\n",
+            label,
+            about,
+            w = width
+        ));
+    }
+}
+
+/// Full help page for one command.
+pub fn render_command_help(spec: &CommandSpec) -> String {
+    let mut out = format!("{{bold}}USAGE:{{reset}}\n  {}\n", render_usage(spec));
+    if !spec.about.is_empty() {
+        out.push_str(&format!("\n{}\n", spec.about));
+    }
+
+    let args: Vec<(String, String)> = spec
+        .args
+        .iter()
+        .map(|arg| {
+            let mut about = arg.about.clone();
+            if arg.ty != global::ValueType::Str {
+                about.push_str(&format!(" ({})", arg.ty.name()));
+            }
+            (arg.usage(), about.trim().to_string())
+        })
+        .collect();
+    section(&mut out, "ARGUMENTS", &args);
+
+    let mut opts: Vec<(String, String)> = spec
+        .opts
+        .iter()
+        .map(|opt| {
+            let mut about = opt.about.clone();
+            if let Some(default) = &opt.default {
+                about.push_str(&format!(" [default: {}]", default));
+            }
+            if opt.required {
+                about.push_str(" (required)");
+            }
+            if opt.repeatable {
+                about.push_str(" (repeatable)");
+            }
+            (opt.label(), about.trim().to_string())
+        })
+        .collect();
+    opts.push(("-h, --help".to_string(), "Show this help".to_string()));
+    section(&mut out, "OPTIONS", &opts);
+
+    if !spec.aliases.is_empty() {
+        out.push_str(&format!(
+            "\n{{bold}}ALIASES:{{reset}} {}\n",
+            spec.aliases.join(", ")
+        ));
+    }
+    if !spec.examples.is_empty() {
+        out.push_str("\n{bold}EXAMPLES:{reset}\n");
+        for example in &spec.examples {
+            out.push_str(&format!("  {} {}\n", program_name(), example));
+        }
+    }
+    expand_colors_unified(&out)
+}
+
+/// Description for a command path: its spec, else the function registry.
+fn describe(path: &str) -> String {
+    if let Some(spec) = find_command(path).filter(|spec| spec.name == path) {
+        return spec.about;
+    }
+    global::list_functions()
+        .into_iter()
+        .find(|(name, _)| name == path)
+        .map(|(_, desc)| desc)
+        .unwrap_or_default()
+}
+
+/// Rows for the subcommands of `path` (`""` for top level): groups are
+/// marked with `<command>`, aliases are listed in parentheses.
+fn command_rows(path: &str) -> Vec<(String, String)> {
+    subcommands(path)
+        .into_iter()
+        .filter(|name| !(path.is_empty() && BUILT_INS.iter().any(|(b, _)| b == name)))
+        .map(|name| {
+            let full = match path.is_empty() {
+                true => name.clone(),
+                false => format!("{} {}", path, name),
+            };
+            let mut label = name;
+            if let Some(spec) = find_command(&full).filter(|spec| !spec.aliases.is_empty()) {
+                label.push_str(&format!(" ({})", spec.aliases.join(", ")));
+            }
+            if is_group(&full) {
+                label.push_str(" <command>");
+            }
+            (label, describe(&full))
+        })
+        .collect()
+}
+
+/// General help: usage, top-level commands (with aliases), built-ins and
+/// user aliases.
+pub fn render_help() -> String {
+    let prog = program_name();
+    let mut out = format!(
+        "{{bold}}{{blue}}{}{{reset}}\n\n{{bold}}USAGE:{{reset}}\n  {} <command> [options]\n",
+        prog, prog
+    );
+    section(&mut out, "COMMANDS", &command_rows(""));
+
+    let built_ins: Vec<(String, String)> = BUILT_INS
+        .iter()
+        .map(|(name, desc)| (name.to_string(), desc.to_string()))
+        .collect();
+    section(&mut out, "BUILT-IN COMMANDS", &built_ins);
+    section(&mut out, "ALIASES", &crate::cli::list_aliases());
+    expand_colors_unified(&out)
+}
+
+/// Help page for a command group: usage, description and its subcommands.
+pub fn render_group_help(path: &str) -> String {
+    let prog = program_name();
+    let mut out = format!(
+        "{{bold}}USAGE:{{reset}}\n  {} {} <command> [options]\n",
+        prog, path
+    );
+    let about = describe(path);
+    if !about.is_empty() {
+        out.push_str(&format!("\n{}\n", about));
+    }
+    section(&mut out, "COMMANDS", &command_rows(path));
+    out.push_str(&format!(
+        "\nRun '{} help {} <command>' for more information on a command.\n",
+        prog, path
+    ));
+    expand_colors_unified(&out)
+}
+
+/// Prints the general help page.
+pub fn show_help() {
+    print!("{}", render_help());
+}
+
+/// Prints the help page for `name` (or alias). Returns false if no spec
+/// is registered for it.
+pub fn show_command_help(name: &str) -> bool {
+    match find_command(name) {
+        Some(spec) => {
+            print!("{}", render_command_help(&spec));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Prints the help page for command group `path`. Returns false if it
+/// isn't a group.
+pub fn show_group_help(path: &str) -> bool {
+    if !is_group(path) {
+        return false;
+    }
+    print!("{}", render_group_help(path));
+    true
+}
+
+/// Reports validation errors for `spec` on stderr, with its usage line.
+pub fn show_usage_errors(spec: &CommandSpec, errors: &[String]) {
+    let mut out = String::new();
+    for err in errors {
+        out.push_str(&format!("{{red}}error:{{reset}} {}\n", err));
+    }
+    out.push_str(&format!(
+        "\n{{bold}}USAGE:{{reset}}\n  {}\n\nRun '{} help {}' for more information.",
+        render_usage(spec),
+        program_name(),
+        spec.name
+    ));
+    eprintln!("{}", expand_colors_unified(&out));
+}
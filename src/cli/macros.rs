@@ -81,14 +81,16 @@ macro_rules! options_ex {
 // --- Dispatch ---
 #[macro_export]
 macro_rules! dispatch {
-    // Enhanced form: optional descriptions per command via `desc: "..."`
-    ($args:expr, { $( $cmd:literal => $handler:ident $(, desc: $desc:expr )? ),* $(,)? }) => {{
+    // Enhanced form: optional descriptions per command via `desc: "..."` and
+    // declarative specs via `spec: CommandSpec::new(..)` (validation + help)
+    ($args:expr, { $( $cmd:literal => $handler:ident $(, desc: $desc:expr )? $(, spec: $spec:expr )? ),* $(,)? }) => {{
         // Register handlers for introspection (ensures built-ins like inspect work)
         $crate::cli::register_handlers(&[$(($cmd, $handler)),*]);
 
         // If vanity descriptions are provided, store them (overrides empty entries)
         $(
-            let _ = dispatch!(@maybe_register_desc $cmd $(, $desc)?);
+            let _ = $crate::dispatch!(@maybe_register_desc $cmd $(, $desc)?);
+            $( $crate::cli::register_command_as($cmd, $spec); )?
         )*
 
         // Delegate to helper function with lookup closure
@@ -107,13 +109,14 @@ macro_rules! dispatch {
 #[macro_export]
 macro_rules! pre_dispatch {
     // Enhanced form with optional descriptions, mirrors dispatch! behavior
-    ($args:expr, { $( $cmd:literal => $handler:ident $(, desc: $desc:expr )? ),* $(,)? }) => {{
+    ($args:expr, { $( $cmd:literal => $handler:ident $(, desc: $desc:expr )? $(, spec: $spec:expr )? ),* $(,)? }) => {{
         // Register handlers for introspection
         $crate::cli::register_handlers(&[$(($cmd, $handler)),*]);
 
-        // If descriptions provided, record them
+        // If descriptions or specs are provided, record them
         $(
-            let _ = pre_dispatch!(@maybe_register_desc $cmd $(, $desc)?);
+            let _ = $crate::pre_dispatch!(@maybe_register_desc $cmd $(, $desc)?);
+            $( $crate::cli::register_command_as($cmd, $spec); )?
         )*
 
         // Delegate to helper function with lookup closure
@@ -12,15 +12,27 @@ pub use dispatch::*;
 mod args;
 pub use args::*;
 
+mod spec;
+pub use spec::*;
+
 mod help;
 pub use help::*;
 
+mod completions;
+pub use completions::*;
+
 mod bootstrap;
 pub use bootstrap::*;
 
 mod options;
 pub use options::*;
 
+mod validators;
+pub use validators::*;
+
+mod aliases;
+pub use aliases::*;
+
 mod flags;
 pub use flags::*;
 
@@ -2,11 +2,23 @@
 //!
 //! Provides the `options` function that works with the `options!` macro for parsing
 //! command line arguments and setting global option variables.
+//!
+//! Parsing is GNU-style: `--key=value` and `--key value`, `-o value` / `-ovalue`,
+//! bundled short flags (`-vqd`) and `--` to end options. Which options take a
+//! value is declared with `OptSpec` (`register_option`); undeclared options are
+//! flags unless written as `--key=value`, so `--debug file.txt` never swallows
+//! the positional.
+//!
+//! Declared values are type-checked and run through their validators
+//! (`cli::validators`). Problems are collected: `options` reports them and
+//! exits, `try_options` returns them.
 
-use crate::cli::Args;
-use crate::global;
-use std::path::Path;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
 
+use crate::cli::{Args, OptSpec, Validator};
+use crate::com::ExitKind;
+use crate::global::{self, ValueType};
 /// Strategy for handling options after processing
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OptionsStrategy {
@@ -48,21 +60,91 @@ impl OptionsStrategy {
 pub struct OptionsContext {
     /// Options that were processed
     pub processed_flags: Vec<String>,
-    /// Whether flag boundary issues were detected
+    /// Indices into `Args::all()` of option tokens and the values they consumed
+    pub option_indices: Vec<usize>,
+    /// Index of the `--` that ended option parsing, if any
+    pub end_of_options: Option<usize>,
+    /// Problems found while parsing: missing values, type mismatches,
+    /// validator failures and missing required options (declared options only)
+    pub errors: Vec<String>,
+    /// Always `false`: `--key value` is no longer ambiguous, since declared
+    /// value options consume the next word
+    #[deprecated(note = "always false; declare value options with `OptSpec::value`")]
     pub has_boundary_issues: bool,
 }
 
+lazy_static! {
+    static ref OPTION_SPECS: Mutex<Vec<OptSpec>> = Mutex::new(Vec::new());
+}
+
+/// Declares an option for `options!` (replacing any with the same long name).
+/// Declared value options consume the next word (`--config app.conf`,
+/// `-o out.txt`), are type-checked, and get their default when absent.
+///
+/// # Examples
+/// ```rust
+/// use rsb::cli::{Args, OptSpec, options, register_option};
+///
+/// register_option(OptSpec::value("output").short('o'));
+/// let args = Args::from_strs(&["bin", "-vo", "out.txt", "input.txt"]);
+/// options(&args);
+///
+/// assert_eq!(rsb::param!("opt_output"), "out.txt");
+/// assert_eq!(rsb::param!("opt_v"), "true");
+/// ```
+pub fn register_option(opt: OptSpec) {
+    let mut specs = OPTION_SPECS.lock().unwrap();
+    specs.retain(|existing| existing.long != opt.long);
+    specs.push(opt);
+}
+
+/// Attaches `validator` to the registered option `long`, declaring it as a
+/// value option if it isn't registered yet.
+///
+/// # Examples
+/// ```rust
+/// use rsb::cli::{add_option_validator, try_options, Args, Validator};
+///
+/// add_option_validator("retries", Validator::range(0.0, 10.0));
+/// let args = Args::from_strs(&["bin", "--retries", "50"]);
+/// let errors = try_options(&args).unwrap_err();
+/// assert!(errors[0].contains("out of range"));
+/// ```
+pub fn add_option_validator(long: &str, validator: Validator) {
+    let mut specs = OPTION_SPECS.lock().unwrap();
+    match specs.iter_mut().find(|opt| opt.long == long) {
+        Some(opt) => *opt = opt.clone().validate(validator),
+        None => specs.push(OptSpec::value(long).validate(validator)),
+    }
+}
+
+/// Forgets all declared options.
+pub fn clear_options() {
+    OPTION_SPECS.lock().unwrap().clear();
+}
+
+/// Options declared with `register_option`.
+pub fn registered_options() -> Vec<OptSpec> {
+    OPTION_SPECS.lock().unwrap().clone()
+}
+
 /// Parse command line options and set global option variables.
 ///
 /// This function processes Args and sets global variables in the format `opt_<name>`
 /// for each option found. It handles:
-/// - Long options: `--verbose`, `--config=value`
-/// - Short options: `-v`, `-d`
-/// - Negation: `--not-verbose` sets `opt_verbose=1` (REBEL false)
+/// - Long options: `--verbose`, `--config=value`, `--config value` (declared)
+/// - Short options: `-v`, bundled `-vqd`, `-o value` / `-ovalue` (declared)
+/// - End of options: everything after `--` is positional
+/// - Repeated options: `--tag=a --tag=b` is stored as an array
+/// - Negation: `--not-verbose` sets `opt_verbose=false`
 /// - Multi-options: `--multi=a,b,!c` sets multiple flags
-/// - Path validation: automatically validates path/file options
+/// - Validation: declared values are type-checked and validated
 /// - Standard options: when `stdopts` feature is enabled
 ///
+/// Fail-fast: problems with declared options are reported together on stderr
+/// and the process exits with `ExitKind::UserFailure`. Use `try_options` to
+/// get the list instead.
+///
 /// # Examples
 /// ```rust
 /// use rsb::cli::{Args, options};
@@ -71,114 +153,242 @@ pub struct OptionsContext {
 /// options(&args);
 ///
 /// // Now opt_verbose and opt_config are set in global context
-/// assert_eq!(rsb::param!("opt_verbose"), "0");
+/// assert_eq!(rsb::param!("opt_verbose"), "true");
 /// assert_eq!(rsb::param!("opt_config"), "app.conf");
 /// ```
 pub fn options(args: &Args) -> OptionsContext {
-    options_with_context(args)
+    let context = options_with_context(args);
+    if !context.errors.is_empty() {
+        for err in &context.errors {
+            crate::utils::stderrx("error", err);
+        }
+        std::process::exit(ExitKind::UserFailure.code() as i32);
+    }
+    context
 }
 
-/// Parse options and return context with processed flags
+/// Like `options`, but returns the problems instead of exiting. Valid
+/// options are still written to Global when others fail.
+pub fn try_options(args: &Args) -> Result<OptionsContext, Vec<String>> {
+    let context = options_with_context(args);
+    match context.errors.is_empty() {
+        true => Ok(context),
+        false => Err(context.errors),
+    }
+}
+
+/// Parse options against the registered declarations and return the context
 pub fn options_with_context(args: &Args) -> OptionsContext {
+    options_with_specs(args, &registered_options())
+}
+
+/// Parse options against `specs` (undeclared options are still accepted)
+pub fn options_with_specs(args: &Args, specs: &[OptSpec]) -> OptionsContext {
     let mut context = OptionsContext::default();
-    for arg in args.all() {
-        if arg.starts_with("--") {
+    // Values in first-seen key order; stored once all words are read
+    let mut values: Vec<(String, Vec<String>)> = Vec::new();
+    let tokens = args.all();
+    let mut i = match args.is_program_index(0) {
+        true => 1,
+        false => 0,
+    };
+
+    while i < tokens.len() {
+        let arg = &tokens[i];
+        if arg == "--" {
+            context.end_of_options = Some(i);
+            break;
+        }
+        if let Some(arg_clean) = arg.strip_prefix("--") {
             context.processed_flags.push(arg.clone());
-            let arg_clean = &arg[2..];
-            let (arg_key, _maybe_val) = if let Some(eq_pos) = arg_clean.find('=') {
-                (&arg_clean[..eq_pos], Some(&arg_clean[eq_pos + 1..]))
-            } else {
-                (arg_clean, None)
+            context.option_indices.push(i);
+            let (arg_key, inline) = match arg_clean.split_once('=') {
+                Some((key, value)) => (key, Some(value.to_string())),
+                None => (arg_clean, None),
             };
 
-            // Handle negation: --not-verbose
-            if arg_key.starts_with("not-") {
-                let base = arg_key.trim_start_matches("not-").replace("-", "_");
-                // Rust-native textual booleans
-                global::set_var(&format!("opt_{}", base), "false");
+            if let Some(opt) = specs.iter().find(|o| o.long == arg_key) {
+                if !opt.takes_value() {
+                    let value = inline.unwrap_or_else(|| "true".to_string());
+                    global::set_var(opt.var_name(), &value);
+                } else if let Some(value) =
+                    inline.or_else(|| take_next(tokens, &mut i, &mut context))
+                {
+                    push_value(&mut values, &opt.var_name(), value);
+                } else {
+                    context
+                        .errors
+                        .push(format!("option '--{}' requires a value", arg_key));
+                }
+                i += 1;
                 continue;
             }
 
-            if let Some(eq_pos) = arg_clean.find('=') {
-                let opt_name = &arg_clean[..eq_pos];
-                let opt_value = &arg_clean[eq_pos + 1..];
-
+            // Handle negation: --not-verbose
+            if let Some(base) = arg_key.strip_prefix("not-") {
+                let key = format!("opt_{}", base.replace('-', "_"));
+                values.retain(|(k, _)| *k != key);
+                // Rust-native textual booleans
+                global::set_var(&key, "false");
+            } else if let Some(opt_value) = inline {
                 // Handle multi-options: --multi=a,b,!c
-                if opt_name == "multi" {
-                    let mut neg = false;
-                    for ch in opt_value.chars() {
-                        if ch == ',' {
-                            neg = false;
-                            continue;
-                        }
-                        if ch == '!' {
-                            neg = !neg;
-                            continue;
-                        }
-                        if ch.is_ascii_alphabetic() {
-                            let base_key = format!("opt_{}", ch);
-                            // Rust-native textual booleans
-                            let val = if neg { "false" } else { "true" };
-                            global::set_var(&base_key, val);
-                        }
-                    }
-                    continue;
+                if arg_key == "multi" {
+                    set_multi(&opt_value);
+                } else {
+                    push_value(
+                        &mut values,
+                        &format!("opt_{}", arg_key.replace('-', "_")),
+                        opt_value,
+                    );
                 }
-
-                // Path validation for path/file options
-                if opt_name.contains("path") || opt_name.contains("file") {
-                    let path = Path::new(opt_value);
-                    if !path.exists() {
-                        eprintln!("Path does not exist: {}", opt_value);
-                        std::process::exit(1);
-                    }
-                }
-
-                global::set_var(&format!("opt_{}", opt_name.replace("-", "_")), opt_value);
             } else {
                 // Flag option (no value) — Rust-native textual boolean
-                global::set_var(&format!("opt_{}", arg_clean.replace("-", "_")), "true");
+                global::set_var(format!("opt_{}", arg_key.replace('-', "_")), "true");
             }
-        } else if arg.starts_with("-") && arg.len() == 2 {
-            // Short option
+        } else if arg.len() > 1 && arg.starts_with('-') && !is_number(arg, specs) {
+            // Short options, possibly bundled: -v, -vqd, -o out.txt, -oout.txt
             context.processed_flags.push(arg.clone());
-            let opt_char = &arg[1..2];
-            // Rust-native textual boolean
-            global::set_var(&format!("opt_{}", opt_char), "true");
-
-            // Standard options mapping (when stdopts feature is enabled)
-            #[cfg(feature = "stdopts")]
-            match opt_char {
-                // Rust-native textual booleans
-                "d" => global::set_var("opt_debug", "true"),
-                "q" => global::set_var("opt_quiet", "true"),
-                "t" => global::set_var("opt_trace", "true"),
-                "D" => global::set_var("opt_dev_mode", "true"),
-                "y" => global::set_var("opt_yes", "true"),
-                "s" => global::set_var("opt_safe", "true"),
-                _ => {}
+            context.option_indices.push(i);
+            let chars: Vec<char> = arg[1..].chars().collect();
+            for (n, c) in chars.iter().enumerate() {
+                let Some(opt) = specs.iter().find(|o| o.short == Some(*c)) else {
+                    set_short_flag(*c);
+                    continue;
+                };
+                if !opt.takes_value() {
+                    global::set_var(opt.var_name(), "true");
+                    continue;
+                }
+                let rest: String = chars[n + 1..].iter().collect();
+                let value = match rest.is_empty() {
+                    true => take_next(tokens, &mut i, &mut context),
+                    false => Some(rest),
+                };
+                match value {
+                    Some(value) => push_value(&mut values, &opt.var_name(), value),
+                    None => context
+                        .errors
+                        .push(format!("option '-{}' requires a value", c)),
+                }
+                break;
             }
         }
+        i += 1;
     }
 
-    // Check for potential flag boundary issues
-    context.has_boundary_issues = check_flag_boundaries(args);
+    for (key, items) in &values {
+        let declared = specs.iter().find(|o| o.var_name() == *key);
+        match declared {
+            Some(opt) if opt.repeatable => set_values(key, items),
+            Some(_) => set_values(key, &items[items.len() - 1..]),
+            None => set_values(key, items),
+        }
+    }
+
+    for opt in specs.iter().filter(|o| o.takes_value()) {
+        let key = opt.var_name();
+        match values.iter().find(|(k, _)| *k == key) {
+            Some((_, items)) => {
+                let ty = opt.ty.as_ref().unwrap_or(&ValueType::Str);
+                for item in items {
+                    let problems = match ty.check(item) {
+                        Ok(()) => crate::cli::run_validators(&opt.validators, item),
+                        Err(msg) => vec![msg],
+                    };
+                    for msg in problems {
+                        context
+                            .errors
+                            .push(format!("option '--{}': {}", opt.long, msg));
+                    }
+                }
+            }
+            None => match &opt.default {
+                Some(default) if opt.repeatable => global::set_array(&key, &[default]),
+                Some(default) => global::set_var(&key, default),
+                None if opt.required => context
+                    .errors
+                    .push(format!("missing required option '--{}'", opt.long)),
+                None => {}
+            },
+        }
+    }
 
     context
 }
 
-/// Check for problematic flag/value boundary patterns
-fn check_flag_boundaries(args: &Args) -> bool {
-    let all_args = args.all();
-    for i in 0..all_args.len().saturating_sub(1) {
-        if all_args[i].starts_with("--")
-            && !all_args[i].contains('=')
-            && !all_args[i + 1].starts_with('-') {
-            // Potential space-separated value
-            return true;
+/// Consumes the word after `*i` as an option value.
+fn take_next(tokens: &[String], i: &mut usize, context: &mut OptionsContext) -> Option<String> {
+    let value = tokens.get(*i + 1)?.clone();
+    *i += 1;
+    context.option_indices.push(*i);
+    Some(value)
+}
+
+fn push_value(values: &mut Vec<(String, Vec<String>)>, key: &str, value: String) {
+    match values.iter_mut().find(|(k, _)| k == key) {
+        Some((_, items)) => items.push(value),
+        None => values.push((key.to_string(), vec![value])),
+    }
+}
+
+/// One value is a plain variable; repeats become an array.
+fn set_values(key: &str, items: &[String]) {
+    if items.len() == 1 {
+        global::unset_var(&format!("{}_LENGTH", key));
+        global::set_var(key, &items[0]);
+    } else {
+        let items: Vec<&str> = items.iter().map(String::as_str).collect();
+        global::set_array(key, &items);
+    }
+}
+
+/// `--multi=dq!ts`: letters become flags, `!` toggles negation.
+fn set_multi(letters: &str) {
+    let mut neg = false;
+    for ch in letters.chars() {
+        if ch == ',' {
+            neg = false;
+            continue;
+        }
+        if ch == '!' {
+            neg = !neg;
+            continue;
+        }
+        if ch.is_ascii_alphabetic() {
+            let base_key = format!("opt_{}", ch);
+            // Rust-native textual booleans
+            let val = if neg { "false" } else { "true" };
+            global::set_var(&base_key, val);
         }
     }
-    false
+}
+
+/// Undeclared short flag: `opt_<char>`, plus its stdopts name.
+fn set_short_flag(opt_char: char) {
+    // Rust-native textual boolean
+    global::set_var(format!("opt_{}", opt_char), "true");
+
+    // Standard options mapping (when stdopts feature is enabled)
+    #[cfg(feature = "stdopts")]
+    match opt_char {
+        // Rust-native textual booleans
+        'd' => global::set_var("opt_debug", "true"),
+        'q' => global::set_var("opt_quiet", "true"),
+        't' => global::set_var("opt_trace", "true"),
+        'D' => global::set_var("opt_dev_mode", "true"),
+        'y' => global::set_var("opt_yes", "true"),
+        's' => global::set_var("opt_safe", "true"),
+        _ => {}
+    }
+}
+
+/// Negative numbers are positionals unless a digit is a declared short option.
+fn is_number(token: &str, specs: &[OptSpec]) -> bool {
+    token.parse::<f64>().is_ok()
+        && token[1..]
+            .chars()
+            .next()
+            .is_none_or(|c| !specs.iter().any(|o| o.short == Some(c)))
 }
 
 /// Check if an option was provided (convenience function).
@@ -219,3 +429,34 @@ pub fn get_option_value(name: &str) -> Option<String> {
         Some(value)
     }
 }
+
+/// Get an option's values: every value of a repeated option, or the single
+/// value (`--tag=a --tag b` gives `["a", "b"]`).
+pub fn get_option_values(name: &str) -> Vec<String> {
+    let key = format!("opt_{}", name);
+    if global::has_var(&format!("{}_LENGTH", key)) {
+        return global::get_array(&key);
+    }
+    get_option_value(name).into_iter().collect()
+}
+
+/// Get an option value as an integer (`None` if absent or not a number).
+pub fn get_option_int(name: &str) -> Option<i64> {
+    global::get_int(&format!("opt_{}", name))
+}
+
+/// Get an option value as a float.
+pub fn get_option_float(name: &str) -> Option<f64> {
+    global::get_float(&format!("opt_{}", name))
+}
+
+/// Get an option as a bool: flags are `true`, negated flags `false`, and
+/// values like `yes`/`0` are parsed.
+pub fn get_option_bool(name: &str) -> Option<bool> {
+    global::get_bool(&format!("opt_{}", name))
+}
+
+/// Get an option value as a path (`~` and `$VARS` expanded).
+pub fn get_option_path(name: &str) -> Option<std::path::PathBuf> {
+    global::get_path(&format!("opt_{}", name))
+}
@@ -0,0 +1,543 @@
+//! Declarative command specs for `dispatch!`
+//!
+//! A `CommandSpec` describes a command's positional arguments, options,
+//! aliases and examples. Registered specs let dispatch validate input before
+//! the handler runs (all problems are reported together) and drive the
+//! per-command help page in `cli::help`.
+//!
+//! Nested commands are named by their full path (`"remote add"`); any
+//! prefix of a command path is a group. A spec registered for a group
+//! supplies its description and an optional pre-hook.
+//!
+//! Validated options are written to Global as `opt_<name>` (dashes become
+//! underscores), matching `options!`; repeatable options are stored as
+//! arrays. Defaults are applied for options that weren't given.
+
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+use crate::cli::{Args, Validator};
+use crate::global::{self, ValueType};
+
+/// Runs before any command at or beneath the spec's path; a non-zero
+/// return aborts dispatch with that exit code.
+pub type PreHook = fn(&Args) -> i32;
+
+/// Supplies completion candidates for a value at completion time, given
+/// the partial word being completed (see `cli::completions`).
+pub type Completer = fn(&str) -> Vec<String>;
+
+/// A positional argument.
+#[derive(Debug, Clone)]
+pub struct ArgSpec {
+    pub name: String,
+    pub about: String,
+    pub ty: ValueType,
+    pub required: bool,
+    /// Takes all remaining positionals (must be the last argument)
+    pub repeatable: bool,
+    pub completer: Option<Completer>,
+}
+
+impl ArgSpec {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            about: String::new(),
+            ty: ValueType::Str,
+            required: false,
+            repeatable: false,
+            completer: None,
+        }
+    }
+
+    pub fn about(mut self, about: &str) -> Self {
+        self.about = about.to_string();
+        self
+    }
+
+    pub fn value_type(mut self, ty: ValueType) -> Self {
+        self.ty = ty;
+        self
+    }
+
+    /// Completes this argument's values with `completer`.
+    pub fn complete_with(mut self, completer: Completer) -> Self {
+        self.completer = Some(completer);
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn repeatable(mut self) -> Self {
+        self.repeatable = true;
+        self
+    }
+
+    /// `<name>` / `[name]`, with `...` when repeatable.
+    pub fn usage(&self) -> String {
+        let dots = if self.repeatable { "..." } else { "" };
+        match self.required {
+            true => format!("<{}>{}", self.name, dots),
+            false => format!("[{}]{}", self.name, dots),
+        }
+    }
+}
+
+/// A named option: a flag (`--release`) or a valued option (`--jobs 4`).
+#[derive(Debug, Clone)]
+pub struct OptSpec {
+    pub long: String,
+    pub short: Option<char>,
+    pub about: String,
+    /// `None` for flags
+    pub ty: Option<ValueType>,
+    pub default: Option<String>,
+    pub required: bool,
+    pub repeatable: bool,
+    pub completer: Option<Completer>,
+    /// Checks run on each value after the type check
+    pub validators: Vec<Validator>,
+}
+
+impl OptSpec {
+    /// A boolean flag; sets `opt_<long>=true` when given.
+    pub fn flag(long: &str) -> Self {
+        Self {
+            long: long.to_string(),
+            short: None,
+            about: String::new(),
+            ty: None,
+            default: None,
+            required: false,
+            repeatable: false,
+            completer: None,
+            validators: Vec::new(),
+        }
+    }
+
+    /// An option taking a string value.
+    pub fn value(long: &str) -> Self {
+        Self {
+            ty: Some(ValueType::Str),
+            ..Self::flag(long)
+        }
+    }
+
+    pub fn short(mut self, short: char) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    pub fn about(mut self, about: &str) -> Self {
+        self.about = about.to_string();
+        self
+    }
+
+    /// Sets the value type (turns a flag into a valued option).
+    pub fn value_type(mut self, ty: ValueType) -> Self {
+        self.ty = Some(ty);
+        self
+    }
+
+    pub fn default(mut self, value: &str) -> Self {
+        self.default = Some(value.to_string());
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn repeatable(mut self) -> Self {
+        self.repeatable = true;
+        self
+    }
+
+    /// Completes this option's values with `completer`.
+    pub fn complete_with(mut self, completer: Completer) -> Self {
+        self.completer = Some(completer);
+        self
+    }
+
+    /// Adds a validator (turns a flag into a valued option).
+    pub fn validate(mut self, validator: Validator) -> Self {
+        self.ty.get_or_insert(ValueType::Str);
+        self.validators.push(validator);
+        self
+    }
+
+    pub fn takes_value(&self) -> bool {
+        self.ty.is_some()
+    }
+
+    /// Global key the option is stored under.
+    pub fn var_name(&self) -> String {
+        format!("opt_{}", self.long.replace('-', "_"))
+    }
+
+    /// `-j, --jobs <int>` style label.
+    pub fn label(&self) -> String {
+        let short = match self.short {
+            Some(c) => format!("-{}, ", c),
+            None => "    ".to_string(),
+        };
+        let value = match &self.ty {
+            Some(ty) => format!(" <{}>", ty.name()),
+            None => String::new(),
+        };
+        let dots = if self.repeatable { "..." } else { "" };
+        format!("{}--{}{}{}", short, self.long, value, dots)
+    }
+}
+
+/// A command: name, description, arguments, options, aliases and examples.
+#[derive(Debug, Clone, Default)]
+pub struct CommandSpec {
+    pub name: String,
+    pub about: String,
+    pub args: Vec<ArgSpec>,
+    pub opts: Vec<OptSpec>,
+    pub aliases: Vec<String>,
+    pub examples: Vec<String>,
+    pub pre: Option<PreHook>,
+}
+
+impl CommandSpec {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn about(mut self, about: &str) -> Self {
+        self.about = about.to_string();
+        self
+    }
+
+    pub fn arg(mut self, arg: ArgSpec) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    pub fn opt(mut self, opt: OptSpec) -> Self {
+        self.opts.push(opt);
+        self
+    }
+
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+
+    /// An example invocation, written without the program name.
+    pub fn example(mut self, example: &str) -> Self {
+        self.examples.push(example.to_string());
+        self
+    }
+
+    /// Runs `hook` before this command (or, for a group, before every
+    /// command beneath it).
+    pub fn pre(mut self, hook: PreHook) -> Self {
+        self.pre = Some(hook);
+        self
+    }
+
+    /// The group path this command lives in (`""` at the top level).
+    pub fn parent(&self) -> &str {
+        self.name.rsplit_once(' ').map_or("", |(parent, _)| parent)
+    }
+
+    /// The last word of the command path.
+    pub fn leaf(&self) -> &str {
+        self.name
+            .rsplit_once(' ')
+            .map_or(self.name.as_str(), |(_, leaf)| leaf)
+    }
+
+    pub fn find_long(&self, long: &str) -> Option<&OptSpec> {
+        self.opts.iter().find(|o| o.long == long)
+    }
+
+    pub fn find_short(&self, short: char) -> Option<&OptSpec> {
+        self.opts.iter().find(|o| o.short == Some(short))
+    }
+
+    /// Negative numbers are positionals unless a digit is a short option.
+    fn is_number(&self, token: &str) -> bool {
+        token.parse::<f64>().is_ok()
+            && token[1..]
+                .chars()
+                .next()
+                .is_none_or(|c| self.find_short(c).is_none())
+    }
+
+    /// Parses the command's arguments (`args` is what the handler receives:
+    /// the command name already popped). Collects every problem.
+    pub fn parse(&self, args: &Args) -> Result<CommandMatches, Vec<String>> {
+        let mut matches = CommandMatches::default();
+        let mut errors = Vec::new();
+        let tokens = args.remaining();
+        let mut iter = tokens.iter();
+        let mut only_positional = false;
+
+        while let Some(token) = iter.next() {
+            if only_positional || token == "-" || !token.starts_with('-') || self.is_number(token) {
+                matches.positionals.push(token.clone());
+            } else if token == "--" {
+                only_positional = true;
+            } else if let Some(long) = token.strip_prefix("--") {
+                let (name, inline) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (long, None),
+                };
+                let Some(opt) = self.find_long(name) else {
+                    errors.push(format!("unknown option '--{}'", name));
+                    continue;
+                };
+                match (opt.takes_value(), inline) {
+                    (false, Some(_)) => {
+                        errors.push(format!("option '--{}' does not take a value", name))
+                    }
+                    (false, None) => matches.push(opt, "true".to_string()),
+                    (true, Some(value)) => matches.push(opt, value),
+                    (true, None) => match iter.next() {
+                        Some(value) => matches.push(opt, value.clone()),
+                        None => errors.push(format!("option '--{}' requires a value", name)),
+                    },
+                }
+            } else {
+                // Short cluster: -rv, -j4, -j 4
+                let chars: Vec<char> = token[1..].chars().collect();
+                for (i, c) in chars.iter().enumerate() {
+                    let Some(opt) = self.find_short(*c) else {
+                        errors.push(format!("unknown option '-{}'", c));
+                        break;
+                    };
+                    if !opt.takes_value() {
+                        matches.push(opt, "true".to_string());
+                        continue;
+                    }
+                    let rest: String = chars[i + 1..].iter().collect();
+                    if !rest.is_empty() {
+                        matches.push(opt, rest);
+                    } else if let Some(value) = iter.next() {
+                        matches.push(opt, value.clone());
+                    } else {
+                        errors.push(format!("option '-{}' requires a value", c));
+                    }
+                    break;
+                }
+            }
+        }
+
+        for opt in &self.opts {
+            let values = matches.values(&opt.long);
+            if values.is_empty() && opt.required {
+                errors.push(format!("missing required option '--{}'", opt.long));
+            }
+            if values.len() > 1 && !opt.repeatable {
+                errors.push(format!("option '--{}' given more than once", opt.long));
+            }
+            if let Some(ty) = &opt.ty {
+                for value in values {
+                    match ty.check(value) {
+                        Err(msg) => errors.push(format!("option '--{}': {}", opt.long, msg)),
+                        Ok(()) => {
+                            for msg in crate::cli::run_validators(&opt.validators, value) {
+                                errors.push(format!("option '--{}': {}", opt.long, msg));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut positionals = matches.positionals.iter();
+        for arg in &self.args {
+            let taken: Vec<&String> = match arg.repeatable {
+                true => positionals.by_ref().collect(),
+                false => positionals.next().into_iter().collect(),
+            };
+            if taken.is_empty() && arg.required {
+                errors.push(format!("missing required argument <{}>", arg.name));
+            }
+            for value in taken {
+                if let Err(msg) = arg.ty.check(value) {
+                    errors.push(format!("argument <{}>: {}", arg.name, msg));
+                }
+            }
+        }
+        for extra in positionals {
+            errors.push(format!("unexpected argument '{}'", extra));
+        }
+
+        match errors.is_empty() {
+            true => Ok(matches),
+            false => Err(errors),
+        }
+    }
+
+    /// Writes parsed options (and defaults for missing ones) to Global.
+    pub fn apply(&self, matches: &CommandMatches) {
+        for opt in &self.opts {
+            let values: Vec<&str> = matches
+                .values(&opt.long)
+                .iter()
+                .map(|v| v.as_str())
+                .collect();
+            let key = opt.var_name();
+            match (values.is_empty(), &opt.default) {
+                (true, Some(default)) if opt.repeatable => global::set_array(&key, &[default]),
+                (true, Some(default)) => global::set_var(&key, default),
+                (true, None) => {}
+                (false, _) if opt.repeatable => global::set_array(&key, &values),
+                (false, _) => global::set_var(&key, values[0]),
+            }
+        }
+    }
+}
+
+/// Options and positionals parsed against a `CommandSpec`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CommandMatches {
+    /// (long name, value) in command-line order; flags have value "true"
+    pub opts: Vec<(String, String)>,
+    pub positionals: Vec<String>,
+}
+
+impl CommandMatches {
+    fn push(&mut self, opt: &OptSpec, value: String) {
+        self.opts.push((opt.long.clone(), value));
+    }
+
+    pub fn values(&self, long: &str) -> Vec<&String> {
+        self.opts
+            .iter()
+            .filter(|(name, _)| name == long)
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    pub fn value(&self, long: &str) -> Option<&String> {
+        self.values(long).into_iter().last()
+    }
+
+    pub fn has(&self, long: &str) -> bool {
+        self.opts.iter().any(|(name, _)| name == long)
+    }
+}
+
+lazy_static! {
+    static ref COMMAND_SPECS: Mutex<Vec<CommandSpec>> = Mutex::new(Vec::new());
+}
+
+/// Registers (or replaces) a command spec; its description also goes to the
+/// function registry so `inspect` shows it.
+pub fn register_command(spec: CommandSpec) {
+    global::register_function(&spec.name, &spec.about);
+    let mut specs = COMMAND_SPECS.lock().unwrap();
+    specs.retain(|s| s.name != spec.name);
+    specs.push(spec);
+}
+
+/// Registers `spec` under `name` (used by `dispatch!`'s `spec:` form).
+pub fn register_command_as(name: &str, spec: CommandSpec) {
+    register_command(CommandSpec {
+        name: name.to_string(),
+        ..spec
+    });
+}
+
+/// Looks a command up by path; the last word may be an alias
+/// (`"remote rm"` finds `"remote remove"` if it declares `rm`).
+pub fn find_command(name: &str) -> Option<CommandSpec> {
+    let specs = COMMAND_SPECS.lock().unwrap();
+    let (parent, leaf) = name.rsplit_once(' ').unwrap_or(("", name));
+    specs
+        .iter()
+        .find(|s| s.name == name)
+        .or_else(|| {
+            specs
+                .iter()
+                .find(|s| s.parent() == parent && s.aliases.iter().any(|a| a == leaf))
+        })
+        .cloned()
+}
+
+/// All registered specs, sorted by name.
+pub fn list_commands() -> Vec<CommandSpec> {
+    let mut specs = COMMAND_SPECS.lock().unwrap().clone();
+    specs.sort_by(|a, b| a.name.cmp(&b.name));
+    specs
+}
+
+/// Every known command path: registered functions and specs.
+pub fn command_paths() -> Vec<String> {
+    let mut paths: Vec<String> = global::list_functions()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    for spec in COMMAND_SPECS.lock().unwrap().iter() {
+        if !paths.contains(&spec.name) {
+            paths.push(spec.name.clone());
+        }
+    }
+    paths.sort();
+    paths
+}
+
+/// Direct subcommand names under `path` (`""` for the top level), sorted.
+pub fn subcommands(path: &str) -> Vec<String> {
+    let prefix = match path.is_empty() {
+        true => String::new(),
+        false => format!("{} ", path),
+    };
+    let mut names: Vec<String> = command_paths()
+        .iter()
+        .filter_map(|p| p.strip_prefix(&prefix))
+        .filter_map(|rest| rest.split(' ').next())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect();
+    names.dedup();
+    names
+}
+
+/// Whether `path` has subcommands.
+pub fn is_group(path: &str) -> bool {
+    !path.is_empty() && !subcommands(path).is_empty()
+}
+
+/// Resolves leading command words to a canonical path, following aliases
+/// and stopping at the first word that isn't a subcommand of the path so far.
+pub fn resolve_path(words: &[String]) -> Vec<String> {
+    let paths = command_paths();
+    let mut resolved: Vec<String> = Vec::new();
+    for word in words {
+        if word.starts_with('-') {
+            break;
+        }
+        let parent = resolved.join(" ");
+        if !resolved.is_empty() && !is_group(&parent) {
+            break;
+        }
+        let candidate = match parent.is_empty() {
+            true => word.clone(),
+            false => format!("{} {}", parent, word),
+        };
+        if paths.contains(&candidate) || is_group(&candidate) {
+            resolved.push(word.clone());
+        } else if let Some(spec) = find_command(&candidate) {
+            resolved.push(spec.leaf().to_string());
+        } else {
+            break;
+        }
+    }
+    resolved
+}
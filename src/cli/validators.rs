@@ -0,0 +1,123 @@
+//! Option validators
+//!
+//! Validators attach to declared options (`OptSpec::validate`, or
+//! `add_option_validator` for a registered name) and run on every value the
+//! option receives, after its type check. Failures are collected rather than
+//! exiting on the first one: `options()` reports them together and exits,
+//! `try_options()` hands the list back, and `CommandSpec` validation reports
+//! them with the command's usage.
+
+use regex::Regex;
+use std::path::Path;
+
+/// Custom check: `Err(message)` rejects the value.
+pub type ValidatorFn = fn(&str) -> Result<(), String>;
+
+/// A check applied to an option value.
+#[derive(Debug, Clone)]
+pub enum Validator {
+    /// The path exists
+    Exists,
+    /// The path can be written: an existing file that isn't read-only, or a
+    /// new file in an existing, writable directory
+    Creatable,
+    /// The path is a directory
+    Dir,
+    /// The path can be opened for reading
+    Readable,
+    /// The value matches the pattern (unanchored; use `^...$` for a full match)
+    Regex(String),
+    /// The value is a number within `min..=max`
+    Range(f64, f64),
+    /// The value is one of the choices
+    OneOf(Vec<String>),
+    Custom(ValidatorFn),
+}
+
+impl Validator {
+    pub fn regex(pattern: &str) -> Self {
+        Validator::Regex(pattern.to_string())
+    }
+
+    pub fn range(min: f64, max: f64) -> Self {
+        Validator::Range(min, max)
+    }
+
+    pub fn one_of(choices: &[&str]) -> Self {
+        Validator::OneOf(choices.iter().map(|c| c.to_string()).collect())
+    }
+
+    pub fn custom(check: ValidatorFn) -> Self {
+        Validator::Custom(check)
+    }
+
+    /// Checks `value`, describing the problem on failure.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let path = Path::new(value);
+        match self {
+            Validator::Exists if !path.exists() => Err(format!("path does not exist: {}", value)),
+            Validator::Dir if !path.is_dir() => Err(format!("not a directory: {}", value)),
+            Validator::Readable => {
+                let readable = match path.is_dir() {
+                    true => std::fs::read_dir(path).is_ok(),
+                    false => std::fs::File::open(path).is_ok(),
+                };
+                match readable {
+                    true => Ok(()),
+                    false => Err(format!("cannot read: {}", value)),
+                }
+            }
+            Validator::Creatable => check_creatable(path, value),
+            Validator::Regex(pattern) => match Regex::new(pattern) {
+                Ok(re) if re.is_match(value) => Ok(()),
+                Ok(_) => Err(format!("'{}' does not match /{}/", value, pattern)),
+                Err(_) => Err(format!("invalid pattern /{}/", pattern)),
+            },
+            Validator::Range(min, max) => match value.trim().parse::<f64>() {
+                Ok(n) if n >= *min && n <= *max => Ok(()),
+                Ok(_) => Err(format!("{} is out of range {}..{}", value, min, max)),
+                Err(_) => Err(format!("'{}' is not a number", value)),
+            },
+            Validator::OneOf(choices) if !choices.iter().any(|c| c == value) => {
+                Err(format!("'{}' is not one of: {}", value, choices.join(", ")))
+            }
+            Validator::Custom(check) => check(value),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn check_creatable(path: &Path, value: &str) -> Result<(), String> {
+    if path.is_dir() {
+        return Err(format!("is a directory: {}", value));
+    }
+    if path.exists() {
+        return match std::fs::metadata(path).map(|m| m.permissions().readonly()) {
+            Ok(false) => Ok(()),
+            _ => Err(format!("file is not writable: {}", value)),
+        };
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match std::fs::metadata(parent) {
+        Ok(meta) if meta.is_dir() && !meta.permissions().readonly() => Ok(()),
+        Ok(meta) if meta.is_dir() => {
+            Err(format!("directory is not writable: {}", parent.display()))
+        }
+        _ => Err(format!(
+            "cannot create {}: directory {} does not exist",
+            value,
+            parent.display()
+        )),
+    }
+}
+
+/// Runs `validators` on `value`, collecting every failure.
+pub fn run_validators(validators: &[Validator], value: &str) -> Vec<String> {
+    validators
+        .iter()
+        .filter_map(|v| v.check(value).err())
+        .collect()
+}
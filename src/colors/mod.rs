@@ -27,6 +27,7 @@ pub mod status;
 pub mod macros;
 
 // Progressive enhancement runtime registry and ergonomic API
+mod palette;
 mod registry;
 mod util;
 
@@ -48,3 +49,7 @@ pub use registry::{
     bg, color, color_enable, color_enable_with, color_mode, colored, colorize, colorize_bg,
     get_all_colors, get_color,
 };
+
+// RGB names and palette fitting (see hosts::term_caps for detection)
+pub use palette::{ansi256_to_rgb, fit_code, parse_rgb, rgb_code, rgb_to_16, rgb_to_256, Rgb};
+pub use util::color_depth;
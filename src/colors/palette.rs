@@ -0,0 +1,193 @@
+//! RGB colors and palette downsampling
+//!
+//! Parses `#rrggbb`, `#rgb` and `rgb(r, g, b)` color names and fits escape
+//! codes to what the terminal supports (see `hosts::ColorDepth`): 24-bit
+//! codes become the nearest 256-color cube/grey entry, and 256-color codes
+//! the nearest of the 16 standard colors.
+
+use crate::hosts::ColorDepth;
+
+pub type Rgb = (u8, u8, u8);
+
+/// The 16 standard colors as xterm draws them.
+const BASIC_RGB: [Rgb; 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Channel levels of the 6x6x6 cube (indices 16–231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Parses `#rrggbb`, `#rgb` or `rgb(r, g, b)`; `None` for anything else.
+pub fn parse_rgb(name: &str) -> Option<Rgb> {
+    let name = name.trim();
+    if let Some(hex) = name.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+        return match hex.len() {
+            6 => Some((
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            )),
+            3 => {
+                let short = |i: usize| channel(&hex[i..i + 1]).map(|v| v * 17);
+                Some((short(0)?, short(1)?, short(2)?))
+            }
+            _ => None,
+        };
+    }
+    let inner = name
+        .strip_prefix("rgb(")
+        .or_else(|| name.strip_prefix("RGB("))?
+        .strip_suffix(')')?;
+    let channels: Vec<u8> = inner
+        .split(',')
+        .map(|c| c.trim().parse::<u8>().ok())
+        .collect::<Option<_>>()?;
+    match channels.as_slice() {
+        [r, g, b] => Some((*r, *g, *b)),
+        _ => None,
+    }
+}
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// RGB value of a 256-color palette index.
+pub fn ansi256_to_rgb(index: u8) -> Rgb {
+    match index {
+        0..=15 => BASIC_RGB[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// Nearest 256-color index, picked from the cube and the grey ramp (the
+/// first 16 entries vary with the terminal theme).
+pub fn rgb_to_256(rgb: Rgb) -> u8 {
+    let level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let cube = 16 + 36 * level(rgb.0) + 6 * level(rgb.1) + level(rgb.2);
+    let avg = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let grey = 232 + ((avg.saturating_sub(3)) / 10).min(23) as u8;
+    if distance(rgb, ansi256_to_rgb(grey)) < distance(rgb, ansi256_to_rgb(cube)) {
+        grey
+    } else {
+        cube
+    }
+}
+
+/// Nearest of the 16 standard colors, as an index 0–15.
+pub fn rgb_to_16(rgb: Rgb) -> u8 {
+    (0..16u8)
+        .min_by_key(|&i| distance(rgb, BASIC_RGB[i as usize]))
+        .unwrap_or(7)
+}
+
+/// SGR parameters for a 16-color index: 30–37/90–97, plus 10 for
+/// backgrounds.
+fn basic_param(index: u8, background: bool) -> String {
+    let base = if index < 8 {
+        30 + index
+    } else {
+        90 + index - 8
+    };
+    (base + if background { 10 } else { 0 }).to_string()
+}
+
+fn rgb_params(rgb: Rgb, background: bool, depth: ColorDepth) -> String {
+    let layer = if background { 48 } else { 38 };
+    match depth {
+        ColorDepth::TrueColor => format!("{};2;{};{};{}", layer, rgb.0, rgb.1, rgb.2),
+        ColorDepth::Ansi256 => format!("{};5;{}", layer, rgb_to_256(rgb)),
+        _ => basic_param(rgb_to_16(rgb), background),
+    }
+}
+
+/// Foreground escape code for `rgb` at `depth` ("" for `ColorDepth::None`).
+pub fn rgb_code(rgb: Rgb, depth: ColorDepth) -> String {
+    match depth {
+        ColorDepth::None => String::new(),
+        _ => format!("\x1B[{}m", rgb_params(rgb, false, depth)),
+    }
+}
+
+/// Rewrites the 256-color and 24-bit parts of an SGR escape code so they
+/// fit `depth`. Other codes come back unchanged; everything is "" for
+/// `ColorDepth::None`.
+pub fn fit_code(code: &str, depth: ColorDepth) -> String {
+    if depth == ColorDepth::None {
+        return String::new();
+    }
+    let params = match code
+        .strip_prefix("\x1B[")
+        .and_then(|rest| rest.strip_suffix('m'))
+    {
+        Some(params) => params,
+        None => return code.to_string(),
+    };
+    let parts: Vec<&str> = params.split(';').collect();
+    let mut out: Vec<String> = Vec::with_capacity(parts.len());
+    let mut i = 0;
+    while i < parts.len() {
+        let background = parts[i] == "48";
+        let extended = parts[i] == "38" || background;
+        let number = |j: usize| parts.get(j).and_then(|p| p.parse::<u8>().ok());
+        match (extended, parts.get(i + 1).copied()) {
+            (true, Some("5")) if number(i + 2).is_some() => {
+                let index = number(i + 2).unwrap_or(0);
+                out.push(match depth {
+                    ColorDepth::Basic => basic_param(rgb_to_16(ansi256_to_rgb(index)), background),
+                    _ => parts[i..i + 3].join(";"),
+                });
+                i += 3;
+            }
+            (true, Some("2")) if (2..5).all(|k| number(i + k).is_some()) => {
+                let rgb = (
+                    number(i + 2).unwrap_or(0),
+                    number(i + 3).unwrap_or(0),
+                    number(i + 4).unwrap_or(0),
+                );
+                out.push(rgb_params(rgb, background, depth));
+                i += 5;
+            }
+            _ => {
+                out.push(parts[i].to_string());
+                i += 1;
+            }
+        }
+    }
+    format!("\x1B[{}m", out.join(";"))
+}
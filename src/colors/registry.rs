@@ -6,11 +6,16 @@
 //! - colorize("Hello", "red") -> String
 //! - colored("Hello {red}world{reset}") -> String
 //! - color_mode("auto|always|never")
+//!
+//! Besides registered names, `#rrggbb`, `#rgb` and `rgb(r, g, b)` work
+//! anywhere a color name does. Codes are fitted to the terminal's color
+//! depth (see `hosts::term_caps`) and cached per name and depth.
 
 use std::collections::HashMap;
 use std::sync::RwLock;
 
 use crate::global::get_var;
+use crate::hosts::ColorDepth;
 
 #[cfg(feature = "colors-named")]
 use super::named;
@@ -18,15 +23,21 @@ use super::simple::{SimpleColor, RESET};
 #[cfg(feature = "colors-status")]
 use super::status::StatusColor;
 
-use super::util::{backgrounds_enabled, colors_enabled, set_backgrounds_enabled, set_color_mode};
+use super::palette::{fit_code, parse_rgb, rgb_code};
+use super::util::{
+    backgrounds_enabled, color_depth, colors_enabled, set_backgrounds_enabled, set_color_mode,
+};
 #[cfg(feature = "glyphs")]
-use crate::visual::glyphs::{glyph, glyphs_enabled, set_glyphs_enabled};
+use crate::visual::glyphs::{glyph, glyphs_enabled, has_glyph, set_glyphs_enabled};
 
 use lazy_static::lazy_static;
 
 lazy_static! {
     static ref REGISTRY: RwLock<HashMap<String, &'static str>> = RwLock::new(HashMap::new());
     static ref LOADED: RwLock<Loaded> = RwLock::new(Loaded::default());
+    /// Codes rewritten for a color depth (or built from RGB), by name
+    static ref FITTED: RwLock<HashMap<(String, ColorDepth), &'static str>> =
+        RwLock::new(HashMap::new());
 }
 
 #[derive(Clone, Copy, Default)]
@@ -270,18 +281,46 @@ pub fn color_mode(mode: &str) {
     set_color_mode(mode);
 }
 
-/// Get a color code by name, or "" if not found/disabled.
+/// Get a color code by name (or `#rrggbb`/`rgb(r, g, b)`), fitted to the
+/// terminal's color depth, or "" if not found/disabled.
 pub fn color(name: &str) -> &'static str {
     ensure_initialized();
-    if !colors_enabled() {
+    let depth = color_depth();
+    if depth == ColorDepth::None {
         return "";
     }
     let n = name.to_ascii_lowercase();
-    if let Some(v) = REGISTRY.read().unwrap().get(&n) {
-        *v
-    } else {
-        ""
+    let registered = REGISTRY.read().unwrap().get(&n).copied();
+    match registered {
+        // 16-color codes and control codes suit every depth
+        Some(code)
+            if depth == ColorDepth::TrueColor
+                || !(code.contains("38;") || code.contains("48;")) =>
+        {
+            code
+        }
+        Some(code) => fitted(&n, depth, || fit_code(code, depth)),
+        None => match parse_rgb(&n) {
+            Some(rgb) => fitted(&n, depth, || rgb_code(rgb, depth)),
+            None => "",
+        },
+    }
+}
+
+/// The cached code for `name` at `depth`, built by `make` on first use.
+fn fitted(name: &str, depth: ColorDepth, make: impl FnOnce() -> String) -> &'static str {
+    let key = (name.to_string(), depth);
+    if let Some(code) = FITTED.read().unwrap().get(&key) {
+        return code;
     }
+    let code: &'static str = Box::leak(make().into_boxed_str());
+    FITTED.write().unwrap().entry(key).or_insert(code)
+}
+
+/// Whether `name` is a registered color or an RGB value.
+fn is_color_name(name: &str) -> bool {
+    let n = name.to_ascii_lowercase();
+    n == "reset" || REGISTRY.read().unwrap().contains_key(&n) || parse_rgb(&n).is_some()
 }
 
 /// Back-compat alias for color(name)
@@ -308,6 +347,10 @@ fn fg_to_bg(code: &str) -> Option<String> {
         // replace the first occurrence only
         return Some(code.replacen("38;5;", "48;5;", 1));
     }
+    // 24-bit form: ESC[38;2;R;G;Bm -> ESC[48;2;R;G;Bm
+    if code.contains("38;2;") {
+        return Some(code.replacen("38;2;", "48;2;", 1));
+    }
     // Simple 8/16 colors: 30-37 -> 40-47, 90-97 -> 100-107
     if code.len() >= 5 && code.ends_with('m') {
         // extract the number sequence inside ESC[ ... m
@@ -349,9 +392,12 @@ pub fn colorize_bg(text: &str, name: &str) -> String {
     }
 }
 
-/// Replace {color} tokens inline. Unknown tags are kept verbatim.
+/// Replace {color} tokens inline (`{#ff8800}` and `{bg:#ff8800}` included).
+/// Unknown tags are kept verbatim; color tags are dropped while colors are
+/// disabled.
 pub fn colored(s: &str) -> String {
     ensure_initialized();
+    let enabled = colors_enabled();
     let mut out = String::with_capacity(s.len() + 8);
     let mut chars = s.chars().peekable();
     while let Some(ch) = chars.next() {
@@ -366,11 +412,14 @@ pub fn colored(s: &str) -> String {
                 name.push(c);
             }
             if name == "reset" {
-                out.push_str(RESET);
+                if enabled {
+                    out.push_str(RESET);
+                }
             } else if name.starts_with("bg:") && backgrounds_enabled() {
                 let key = &name[name.find(':').unwrap() + 1..];
                 let code = bg(key);
-                if code.is_empty() {
+                // With colors disabled, known color tags are dropped
+                if code.is_empty() && (enabled || !is_color_name(key)) {
                     out.push('{');
                     out.push_str(&name);
                     out.push('}');
@@ -382,7 +431,8 @@ pub fn colored(s: &str) -> String {
                 {
                     let key = &name[name.find(':').unwrap() + 1..];
                     let g = if glyphs_enabled() { glyph(key) } else { "" };
-                    if g.is_empty() {
+                    // A known glyph may have no fallback for this terminal
+                    if g.is_empty() && !(glyphs_enabled() && has_glyph(key)) {
                         out.push('{');
                         out.push_str(&name);
                         out.push('}');
@@ -398,7 +448,7 @@ pub fn colored(s: &str) -> String {
                 }
             } else {
                 let code = color(&name);
-                if code.is_empty() {
+                if code.is_empty() && (enabled || !is_color_name(&name)) {
                     out.push('{');
                     out.push_str(&name);
                     out.push('}');
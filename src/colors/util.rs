@@ -1,30 +1,55 @@
 //! Internal utilities for color enablement and gate logic
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
-static COLOR_FORCED_OFF: AtomicBool = AtomicBool::new(false);
+use crate::hosts::{term_caps, ColorDepth};
+
+const MODE_ALWAYS: u8 = 0;
+const MODE_AUTO: u8 = 1;
+const MODE_NEVER: u8 = 2;
+
+static COLOR_MODE: AtomicU8 = AtomicU8::new(MODE_ALWAYS);
 static BACKGROUNDS_ON: AtomicBool = AtomicBool::new(false);
 
-/// Enable/disable color output globally at runtime.
-/// Mode: "auto" | "always" | "never". Currently supports env guards, no TTY detect.
-pub fn set_color_mode(mode: &str) {
+fn parse_mode(mode: &str) -> Option<u8> {
     match mode.to_ascii_lowercase().as_str() {
-        "never" => COLOR_FORCED_OFF.store(true, Ordering::SeqCst),
-        _ => COLOR_FORCED_OFF.store(false, Ordering::SeqCst),
+        "always" | "on" => Some(MODE_ALWAYS),
+        "auto" => Some(MODE_AUTO),
+        "never" | "off" => Some(MODE_NEVER),
+        _ => None,
     }
 }
 
-/// Returns whether colors are enabled considering env and explicit mode.
-pub fn colors_enabled() -> bool {
+/// Enable/disable color output globally at runtime.
+/// Mode: "auto" (only when stdout is a terminal, at its color depth) |
+/// "always" (at least 16 colors) | "never". Unknown modes mean "always".
+pub fn set_color_mode(mode: &str) {
+    let mode = parse_mode(mode).unwrap_or(MODE_ALWAYS);
+    COLOR_MODE.store(mode, Ordering::SeqCst);
+}
+
+/// Color depth to emit, considering NO_COLOR, RSB_COLOR (which wins over
+/// `set_color_mode`) and the detected terminal capabilities.
+pub fn color_depth() -> ColorDepth {
     if std::env::var("NO_COLOR").is_ok() {
-        return false;
+        return ColorDepth::None;
     }
-    if let Ok(mode) = std::env::var("RSB_COLOR") {
-        if mode.eq_ignore_ascii_case("never") {
-            return false;
-        }
+    let mode = std::env::var("RSB_COLOR")
+        .ok()
+        .and_then(|m| parse_mode(&m))
+        .unwrap_or_else(|| COLOR_MODE.load(Ordering::SeqCst));
+    let caps = term_caps();
+    match mode {
+        MODE_NEVER => ColorDepth::None,
+        MODE_AUTO if !caps.is_tty => ColorDepth::None,
+        MODE_AUTO => caps.color,
+        _ => caps.color.max(ColorDepth::Basic),
     }
-    !COLOR_FORCED_OFF.load(Ordering::SeqCst)
+}
+
+/// Returns whether colors are enabled considering env and explicit mode.
+pub fn colors_enabled() -> bool {
+    color_depth() != ColorDepth::None
 }
 
 pub fn set_backgrounds_enabled(enable: bool) {
@@ -10,26 +10,24 @@ pub const FALSE: bool = false;
 pub const TRUE_STR: &str = "true";
 pub const FALSE_STR: &str = "false";
 
+/// Strictly parse a boolean-like value: true/false/yes/no/on/off/enabled/
+/// disabled/pass/fail/success/error (case-insensitive) and integers
+/// (non-zero -> true). `None` for anything else.
+pub fn parse_bool_val<S: AsRef<str>>(v: S) -> Option<bool> {
+    let s = v.as_ref().trim();
+    match s.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "enabled" | "pass" | "success" => Some(true),
+        "false" | "no" | "off" | "disabled" | "fail" | "error" => Some(false),
+        _ => s.parse::<i64>().ok().map(|n| n != 0),
+    }
+}
+
 /// Interpret a boolean-like value according to Rust-native semantics.
 /// Accepts textual true/false/yes/no/on/off (case-insensitive) and numeric 1/0
 /// for compatibility (non-zero -> true, zero -> false).
 #[inline]
 pub fn is_true_val<S: AsRef<str>>(v: S) -> bool {
-    let s = v.as_ref().trim();
-    if s.is_empty() {
-        return false;
-    }
-    match s.to_ascii_lowercase().as_str() {
-        "true" | "yes" | "on" | "enabled" | "pass" | "success" => return true,
-        "false" | "no" | "off" | "disabled" | "fail" | "error" => return false,
-        "1" => return true,
-        "0" => return false,
-        _ => {}
-    }
-    if let Ok(n) = s.parse::<i64>() {
-        return n != 0;
-    }
-    false
+    parse_bool_val(v).unwrap_or(false)
 }
 
 #[inline]
@@ -0,0 +1,286 @@
+//! Loopback HTTP test server (feature `dev-http`)
+//!
+//! A tiny HTTP/1.1 server bound to `127.0.0.1` on a random port. Tests
+//! script responses per method + path and inspect the requests received,
+//! so `rsb::http` (and `curl!`/`get!`) can be exercised offline.
+//!
+//! ```ignore
+//! use rsb::dev::{MockResponse, TestServer};
+//!
+//! let server = TestServer::start();
+//! server.respond("GET", "/hello", MockResponse::new(200).body("hi"));
+//! let resp = rsb::http::get(&server.url("/hello")).send().unwrap();
+//! assert_eq!(resp.text(), "hi");
+//! assert_eq!(server.requests()[0].path, "/hello");
+//! ```
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A scripted response.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Send the body with `Transfer-Encoding: chunked`.
+    pub chunked: bool,
+    /// Wait this long before answering (for timeout tests).
+    pub delay: Option<Duration>,
+}
+
+impl MockResponse {
+    pub fn new(status: u16) -> Self {
+        MockResponse {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            chunked: false,
+            delay: None,
+        }
+    }
+
+    /// A redirect to `location`.
+    pub fn redirect(status: u16, location: &str) -> Self {
+        MockResponse::new(status).header("Location", location)
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn json(self, value: &serde_json::Value) -> Self {
+        self.header("Content-Type", "application/json")
+            .body(value.to_string())
+    }
+
+    pub fn chunked(mut self) -> Self {
+        self.chunked = true;
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// A request as the server saw it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path including the query string.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// First header with this name (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+#[derive(Default)]
+struct ServerState {
+    routes: HashMap<(String, String), MockResponse>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// The running server; it stops when dropped.
+pub struct TestServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<ServerState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TestServer {
+    /// Binds `127.0.0.1:0` and starts serving. Unscripted routes get 404.
+    pub fn start() -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind loopback test server");
+        let addr = listener.local_addr().expect("test server address");
+        let state = Arc::new(Mutex::new(ServerState::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = Arc::clone(&state);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                for conn in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(conn) = conn {
+                        let state = Arc::clone(&state);
+                        thread::spawn(move || handle(conn, &state));
+                    }
+                }
+            })
+        };
+
+        TestServer {
+            addr,
+            state,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// `http://127.0.0.1:<port>` + `path`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Scripts the response for `method path` (path includes any query).
+    pub fn respond(&self, method: &str, path: &str, response: MockResponse) {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .insert((method.to_uppercase(), path.to_string()), response);
+    }
+
+    /// Requests received so far, in arrival order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the stop flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn handle(conn: TcpStream, state: &Mutex<ServerState>) {
+    let _ = conn.set_read_timeout(Some(Duration::from_secs(5)));
+    let Some(request) = read_request(&conn) else {
+        return;
+    };
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(request.clone());
+        state
+            .routes
+            .get(&(request.method.clone(), request.path.clone()))
+            .cloned()
+            .unwrap_or_else(|| MockResponse::new(404).body("not found"))
+    };
+    if let Some(delay) = response.delay {
+        thread::sleep(delay);
+    }
+    let _ = write_response(conn, &request.method, &response);
+}
+
+fn read_request(conn: &TcpStream) -> Option<RecordedRequest> {
+    let mut reader = BufReader::new(conn);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("Content-Length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn write_response(mut conn: TcpStream, method: &str, response: &MockResponse) -> std::io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason(response.status));
+    for (key, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", key, value));
+    }
+    if response.chunked {
+        head.push_str("Transfer-Encoding: chunked\r\n");
+    } else {
+        head.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    conn.write_all(head.as_bytes())?;
+    if method == "HEAD" {
+        return conn.flush();
+    }
+    if response.chunked {
+        for chunk in response.body.chunks(7) {
+            conn.write_all(format!("{:x}\r\n", chunk.len()).as_bytes())?;
+            conn.write_all(chunk)?;
+            conn.write_all(b"\r\n")?;
+        }
+        conn.write_all(b"0\r\n\r\n")?;
+    } else {
+        conn.write_all(&response.body)?;
+    }
+    conn.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
@@ -11,3 +11,9 @@ pub mod pty;
 
 #[cfg(feature = "dev-pty")]
 pub use pty::*;
+
+#[cfg(feature = "dev-http")]
+pub mod http_server;
+
+#[cfg(feature = "dev-http")]
+pub use http_server::*;
@@ -17,9 +17,11 @@
 //! ```
 
 use std::io::{Error as IoError, ErrorKind, Read, Write};
-use std::sync::mpsc;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 
@@ -41,54 +43,132 @@ impl Default for PtyOptions {
 }
 
 pub struct PtySession {
-    master: Box<dyn portable_pty::MasterPty + Send>,
+    /// `None` once the session has hung up
+    master: Option<Box<dyn portable_pty::MasterPty + Send>>,
     child: Box<dyn portable_pty::Child + Send>,
+    /// Our own copy of the master for writing (portable-pty's writer types
+    /// a newline and EOF when dropped, which a hangup must not do)
+    writer: Option<std::fs::File>,
+    /// Chunks from a single background reader, so reads stay in order
+    output: Option<mpsc::Receiver<std::io::Result<Vec<u8>>>>,
+    /// The reader and its stop flag; it holds its own copy of the master
+    reader: Option<(thread::JoinHandle<()>, Arc<AtomicBool>)>,
 }
 
 impl PtySession {
     /// Write bytes to the PTY as if typed.
     pub fn write_all(&mut self, data: &[u8]) -> std::io::Result<()> {
-        let mut w = self.master.take_writer().map_err(to_io_err)?;
-        w.write_all(data)
+        let w = self.writer()?;
+        w.write_all(data)?;
+        w.flush()
     }
 
     /// Convenience: write a line (appends newline).
     pub fn writeln(&mut self, s: &str) -> std::io::Result<()> {
-        let mut w = self.master.take_writer().map_err(to_io_err)?;
+        let w = self.writer()?;
         w.write_all(s.as_bytes())?;
-        w.write_all(b"\n")
+        w.write_all(b"\n")?;
+        w.flush()
     }
 
-    /// Read whatever is available within the duration. Returns empty string on timeout.
-    pub fn read_for(&mut self, dur: Duration) -> std::io::Result<String> {
-        let (tx, rx) = mpsc::channel();
-        let mut reader = self.master.try_clone_reader().map_err(to_io_err)?;
-
-        thread::spawn(move || {
-            let mut buf = [0u8; 4096];
-            match reader.read(&mut buf) {
-                Ok(0) => {
-                    let _ = tx.send(Ok(String::new()));
-                }
-                Ok(n) => {
-                    let s = String::from_utf8_lossy(&buf[..n]).to_string();
-                    let _ = tx.send(Ok(s));
-                }
-                Err(e) => {
-                    let _ = tx.send(Err(e));
+    fn master(&self) -> std::io::Result<&(dyn portable_pty::MasterPty + Send)> {
+        self.master
+            .as_deref()
+            .ok_or_else(|| IoError::new(ErrorKind::NotConnected, "pty session hung up"))
+    }
+
+    fn writer(&mut self) -> std::io::Result<&mut std::fs::File> {
+        if self.writer.is_none() {
+            self.writer = Some(self.dup_master()?);
+        }
+        Ok(self.writer.as_mut().unwrap())
+    }
+
+    /// A descriptor of the master owned by the caller.
+    fn dup_master(&self) -> std::io::Result<std::fs::File> {
+        let fd = self
+            .master()?
+            .as_raw_fd()
+            .ok_or_else(|| IoError::new(ErrorKind::Unsupported, "pty has no descriptor"))?;
+        // SAFETY: dup only creates a new descriptor.
+        let fd = unsafe { libc::dup(fd) };
+        if fd < 0 {
+            return Err(IoError::last_os_error());
+        }
+        // SAFETY: fd is fresh and owned by nothing else.
+        Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+    }
+
+    fn output(&mut self) -> std::io::Result<&mpsc::Receiver<std::io::Result<Vec<u8>>>> {
+        if self.output.is_none() {
+            let (tx, rx) = mpsc::channel();
+            let mut reader = self.dup_master()?;
+            let fd = reader.as_raw_fd();
+            let stop = Arc::new(AtomicBool::new(false));
+            let stopped = Arc::clone(&stop);
+            let handle = thread::spawn(move || {
+                let mut buf = [0u8; 4096];
+                // Poll so `hang_up` can stop the reader and release its copy
+                // of the master
+                while !stopped.load(Ordering::SeqCst) {
+                    let mut fds = libc::pollfd {
+                        fd,
+                        events: libc::POLLIN,
+                        revents: 0,
+                    };
+                    // SAFETY: one valid pollfd.
+                    if unsafe { libc::poll(&mut fds, 1, 50) } <= 0 {
+                        continue;
+                    }
+                    match reader.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(n) => {
+                            if tx.send(Ok(buf[..n].to_vec())).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            let _ = tx.send(Err(e));
+                            break;
+                        }
+                    }
                 }
-            }
-        });
+            });
+            self.output = Some(rx);
+            self.reader = Some((handle, stop));
+        }
+        Ok(self.output.as_ref().unwrap())
+    }
 
-        match rx.recv_timeout(dur) {
-            Ok(res) => res,
+    /// Read whatever is available within the duration. Returns empty string on timeout.
+    pub fn read_for(&mut self, dur: Duration) -> std::io::Result<String> {
+        match self.output()?.recv_timeout(dur) {
+            Ok(res) => res.map(|bytes| String::from_utf8_lossy(&bytes).to_string()),
             Err(_timeout) => Ok(String::new()),
         }
     }
 
+    /// Read until the output so far contains `needle`, or `timeout` passes.
+    /// Returns everything read either way; check it for `needle`.
+    pub fn read_until(&mut self, needle: &str, timeout: Duration) -> std::io::Result<String> {
+        let deadline = Instant::now() + timeout;
+        let mut seen = Vec::new();
+        while !String::from_utf8_lossy(&seen).contains(needle) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            match self.output()?.recv_timeout(left) {
+                Ok(chunk) => seen.extend(chunk?),
+                Err(_) => break,
+            }
+        }
+        Ok(String::from_utf8_lossy(&seen).to_string())
+    }
+
     /// Resize the PTY.
     pub fn resize(&mut self, cols: u16, rows: u16) -> std::io::Result<()> {
-        self.master
+        self.master()?
             .resize(PtySize {
                 rows,
                 cols,
@@ -104,6 +184,33 @@ impl PtySession {
         Ok(if status.success() { 0 } else { 1 })
     }
 
+    /// Wait up to `timeout` for the child to exit; `None` if it is still
+    /// running.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> std::io::Result<Option<i32>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(Some(if status.success() { 0 } else { 1 }));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// Close the terminal as a disconnect would: the child sees a hangup
+    /// and EOF on its side. Output already read stays available; writes and
+    /// resizes fail afterwards.
+    pub fn hang_up(&mut self) {
+        if let Some((handle, stop)) = self.reader.take() {
+            stop.store(true, Ordering::SeqCst);
+            let _ = handle.join();
+        }
+        self.writer = None;
+        self.master = None;
+    }
+
     /// Kill the child process.
     pub fn kill(&mut self) -> std::io::Result<()> {
         self.child.kill().map_err(to_io_err)
@@ -133,8 +240,11 @@ pub fn spawn_pty(command: &str, opts: &PtyOptions) -> std::io::Result<PtySession
     drop(pair.slave); // Close slave in parent
 
     Ok(PtySession {
-        master: pair.master,
+        master: Some(pair.master),
         child,
+        writer: None,
+        output: None,
+        reader: None,
     })
 }
 
@@ -0,0 +1,730 @@
+//! Native Archives (feature `archive`)
+//!
+//! Creates, lists and extracts tar, tar.gz and zip without shelling out, so
+//! paths with spaces work and no `tar`/`zip` binaries are required. Entries
+//! keep their unix permissions and mtimes; include/exclude globs select what
+//! goes in or comes out; extraction refuses entries that would land outside
+//! the destination (absolute paths, `..`, writes through symlinks, links
+//! pointing outside).
+//!
+//! ```ignore
+//! use rsb::fs::archive::{create_archive, extract_archive, ArchiveOptions};
+//!
+//! let opts = ArchiveOptions::new().exclude("*.log").exclude("target");
+//! create_archive("release.tar.gz", &["src", "README.md"], &opts)?;
+//! extract_archive("release.tar.gz", "/tmp/out", &ArchiveOptions::new())?;
+//! ```
+
+mod deflate;
+mod tar;
+mod zip;
+
+use crate::global::expand_vars;
+use crate::hosts::command::CmdResult;
+use glob::Pattern;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Archive container formats, detected from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// `.tar`, `.tar.gz`/`.tgz` or `.zip` (case-insensitive).
+    pub fn from_path(path: &str) -> Option<ArchiveFormat> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if lower.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if lower.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+
+    /// Detects an existing archive from its first bytes: gzip is assumed to
+    /// wrap a tar, `PK` is zip, a valid ustar magic is tar.
+    pub fn sniff(path: &str) -> Option<ArchiveFormat> {
+        let mut head = [0u8; 263];
+        let n = File::open(path).and_then(|mut f| f.read(&mut head)).ok()?;
+        match &head[..n] {
+            [0x1f, 0x8b, ..] => Some(ArchiveFormat::TarGz),
+            [b'P', b'K', 3, 4, ..] | [b'P', b'K', 5, 6, ..] => Some(ArchiveFormat::Zip),
+            bytes if bytes.len() >= 262 && &bytes[257..262] == b"ustar" => Some(ArchiveFormat::Tar),
+            _ => None,
+        }
+    }
+}
+
+/// What an entry is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    /// Symbolic link with its target.
+    Symlink(String),
+    /// Hard link to an earlier entry (tar only).
+    Hardlink(String),
+}
+
+/// One archive member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Relative path inside the archive (`/`-separated, no trailing `/`).
+    pub path: String,
+    pub kind: EntryKind,
+    /// Size in bytes (files only).
+    pub size: u64,
+    /// Unix permission bits.
+    pub mode: u32,
+    /// Modification time, seconds since the epoch.
+    pub mtime: i64,
+}
+
+impl ArchiveEntry {
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Dir
+    }
+
+    /// The path as `tar -t` prints it (directories end in `/`).
+    pub fn display_path(&self) -> String {
+        if self.is_dir() {
+            format!("{}/", self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+}
+
+/// Options shared by create, list and extract.
+#[derive(Debug, Clone)]
+pub struct ArchiveOptions {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    base_dir: Option<String>,
+    format: Option<ArchiveFormat>,
+    preserve_permissions: bool,
+    preserve_mtime: bool,
+    level: u8,
+}
+
+impl Default for ArchiveOptions {
+    fn default() -> Self {
+        ArchiveOptions {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            base_dir: None,
+            format: None,
+            preserve_permissions: true,
+            preserve_mtime: true,
+            level: 6,
+        }
+    }
+}
+
+impl ArchiveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only files/links matching one of these globs are processed. A glob
+    /// matches the full entry path or its file name.
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(pattern.to_string());
+        self
+    }
+
+    /// Entries matching these globs (or inside a matching directory) are skipped.
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    /// Create: resolve sources relative to `dir` and store them relative to it
+    /// (like `tar -C dir`).
+    pub fn base_dir(mut self, dir: &str) -> Self {
+        self.base_dir = Some(dir.to_string());
+        self
+    }
+
+    /// Overrides extension-based format detection.
+    pub fn format(mut self, format: ArchiveFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Extract: apply stored permission bits (default on).
+    pub fn preserve_permissions(mut self, on: bool) -> Self {
+        self.preserve_permissions = on;
+        self
+    }
+
+    /// Extract: apply stored modification times (default on).
+    pub fn preserve_mtime(mut self, on: bool) -> Self {
+        self.preserve_mtime = on;
+        self
+    }
+
+    /// Deflate level 0-9 for tar.gz and zip (default 6).
+    pub fn level(mut self, level: u8) -> Self {
+        self.level = level.min(9);
+        self
+    }
+
+    /// Explicit format, then the extension, then (for existing archives)
+    /// the file's magic bytes.
+    fn resolve_format(&self, archive: &str) -> Result<ArchiveFormat, String> {
+        self.format
+            .or_else(|| ArchiveFormat::from_path(archive))
+            .or_else(|| ArchiveFormat::sniff(archive))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown archive format for '{}' (use .tar, .tar.gz, .tgz or .zip)",
+                    archive
+                )
+            })
+    }
+
+    fn filter(&self) -> Result<Filter, String> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| Pattern::new(p).map_err(|e| format!("Invalid glob '{}': {}", p, e)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Filter {
+            include: compile(&self.include)?,
+            exclude: compile(&self.exclude)?,
+        })
+    }
+}
+
+struct Filter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl Filter {
+    fn matches(patterns: &[Pattern], path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        patterns.iter().any(|p| p.matches(path) || p.matches(name))
+    }
+
+    /// Excluded itself or below an excluded directory.
+    fn excluded(&self, path: &str) -> bool {
+        let mut prefix = path;
+        loop {
+            if Self::matches(&self.exclude, prefix) {
+                return true;
+            }
+            match prefix.rfind('/') {
+                Some(i) => prefix = &prefix[..i],
+                None => return false,
+            }
+        }
+    }
+
+    fn included(&self, path: &str) -> bool {
+        self.include.is_empty() || Self::matches(&self.include, path)
+    }
+
+    /// Directories pass include filters; files and links must match.
+    fn selects(&self, entry: &ArchiveEntry) -> bool {
+        !self.excluded(&entry.path) && (entry.is_dir() || self.included(&entry.path))
+    }
+}
+
+/// Progress callback: the entry being processed, entries done so far
+/// (including this one), and the total when known up front.
+pub type ArchiveProgress<'a> = dyn FnMut(&ArchiveEntry, usize, Option<usize>) + 'a;
+
+/// Adapts an archive result to the `CmdResult` shape the bash helpers
+/// return: entry paths (`tar -t` style) on stdout, the message on failure.
+pub fn to_cmd_result(result: Result<Vec<ArchiveEntry>, String>) -> CmdResult {
+    match result {
+        Ok(entries) => CmdResult {
+            status: 0,
+            output: entries
+                .iter()
+                .map(ArchiveEntry::display_path)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            error: String::new(),
+        },
+        Err(error) => CmdResult {
+            status: 1,
+            output: String::new(),
+            error,
+        },
+    }
+}
+
+// --- Create ---
+
+/// Creates `archive` from `sources` (files or directories, recursed).
+/// Returns the entries written (in dry-run mode, the entries that would be).
+pub fn create_archive(
+    archive: &str,
+    sources: &[&str],
+    opts: &ArchiveOptions,
+) -> Result<Vec<ArchiveEntry>, String> {
+    create_archive_with_progress(archive, sources, opts, &mut |_, _, _| {})
+}
+
+/// `create_archive` reporting each entry to `progress`.
+pub fn create_archive_with_progress(
+    archive: &str,
+    sources: &[&str],
+    opts: &ArchiveOptions,
+    progress: &mut ArchiveProgress,
+) -> Result<Vec<ArchiveEntry>, String> {
+    let archive = expand_vars(archive);
+    let format = opts.resolve_format(&archive)?;
+    let filter = opts.filter()?;
+    let base = opts
+        .base_dir
+        .as_deref()
+        .map(|dir| PathBuf::from(expand_vars(dir)));
+
+    let mut members = Vec::new();
+    for source in sources {
+        let source = expand_vars(source);
+        let disk = match &base {
+            Some(base) => base.join(&source),
+            None => PathBuf::from(&source),
+        };
+        let name = archive_name(&source);
+        if name.is_empty() {
+            return Err(format!("Cannot archive '{}': no usable entry name", source));
+        }
+        collect(&disk, &name, &filter, &mut members)?;
+    }
+    if members.is_empty() {
+        return Err("Nothing to archive (no sources matched)".to_string());
+    }
+
+    let what = format!("create {} ({} entries)", archive, members.len());
+    if !crate::hosts::dry_run_skip(&what) {
+        crate::hosts::traced(&what, || {
+            write_archive(&archive, format, opts, &members, progress)
+        })?;
+    }
+    Ok(members.into_iter().map(|(_, entry)| entry).collect())
+}
+
+fn write_archive(
+    archive: &str,
+    format: ArchiveFormat,
+    opts: &ArchiveOptions,
+    members: &[(PathBuf, ArchiveEntry)],
+    progress: &mut ArchiveProgress,
+) -> Result<(), String> {
+    let fail = |e: io::Error| format!("Failed to create archive '{}': {}", archive, e);
+    if let Some(parent) = Path::new(&archive)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).map_err(fail)?;
+    }
+    let out = BufWriter::new(File::create(archive).map_err(fail)?);
+    match format {
+        ArchiveFormat::Tar => {
+            let mut writer = tar::TarWriter::new(out);
+            write_members(members, &mut |e, d| writer.append(e, d), progress).map_err(fail)?;
+            writer.finish().map_err(fail)?;
+        }
+        ArchiveFormat::TarGz => {
+            let gz = deflate::GzEncoder::new(out, opts.level).map_err(fail)?;
+            let mut writer = tar::TarWriter::new(gz);
+            write_members(members, &mut |e, d| writer.append(e, d), progress).map_err(fail)?;
+            let gz = writer.finish().map_err(fail)?;
+            let mut out = gz.finish().map_err(fail)?;
+            io::Write::flush(&mut out).map_err(fail)?;
+        }
+        ArchiveFormat::Zip => {
+            let mut writer = zip::ZipWriter::new(out, opts.level);
+            write_members(members, &mut |e, d| writer.append(e, d), progress).map_err(fail)?;
+            writer.finish().map_err(fail)?;
+        }
+    }
+    Ok(())
+}
+
+/// Appends one entry (with its file data) to a tar or zip writer.
+type AppendFn<'a> = dyn FnMut(&ArchiveEntry, Option<&mut dyn Read>) -> io::Result<()> + 'a;
+
+fn write_members(
+    members: &[(PathBuf, ArchiveEntry)],
+    append: &mut AppendFn,
+    progress: &mut ArchiveProgress,
+) -> io::Result<()> {
+    for (i, (disk, entry)) in members.iter().enumerate() {
+        if entry.kind == EntryKind::File {
+            let mut file = File::open(disk)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", disk.display(), e)))?;
+            append(entry, Some(&mut file))?;
+        } else {
+            append(entry, None)?;
+        }
+        progress(entry, i + 1, Some(members.len()));
+    }
+    Ok(())
+}
+
+/// Entry name for a source path: leading `/` and `./` are dropped; paths
+/// that climb with `..` are stored under their final component.
+fn archive_name(source: &str) -> String {
+    let path = Path::new(source);
+    if path.components().any(|c| c == Component::ParentDir) {
+        return path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn collect(
+    disk: &Path,
+    name: &str,
+    filter: &Filter,
+    out: &mut Vec<(PathBuf, ArchiveEntry)>,
+) -> Result<(), String> {
+    if filter.excluded(name) {
+        return Ok(());
+    }
+    let meta = fs::symlink_metadata(disk)
+        .map_err(|e| format!("Cannot archive '{}': {}", disk.display(), e))?;
+    let mut entry = ArchiveEntry {
+        path: name.to_string(),
+        kind: EntryKind::File,
+        size: 0,
+        mode: meta.permissions().mode() & 0o7777,
+        mtime: meta.mtime(),
+    };
+
+    if meta.file_type().is_symlink() {
+        let target = fs::read_link(disk)
+            .map_err(|e| format!("Cannot read link '{}': {}", disk.display(), e))?;
+        entry.kind = EntryKind::Symlink(target.to_string_lossy().into_owned());
+    } else if meta.is_dir() {
+        entry.kind = EntryKind::Dir;
+        let mut children = fs::read_dir(disk)
+            .and_then(|dir| {
+                dir.map(|e| e.map(|e| e.file_name()))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .map_err(|e| format!("Cannot read directory '{}': {}", disk.display(), e))?;
+        children.sort();
+
+        let slot = out.len();
+        out.push((disk.to_path_buf(), entry));
+        for child in children {
+            let child_name = format!("{}/{}", name, child.to_string_lossy());
+            collect(&disk.join(&child), &child_name, filter, out)?;
+        }
+        // With include globs, keep a directory only if something under it (or
+        // the directory itself) was selected
+        if !filter.include.is_empty() && out.len() == slot + 1 && !filter.included(name) {
+            out.pop();
+        }
+        return Ok(());
+    } else if meta.is_file() {
+        entry.size = meta.len();
+    } else {
+        // Sockets, fifos, devices
+        return Ok(());
+    }
+
+    if filter.included(name) {
+        out.push((disk.to_path_buf(), entry));
+    }
+    Ok(())
+}
+
+// --- List ---
+
+/// Lists the entries of `archive` (filters apply).
+pub fn list_archive(archive: &str, opts: &ArchiveOptions) -> Result<Vec<ArchiveEntry>, String> {
+    let archive = expand_vars(archive);
+    let format = opts.resolve_format(&archive)?;
+    let filter = opts.filter()?;
+    let fail = |e: io::Error| format!("Failed to read archive '{}': {}", archive, e);
+    let file = File::open(&archive).map_err(fail)?;
+
+    let mut entries = Vec::new();
+    match format {
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let input: Box<dyn Read> = match format {
+                ArchiveFormat::TarGz => {
+                    Box::new(deflate::GzDecoder::new(BufReader::new(file)).map_err(fail)?)
+                }
+                _ => Box::new(BufReader::new(file)),
+            };
+            let mut reader = tar::TarReader::new(input);
+            while let Some(entry) = reader.next_entry().map_err(fail)? {
+                entries.push(entry);
+            }
+        }
+        ArchiveFormat::Zip => {
+            let reader = zip::ZipReader::new(BufReader::new(file)).map_err(fail)?;
+            entries.extend(reader.entries().cloned());
+        }
+    }
+    entries.retain(|entry| filter.selects(entry));
+    Ok(entries)
+}
+
+// --- Extract ---
+
+/// Extracts `archive` into `dest` (created if missing). Returns the entries
+/// written. Unsafe entries abort the extraction with an error.
+pub fn extract_archive(
+    archive: &str,
+    dest: &str,
+    opts: &ArchiveOptions,
+) -> Result<Vec<ArchiveEntry>, String> {
+    extract_archive_with_progress(archive, dest, opts, &mut |_, _, _| {})
+}
+
+/// `extract_archive` reporting each entry to `progress` (the total is known
+/// for zip only). In dry-run mode nothing is written and the selected
+/// entries are returned as listed.
+pub fn extract_archive_with_progress(
+    archive: &str,
+    dest: &str,
+    opts: &ArchiveOptions,
+    progress: &mut ArchiveProgress,
+) -> Result<Vec<ArchiveEntry>, String> {
+    let archive = expand_vars(archive);
+    let dest = expand_vars(dest);
+    if crate::hosts::is_dry_run() {
+        let entries = list_archive(&archive, opts)?;
+        let what = format!(
+            "extract {} into {} ({} entries)",
+            archive,
+            dest,
+            entries.len()
+        );
+        crate::hosts::dry_run_skip(&what);
+        return Ok(entries);
+    }
+    let what = format!("extract {} into {}", archive, dest);
+    crate::hosts::traced(&what, || extract_into(&archive, &dest, opts, progress))
+}
+
+fn extract_into(
+    archive: &str,
+    dest: &str,
+    opts: &ArchiveOptions,
+    progress: &mut ArchiveProgress,
+) -> Result<Vec<ArchiveEntry>, String> {
+    let format = opts.resolve_format(archive)?;
+    let filter = opts.filter()?;
+    let dest = PathBuf::from(dest);
+    let fail = |e: io::Error| format!("Failed to extract '{}': {}", archive, e);
+    fs::create_dir_all(&dest).map_err(fail)?;
+    let file = File::open(archive).map_err(fail)?;
+
+    let mut extractor = Extractor {
+        dest,
+        opts,
+        dirs: Vec::new(),
+        written: Vec::new(),
+    };
+    match format {
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let input: Box<dyn Read> = match format {
+                ArchiveFormat::TarGz => {
+                    Box::new(deflate::GzDecoder::new(BufReader::new(file)).map_err(fail)?)
+                }
+                _ => Box::new(BufReader::new(file)),
+            };
+            let mut reader = tar::TarReader::new(input);
+            while let Some(entry) = reader.next_entry().map_err(fail)? {
+                if filter.selects(&entry) {
+                    extractor.extract(&entry, &mut reader.data())?;
+                    progress(&entry, extractor.written.len(), None);
+                }
+            }
+            // Drain to the end so a gzip trailer gets verified
+            io::copy(&mut reader.into_inner(), &mut io::sink()).map_err(fail)?;
+        }
+        ArchiveFormat::Zip => {
+            let mut reader = zip::ZipReader::new(BufReader::new(file)).map_err(fail)?;
+            let selected: Vec<usize> = (0..reader.len())
+                .filter(|&i| filter.selects(reader.entry(i)))
+                .collect();
+            for &i in &selected {
+                let entry = reader.entry(i).clone();
+                let mut data = reader.open(i).map_err(fail)?;
+                extractor.extract(&entry, &mut data)?;
+                progress(&entry, extractor.written.len(), Some(selected.len()));
+            }
+        }
+    }
+    extractor.finish()
+}
+
+struct Extractor<'a> {
+    dest: PathBuf,
+    opts: &'a ArchiveOptions,
+    /// Directory metadata is applied last so writing children doesn't undo it.
+    dirs: Vec<(PathBuf, u32, i64)>,
+    written: Vec<ArchiveEntry>,
+}
+
+impl Extractor<'_> {
+    fn extract(&mut self, entry: &ArchiveEntry, data: &mut dyn Read) -> Result<(), String> {
+        let Some(target) = safe_join(&self.dest, &entry.path)? else {
+            return Ok(());
+        };
+        let fail = |e: io::Error| format!("Failed to extract '{}': {}", entry.path, e);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(fail)?;
+        }
+        // Never write through whatever is already there if it's a link
+        if fs::symlink_metadata(&target).is_ok_and(|m| !m.is_dir() || entry.kind != EntryKind::Dir)
+        {
+            fs::remove_file(&target).map_err(fail)?;
+        }
+
+        match &entry.kind {
+            EntryKind::Dir => {
+                fs::create_dir_all(&target).map_err(fail)?;
+                self.dirs.push((target, entry.mode, entry.mtime));
+            }
+            EntryKind::File => {
+                let mut file = File::create(&target).map_err(fail)?;
+                io::copy(data, &mut file).map_err(fail)?;
+                if self.opts.preserve_permissions {
+                    file.set_permissions(fs::Permissions::from_mode(entry.mode & 0o777))
+                        .map_err(fail)?;
+                }
+                if self.opts.preserve_mtime {
+                    file.set_modified(system_time(entry.mtime)).map_err(fail)?;
+                }
+            }
+            EntryKind::Symlink(link) => {
+                check_link_target(&self.dest, &entry.path, link)?;
+                std::os::unix::fs::symlink(link, &target).map_err(fail)?;
+            }
+            EntryKind::Hardlink(link) => {
+                let Some(source) = safe_join(&self.dest, link)? else {
+                    return Err(format!(
+                        "Refusing to extract '{}': empty hard link target",
+                        entry.path
+                    ));
+                };
+                if fs::symlink_metadata(&source).is_ok_and(|m| m.file_type().is_symlink()) {
+                    return Err(format!(
+                        "Refusing to extract '{}': hard link to a symlink",
+                        entry.path
+                    ));
+                }
+                fs::hard_link(&source, &target).map_err(fail)?;
+            }
+        }
+        self.written.push(entry.clone());
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<ArchiveEntry>, String> {
+        // Deepest first, so a read-only parent is locked after its children
+        self.dirs
+            .sort_by_key(|(path, _, _)| std::cmp::Reverse(path.components().count()));
+        for (path, mode, mtime) in &self.dirs {
+            let fail =
+                |e: io::Error| format!("Failed to set metadata on '{}': {}", path.display(), e);
+            if self.opts.preserve_mtime {
+                File::open(path)
+                    .and_then(|dir| dir.set_modified(system_time(*mtime)))
+                    .map_err(fail)?;
+            }
+            if self.opts.preserve_permissions {
+                fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+                    .map_err(fail)?;
+            }
+        }
+        Ok(self.written)
+    }
+}
+
+fn system_time(mtime: i64) -> std::time::SystemTime {
+    if mtime >= 0 {
+        UNIX_EPOCH + Duration::from_secs(mtime as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(mtime.unsigned_abs())
+    }
+}
+
+/// Joins an entry path onto `dest`, refusing anything that could escape it:
+/// absolute paths, `..` components, and existing symlinks along the way.
+/// `None` for entries that name the destination itself (e.g. `./`).
+fn safe_join(dest: &Path, entry: &str) -> Result<Option<PathBuf>, String> {
+    let refuse = |why: &str| Err(format!("Refusing to extract '{}': {}", entry, why));
+    let mut target = dest.to_path_buf();
+    let mut parts = Vec::new();
+    for component in Path::new(entry).components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => return refuse("path contains '..'"),
+            Component::RootDir | Component::Prefix(_) => return refuse("absolute path"),
+        }
+    }
+    let Some((last, dirs)) = parts.split_last() else {
+        return Ok(None);
+    };
+    for part in dirs {
+        target.push(part);
+        if fs::symlink_metadata(&target).is_ok_and(|m| m.file_type().is_symlink()) {
+            return refuse("parent directory is a symlink");
+        }
+    }
+    target.push(last);
+    Ok(Some(target))
+}
+
+/// A symlink may only point at something inside the destination.
+fn check_link_target(dest: &Path, entry: &str, link: &str) -> Result<(), String> {
+    if Path::new(link).is_absolute() {
+        return Err(format!(
+            "Refusing to extract '{}': symlink to absolute path '{}'",
+            entry, link
+        ));
+    }
+    let mut depth: i64 = 0;
+    let parent = Path::new(entry).parent().unwrap_or(Path::new(""));
+    for component in parent.components().chain(Path::new(link).components()) {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::ParentDir => depth -= 1,
+            _ => {}
+        }
+        if depth < 0 {
+            return Err(format!(
+                "Refusing to extract '{}': symlink target '{}' leaves {}",
+                entry,
+                link,
+                dest.display()
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,308 @@
+//! Streaming deflate/inflate and gzip framing over `miniz_oxide`.
+
+use miniz_oxide::deflate::core::{create_comp_flags_from_zip_params, CompressorOxide};
+use miniz_oxide::inflate::stream::InflateState;
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+use std::io::{self, Read, Write};
+
+const BUF_SIZE: usize = 32 * 1024;
+
+// --- CRC-32 (IEEE, as used by gzip and zip) ---
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Crc32(u32);
+
+fn crc_table() -> &'static [u32; 256] {
+    static TABLE: std::sync::OnceLock<[u32; 256]> = std::sync::OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0u32; 256];
+        for (i, slot) in table.iter_mut().enumerate() {
+            let mut c = i as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 {
+                    0xEDB8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *slot = c;
+        }
+        table
+    })
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32(0xFFFF_FFFF)
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        let table = crc_table();
+        for &byte in data {
+            self.0 = table[((self.0 ^ byte as u32) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub(crate) fn value(&self) -> u32 {
+        !self.0
+    }
+}
+
+// --- Raw deflate ---
+
+/// Compresses everything written into raw deflate on `inner`, tracking the
+/// CRC-32 and length of the uncompressed data.
+pub(crate) struct Deflater<W: Write> {
+    inner: W,
+    compressor: Box<CompressorOxide>,
+    buf: Vec<u8>,
+    crc: Crc32,
+    count: u64,
+}
+
+impl<W: Write> Deflater<W> {
+    pub(crate) fn new(inner: W, level: u8) -> Self {
+        // Negative window bits select raw deflate (no zlib header)
+        let flags = create_comp_flags_from_zip_params(level as i32, -15, 0);
+        Deflater {
+            inner,
+            compressor: Box::new(CompressorOxide::new(flags)),
+            buf: vec![0; BUF_SIZE],
+            crc: Crc32::new(),
+            count: 0,
+        }
+    }
+
+    fn pump(&mut self, mut input: &[u8], flush: MZFlush) -> io::Result<()> {
+        loop {
+            let res = miniz_oxide::deflate::stream::deflate(
+                &mut self.compressor,
+                input,
+                &mut self.buf,
+                flush,
+            );
+            self.inner.write_all(&self.buf[..res.bytes_written])?;
+            input = &input[res.bytes_consumed..];
+            match res.status {
+                Ok(MZStatus::StreamEnd) => return Ok(()),
+                Ok(_) if flush != MZFlush::Finish && input.is_empty() => return Ok(()),
+                Ok(_) => {}
+                Err(MZError::Buf) if flush != MZFlush::Finish => return Ok(()),
+                Err(e) => return Err(io::Error::other(format!("deflate failed: {:?}", e))),
+            }
+        }
+    }
+
+    /// Flushes the final block; returns the inner writer, CRC-32 and size.
+    pub(crate) fn finish(mut self) -> io::Result<(W, u32, u64)> {
+        self.pump(&[], MZFlush::Finish)?;
+        Ok((self.inner, self.crc.value(), self.count))
+    }
+}
+
+impl<W: Write> Write for Deflater<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.crc.update(data);
+        self.count += data.len() as u64;
+        self.pump(data, MZFlush::None)?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decompresses raw deflate from `inner`, tracking CRC-32 and size of the
+/// output. Stops at the end of the deflate stream.
+pub(crate) struct Inflater<R: Read> {
+    inner: R,
+    state: Box<InflateState>,
+    input: Vec<u8>,
+    pos: usize,
+    len: usize,
+    eof: bool,
+    done: bool,
+    crc: Crc32,
+    count: u64,
+}
+
+impl<R: Read> Inflater<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Inflater {
+            inner,
+            state: InflateState::new_boxed(DataFormat::Raw),
+            input: vec![0; BUF_SIZE],
+            pos: 0,
+            len: 0,
+            eof: false,
+            done: false,
+            crc: Crc32::new(),
+            count: 0,
+        }
+    }
+
+    pub(crate) fn crc(&self) -> u32 {
+        self.crc.value()
+    }
+
+    pub(crate) fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Reads exactly `buf.len()` bytes that follow the deflate stream
+    /// (trailers), using buffered input first.
+    fn read_trailer(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let buffered = (self.len - self.pos).min(buf.len());
+        buf[..buffered].copy_from_slice(&self.input[self.pos..self.pos + buffered]);
+        self.pos += buffered;
+        self.inner.read_exact(&mut buf[buffered..])
+    }
+}
+
+impl<R: Read> Read for Inflater<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.done || out.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.pos == self.len && !self.eof {
+                self.len = self.inner.read(&mut self.input)?;
+                self.pos = 0;
+                self.eof = self.len == 0;
+            }
+            let res = miniz_oxide::inflate::stream::inflate(
+                &mut self.state,
+                &self.input[self.pos..self.len],
+                out,
+                MZFlush::None,
+            );
+            self.pos += res.bytes_consumed;
+            let written = res.bytes_written;
+            self.crc.update(&out[..written]);
+            self.count += written as u64;
+            match res.status {
+                Ok(MZStatus::StreamEnd) => {
+                    self.done = true;
+                    return Ok(written);
+                }
+                Ok(_) | Err(MZError::Buf) => {
+                    if written > 0 {
+                        return Ok(written);
+                    }
+                    if self.eof && res.bytes_consumed == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "truncated deflate stream",
+                        ));
+                    }
+                }
+                Err(e) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("corrupt deflate stream: {:?}", e),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+// --- gzip ---
+
+/// gzip writer: header, raw deflate body, CRC/size trailer on `finish`.
+pub(crate) struct GzEncoder<W: Write> {
+    deflater: Deflater<W>,
+}
+
+impl<W: Write> GzEncoder<W> {
+    pub(crate) fn new(mut inner: W, level: u8) -> io::Result<Self> {
+        // magic, CM=deflate, no flags, mtime 0, XFL 0, OS=unix
+        inner.write_all(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3])?;
+        Ok(GzEncoder {
+            deflater: Deflater::new(inner, level),
+        })
+    }
+
+    pub(crate) fn finish(self) -> io::Result<W> {
+        let (mut inner, crc, count) = self.deflater.finish()?;
+        inner.write_all(&crc.to_le_bytes())?;
+        inner.write_all(&(count as u32).to_le_bytes())?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for GzEncoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.deflater.write(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.deflater.flush()
+    }
+}
+
+/// gzip reader: validates the header and the CRC/size trailer.
+pub(crate) struct GzDecoder<R: Read> {
+    inflater: Inflater<R>,
+    checked: bool,
+}
+
+impl<R: Read> GzDecoder<R> {
+    pub(crate) fn new(mut inner: R) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut header = [0u8; 10];
+        inner.read_exact(&mut header)?;
+        if header[0..2] != [0x1f, 0x8b] || header[2] != 8 {
+            return Err(invalid("not a gzip file"));
+        }
+        let flags = header[3];
+        if flags & 0x04 != 0 {
+            let mut len = [0u8; 2];
+            inner.read_exact(&mut len)?;
+            io::copy(
+                &mut (&mut inner).take(u16::from_le_bytes(len) as u64),
+                &mut io::sink(),
+            )?;
+        }
+        for flag in [0x08, 0x10] {
+            // zero-terminated file name / comment
+            if flags & flag != 0 {
+                let mut byte = [0u8; 1];
+                loop {
+                    inner.read_exact(&mut byte)?;
+                    if byte[0] == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        if flags & 0x02 != 0 {
+            inner.read_exact(&mut [0u8; 2])?;
+        }
+        Ok(GzDecoder {
+            inflater: Inflater::new(inner),
+            checked: false,
+        })
+    }
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let n = self.inflater.read(out)?;
+        if n == 0 && !out.is_empty() && !self.checked {
+            self.checked = true;
+            let mut trailer = [0u8; 8];
+            self.inflater.read_trailer(&mut trailer)?;
+            let crc = u32::from_le_bytes(trailer[0..4].try_into().unwrap());
+            let size = u32::from_le_bytes(trailer[4..8].try_into().unwrap());
+            if crc != self.inflater.crc() || size != self.inflater.count() as u32 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "gzip checksum mismatch",
+                ));
+            }
+        }
+        Ok(n)
+    }
+}
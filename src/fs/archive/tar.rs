@@ -0,0 +1,416 @@
+//! ustar reader/writer with GNU long-name and pax path support.
+
+use super::{ArchiveEntry, EntryKind};
+use std::io::{self, Read, Write};
+
+const BLOCK: usize = 512;
+/// Archives are padded to a multiple of the classic 20-block record.
+const RECORD: u64 = 20 * BLOCK as u64;
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+// --- Writer ---
+
+pub(crate) struct TarWriter<W: Write> {
+    out: W,
+    written: u64,
+}
+
+impl<W: Write> TarWriter<W> {
+    pub(crate) fn new(out: W) -> Self {
+        TarWriter { out, written: 0 }
+    }
+
+    fn write_block(&mut self, block: &[u8; BLOCK]) -> io::Result<()> {
+        self.out.write_all(block)?;
+        self.written += BLOCK as u64;
+        Ok(())
+    }
+
+    fn pad(&mut self, size: u64) -> io::Result<()> {
+        let rem = (size % BLOCK as u64) as usize;
+        if rem != 0 {
+            self.out.write_all(&[0u8; BLOCK][..BLOCK - rem])?;
+            self.written += (BLOCK - rem) as u64;
+        }
+        Ok(())
+    }
+
+    /// GNU `././@LongLink` record carrying a name that does not fit.
+    fn long_name(&mut self, kind: u8, name: &str) -> io::Result<()> {
+        let data = [name.as_bytes(), b"\0"].concat();
+        let mut header = [0u8; BLOCK];
+        put_str(&mut header[0..100], "././@LongLink");
+        put_octal(&mut header[100..108], 0o644);
+        put_octal(&mut header[108..116], 0);
+        put_octal(&mut header[116..124], 0);
+        put_octal(&mut header[124..136], data.len() as u64);
+        put_octal(&mut header[136..148], 0);
+        header[156] = kind;
+        finish_header(&mut header);
+        self.write_block(&header)?;
+        self.out.write_all(&data)?;
+        self.written += data.len() as u64;
+        self.pad(data.len() as u64)
+    }
+
+    /// Appends one entry; `data` must yield exactly `entry.size` bytes for files.
+    pub(crate) fn append(
+        &mut self,
+        entry: &ArchiveEntry,
+        data: Option<&mut dyn Read>,
+    ) -> io::Result<()> {
+        let mut name = entry.path.clone();
+        if entry.kind == EntryKind::Dir && !name.ends_with('/') {
+            name.push('/');
+        }
+        let (typeflag, link, size) = match &entry.kind {
+            EntryKind::File => (b'0', "", entry.size),
+            EntryKind::Dir => (b'5', "", 0),
+            EntryKind::Symlink(target) => (b'2', target.as_str(), 0),
+            EntryKind::Hardlink(target) => (b'1', target.as_str(), 0),
+        };
+
+        let mut header = [0u8; BLOCK];
+        match split_ustar_name(&name) {
+            Some((prefix, short)) => {
+                put_str(&mut header[0..100], short);
+                put_str(&mut header[345..500], prefix);
+            }
+            None => {
+                self.long_name(b'L', &name)?;
+                put_str(&mut header[0..100], truncate(&name, 100));
+            }
+        }
+        if link.len() > 100 {
+            self.long_name(b'K', link)?;
+        }
+        put_str(&mut header[157..257], truncate(link, 100));
+        put_octal(&mut header[100..108], (entry.mode & 0o7777) as u64);
+        put_octal(&mut header[108..116], 0);
+        put_octal(&mut header[116..124], 0);
+        put_number(&mut header[124..136], size);
+        put_octal(&mut header[136..148], entry.mtime.max(0) as u64);
+        header[156] = typeflag;
+        finish_header(&mut header);
+        self.write_block(&header)?;
+
+        if let (EntryKind::File, Some(data)) = (&entry.kind, data) {
+            let copied = io::copy(&mut data.take(size), &mut self.out)?;
+            if copied != size {
+                return Err(invalid(format!(
+                    "'{}' changed size while archiving",
+                    entry.path
+                )));
+            }
+            self.written += size;
+            self.pad(size)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the end-of-archive marker and record padding.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.write_block(&[0u8; BLOCK])?;
+        self.write_block(&[0u8; BLOCK])?;
+        let rem = self.written % RECORD;
+        if rem != 0 {
+            io::copy(&mut io::repeat(0).take(RECORD - rem), &mut self.out)?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Splits `name` into ustar (prefix, name) fields, or `None` if it needs a
+/// long-name record.
+fn split_ustar_name(name: &str) -> Option<(&str, &str)> {
+    if name.len() <= 100 {
+        return Some(("", name));
+    }
+    // Split at a '/' so prefix <= 155 and the rest <= 100 (ignore a trailing '/')
+    let trimmed = name.trim_end_matches('/');
+    trimmed
+        .match_indices('/')
+        .map(|(i, _)| i)
+        .find(|&i| i <= 155 && name.len() - i - 1 <= 100)
+        .map(|i| (&name[..i], &name[i + 1..]))
+}
+
+fn truncate(s: &str, max: usize) -> &str {
+    let mut end = max.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+fn put_str(field: &mut [u8], value: &str) {
+    let bytes = value.as_bytes();
+    let n = bytes.len().min(field.len());
+    field[..n].copy_from_slice(&bytes[..n]);
+}
+
+fn put_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    put_str(field, &digits);
+    field[field.len() - 1] = 0;
+}
+
+/// Octal when it fits, otherwise GNU base-256 (sizes over 8 GiB).
+fn put_number(field: &mut [u8], value: u64) {
+    if value < 1u64 << (3 * (field.len() - 1)) {
+        put_octal(field, value);
+    } else {
+        field.fill(0);
+        field[0] = 0x80;
+        let bytes = value.to_be_bytes();
+        let n = field.len();
+        field[n - 8..].copy_from_slice(&bytes);
+    }
+}
+
+fn finish_header(header: &mut [u8; BLOCK]) {
+    put_str(&mut header[257..263], "ustar\0");
+    put_str(&mut header[263..265], "00");
+    header[148..156].fill(b' ');
+    let sum: u32 = header.iter().map(|&b| b as u32).sum();
+    put_str(&mut header[148..156], &format!("{:06o}\0 ", sum));
+}
+
+// --- Reader ---
+
+pub(crate) struct TarReader<R: Read> {
+    input: R,
+    /// Unread bytes of the current entry's data, plus its block padding.
+    remaining: u64,
+    padding: u64,
+}
+
+impl<R: Read> TarReader<R> {
+    pub(crate) fn new(input: R) -> Self {
+        TarReader {
+            input,
+            remaining: 0,
+            padding: 0,
+        }
+    }
+
+    fn skip(&mut self, n: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.input).take(n), &mut io::sink())?;
+        if skipped != n {
+            return Err(invalid("truncated tar archive"));
+        }
+        Ok(())
+    }
+
+    fn read_data(&mut self, size: u64) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        (&mut self.input).take(size).read_to_end(&mut data)?;
+        if data.len() as u64 != size {
+            return Err(invalid("truncated tar archive"));
+        }
+        self.skip(padding_for(size))?;
+        Ok(data)
+    }
+
+    /// Advances to the next entry, skipping unread data of the previous one.
+    /// Returns `None` at the end-of-archive marker (or a clean EOF).
+    pub(crate) fn next_entry(&mut self) -> io::Result<Option<ArchiveEntry>> {
+        self.skip(self.remaining + self.padding)?;
+        self.remaining = 0;
+        self.padding = 0;
+
+        let mut long_path: Option<String> = None;
+        let mut long_link: Option<String> = None;
+        let mut pax_mtime: Option<i64> = None;
+        let mut pax_size: Option<u64> = None;
+
+        loop {
+            let mut header = [0u8; BLOCK];
+            if !read_block(&mut self.input, &mut header)? || header.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+            verify_checksum(&header)?;
+            let typeflag = header[156];
+            let size = pax_size.unwrap_or(parse_number(&header[124..136])?);
+
+            match typeflag {
+                b'L' => {
+                    long_path = Some(c_string(&self.read_data(size)?));
+                    continue;
+                }
+                b'K' => {
+                    long_link = Some(c_string(&self.read_data(size)?));
+                    continue;
+                }
+                b'x' => {
+                    for (key, value) in parse_pax(&self.read_data(size)?) {
+                        match key.as_str() {
+                            "path" => long_path = Some(value),
+                            "linkpath" => long_link = Some(value),
+                            "mtime" => {
+                                pax_mtime = value.split('.').next().and_then(|s| s.parse().ok())
+                            }
+                            "size" => pax_size = value.parse().ok(),
+                            _ => {}
+                        }
+                    }
+                    continue;
+                }
+                b'g' => {
+                    self.read_data(size)?;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let path = long_path.take().unwrap_or_else(|| {
+                let name = c_string(&header[0..100]);
+                let prefix = c_string(&header[345..500]);
+                if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                    format!("{}/{}", prefix, name)
+                } else {
+                    name
+                }
+            });
+            let link = long_link
+                .take()
+                .unwrap_or_else(|| c_string(&header[157..257]));
+            let kind = match typeflag {
+                b'0' | b'\0' | b'7' if !path.ends_with('/') => EntryKind::File,
+                b'0' | b'\0' | b'7' | b'5' => EntryKind::Dir,
+                b'2' => EntryKind::Symlink(link),
+                b'1' => EntryKind::Hardlink(link),
+                // Devices, fifos and unknown types: skip the data and move on
+                _ => {
+                    self.skip(size + padding_for(size))?;
+                    pax_size = None;
+                    pax_mtime = None;
+                    continue;
+                }
+            };
+            let size = if kind == EntryKind::File { size } else { 0 };
+            self.remaining = size;
+            self.padding = padding_for(size);
+            return Ok(Some(ArchiveEntry {
+                path: path.trim_end_matches('/').to_string(),
+                kind,
+                size,
+                mode: parse_number(&header[100..108])? as u32 & 0o7777,
+                mtime: pax_mtime.unwrap_or(parse_number(&header[136..148])? as i64),
+            }));
+        }
+    }
+
+    /// Reader over the current entry's data.
+    pub(crate) fn data(&mut self) -> impl Read + '_ {
+        EntryData { reader: self }
+    }
+
+    /// Consumes the reader, returning the underlying input.
+    pub(crate) fn into_inner(self) -> R {
+        self.input
+    }
+}
+
+struct EntryData<'a, R: Read> {
+    reader: &'a mut TarReader<R>,
+}
+
+impl<R: Read> Read for EntryData<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = (buf.len() as u64).min(self.reader.remaining) as usize;
+        if max == 0 {
+            return Ok(0);
+        }
+        let n = self.reader.input.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(invalid("truncated tar archive"));
+        }
+        self.reader.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+fn padding_for(size: u64) -> u64 {
+    (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64
+}
+
+/// Fills `block`; `false` on a clean EOF before any byte.
+fn read_block(input: &mut impl Read, block: &mut [u8; BLOCK]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < BLOCK {
+        match input.read(&mut block[filled..])? {
+            0 if filled == 0 => return Ok(false),
+            0 => return Err(invalid("truncated tar header")),
+            n => filled += n,
+        }
+    }
+    Ok(true)
+}
+
+fn verify_checksum(header: &[u8; BLOCK]) -> io::Result<()> {
+    let stored = parse_number(&header[148..156])? as u32;
+    let sum: u32 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (148..156).contains(&i) {
+                b' ' as u32
+            } else {
+                b as u32
+            }
+        })
+        .sum();
+    if sum != stored {
+        return Err(invalid("tar header checksum mismatch (not a tar archive?)"));
+    }
+    Ok(())
+}
+
+fn parse_number(field: &[u8]) -> io::Result<u64> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        let mut value = (field[0] & 0x7f) as u64;
+        for &b in &field[1..] {
+            value = (value << 8) | b as u64;
+        }
+        return Ok(value);
+    }
+    let text = String::from_utf8_lossy(field);
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| invalid(format!("bad tar number field '{}'", text)))
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// pax records: `"<len> <key>=<value>\n"`.
+fn parse_pax(data: &[u8]) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|&b| b == b' ') {
+        let Some(len) = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+        else {
+            break;
+        };
+        if len <= space || len > rest.len() {
+            break;
+        }
+        let record = String::from_utf8_lossy(&rest[space + 1..len]);
+        if let Some((key, value)) = record.trim_end_matches('\n').split_once('=') {
+            out.push((key.to_string(), value.to_string()));
+        }
+        rest = &rest[len..];
+    }
+    out
+}
@@ -0,0 +1,488 @@
+//! zip reader/writer (stored + deflate, unix modes, extended timestamps).
+
+use super::deflate::{Crc32, Deflater, Inflater};
+use super::{ArchiveEntry, EntryKind};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+const LOCAL_SIG: u32 = 0x0403_4b50;
+const CENTRAL_SIG: u32 = 0x0201_4b50;
+const END_SIG: u32 = 0x0605_4b50;
+const DESCRIPTOR_SIG: u32 = 0x0807_4b50;
+
+/// Extended timestamp extra field (mtime only).
+const EXT_TIME_ID: u16 = 0x5455;
+/// General purpose flags: data descriptor follows, names are UTF-8.
+const FLAG_DESCRIPTOR: u16 = 0x0008;
+const FLAG_UTF8: u16 = 0x0800;
+/// Made by: unix, spec 2.0.
+const MADE_BY: u16 = (3 << 8) | 20;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn too_large() -> io::Error {
+    invalid("archive exceeds 4 GiB (zip64 is not supported)")
+}
+
+// --- Writer ---
+
+struct CentralRecord {
+    name: String,
+    flags: u16,
+    method: u16,
+    dos_time: (u16, u16),
+    crc: u32,
+    compressed: u32,
+    size: u32,
+    mode: u32,
+    mtime: i64,
+    offset: u32,
+}
+
+struct Counting<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for Counting<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(data)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub(crate) struct ZipWriter<W: Write> {
+    out: Counting<W>,
+    records: Vec<CentralRecord>,
+    level: u8,
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub(crate) fn new(out: W, level: u8) -> Self {
+        ZipWriter {
+            out: Counting {
+                inner: out,
+                count: 0,
+            },
+            records: Vec::new(),
+            level,
+        }
+    }
+
+    fn offset(&self) -> io::Result<u32> {
+        u32::try_from(self.out.count).map_err(|_| too_large())
+    }
+
+    /// Appends one entry. Files are deflated and followed by a data
+    /// descriptor; directories and symlinks are stored.
+    pub(crate) fn append(
+        &mut self,
+        entry: &ArchiveEntry,
+        data: Option<&mut dyn Read>,
+    ) -> io::Result<()> {
+        let offset = self.offset()?;
+        let dos_time = to_dos_time(entry.mtime);
+        let (name, mode, stored) = match &entry.kind {
+            EntryKind::Dir => (
+                format!("{}/", entry.path.trim_end_matches('/')),
+                S_IFDIR,
+                Vec::new(),
+            ),
+            EntryKind::Symlink(target) => (entry.path.clone(), S_IFLNK, target.as_bytes().to_vec()),
+            EntryKind::Hardlink(_) => {
+                return Err(invalid(format!(
+                    "'{}': zip cannot store hard links",
+                    entry.path
+                )))
+            }
+            EntryKind::File => (entry.path.clone(), S_IFREG, Vec::new()),
+        };
+        let mode = mode | (entry.mode & 0o7777);
+        let mut record = CentralRecord {
+            name,
+            flags: FLAG_UTF8,
+            method: 0,
+            dos_time,
+            crc: 0,
+            compressed: 0,
+            size: 0,
+            mode,
+            mtime: entry.mtime,
+            offset,
+        };
+
+        if entry.kind == EntryKind::File {
+            record.flags |= FLAG_DESCRIPTOR;
+            record.method = 8;
+            write_local_header(&mut self.out, &record)?;
+            let start = self.out.count;
+            let mut deflater = Deflater::new(&mut self.out, self.level);
+            if let Some(data) = data {
+                io::copy(data, &mut deflater)?;
+            }
+            let (_, crc, size) = deflater.finish()?;
+            let compressed = self.out.count - start;
+            record.crc = crc;
+            record.size = u32::try_from(size).map_err(|_| too_large())?;
+            record.compressed = u32::try_from(compressed).map_err(|_| too_large())?;
+            let mut descriptor = Vec::with_capacity(16);
+            put_u32(&mut descriptor, DESCRIPTOR_SIG);
+            put_u32(&mut descriptor, record.crc);
+            put_u32(&mut descriptor, record.compressed);
+            put_u32(&mut descriptor, record.size);
+            self.out.write_all(&descriptor)?;
+        } else {
+            let mut crc = Crc32::new();
+            crc.update(&stored);
+            record.crc = crc.value();
+            record.size = stored.len() as u32;
+            record.compressed = stored.len() as u32;
+            write_local_header(&mut self.out, &record)?;
+            self.out.write_all(&stored)?;
+        }
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Writes the central directory and end record.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        let start = self.offset()?;
+        for record in &self.records {
+            let extra = ext_time_extra(record.mtime);
+            let mut buf = Vec::with_capacity(46 + record.name.len() + extra.len());
+            put_u32(&mut buf, CENTRAL_SIG);
+            put_u16(&mut buf, MADE_BY);
+            put_u16(&mut buf, 20);
+            put_u16(&mut buf, record.flags);
+            put_u16(&mut buf, record.method);
+            put_u16(&mut buf, record.dos_time.0);
+            put_u16(&mut buf, record.dos_time.1);
+            put_u32(&mut buf, record.crc);
+            put_u32(&mut buf, record.compressed);
+            put_u32(&mut buf, record.size);
+            put_u16(&mut buf, record.name.len() as u16);
+            put_u16(&mut buf, extra.len() as u16);
+            put_u16(&mut buf, 0); // comment
+            put_u16(&mut buf, 0); // disk
+            put_u16(&mut buf, 0); // internal attrs
+            put_u32(&mut buf, record.mode << 16);
+            put_u32(&mut buf, record.offset);
+            buf.extend_from_slice(record.name.as_bytes());
+            buf.extend_from_slice(&extra);
+            self.out.write_all(&buf)?;
+        }
+        let end = self.offset()?;
+        let count = u16::try_from(self.records.len())
+            .map_err(|_| invalid("too many entries for zip (zip64 is not supported)"))?;
+        let mut buf = Vec::with_capacity(22);
+        put_u32(&mut buf, END_SIG);
+        put_u16(&mut buf, 0);
+        put_u16(&mut buf, 0);
+        put_u16(&mut buf, count);
+        put_u16(&mut buf, count);
+        put_u32(&mut buf, end - start);
+        put_u32(&mut buf, start);
+        put_u16(&mut buf, 0);
+        self.out.write_all(&buf)?;
+        self.out.flush()?;
+        Ok(self.out.inner)
+    }
+}
+
+fn write_local_header(out: &mut impl Write, record: &CentralRecord) -> io::Result<()> {
+    let extra = ext_time_extra(record.mtime);
+    let mut buf = Vec::with_capacity(30 + record.name.len() + extra.len());
+    put_u32(&mut buf, LOCAL_SIG);
+    put_u16(&mut buf, 20);
+    put_u16(&mut buf, record.flags);
+    put_u16(&mut buf, record.method);
+    put_u16(&mut buf, record.dos_time.0);
+    put_u16(&mut buf, record.dos_time.1);
+    // With a data descriptor these are filled in after the data
+    put_u32(&mut buf, record.crc);
+    put_u32(&mut buf, record.compressed);
+    put_u32(&mut buf, record.size);
+    put_u16(&mut buf, record.name.len() as u16);
+    put_u16(&mut buf, extra.len() as u16);
+    buf.extend_from_slice(record.name.as_bytes());
+    buf.extend_from_slice(&extra);
+    out.write_all(&buf)
+}
+
+fn ext_time_extra(mtime: i64) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(9);
+    put_u16(&mut buf, EXT_TIME_ID);
+    put_u16(&mut buf, 5);
+    buf.push(1); // mtime present
+    buf.extend_from_slice(&(mtime.clamp(i32::MIN as i64, i32::MAX as i64) as i32).to_le_bytes());
+    buf
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+/// (time, date) in MS-DOS format; clamped to the 1980..=2107 range.
+fn to_dos_time(mtime: i64) -> (u16, u16) {
+    let Some(dt) = DateTime::from_timestamp(mtime, 0) else {
+        return (0, (1 << 5) | 1);
+    };
+    if dt.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let year = (dt.year() - 1980).min(127) as u16;
+    let time = ((dt.hour() as u16) << 11) | ((dt.minute() as u16) << 5) | (dt.second() as u16 / 2);
+    let date = (year << 9) | ((dt.month() as u16) << 5) | dt.day() as u16;
+    (time, date)
+}
+
+fn from_dos_time(time: u16, date: u16) -> i64 {
+    NaiveDate::from_ymd_opt(
+        1980 + (date >> 9) as i32,
+        ((date >> 5) & 0xf) as u32,
+        (date & 0x1f) as u32,
+    )
+    .and_then(|d| {
+        d.and_hms_opt(
+            (time >> 11) as u32,
+            ((time >> 5) & 0x3f) as u32,
+            ((time & 0x1f) * 2) as u32,
+        )
+    })
+    .map(|dt| dt.and_utc().timestamp())
+    .unwrap_or(0)
+}
+
+// --- Reader ---
+
+pub(crate) struct ZipEntry {
+    pub(crate) entry: ArchiveEntry,
+    method: u16,
+    crc: u32,
+    compressed: u64,
+    offset: u64,
+}
+
+pub(crate) struct ZipReader<R: Read + Seek> {
+    input: R,
+    entries: Vec<ZipEntry>,
+}
+
+fn u16_at(buf: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([buf[at], buf[at + 1]])
+}
+
+fn u32_at(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+impl<R: Read + Seek> ZipReader<R> {
+    /// Reads the central directory.
+    pub(crate) fn new(mut input: R) -> io::Result<Self> {
+        let len = input.seek(SeekFrom::End(0))?;
+        let tail_len = len.min(22 + 0xFFFF);
+        input.seek(SeekFrom::Start(len - tail_len))?;
+        let mut tail = vec![0u8; tail_len as usize];
+        input.read_exact(&mut tail)?;
+        let end = (0..tail.len().saturating_sub(21))
+            .rev()
+            .find(|&i| u32_at(&tail, i) == END_SIG)
+            .ok_or_else(|| invalid("zip end record not found (not a zip archive?)"))?;
+        let count = u16_at(&tail, end + 10) as usize;
+        let cd_size = u32_at(&tail, end + 12) as usize;
+        let cd_offset = u32_at(&tail, end + 16) as u64;
+        if count == 0xFFFF || cd_offset == 0xFFFF_FFFF {
+            return Err(invalid("zip64 archives are not supported"));
+        }
+
+        input.seek(SeekFrom::Start(cd_offset))?;
+        let mut cd = vec![0u8; cd_size];
+        input.read_exact(&mut cd)?;
+        let mut entries = Vec::with_capacity(count);
+        let mut at = 0;
+        for _ in 0..count {
+            if at + 46 > cd.len() || u32_at(&cd, at) != CENTRAL_SIG {
+                return Err(invalid("corrupt zip central directory"));
+            }
+            let made_by = u16_at(&cd, at + 4);
+            let method = u16_at(&cd, at + 10);
+            let (time, date) = (u16_at(&cd, at + 12), u16_at(&cd, at + 14));
+            let crc = u32_at(&cd, at + 16);
+            let compressed = u32_at(&cd, at + 20) as u64;
+            let size = u32_at(&cd, at + 24) as u64;
+            let name_len = u16_at(&cd, at + 28) as usize;
+            let extra_len = u16_at(&cd, at + 30) as usize;
+            let comment_len = u16_at(&cd, at + 32) as usize;
+            let external = u32_at(&cd, at + 38);
+            let offset = u32_at(&cd, at + 42) as u64;
+            let name_end = at + 46 + name_len;
+            if name_end + extra_len > cd.len() {
+                return Err(invalid("corrupt zip central directory"));
+            }
+            let name = String::from_utf8_lossy(&cd[at + 46..name_end]).into_owned();
+            let extra = &cd[name_end..name_end + extra_len];
+            at = name_end + extra_len + comment_len;
+
+            let unix_mode = if made_by >> 8 == 3 { external >> 16 } else { 0 };
+            let is_dir = name.ends_with('/') || unix_mode & S_IFMT == S_IFDIR;
+            let is_link = unix_mode & S_IFMT == S_IFLNK;
+            let mode = match unix_mode & 0o7777 {
+                0 if is_dir => 0o755,
+                0 => 0o644,
+                bits => bits,
+            };
+            let mtime = ext_time(extra).unwrap_or_else(|| from_dos_time(time, date));
+            let kind = if is_dir {
+                EntryKind::Dir
+            } else if is_link {
+                // The link target is the entry's data; read it below
+                EntryKind::Symlink(String::new())
+            } else {
+                EntryKind::File
+            };
+            entries.push(ZipEntry {
+                entry: ArchiveEntry {
+                    path: name.trim_end_matches('/').to_string(),
+                    size: if kind == EntryKind::File { size } else { 0 },
+                    kind,
+                    mode,
+                    mtime,
+                },
+                method,
+                crc,
+                compressed,
+                offset,
+            });
+        }
+
+        let mut reader = ZipReader { input, entries };
+        for i in 0..reader.entries.len() {
+            if matches!(reader.entries[i].entry.kind, EntryKind::Symlink(_)) {
+                let mut target = String::new();
+                reader.open(i)?.read_to_string(&mut target)?;
+                reader.entries[i].entry.kind = EntryKind::Symlink(target);
+            }
+        }
+        Ok(reader)
+    }
+
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &ArchiveEntry> {
+        self.entries.iter().map(|e| &e.entry)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn entry(&self, index: usize) -> &ArchiveEntry {
+        &self.entries[index].entry
+    }
+
+    /// Reader over entry `index`'s decompressed data; the CRC is checked
+    /// when it is read to the end.
+    pub(crate) fn open(&mut self, index: usize) -> io::Result<Box<dyn Read + '_>> {
+        let (offset, method, compressed, crc) = {
+            let e = &self.entries[index];
+            (e.offset, e.method, e.compressed, e.crc)
+        };
+        self.input.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 30];
+        self.input.read_exact(&mut header)?;
+        if u32_at(&header, 0) != LOCAL_SIG {
+            return Err(invalid("corrupt zip local header"));
+        }
+        let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+        self.input.seek(SeekFrom::Current(skip))?;
+        let raw = (&mut self.input).take(compressed);
+        match method {
+            0 => Ok(Box::new(Checked {
+                inner: Stored {
+                    inner: raw,
+                    crc: Crc32::new(),
+                },
+                expected: crc,
+            })),
+            8 => Ok(Box::new(Checked {
+                inner: Inflater::new(raw),
+                expected: crc,
+            })),
+            other => Err(invalid(format!(
+                "unsupported zip compression method {}",
+                other
+            ))),
+        }
+    }
+}
+
+fn ext_time(mut extra: &[u8]) -> Option<i64> {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let len = u16_at(extra, 2) as usize;
+        let body = extra.get(4..4 + len)?;
+        if id == EXT_TIME_ID && len >= 5 && body[0] & 1 != 0 {
+            return Some(i32::from_le_bytes([body[1], body[2], body[3], body[4]]) as i64);
+        }
+        extra = &extra[4 + len..];
+    }
+    None
+}
+
+trait Crc {
+    fn crc(&self) -> u32;
+}
+
+impl<R: Read> Crc for Inflater<R> {
+    fn crc(&self) -> u32 {
+        Inflater::crc(self)
+    }
+}
+
+struct Stored<R: Read> {
+    inner: R,
+    crc: Crc32,
+}
+
+impl<R: Read> Read for Stored<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<R: Read> Crc for Stored<R> {
+    fn crc(&self) -> u32 {
+        self.crc.value()
+    }
+}
+
+struct Checked<R: Read + Crc> {
+    inner: R,
+    expected: u32,
+}
+
+impl<R: Read + Crc> Read for Checked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 && !buf.is_empty() && self.inner.crc() != self.expected {
+            return Err(invalid("zip entry checksum mismatch"));
+        }
+        Ok(n)
+    }
+}
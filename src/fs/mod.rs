@@ -1,4 +1,8 @@
 // src/fs/mod.rs — Orchestrator only (MODULE_SPEC)
 pub mod utils;
 pub use utils::*;
+pub mod transaction;
+pub use transaction::*;
 pub mod macros;
+#[cfg(feature = "archive")]
+pub mod archive;
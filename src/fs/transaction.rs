@@ -0,0 +1,637 @@
+//! Transactional file operations
+//!
+//! A `Transaction` stages file writes, sentinel blocks, copies, moves, chmods
+//! and deletes, then `commit` applies them in order. Files are replaced via a
+//! temp file and a rename, anything overwritten or deleted is kept in a
+//! backup directory, and every step is recorded in a manifest that is saved
+//! after each operation. If a step fails, the steps before it are undone.
+//! The manifest can be replayed in reverse later to uninstall.
+//!
+//! ```ignore
+//! use rsb::fs::Transaction;
+//!
+//! Transaction::new("mytool")
+//!     .write("$XDG_ETC_HOME/mytool.conf", "port=8080\n")
+//!     .cp("target/release/mytool", "$XDG_BIN_HOME/mytool")
+//!     .chmod("$XDG_BIN_HOME/mytool", "755")
+//!     .insert_block("$HOME/.bashrc", "mytool", "export PATH=\"$XDG_BIN_HOME:$PATH\"")
+//!     .commit()?;
+//!
+//! // Later
+//! Transaction::new("mytool").uninstall()?;
+//! ```
+//!
+//! State lives in `$RSB_DATA_HOME/transactions/<name>` (`manifest.json` plus
+//! `backup/`) unless `state_dir` says otherwise. Committing again under the
+//! same name appends to the manifest, so an uninstall unwinds every commit.
+//! Paths are expanded with `$VARS` when staged. Dry-run and trace modes
+//! (`hosts::effects`) apply to both commit and uninstall.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::global::expand_vars;
+use crate::hosts::{dry_run_skip, traced};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const BACKUP_DIR: &str = "backup";
+
+/// One staged operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileOp {
+    Write {
+        path: String,
+        content: String,
+    },
+    /// Adds or replaces the lines between `# >>> id >>>` and `# <<< id <<<`
+    InsertBlock {
+        path: String,
+        id: String,
+        content: String,
+    },
+    Copy {
+        src: String,
+        dest: String,
+    },
+    Move {
+        src: String,
+        dest: String,
+    },
+    Chmod {
+        path: String,
+        mode: u32,
+    },
+    /// Deletes a file or directory; a missing path is not an error
+    Remove {
+        path: String,
+    },
+}
+
+impl FileOp {
+    /// Shell-like description (`cp a b`, `write x (12 bytes)`).
+    pub fn describe(&self) -> String {
+        match self {
+            FileOp::Write { path, content } => format!("write {} ({} bytes)", path, content.len()),
+            FileOp::InsertBlock { path, id, .. } => format!("insert block '{}' into {}", id, path),
+            FileOp::Copy { src, dest } => format!("cp {} {}", src, dest),
+            FileOp::Move { src, dest } => format!("mv {} {}", src, dest),
+            FileOp::Chmod { path, mode } => format!("chmod {:03o} {}", mode, path),
+            FileOp::Remove { path } => format!("rm -rf {}", path),
+        }
+    }
+}
+
+/// A step `commit` took, with what it takes to undo it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ManifestEntry {
+    /// A directory that did not exist (removed on undo if empty)
+    MadeDir {
+        path: String,
+    },
+    /// A file that did not exist
+    Created {
+        path: String,
+    },
+    /// A file that was overwritten; the old one is in `backup`
+    Replaced {
+        path: String,
+        backup: String,
+    },
+    /// A sentinel block; `previous` is the block content it replaced
+    Block {
+        path: String,
+        id: String,
+        previous: Option<String>,
+    },
+    Moved {
+        from: String,
+        to: String,
+    },
+    /// A mode change; `previous` is the mode before it
+    Mode {
+        path: String,
+        previous: u32,
+    },
+    /// A file or directory that was deleted; it is in `backup`
+    Removed {
+        path: String,
+        backup: String,
+    },
+}
+
+impl ManifestEntry {
+    /// Shell-like description of the undo step.
+    pub fn describe_undo(&self) -> String {
+        match self {
+            ManifestEntry::MadeDir { path } => format!("rmdir {}", path),
+            ManifestEntry::Created { path } => format!("rm {}", path),
+            ManifestEntry::Replaced { path, backup } | ManifestEntry::Removed { path, backup } => {
+                format!("restore {} from {}", path, backup)
+            }
+            ManifestEntry::Block { path, id, previous } => match previous {
+                Some(_) => format!("restore block '{}' in {}", id, path),
+                None => format!("remove block '{}' from {}", id, path),
+            },
+            ManifestEntry::Moved { from, to } => format!("mv {} {}", to, from),
+            ManifestEntry::Mode { path, previous } => format!("chmod {:03o} {}", previous, path),
+        }
+    }
+}
+
+/// The record of everything committed under one transaction name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Manifest, String> {
+        let path = expand_vars(path);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read manifest '{}': {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid manifest '{}': {}", path, e))
+    }
+
+    /// Writes the manifest as JSON, replacing the file atomically.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let path = expand_vars(path);
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        replace_file(Path::new(&path), json.as_bytes(), None)
+            .map_err(|e| format!("Cannot write manifest '{}': {}", path, e))
+    }
+
+    /// Undoes every entry, newest first. Keeps going past failures and
+    /// reports them all at the end.
+    pub fn rollback(&self) -> Result<(), String> {
+        undo_all(&self.entries)
+    }
+}
+
+/// A staged set of file operations (builder; nothing happens until
+/// `commit`).
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    name: String,
+    state_dir: Option<String>,
+    ops: Vec<FileOp>,
+}
+
+impl Transaction {
+    pub fn new(name: &str) -> Self {
+        Transaction {
+            name: name.to_string(),
+            state_dir: None,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Where the manifest and backups are kept.
+    pub fn state_dir(mut self, dir: &str) -> Self {
+        self.state_dir = Some(expand_vars(dir));
+        self
+    }
+
+    /// Writes `content` to `path`, creating parent directories. An existing
+    /// file keeps its permissions.
+    pub fn write(mut self, path: &str, content: &str) -> Self {
+        self.ops.push(FileOp::Write {
+            path: expand_vars(path),
+            content: content.to_string(),
+        });
+        self
+    }
+
+    /// Adds (or replaces) a block delimited by `# >>> id >>>` and
+    /// `# <<< id <<<` lines, creating the file if needed. Uninstalling
+    /// removes just the block, keeping later edits to the rest of the file.
+    pub fn insert_block(mut self, path: &str, id: &str, content: &str) -> Self {
+        self.ops.push(FileOp::InsertBlock {
+            path: expand_vars(path),
+            id: id.to_string(),
+            content: content.to_string(),
+        });
+        self
+    }
+
+    /// Copies a file, keeping its permissions.
+    pub fn cp(mut self, src: &str, dest: &str) -> Self {
+        self.ops.push(FileOp::Copy {
+            src: expand_vars(src),
+            dest: expand_vars(dest),
+        });
+        self
+    }
+
+    /// Moves a file or directory.
+    pub fn mv(mut self, src: &str, dest: &str) -> Self {
+        self.ops.push(FileOp::Move {
+            src: expand_vars(src),
+            dest: expand_vars(dest),
+        });
+        self
+    }
+
+    /// Sets an octal mode (`"755"`); invalid modes fall back to `644`, as in
+    /// `fs::chmod`.
+    pub fn chmod(mut self, path: &str, mode: &str) -> Self {
+        self.ops.push(FileOp::Chmod {
+            path: expand_vars(path),
+            mode: u32::from_str_radix(mode, 8).unwrap_or(0o644),
+        });
+        self
+    }
+
+    /// Deletes a file or directory (kept in the backup directory).
+    pub fn rm(mut self, path: &str) -> Self {
+        self.ops.push(FileOp::Remove {
+            path: expand_vars(path),
+        });
+        self
+    }
+
+    pub fn ops(&self) -> &[FileOp] {
+        &self.ops
+    }
+
+    /// One description per staged operation, for previews.
+    pub fn plan(&self) -> Vec<String> {
+        self.ops.iter().map(FileOp::describe).collect()
+    }
+
+    /// The directory holding the manifest and backups.
+    pub fn dir(&self) -> PathBuf {
+        match &self.state_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(crate::hosts::rsb_data_path(&format!(
+                "transactions/{}",
+                self.name
+            ))),
+        }
+    }
+
+    pub fn manifest_path(&self) -> String {
+        self.dir().join(MANIFEST_FILE).to_string_lossy().to_string()
+    }
+
+    /// Applies the staged operations in order and returns the updated
+    /// manifest. On failure the operations of this commit are undone and the
+    /// error names the step that failed.
+    pub fn commit(self) -> Result<Manifest, String> {
+        let manifest_path = self.manifest_path();
+        let mut manifest = match Path::new(&manifest_path).exists() {
+            true => Manifest::load(&manifest_path)?,
+            false => Manifest {
+                name: self.name.clone(),
+                entries: Vec::new(),
+            },
+        };
+        if crate::hosts::is_dry_run() {
+            for op in &self.ops {
+                dry_run_skip(&op.describe());
+            }
+            return Ok(manifest);
+        }
+
+        let backups = self.dir().join(BACKUP_DIR);
+        let start = manifest.entries.len();
+        for op in &self.ops {
+            let what = op.describe();
+            let applied = traced(&what, || apply(op, &backups, &mut manifest.entries))
+                .map_err(|e| format!("{}: {}", what, e))
+                .and_then(|_| manifest.save(&manifest_path));
+            if let Err(err) = applied {
+                let undone = undo_all(&manifest.entries[start..]);
+                manifest.entries.truncate(start);
+                let saved = match start {
+                    0 => fs::remove_file(&manifest_path).or_else(ignore_missing),
+                    _ => manifest.save(&manifest_path).map_err(io::Error::other),
+                };
+                // Only succeeds when nothing else was left in them
+                let _ = fs::remove_dir(&backups);
+                let _ = fs::remove_dir(self.dir());
+                return Err(match (undone, saved) {
+                    (Ok(()), Ok(())) => format!("{} (rolled back)", err),
+                    (Err(e), _) => format!("{} (rollback failed: {})", err, e),
+                    (_, Err(e)) => format!("{} (manifest not restored: {})", err, e),
+                });
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Undoes everything committed under this name, newest first, then
+    /// removes the state directory. Returns the number of steps undone.
+    pub fn uninstall(&self) -> Result<usize, String> {
+        let manifest = Manifest::load(&self.manifest_path())?;
+        manifest.rollback()?;
+        if !crate::hosts::is_dry_run() {
+            fs::remove_dir_all(self.dir())
+                .map_err(|e| format!("Cannot remove '{}': {}", self.dir().display(), e))?;
+        }
+        Ok(manifest.entries.len())
+    }
+}
+
+// --- Apply ---
+
+fn apply(op: &FileOp, backups: &Path, entries: &mut Vec<ManifestEntry>) -> Result<(), String> {
+    let io_err = |e: io::Error| e.to_string();
+    match op {
+        FileOp::Write { path, content } => {
+            put_file(Path::new(path), content.as_bytes(), None, backups, entries)
+        }
+        FileOp::Copy { src, dest } => {
+            let meta = fs::metadata(src).map_err(io_err)?;
+            if meta.is_dir() {
+                return Err(format!("'{}' is a directory", src));
+            }
+            let data = fs::read(src).map_err(io_err)?;
+            put_file(
+                Path::new(dest),
+                &data,
+                Some(meta.permissions()),
+                backups,
+                entries,
+            )
+        }
+        FileOp::InsertBlock { path, id, content } => {
+            let target = Path::new(path);
+            let existing = match target.exists() {
+                true => Some(fs::read_to_string(target).map_err(io_err)?),
+                false => None,
+            };
+            let (text, previous) = set_block(existing.as_deref().unwrap_or(""), id, Some(content))?;
+            if existing.is_none() {
+                make_parents(target, entries).map_err(io_err)?;
+                entries.push(ManifestEntry::Created { path: path.clone() });
+            }
+            entries.push(ManifestEntry::Block {
+                path: path.clone(),
+                id: id.clone(),
+                previous,
+            });
+            replace_file(target, text.as_bytes(), None).map_err(io_err)
+        }
+        FileOp::Move { src, dest } => {
+            if fs::symlink_metadata(src).is_err() {
+                return Err(format!("'{}' does not exist", src));
+            }
+            let target = Path::new(dest);
+            if fs::symlink_metadata(target).is_ok() {
+                let backup = next_backup(backups).map_err(io_err)?;
+                move_path(target, &backup).map_err(io_err)?;
+                entries.push(ManifestEntry::Replaced {
+                    path: dest.clone(),
+                    backup: backup.to_string_lossy().to_string(),
+                });
+            } else {
+                make_parents(target, entries).map_err(io_err)?;
+            }
+            entries.push(ManifestEntry::Moved {
+                from: src.clone(),
+                to: dest.clone(),
+            });
+            move_path(Path::new(src), target).map_err(io_err)
+        }
+        FileOp::Chmod { path, mode } => {
+            let previous = fs::metadata(path).map_err(io_err)?.permissions().mode() & 0o7777;
+            entries.push(ManifestEntry::Mode {
+                path: path.clone(),
+                previous,
+            });
+            fs::set_permissions(path, fs::Permissions::from_mode(*mode)).map_err(io_err)
+        }
+        FileOp::Remove { path } => {
+            if fs::symlink_metadata(path).is_err() {
+                return Ok(());
+            }
+            let backup = next_backup(backups).map_err(io_err)?;
+            move_path(Path::new(path), &backup).map_err(io_err)?;
+            entries.push(ManifestEntry::Removed {
+                path: path.clone(),
+                backup: backup.to_string_lossy().to_string(),
+            });
+            Ok(())
+        }
+    }
+}
+
+/// Writes a whole file, backing up what was there.
+fn put_file(
+    target: &Path,
+    data: &[u8],
+    perms: Option<fs::Permissions>,
+    backups: &Path,
+    entries: &mut Vec<ManifestEntry>,
+) -> Result<(), String> {
+    let io_err = |e: io::Error| e.to_string();
+    let path = target.to_string_lossy().to_string();
+    match fs::metadata(target) {
+        Ok(meta) if meta.is_dir() => Err(format!("'{}' is a directory", path)),
+        Ok(meta) => {
+            let backup = next_backup(backups).map_err(io_err)?;
+            fs::copy(target, &backup).map_err(io_err)?;
+            entries.push(ManifestEntry::Replaced {
+                path,
+                backup: backup.to_string_lossy().to_string(),
+            });
+            let perms = perms.unwrap_or_else(|| meta.permissions());
+            replace_file(target, data, Some(perms)).map_err(io_err)
+        }
+        Err(_) => {
+            make_parents(target, entries).map_err(io_err)?;
+            entries.push(ManifestEntry::Created { path });
+            replace_file(target, data, perms).map_err(io_err)
+        }
+    }
+}
+
+/// Writes `data` to a temp file next to `target`, then renames it over.
+fn replace_file(target: &Path, data: &[u8], perms: Option<fs::Permissions>) -> io::Result<()> {
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = target.with_file_name(format!(".{}.rsb-tx-{}", name, std::process::id()));
+    let written = fs::write(&temp, data)
+        .and_then(|_| match perms {
+            Some(perms) => fs::set_permissions(&temp, perms),
+            None => Ok(()),
+        })
+        .and_then(|_| fs::rename(&temp, target));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Creates the missing ancestors of `target`, recording each one.
+fn make_parents(target: &Path, entries: &mut Vec<ManifestEntry>) -> io::Result<()> {
+    let mut missing: Vec<&Path> = target
+        .ancestors()
+        .skip(1)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .take_while(|dir| !dir.exists())
+        .collect();
+    missing.reverse();
+    for dir in missing {
+        fs::create_dir(dir)?;
+        entries.push(ManifestEntry::MadeDir {
+            path: dir.to_string_lossy().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// A fresh path in the backup directory.
+fn next_backup(backups: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(backups)?;
+    let mut n = fs::read_dir(backups)?.count();
+    while fs::symlink_metadata(backups.join(n.to_string())).is_ok() {
+        n += 1;
+    }
+    Ok(backups.join(n.to_string()))
+}
+
+/// Renames, falling back to copy + delete across filesystems.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    let renamed = match fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+    let copied = match from.is_dir() {
+        true => super::utils::copy_tree(from, to),
+        false => fs::copy(from, to).is_ok(),
+    };
+    if !copied {
+        return Err(renamed);
+    }
+    match from.is_dir() {
+        true => fs::remove_dir_all(from),
+        false => fs::remove_file(from),
+    }
+}
+
+fn ignore_missing(e: io::Error) -> io::Result<()> {
+    match e.kind() {
+        io::ErrorKind::NotFound => Ok(()),
+        _ => Err(e),
+    }
+}
+
+/// Sets (`Some`) or removes (`None`) the block `id` in `text`, returning the
+/// new text and the block's previous content.
+fn set_block(
+    text: &str,
+    id: &str,
+    content: Option<&str>,
+) -> Result<(String, Option<String>), String> {
+    let begin = format!("# >>> {} >>>", id);
+    let end = format!("# <<< {} <<<", id);
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+    let start = lines.iter().position(|line| line.trim() == begin);
+    let range = match start {
+        Some(start) => {
+            let len = lines[start..]
+                .iter()
+                .position(|line| line.trim() == end)
+                .ok_or_else(|| format!("block '{}' has no end marker", id))?;
+            Some(start..=start + len)
+        }
+        None => None,
+    };
+    let previous = range
+        .clone()
+        .map(|range| lines[*range.start() + 1..*range.end()].join("\n"));
+
+    let block: Vec<String> = match content {
+        Some(content) => std::iter::once(begin)
+            .chain(content.lines().map(str::to_string))
+            .chain(std::iter::once(end))
+            .collect(),
+        None => Vec::new(),
+    };
+    match range {
+        Some(range) => {
+            lines.splice(range, block);
+        }
+        None => lines.extend(block),
+    }
+
+    let mut out = lines.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok((out, previous))
+}
+
+// --- Undo ---
+
+fn undo_all(entries: &[ManifestEntry]) -> Result<(), String> {
+    let mut errors = Vec::new();
+    for entry in entries.iter().rev() {
+        let what = entry.describe_undo();
+        if dry_run_skip(&what) {
+            continue;
+        }
+        if let Err(e) = traced(&what, || undo(entry)) {
+            errors.push(format!("{}: {}", what, e));
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.join("; ")),
+    }
+}
+
+fn undo(entry: &ManifestEntry) -> Result<(), String> {
+    let io_err = |e: io::Error| e.to_string();
+    match entry {
+        // Left alone if something else was put there since
+        ManifestEntry::MadeDir { path } => {
+            let _ = fs::remove_dir(path);
+            Ok(())
+        }
+        ManifestEntry::Created { path } => fs::remove_file(path)
+            .or_else(ignore_missing)
+            .map_err(io_err),
+        ManifestEntry::Replaced { path, backup } | ManifestEntry::Removed { path, backup } => {
+            let target = Path::new(path);
+            if target.is_dir() && !Path::new(backup).is_dir() {
+                return Err(format!("'{}' is now a directory", path));
+            }
+            if target.is_dir() {
+                fs::remove_dir_all(target).map_err(io_err)?;
+            }
+            move_path(Path::new(backup), target).map_err(io_err)
+        }
+        ManifestEntry::Block { path, id, previous } => {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(e) => return Err(e.to_string()),
+            };
+            let (text, _) = set_block(&text, id, previous.as_deref())?;
+            let perms = fs::metadata(path).map_err(io_err)?.permissions();
+            replace_file(Path::new(path), text.as_bytes(), Some(perms)).map_err(io_err)
+        }
+        ManifestEntry::Moved { from, to } => match fs::symlink_metadata(to) {
+            Ok(_) => move_path(Path::new(to), Path::new(from)).map_err(io_err),
+            Err(_) => Ok(()),
+        },
+        ManifestEntry::Mode { path, previous } => {
+            fs::set_permissions(path, fs::Permissions::from_mode(*previous))
+                .or_else(ignore_missing)
+                .map_err(io_err)
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::com::ErrorCode;
 use crate::global::expand_vars;
+use crate::hosts::side_effect;
 use lazy_static::lazy_static;
 use std::path::Path;
 use std::sync::Mutex;
@@ -23,6 +24,11 @@ pub fn read_file(path: &str) -> String {
 
 pub fn write_file(path: &str, content: &str) {
     let expanded_path = expand_vars(path);
+    let what = format!("write {} ({} bytes)", expanded_path, content.len());
+    side_effect(&what, (), || write_file_now(&expanded_path, content))
+}
+
+fn write_file_now(expanded_path: &str, content: &str) {
     if let Some(parent) = Path::new(&expanded_path).parent() {
         if let Err(e) = std::fs::create_dir_all(parent) {
             eprintln!(
@@ -33,7 +39,7 @@ pub fn write_file(path: &str, content: &str) {
             std::process::exit(1);
         }
     }
-    if let Err(e) = std::fs::write(&expanded_path, content) {
+    if let Err(e) = std::fs::write(expanded_path, content) {
         eprintln!(
             "rsb-error: Failed to write to file '{}': {}",
             expanded_path, e
@@ -43,10 +49,15 @@ pub fn write_file(path: &str, content: &str) {
 }
 
 pub fn append_file(path: &str, content: &str) {
+    let expanded_path = expand_vars(path);
+    let what = format!("append {} ({} bytes)", expanded_path, content.len() + 1);
+    side_effect(&what, (), || append_file_now(&expanded_path, content))
+}
+
+fn append_file_now(expanded_path: &str, content: &str) {
     use std::fs::OpenOptions;
     use std::io::Write;
 
-    let expanded_path = expand_vars(path);
     if let Some(parent) = Path::new(&expanded_path).parent() {
         if let Err(e) = std::fs::create_dir_all(parent) {
             eprintln!(
@@ -62,7 +73,7 @@ pub fn append_file(path: &str, content: &str) {
         .create(true)
         .write(true)
         .append(true)
-        .open(&expanded_path)
+        .open(expanded_path)
     {
         Ok(f) => f,
         Err(e) => {
@@ -85,32 +96,43 @@ pub fn append_file(path: &str, content: &str) {
 
 // --- File System Manipulation Functions ---
 
+// These report success in dry-run mode (see `hosts::effects`).
+
 pub fn mkdir_p(path: &str) -> bool {
-    std::fs::create_dir_all(expand_vars(path)).is_ok()
+    let expanded_path = expand_vars(path);
+    side_effect(&format!("mkdir -p {}", expanded_path), true, || {
+        std::fs::create_dir_all(&expanded_path).is_ok()
+    })
 }
 
 pub fn rm(path: &str) -> bool {
     let expanded_path = expand_vars(path);
-    if Path::new(&expanded_path).is_dir() {
-        std::fs::remove_dir(&expanded_path).is_ok()
-    } else {
-        std::fs::remove_file(&expanded_path).is_ok()
-    }
+    side_effect(&format!("rm {}", expanded_path), true, || {
+        if Path::new(&expanded_path).is_dir() {
+            std::fs::remove_dir(&expanded_path).is_ok()
+        } else {
+            std::fs::remove_file(&expanded_path).is_ok()
+        }
+    })
 }
 
 pub fn rm_rf(path: &str) -> bool {
     let expanded_path = expand_vars(path);
-    if Path::new(&expanded_path).is_dir() {
-        std::fs::remove_dir_all(&expanded_path).is_ok()
-    } else {
-        std::fs::remove_file(&expanded_path).is_ok()
-    }
+    side_effect(&format!("rm -rf {}", expanded_path), true, || {
+        if Path::new(&expanded_path).is_dir() {
+            std::fs::remove_dir_all(&expanded_path).is_ok()
+        } else {
+            std::fs::remove_file(&expanded_path).is_ok()
+        }
+    })
 }
 
 pub fn cp(src: &str, dest: &str) -> bool {
     let src_exp = expand_vars(src);
     let dest_exp = expand_vars(dest);
-    std::fs::copy(&src_exp, &dest_exp).is_ok()
+    side_effect(&format!("cp {} {}", src_exp, dest_exp), true, || {
+        std::fs::copy(&src_exp, &dest_exp).is_ok()
+    })
 }
 
 pub fn cp_r(src: &str, dest: &str) -> bool {
@@ -118,52 +140,64 @@ pub fn cp_r(src: &str, dest: &str) -> bool {
     let src_exp = expand_vars(src);
     let dest_exp = expand_vars(dest);
 
-    if is_command("cp") {
-        let status = std::process::Command::new("cp")
-            .arg("-r")
-            .arg(&src_exp)
-            .arg(&dest_exp)
-            .status();
-        status.map(|s| s.success()).unwrap_or(false)
-    } else {
-        if !is_dir(&src_exp) {
-            return false;
+    side_effect(&format!("cp -r {} {}", src_exp, dest_exp), true, || {
+        if is_command("cp") {
+            let status = std::process::Command::new("cp")
+                .arg("-r")
+                .arg(&src_exp)
+                .arg(&dest_exp)
+                .status();
+            status.map(|s| s.success()).unwrap_or(false)
+        } else {
+            copy_tree(Path::new(&src_exp), Path::new(&dest_exp))
         }
-        if !mkdir_p(&dest_exp) {
+    })
+}
+
+/// `cp_r` without a `cp` binary.
+pub(crate) fn copy_tree(src: &Path, dest: &Path) -> bool {
+    if !src.is_dir() || std::fs::create_dir_all(dest).is_err() {
+        return false;
+    }
+    let Ok(entries) = std::fs::read_dir(src) else {
+        return false;
+    };
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        let dest_path = dest.join(entry.file_name());
+        let copied = match entry_path.is_dir() {
+            true => copy_tree(&entry_path, &dest_path),
+            false => std::fs::copy(&entry_path, &dest_path).is_ok(),
+        };
+        if !copied {
             return false;
         }
-        for entry in std::fs::read_dir(&src_exp).unwrap() {
-            let entry = entry.unwrap();
-            let entry_path = entry.path();
-            let dest_path = Path::new(&dest_exp).join(entry.file_name());
-            if entry_path.is_dir() {
-                if !cp_r(entry_path.to_str().unwrap(), dest_path.to_str().unwrap()) {
-                    return false;
-                }
-            } else if let Err(_) = std::fs::copy(&entry_path, &dest_path) {
-                return false;
-            }
-        }
-        true
     }
+    true
 }
 
 pub fn mv(src: &str, dest: &str) -> bool {
-    std::fs::rename(expand_vars(src), expand_vars(dest)).is_ok()
+    let src_exp = expand_vars(src);
+    let dest_exp = expand_vars(dest);
+    side_effect(&format!("mv {} {}", src_exp, dest_exp), true, || {
+        std::fs::rename(&src_exp, &dest_exp).is_ok()
+    })
 }
 
 pub fn touch(path: &str) -> bool {
     use std::fs::OpenOptions;
     let expanded_path = expand_vars(path);
-    if Path::new(&expanded_path).exists() {
-        let file = OpenOptions::new().append(true).open(&expanded_path);
-        if let Ok(file) = file {
-            return file.set_len(file.metadata().unwrap().len()).is_ok();
+    side_effect(&format!("touch {}", expanded_path), true, || {
+        if Path::new(&expanded_path).exists() {
+            let file = OpenOptions::new().append(true).open(&expanded_path);
+            if let Ok(file) = file {
+                return file.set_len(file.metadata().unwrap().len()).is_ok();
+            }
+            false
+        } else {
+            std::fs::File::create(&expanded_path).is_ok()
         }
-        false
-    } else {
-        std::fs::File::create(&expanded_path).is_ok()
-    }
+    })
 }
 
 // --- Meta Parsing ---
@@ -189,7 +223,8 @@ pub fn extract_meta_from_file(path: &str) -> std::collections::HashMap<String, S
 pub fn backup_file(path: &str, suffix: &str) -> Result<String, std::io::Error> {
     let expanded_path = expand_vars(path);
     let backup_path = format!("{}{}", expanded_path, suffix);
-    std::fs::copy(&expanded_path, &backup_path)?;
+    let what = format!("cp {} {}", expanded_path, backup_path);
+    side_effect(&what, Ok(0), || std::fs::copy(&expanded_path, &backup_path))?;
     Ok(backup_path)
 }
 
@@ -200,7 +235,12 @@ use std::os::unix::fs::PermissionsExt;
 pub fn chmod(path: &str, mode: &str) -> Result<(), std::io::Error> {
     let mode_octal = u32::from_str_radix(mode, 8).unwrap_or(0o644);
     let perms = std::fs::Permissions::from_mode(mode_octal);
-    std::fs::set_permissions(expand_vars(path), perms)
+    let expanded_path = expand_vars(path);
+    side_effect(
+        &format!("chmod {:03o} {}", mode_octal, expanded_path),
+        Ok(()),
+        || std::fs::set_permissions(&expanded_path, perms),
+    )
 }
 
 #[cfg(not(unix))]
@@ -393,13 +433,16 @@ pub fn create_temp_file_path(name_type: &str) -> String {
     };
     let mut path = std::path::PathBuf::from(tmp_dir);
     path.push(filename);
-    path.to_string_lossy().to_string()
+    let path = path.to_string_lossy().to_string();
+    // Temp paths are removed when the process exits
+    TEMP_FILES_TO_CLEAN.lock().unwrap().push(path.clone());
+    crate::jobs::install_exit_hook();
+    path
 }
 
 pub fn capture_stream_to_temp_file(stream: &mut crate::streams::Stream) -> String {
     let path = create_temp_file_path("random");
     stream.clone().to_file(&path);
-    TEMP_FILES_TO_CLEAN.lock().unwrap().push(path.clone());
     path
 }
 
@@ -1,90 +1,228 @@
 //! Global configuration parsing and I/O
+//!
+//! Config files use bash syntax (`.env`/`.conf` style) and load into the
+//! global store. Arrays follow the `KEY_LENGTH` + `KEY_<i>` convention;
+//! `source`d files resolve relative to the file that sources them.
+//! `save_config_file` updates an existing file in place, keeping comments
+//! and ordering.
 
-use std::path::Path;
+mod document;
+mod parser;
 
-use crate::global::{expand_vars, get_var, has_var, set_var};
+pub use document::{quote_config_value, ConfigDoc};
+pub use parser::ConfigError;
 
-pub fn parse_config_content(content: &str) {
-    for line in content.lines() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') {
-            continue;
-        }
-        if let Some((key, value)) = line.split_once('=') {
-            let key = key.trim();
-            let value = value.trim();
-            let value = value
-                .strip_prefix('"')
-                .and_then(|v| v.strip_suffix('"'))
-                .unwrap_or(value);
-            let value = value
-                .strip_prefix('\'')
-                .and_then(|v| v.strip_suffix('\''))
-                .unwrap_or(value);
-            if value.starts_with('(') && value.ends_with(')') {
-                let array_content = &value[1..value.len() - 1];
-                let mut items = Vec::new();
-                let mut current_item = String::new();
-                let mut in_quotes = false;
-                for ch in array_content.chars() {
-                    match ch {
-                        '"' => in_quotes = !in_quotes,
-                        ' ' if !in_quotes => {
-                            if !current_item.is_empty() {
-                                items.push(current_item.clone());
-                                current_item.clear();
-                            }
+use parser::{eval_word, Stmt, Value};
+use std::path::{Path, PathBuf};
+
+use crate::global::{expand_vars, get_array, get_var, has_var, set_array, set_var};
+
+/// How deep `source` may nest before we assume a cycle.
+const MAX_SOURCE_DEPTH: usize = 16;
+
+fn lookup(name: &str) -> Option<String> {
+    if has_var(name) {
+        Some(get_var(name))
+    } else {
+        std::env::var(name).ok()
+    }
+}
+
+fn warn(err: &ConfigError) {
+    crate::utils::stderrx("warn", &format!("config: {}", err));
+}
+
+/// Applies a parsed document to the global store; returns the keys set.
+/// `base` is the directory `source` paths are relative to. When `lenient`,
+/// errors in `source`d files are reported and the rest still applies.
+fn apply(
+    doc: &ConfigDoc,
+    base: Option<&Path>,
+    depth: usize,
+    path: Option<&str>,
+    lenient: bool,
+) -> Result<Vec<String>, ConfigError> {
+    let mut keys = Vec::new();
+    for line in &doc.lines {
+        match &line.stmt {
+            Stmt::Text => {}
+            Stmt::Assign {
+                key, append, value, ..
+            } => {
+                match value {
+                    Value::Scalar(word) => {
+                        let mut text = eval_word(word, &lookup);
+                        if *append {
+                            text = get_var(key) + &text;
                         }
-                        _ => current_item.push(ch),
+                        set_var(key, &text);
+                    }
+                    Value::Array(words) => {
+                        let mut items = if *append { get_array(key) } else { Vec::new() };
+                        items.extend(words.iter().map(|word| eval_word(word, &lookup)));
+                        set_array(key, &items.iter().map(String::as_str).collect::<Vec<_>>());
                     }
                 }
-                if !current_item.is_empty() {
-                    items.push(current_item);
+                if !keys.contains(key) {
+                    keys.push(key.clone());
                 }
-                set_var(&format!("{}_LENGTH", key), &items.len().to_string());
-                for (i, item) in items.iter().enumerate() {
-                    set_var(&format!("{}_{}", key, i), item);
+            }
+            Stmt::Source(word) => {
+                let fail = |message: String| {
+                    let err = ConfigError::new(line.line, message);
+                    match path {
+                        Some(path) => err.in_file(path),
+                        None => err,
+                    }
+                };
+                let sourced = if depth >= MAX_SOURCE_DEPTH {
+                    Err(fail("'source' nested too deeply (cycle?)".to_string()))
+                } else {
+                    let target = PathBuf::from(eval_word(word, &lookup));
+                    let target = match base {
+                        Some(base) if target.is_relative() => base.join(target),
+                        _ => target,
+                    };
+                    let target = target.to_string_lossy().to_string();
+                    std::fs::read_to_string(&target)
+                        .map_err(|e| fail(format!("cannot source '{}': {}", target, e)))
+                        .and_then(|content| {
+                            load_content(&content, Some(&target), depth + 1, lenient)
+                        })
+                };
+                match sourced {
+                    Ok(sourced) => {
+                        for key in sourced {
+                            if !keys.contains(&key) {
+                                keys.push(key);
+                            }
+                        }
+                    }
+                    Err(err) if lenient => warn(&err),
+                    Err(err) => return Err(err),
                 }
-                set_var(key, &items.join(" "));
-            } else {
-                set_var(key, value);
             }
         }
     }
+    Ok(keys)
 }
 
+/// Parses and applies `content`. Strict loads fail on the first syntax
+/// error; lenient ones report each bad line and apply the rest.
+fn load_content(
+    content: &str,
+    path: Option<&str>,
+    depth: usize,
+    lenient: bool,
+) -> Result<Vec<String>, ConfigError> {
+    let in_file = |e: ConfigError| match path {
+        Some(path) => e.in_file(path),
+        None => e,
+    };
+    let doc = if lenient {
+        let (doc, errors) = ConfigDoc::parse_lenient(content);
+        for err in errors {
+            warn(&in_file(err));
+        }
+        doc
+    } else {
+        ConfigDoc::parse(content).map_err(in_file)?
+    };
+    let base = path
+        .and_then(|p| Path::new(p).parent())
+        .map(Path::to_path_buf);
+    apply(&doc, base.as_deref(), depth, path, lenient)
+}
+
+/// Parses and applies config content; nothing is applied if it has a syntax
+/// error. Returns the keys set.
+pub fn try_parse_config_content(content: &str) -> Result<Vec<String>, ConfigError> {
+    load_content(content, None, 0, false)
+}
+
+/// Parses and applies config content, best effort: valid statements are
+/// applied and each error is reported on stderr with its line number.
+pub fn parse_config_content(content: &str) {
+    if let Err(err) = load_content(content, None, 0, true) {
+        warn(&err);
+    }
+}
+
+/// Loads a config file into the global store (path is variable-expanded).
+/// Returns the keys set; fails on unreadable files and syntax errors.
+pub fn try_load_config_file(path: &str) -> Result<Vec<String>, ConfigError> {
+    let path = expand_vars(path);
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| ConfigError::new(0, format!("cannot read config: {}", e)).in_file(&path))?;
+    load_content(&content, Some(&path), 0, false)
+}
+
+/// Loads a config file if it exists, best effort: errors, including those
+/// in `source`d files, go to stderr and the remaining lines still load.
 pub fn load_config_file(path: &str) {
     let expanded_path = expand_vars(path);
-    if let Ok(content) = std::fs::read_to_string(&expanded_path) {
-        parse_config_content(&content);
+    let Ok(content) = std::fs::read_to_string(&expanded_path) else {
+        return;
+    };
+    if let Err(err) = load_content(&content, Some(&expanded_path), 0, true) {
+        warn(&err);
     }
 }
 
-pub fn save_config_file(path: &str, keys: &[&str]) {
+/// Reads `path` (or starts a new document), lets `edit` change it and
+/// writes it back, creating the parent directory.
+fn update_config_file(
+    path: &str,
+    edit: impl FnOnce(&mut ConfigDoc),
+) -> Result<(), ConfigError> {
     let expanded_path = expand_vars(path);
-    let mut content = String::new();
-    content.push_str("# RSB Configuration File\n");
-    content.push_str(&format!(
-        "# Generated on {}\n\n",
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    ));
-    for key in keys {
-        if has_var(key) {
-            let value = get_var(key);
-            if value.contains(' ') {
-                content.push_str(&format!("{}=\"{}\"\n", key, value));
-            } else {
-                content.push_str(&format!("{}={}\n", key, value));
-            }
+    let fail = |e: std::io::Error| ConfigError::new(0, e.to_string()).in_file(&expanded_path);
+    let mut doc = match std::fs::read_to_string(&expanded_path) {
+        Ok(content) => ConfigDoc::parse(&content).map_err(|e| e.in_file(&expanded_path))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            ConfigDoc::parse("# RSB Configuration File\n\n").expect("static header parses")
         }
-    }
+        Err(e) => return Err(fail(e)),
+    };
+    edit(&mut doc);
     if let Some(parent) = Path::new(&expanded_path).parent() {
-        let _ = std::fs::create_dir_all(parent);
+        std::fs::create_dir_all(parent).map_err(fail)?;
+    }
+    std::fs::write(&expanded_path, doc.to_string()).map_err(fail)
+}
+
+/// Writes `keys` from the global store to `path`. An existing file is
+/// updated in place: matching assignments get the new value, other lines
+/// (comments, ordering, unrelated keys) are kept, new keys are appended.
+/// Keys with a `KEY_LENGTH` are written as arrays; unset keys are skipped.
+pub fn try_save_config_file(path: &str, keys: &[&str]) -> Result<(), ConfigError> {
+    update_config_file(path, |doc| {
+        for key in keys {
+            if has_var(&format!("{}_LENGTH", key)) {
+                let items = get_array(key);
+                doc.set_array(key, &items.iter().map(String::as_str).collect::<Vec<_>>());
+            } else if has_var(key) {
+                doc.set(key, &get_var(key));
+            }
+        }
+    })
+}
+
+/// Writes `values` to `path` like `try_save_config_file`, without reading
+/// or changing the global store.
+pub fn try_save_config_values(path: &str, values: &[(&str, &str)]) -> Result<(), ConfigError> {
+    update_config_file(path, |doc| {
+        for (key, value) in values {
+            doc.set(key, value);
+        }
+    })
+}
+
+/// `try_save_config_file`, reporting failures on stderr.
+pub fn save_config_file(path: &str, keys: &[&str]) {
+    if let Err(err) = try_save_config_file(path, keys) {
+        crate::utils::stderrx("error", &format!("config: {}", err));
     }
-    let _ = std::fs::write(&expanded_path, &content);
 }
 
 pub fn export_vars(path: &str) {
@@ -92,7 +230,7 @@ pub fn export_vars(path: &str) {
     let all_vars = crate::global::get_all_vars();
     let mut content = String::new();
     for (key, value) in all_vars.iter() {
-        content.push_str(&format!("export {}='{}'\n", key, value));
+        content.push_str(&format!("export {}={}\n", key, quote_config_value(value)));
     }
     if let Some(parent) = Path::new(&expanded_path).parent() {
         let _ = std::fs::create_dir_all(parent);
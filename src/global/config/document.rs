@@ -0,0 +1,184 @@
+//! Round-trip config documents
+//!
+//! A parsed config file that remembers every line's original text. Setting
+//! or removing a key rewrites only that assignment's value (the `export`
+//! prefix and any trailing comment stay put) and drops the `KEY+=` lines
+//! after it; new keys are appended.
+
+use super::parser::{self, ConfigError, Line, Part, Stmt, Value};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigDoc {
+    pub(crate) lines: Vec<Line>,
+}
+
+impl ConfigDoc {
+    /// Parses `content`, failing on the first syntax error.
+    pub fn parse(content: &str) -> Result<ConfigDoc, ConfigError> {
+        let (doc, mut errors) = Self::parse_lenient(content);
+        match errors.is_empty() {
+            true => Ok(doc),
+            false => Err(errors.remove(0)),
+        }
+    }
+
+    /// Parses `content`, keeping bad statements as plain text.
+    pub(crate) fn parse_lenient(content: &str) -> (ConfigDoc, Vec<ConfigError>) {
+        let (lines, errors) = parser::parse(content);
+        (ConfigDoc { lines }, errors)
+    }
+
+    /// Keys assigned in the document, in first-assignment order.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for line in &self.lines {
+            if let Stmt::Assign { key, .. } = &line.stmt {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+        keys
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.last_assignment(key).is_some()
+    }
+
+    /// The literal value of `key`'s last plain assignment, if it has no
+    /// variable references (`None` for arrays and `$VAR` values).
+    pub fn get(&self, key: &str) -> Option<String> {
+        let index = self.last_assignment(key)?;
+        match &self.lines[index].stmt {
+            Stmt::Assign {
+                value: Value::Scalar(word),
+                ..
+            } => word
+                .iter()
+                .map(|part| match part {
+                    Part::Lit(text) => Some(text.as_str()),
+                    Part::Var { .. } => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+
+    /// Sets `key` to a scalar value (quoted as needed).
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.replace(
+            key,
+            &quote_config_value(value),
+            Value::Scalar(literal(value)),
+        );
+    }
+
+    /// Sets `key` to a bash array `(a 'b c')`.
+    pub fn set_array(&mut self, key: &str, items: &[&str]) {
+        let text = format!(
+            "({})",
+            items
+                .iter()
+                .map(|item| quote_config_value(item))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        let value = Value::Array(items.iter().map(|item| literal(item)).collect());
+        self.replace(key, &text, value);
+    }
+
+    /// Removes every assignment of `key`. Returns whether any was found.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines
+            .retain(|line| !matches!(&line.stmt, Stmt::Assign { key: k, .. } if k == key));
+        self.lines.len() != before
+    }
+
+    /// Index of the last non-append assignment of `key`.
+    fn last_assignment(&self, key: &str) -> Option<usize> {
+        self.lines.iter().rposition(
+            |line| matches!(&line.stmt, Stmt::Assign { key: k, append: false, .. } if k == key),
+        )
+    }
+
+    fn replace(&mut self, key: &str, text: &str, new_value: Value) {
+        match self.last_assignment(key) {
+            Some(index) => {
+                let line = &mut self.lines[index];
+                if let Stmt::Assign { value, span, .. } = &mut line.stmt {
+                    line.raw.replace_range(span.0..span.1, text);
+                    *span = (span.0, span.0 + text.len());
+                    *value = new_value;
+                }
+                // Later `KEY+=` lines would extend the new value on reload
+                let mut at = 0;
+                self.lines.retain(|line| {
+                    at += 1;
+                    at <= index + 1
+                        || !matches!(&line.stmt, Stmt::Assign { key: k, append: true, .. } if k == key)
+                });
+            }
+            None => {
+                if self
+                    .lines
+                    .last()
+                    .is_some_and(|line| !line.raw.ends_with('\n'))
+                {
+                    self.lines.last_mut().unwrap().raw.push('\n');
+                }
+                let prefix = format!("{}=", key);
+                self.lines.push(Line {
+                    raw: format!("{}{}\n", prefix, text),
+                    line: 0,
+                    stmt: Stmt::Assign {
+                        key: key.to_string(),
+                        append: false,
+                        value: new_value,
+                        span: (prefix.len(), prefix.len() + text.len()),
+                    },
+                });
+            }
+        }
+    }
+}
+
+impl Display for ConfigDoc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for line in &self.lines {
+            f.write_str(&line.raw)?;
+        }
+        Ok(())
+    }
+}
+
+fn literal(value: &str) -> Vec<Part> {
+    if value.is_empty() {
+        Vec::new()
+    } else {
+        vec![Part::Lit(value.to_string())]
+    }
+}
+
+/// Quotes a value so a bash-compatible reader gets it back verbatim: bare
+/// when it only has safe characters, otherwise single-quoted (double-quoted
+/// with escapes if it contains `'`).
+pub fn quote_config_value(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+,=".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        value.to_string()
+    } else if !value.contains('\'') {
+        format!("'{}'", value)
+    } else {
+        let mut out = String::from("\"");
+        for c in value.chars() {
+            if matches!(c, '"' | '\\' | '$' | '`') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push('"');
+        out
+    }
+}
@@ -0,0 +1,542 @@
+//! Bash-style config parser
+//!
+//! Splits a `.env`/`.conf` file into statements while keeping each
+//! statement's exact source text, so the document can be written back with
+//! comments and ordering intact. Understands `export`/`readonly`/`declare`
+//! prefixes, single/double/`$'...'` quoting, backslash escapes and line
+//! continuations, `$VAR`/`${VAR}`/`${VAR:-default}` references, trailing
+//! comments, multi-line quoted values, `( ... )` arrays and `source` lines.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// A config syntax or load error. `line` is 1-based (0 when the error is
+/// not tied to a line, e.g. an unreadable file).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub path: Option<String>,
+    pub line: usize,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        ConfigError {
+            path: None,
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn in_file(mut self, path: &str) -> Self {
+        if self.path.is_none() {
+            self.path = Some(path.to_string());
+        }
+        self
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match (&self.path, self.line) {
+            (Some(path), 0) => write!(f, "{}: {}", path, self.message),
+            (Some(path), line) => write!(f, "{}:{}: {}", path, line, self.message),
+            (None, 0) => write!(f, "{}", self.message),
+            (None, line) => write!(f, "line {}: {}", line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// One piece of a word: literal text or a variable reference.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Part {
+    Lit(String),
+    /// `$NAME`, `${NAME}`, `${NAME:-word}` (colon) or `${NAME-word}`.
+    Var {
+        name: String,
+        default: Option<(bool, Word)>,
+    },
+}
+
+pub(crate) type Word = Vec<Part>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Scalar(Word),
+    Array(Vec<Word>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Stmt {
+    /// Blank lines, comments, bare `export KEY` and unparsable text.
+    Text,
+    Assign {
+        key: String,
+        append: bool,
+        value: Value,
+        /// Byte range of the value's source text within the statement.
+        span: (usize, usize),
+    },
+    Source(Word),
+}
+
+/// A statement with its exact source text and starting line.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Line {
+    pub(crate) raw: String,
+    pub(crate) line: usize,
+    pub(crate) stmt: Stmt,
+}
+
+/// Parses `src` into statements. Statements with errors are kept as text
+/// (so nothing is lost on write-back) and their errors returned alongside.
+pub(crate) fn parse(src: &str) -> (Vec<Line>, Vec<ConfigError>) {
+    let mut parser = Parser {
+        src,
+        pos: 0,
+        line: 1,
+    };
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    while parser.peek().is_some() {
+        let (start, line) = (parser.pos, parser.line);
+        let stmt = match parser.statement(start) {
+            Ok(stmt) => stmt,
+            Err(Failure::Line(err)) => {
+                errors.push(err);
+                parser.skip_line();
+                Stmt::Text
+            }
+            Err(Failure::Fatal(err)) => {
+                // Unterminated construct: the rest of the file is unreadable
+                errors.push(err);
+                parser.pos = src.len();
+                Stmt::Text
+            }
+        };
+        lines.push(Line {
+            raw: src[start..parser.pos].to_string(),
+            line,
+            stmt,
+        });
+    }
+    (lines, errors)
+}
+
+/// Evaluates a word; `lookup` resolves variable names (`None` = unset).
+pub(crate) fn eval_word(word: &Word, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    for part in word {
+        match part {
+            Part::Lit(text) => out.push_str(text),
+            Part::Var { name, default } => {
+                let value = lookup(name);
+                match default {
+                    Some((colon, fallback)) => {
+                        let use_default = match &value {
+                            None => true,
+                            Some(v) => *colon && v.is_empty(),
+                        };
+                        if use_default {
+                            out.push_str(&eval_word(fallback, lookup));
+                        } else {
+                            out.push_str(value.as_deref().unwrap_or(""));
+                        }
+                    }
+                    None => out.push_str(value.as_deref().unwrap_or("")),
+                }
+            }
+        }
+    }
+    out
+}
+
+enum Failure {
+    /// Bad statement; parsing resumes on the next line.
+    Line(ConfigError),
+    /// Unterminated quote/array/expansion; parsing stops.
+    Fatal(ConfigError),
+}
+
+type Parsed<T> = Result<T, Failure>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    /// Scalar value or `source` path: ends at unquoted blanks.
+    Scalar,
+    /// Array element: also ends at `)`.
+    Array,
+    /// `${NAME:-...}` default: blanks are literal, ends at `}`.
+    Brace,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+fn is_blank(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\r'
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.src[self.pos..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> Failure {
+        Failure::Line(ConfigError::new(self.line, message))
+    }
+
+    fn unterminated(&self, what: &str, line: usize) -> Failure {
+        Failure::Fatal(ConfigError::new(line, format!("unterminated {}", what)))
+    }
+
+    fn skip_blanks(&mut self) {
+        while self.peek().is_some_and(is_blank) {
+            self.bump();
+        }
+    }
+
+    /// Skips to just past the end of the current line.
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            let ok =
+                c == '_' || c.is_ascii_alphabetic() || (!name.is_empty() && c.is_ascii_digit());
+            if !ok {
+                break;
+            }
+            name.push(c);
+            self.bump();
+        }
+        name
+    }
+
+    fn statement(&mut self, start: usize) -> Parsed<Stmt> {
+        self.skip_blanks();
+        match self.peek() {
+            None => return Ok(Stmt::Text),
+            Some('\n') => {
+                self.bump();
+                return Ok(Stmt::Text);
+            }
+            Some('#') => {
+                self.skip_line();
+                return Ok(Stmt::Text);
+            }
+            Some('.') if self.peek_second().is_some_and(is_blank) => {
+                self.bump();
+                return self.source();
+            }
+            _ => {}
+        }
+
+        let word_start = self.pos;
+        let word = self.identifier();
+        match word.as_str() {
+            "source" if self.peek().is_some_and(is_blank) => self.source(),
+            "export" | "readonly" | "declare" | "typeset" | "local"
+                if self.peek().is_some_and(is_blank) =>
+            {
+                self.skip_blanks();
+                // Flags such as `declare -a` / `export -n`
+                while self.peek() == Some('-') {
+                    while self.peek().is_some_and(|c| !is_blank(c) && c != '\n') {
+                        self.bump();
+                    }
+                    self.skip_blanks();
+                }
+                self.assignment(start, true)
+            }
+            _ => {
+                self.pos = word_start;
+                self.assignment(start, false)
+            }
+        }
+    }
+
+    fn source(&mut self) -> Parsed<Stmt> {
+        self.skip_blanks();
+        let path = self.word(Mode::Scalar)?;
+        if path.is_empty() {
+            return Err(self.error("'source' needs a file name"));
+        }
+        self.end_of_statement()?;
+        Ok(Stmt::Source(path))
+    }
+
+    fn assignment(&mut self, start: usize, allow_bare: bool) -> Parsed<Stmt> {
+        let name = self.identifier();
+        if name.is_empty() {
+            let found = self.peek().map(|c| format!("'{}'", c)).unwrap_or_default();
+            return Err(self.error(format!("expected KEY=VALUE, found {}", found)));
+        }
+        let append = self.peek() == Some('+') && self.peek_second() == Some('=');
+        if append {
+            self.bump();
+        }
+        if self.peek() != Some('=') {
+            if allow_bare {
+                // `export KEY` on its own: nothing to assign
+                self.end_of_statement()?;
+                return Ok(Stmt::Text);
+            }
+            return Err(self.error(format!("expected '=' after '{}'", name)));
+        }
+        self.bump();
+
+        let value_start = self.pos;
+        let value = if self.peek() == Some('(') {
+            self.bump();
+            Value::Array(self.array()?)
+        } else {
+            Value::Scalar(self.word(Mode::Scalar)?)
+        };
+        let span = (value_start - start, self.pos - start);
+        self.end_of_statement()?;
+        Ok(Stmt::Assign {
+            key: name,
+            append,
+            value,
+            span,
+        })
+    }
+
+    /// Blanks, an optional `# comment` (or `;`), then end of line.
+    fn end_of_statement(&mut self) -> Parsed<()> {
+        self.skip_blanks();
+        if self.peek() == Some(';') {
+            self.bump();
+            self.skip_blanks();
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') | Some('#') => {
+                self.skip_line();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!(
+                "unexpected '{}' after value (quote values that contain spaces)",
+                c
+            ))),
+        }
+    }
+
+    fn array(&mut self) -> Parsed<Vec<Word>> {
+        let open_line = self.line;
+        let mut items = Vec::new();
+        loop {
+            while self.peek().is_some_and(|c| is_blank(c) || c == '\n') {
+                self.bump();
+            }
+            match self.peek() {
+                None => return Err(self.unterminated("array '('", open_line)),
+                Some(')') => {
+                    self.bump();
+                    return Ok(items);
+                }
+                Some('#') => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                Some(c @ (';' | '(')) => {
+                    return Err(self.error(format!("unexpected '{}' in array", c)))
+                }
+                Some(_) => items.push(self.word(Mode::Array)?),
+            }
+        }
+    }
+
+    fn word(&mut self, mode: Mode) -> Parsed<Word> {
+        let mut parts = Vec::new();
+        let mut lit = String::new();
+        let start_line = self.line;
+        loop {
+            let Some(c) = self.peek() else {
+                if mode == Mode::Brace {
+                    return Err(self.unterminated("'${'", start_line));
+                }
+                break;
+            };
+            match c {
+                '}' if mode == Mode::Brace => break,
+                '\n' if mode == Mode::Brace => return Err(self.unterminated("'${'", start_line)),
+                ' ' | '\t' | '\r' if mode == Mode::Brace => {
+                    lit.push(c);
+                    self.bump();
+                }
+                ' ' | '\t' | '\r' | '\n' | ';' => break,
+                ')' if mode == Mode::Array => break,
+                '(' | ')' => return Err(self.error(format!("unexpected '{}' (quote it)", c))),
+                '\'' => {
+                    let line = self.line;
+                    self.bump();
+                    loop {
+                        match self.bump() {
+                            None => return Err(self.unterminated("single quote", line)),
+                            Some('\'') => break,
+                            Some(ch) => lit.push(ch),
+                        }
+                    }
+                }
+                '"' => self.double_quoted(&mut parts, &mut lit)?,
+                '\\' => {
+                    self.bump();
+                    match self.bump() {
+                        None | Some('\n') => {}
+                        Some(ch) => lit.push(ch),
+                    }
+                }
+                '$' => self.dollar(&mut parts, &mut lit, false)?,
+                '`' => return Err(self.error("command substitution is not supported")),
+                _ => {
+                    lit.push(c);
+                    self.bump();
+                }
+            }
+        }
+        flush(&mut parts, &mut lit);
+        Ok(parts)
+    }
+
+    fn double_quoted(&mut self, parts: &mut Word, lit: &mut String) -> Parsed<()> {
+        let line = self.line;
+        self.bump();
+        loop {
+            match self.peek() {
+                None => return Err(self.unterminated("double quote", line)),
+                Some('"') => {
+                    self.bump();
+                    return Ok(());
+                }
+                Some('\\') => {
+                    self.bump();
+                    match self.peek() {
+                        Some(c @ ('$' | '`' | '"' | '\\')) => {
+                            lit.push(c);
+                            self.bump();
+                        }
+                        Some('\n') => {
+                            self.bump();
+                        }
+                        _ => lit.push('\\'),
+                    }
+                }
+                Some('$') => self.dollar(parts, lit, true)?,
+                Some('`') => return Err(self.error("command substitution is not supported")),
+                Some(c) => {
+                    lit.push(c);
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    fn dollar(&mut self, parts: &mut Word, lit: &mut String, quoted: bool) -> Parsed<()> {
+        self.bump();
+        match self.peek() {
+            Some('\'') if !quoted => self.ansi_c(lit),
+            Some('(') => Err(self.error("command substitution is not supported")),
+            Some('{') => {
+                let line = self.line;
+                self.bump();
+                let name = self.identifier();
+                if name.is_empty() {
+                    return Err(self.error("bad substitution: expected a variable name after '${'"));
+                }
+                let default = match (self.peek(), self.peek_second()) {
+                    (Some('}'), _) => None,
+                    (Some(':'), Some('-')) => {
+                        self.bump();
+                        self.bump();
+                        Some((true, self.word(Mode::Brace)?))
+                    }
+                    (Some('-'), _) => {
+                        self.bump();
+                        Some((false, self.word(Mode::Brace)?))
+                    }
+                    (None, _) => return Err(self.unterminated("'${'", line)),
+                    (Some(c), _) => {
+                        return Err(
+                            self.error(format!("unsupported expansion '${{{}{}...}}'", name, c))
+                        )
+                    }
+                };
+                self.bump(); // '}'
+                flush(parts, lit);
+                parts.push(Part::Var { name, default });
+                Ok(())
+            }
+            Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                let name = self.identifier();
+                flush(parts, lit);
+                parts.push(Part::Var {
+                    name,
+                    default: None,
+                });
+                Ok(())
+            }
+            _ => {
+                lit.push('$');
+                Ok(())
+            }
+        }
+    }
+
+    /// `$'...'` with C escapes.
+    fn ansi_c(&mut self, lit: &mut String) -> Parsed<()> {
+        let line = self.line;
+        self.bump();
+        loop {
+            match self.bump() {
+                None => return Err(self.unterminated("$'...' string", line)),
+                Some('\'') => return Ok(()),
+                Some('\\') => match self.bump() {
+                    None => return Err(self.unterminated("$'...' string", line)),
+                    Some('n') => lit.push('\n'),
+                    Some('t') => lit.push('\t'),
+                    Some('r') => lit.push('\r'),
+                    Some('e') | Some('E') => lit.push('\x1b'),
+                    Some('0') => lit.push('\0'),
+                    Some(c @ ('\\' | '\'' | '"')) => lit.push(c),
+                    Some(c) => {
+                        lit.push('\\');
+                        lit.push(c);
+                    }
+                },
+                Some(c) => lit.push(c),
+            }
+        }
+    }
+}
+
+fn flush(parts: &mut Word, lit: &mut String) {
+    if !lit.is_empty() {
+        parts.push(Part::Lit(std::mem::take(lit)));
+    }
+}
@@ -33,7 +33,10 @@ macro_rules! src {
 
 #[macro_export]
 macro_rules! load_config {
-    ($($path:expr),+) => { $( $crate::global::load_config_file($path); )+ };
+    ($($path:expr),+) => {{
+        $( $crate::global::load_config_file($path); )+
+        $crate::global::validate_schema();
+    }};
 }
 
 // --- Validation Macros ---
@@ -13,6 +13,9 @@ pub use utils::*;
 mod config;
 pub use config::*;
 
+pub mod schema;
+pub use schema::*;
+
 mod adapter;
 pub use adapter::*;
 
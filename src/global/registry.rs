@@ -1,9 +1,16 @@
-//! Global registries: functions, call stack, and visual registries
+//! Global registries: functions, command handlers, call stack, and visual registries
+//!
+//! The handler table is the one command table shared by `dispatch!`, flag
+//! handling (`--help`/`--version`) and the REPL: anything registered here can
+//! be called by name from any of them.
 
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+/// A callable command; the same signature as `dispatch!` handlers.
+pub type CommandFn = fn(crate::cli::Args) -> i32;
+
 #[derive(Debug, Clone)]
 pub struct CallFrame {
     pub function: String,
@@ -15,6 +22,8 @@ pub struct CallFrame {
 lazy_static! {
     pub(crate) static ref FUNCTION_REGISTRY: Arc<Mutex<HashMap<String, String>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    pub(crate) static ref HANDLER_REGISTRY: Arc<Mutex<HashMap<String, CommandFn>>> =
+        Arc::new(Mutex::new(HashMap::new()));
     pub(crate) static ref CALL_STACK: Arc<Mutex<Vec<CallFrame>>> = Arc::new(Mutex::new(Vec::new()));
     pub(crate) static ref COLORS: Arc<Mutex<HashMap<String, String>>> =
         Arc::new(Mutex::new(initialize_default_colors()));
@@ -65,6 +74,44 @@ pub fn list_functions() -> Vec<(String, String)> {
     funcs
 }
 
+/// Registers a callable handler under `name` (replacing any previous one).
+/// The name is also listed by `inspect` if it isn't already.
+pub fn register_handler(name: &str, handler: CommandFn) {
+    HANDLER_REGISTRY
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), handler);
+    let mut functions = FUNCTION_REGISTRY.lock().unwrap();
+    functions.entry(name.to_string()).or_default();
+}
+
+pub fn unregister_handler(name: &str) {
+    HANDLER_REGISTRY.lock().unwrap().remove(name);
+}
+
+pub fn get_handler(name: &str) -> Option<CommandFn> {
+    HANDLER_REGISTRY.lock().unwrap().get(name).copied()
+}
+
+pub fn has_handler(name: &str) -> bool {
+    HANDLER_REGISTRY.lock().unwrap().contains_key(name)
+}
+
+/// Calls the handler registered under `name`; `None` if there is none.
+pub fn call_handler(name: &str, args: crate::cli::Args) -> Option<i32> {
+    // Copy the pointer out so the handler can use the registry itself
+    let handler = get_handler(name)?;
+    Some(handler(args))
+}
+
+/// Names of all registered handlers, sorted.
+pub fn list_handlers() -> Vec<String> {
+    let mut names: Vec<String> = HANDLER_REGISTRY.lock().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Pushes a call frame; logged as a `trace` span record (`rsb::span`).
 pub fn push_call(function: &str, args: &[String]) {
     let frame = CallFrame {
         function: function.to_string(),
@@ -73,10 +120,21 @@ pub fn push_call(function: &str, args: &[String]) {
         context_snapshot: crate::global::get_all_vars(),
     };
     CALL_STACK.lock().unwrap().push(frame);
+    crate::log::span_event(true, function, &[("args", args.join(" "))]);
 }
 
+/// Pops the innermost call frame, logging its exit with the elapsed time.
 pub fn pop_call() -> Option<CallFrame> {
-    CALL_STACK.lock().unwrap().pop()
+    let frame = CALL_STACK.lock().unwrap().pop()?;
+    let elapsed = frame.timestamp.elapsed().map(|d| d.as_millis()).unwrap_or(0);
+    crate::log::span_event(false, &frame.function, &[("elapsed_ms", elapsed.to_string())]);
+    Some(frame)
+}
+
+/// Function names on the call stack, outermost first.
+pub fn call_path() -> Vec<String> {
+    let stack = CALL_STACK.lock().unwrap();
+    stack.iter().map(|frame| frame.function.clone()).collect()
 }
 
 pub fn get_call_stack() -> Vec<CallFrame> {
@@ -0,0 +1,359 @@
+//! Typed schema for the global store
+//!
+//! Values in Global are strings; a schema declares what some keys should
+//! hold (type, default, required, description) so misconfiguration is caught
+//! up front. Once registered, `load_config!` and `cli_bootstrap` fill in
+//! defaults and report every problem at once. The typed getters
+//! (`get_int`, `get_list`, `get_path`, ...) fall back to schema defaults.
+
+use lazy_static::lazy_static;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::com::parse_bool_val;
+use crate::global::{expand_vars, get_array, get_var, has_var, set_var};
+
+/// The type a schema key must parse as.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueType {
+    /// Any string
+    Str,
+    Int,
+    Float,
+    /// true/false/yes/no/on/off/1/0 (see `com::is_true_val`)
+    Bool,
+    /// Non-empty path; `$VAR` and `~` are expanded by `get_path`
+    Path,
+    /// One of a fixed set of values
+    Enum(Vec<String>),
+    /// Bash array (`KEY_LENGTH`/`KEY_<i>`) or a comma-separated string
+    List,
+}
+
+impl ValueType {
+    /// Short name used in help and error output.
+    pub fn name(&self) -> String {
+        match self {
+            ValueType::Str => "string".to_string(),
+            ValueType::Int => "int".to_string(),
+            ValueType::Float => "float".to_string(),
+            ValueType::Bool => "bool".to_string(),
+            ValueType::Path => "path".to_string(),
+            ValueType::Enum(choices) => choices.join("|"),
+            ValueType::List => "list".to_string(),
+        }
+    }
+
+    /// Checks a raw value, returning a message describing the problem.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match self {
+            ValueType::Str | ValueType::List => Ok(()),
+            ValueType::Int => value
+                .parse::<i64>()
+                .map(|_| ())
+                .map_err(|_| format!("expected an int, got '{}'", value)),
+            ValueType::Float => value
+                .parse::<f64>()
+                .map(|_| ())
+                .map_err(|_| format!("expected a float, got '{}'", value)),
+            ValueType::Bool => match parse_bool_val(value) {
+                Some(_) => Ok(()),
+                None => Err(format!("expected a bool (true/false), got '{}'", value)),
+            },
+            ValueType::Path if value.is_empty() => Err("expected a path, got ''".to_string()),
+            ValueType::Path => Ok(()),
+            ValueType::Enum(choices) if choices.iter().any(|c| c == value) => Ok(()),
+            ValueType::Enum(choices) => Err(format!(
+                "expected one of {}, got '{}'",
+                choices.join("|"),
+                value
+            )),
+        }
+    }
+}
+
+/// One key in a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySpec {
+    pub key: String,
+    pub ty: ValueType,
+    pub default: Option<String>,
+    pub required: bool,
+    pub description: String,
+}
+
+impl KeySpec {
+    pub fn new(key: &str, ty: ValueType) -> Self {
+        Self {
+            key: key.to_string(),
+            ty,
+            default: None,
+            required: false,
+            description: String::new(),
+        }
+    }
+
+    pub fn string(key: &str) -> Self {
+        Self::new(key, ValueType::Str)
+    }
+    pub fn int(key: &str) -> Self {
+        Self::new(key, ValueType::Int)
+    }
+    pub fn float(key: &str) -> Self {
+        Self::new(key, ValueType::Float)
+    }
+    pub fn bool(key: &str) -> Self {
+        Self::new(key, ValueType::Bool)
+    }
+    pub fn path(key: &str) -> Self {
+        Self::new(key, ValueType::Path)
+    }
+    pub fn list(key: &str) -> Self {
+        Self::new(key, ValueType::List)
+    }
+    pub fn one_of(key: &str, choices: &[&str]) -> Self {
+        Self::new(
+            key,
+            ValueType::Enum(choices.iter().map(|c| c.to_string()).collect()),
+        )
+    }
+
+    pub fn default(mut self, value: &str) -> Self {
+        self.default = Some(value.to_string());
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn describe(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Checks the key's current value in Global.
+    fn check(&self) -> Option<SchemaIssue> {
+        let issue = |message: String| SchemaIssue {
+            key: self.key.clone(),
+            message,
+        };
+        if !has_var(&self.key) {
+            return match self.required {
+                true => Some(issue("required but not set".to_string())),
+                false => None,
+            };
+        }
+        if self.ty == ValueType::List && has_var(&format!("{}_LENGTH", self.key)) {
+            return None;
+        }
+        self.ty.check(&get_var(&self.key)).err().map(issue)
+    }
+}
+
+/// A problem with one key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaIssue {
+    pub key: String,
+    pub message: String,
+}
+
+impl Display for SchemaIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// Every problem found by a validation pass.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SchemaReport {
+    pub issues: Vec<SchemaIssue>,
+}
+
+impl SchemaReport {
+    /// Colored multi-line summary (tags are stripped without `colors-core`).
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "{{red}}{{bold}}Configuration has {} problem{}:{{reset}}",
+            self.issues.len(),
+            if self.issues.len() == 1 { "" } else { "s" }
+        );
+        for issue in &self.issues {
+            let spec = schema_key(&issue.key);
+            out.push_str(&format!(
+                "\n  {{yellow}}{}{{reset}} {}",
+                issue.key, issue.message
+            ));
+            if let Some(spec) = spec.filter(|s| !s.description.is_empty()) {
+                out.push_str(&format!(
+                    " {{grey}}({}: {}){{reset}}",
+                    spec.ty.name(),
+                    spec.description
+                ));
+            }
+        }
+        crate::utils::expand_colors_unified(&out)
+    }
+}
+
+impl Display for SchemaReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let lines: Vec<String> = self.issues.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// A set of key specs, built with `Schema::new().key(...)`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Schema {
+    pub keys: Vec<KeySpec>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds (or replaces) a key spec.
+    pub fn key(mut self, spec: KeySpec) -> Self {
+        self.keys.retain(|k| k.key != spec.key);
+        self.keys.push(spec);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&KeySpec> {
+        self.keys.iter().find(|k| k.key == key)
+    }
+
+    /// Sets defaults for keys that are unset.
+    pub fn apply_defaults(&self) {
+        for spec in &self.keys {
+            if let Some(default) = &spec.default {
+                if !has_var(&spec.key) {
+                    set_var(&spec.key, default);
+                }
+            }
+        }
+    }
+
+    /// Checks every key against Global and collects all problems.
+    pub fn validate(&self) -> Result<(), SchemaReport> {
+        let issues: Vec<SchemaIssue> = self.keys.iter().filter_map(KeySpec::check).collect();
+        match issues.is_empty() {
+            true => Ok(()),
+            false => Err(SchemaReport { issues }),
+        }
+    }
+}
+
+lazy_static! {
+    static ref SCHEMA: Mutex<Option<Schema>> = Mutex::new(None);
+}
+
+/// Registers `schema` for the process; keys merge into any registered before.
+pub fn register_schema(schema: Schema) {
+    let mut current = SCHEMA.lock().unwrap();
+    let merged = schema
+        .keys
+        .into_iter()
+        .fold(current.take().unwrap_or_default(), Schema::key);
+    *current = Some(merged);
+}
+
+/// Removes the registered schema.
+pub fn clear_schema() {
+    *SCHEMA.lock().unwrap() = None;
+}
+
+/// A copy of the registered schema, if any.
+pub fn registered_schema() -> Option<Schema> {
+    SCHEMA.lock().unwrap().clone()
+}
+
+fn schema_key(key: &str) -> Option<KeySpec> {
+    SCHEMA.lock().unwrap().as_ref()?.get(key).cloned()
+}
+
+/// Applies defaults and validates Global against the registered schema.
+/// Succeeds when no schema is registered.
+pub fn try_validate_schema() -> Result<(), SchemaReport> {
+    match registered_schema() {
+        Some(schema) => {
+            schema.apply_defaults();
+            schema.validate()
+        }
+        None => Ok(()),
+    }
+}
+
+/// `try_validate_schema`, printing the colored summary to stderr on failure.
+/// Returns whether the store is valid.
+pub fn validate_schema() -> bool {
+    match try_validate_schema() {
+        Ok(()) => true,
+        Err(report) => {
+            eprintln!("{}", report.summary());
+            false
+        }
+    }
+}
+
+/// The key's value, or its schema default when unset.
+fn raw_value(key: &str) -> Option<String> {
+    if has_var(key) {
+        return Some(get_var(key));
+    }
+    schema_key(key).and_then(|spec| spec.default)
+}
+
+/// Reads `key` as an int (falls back to the schema default).
+pub fn get_int(key: &str) -> Option<i64> {
+    raw_value(key)?.trim().parse().ok()
+}
+
+/// Reads `key` as a float (falls back to the schema default).
+pub fn get_float(key: &str) -> Option<f64> {
+    raw_value(key)?.trim().parse().ok()
+}
+
+/// Reads `key` as a bool (falls back to the schema default); `None` if the
+/// value isn't recognisably true or false.
+pub fn get_bool(key: &str) -> Option<bool> {
+    parse_bool_val(raw_value(key)?.trim())
+}
+
+/// Reads `key` as a list: a bash array if `KEY_LENGTH` is set, otherwise
+/// the value split on commas (or whitespace when there are none).
+pub fn get_list(key: &str) -> Vec<String> {
+    if has_var(&format!("{}_LENGTH", key)) {
+        return get_array(key);
+    }
+    let value = raw_value(key).unwrap_or_default();
+    if value.contains(',') {
+        value
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    } else {
+        value.split_whitespace().map(String::from).collect()
+    }
+}
+
+/// Reads `key` as a path with `$VAR` and a leading `~` expanded (falls back
+/// to the schema default). `None` when unset or empty.
+pub fn get_path(key: &str) -> Option<PathBuf> {
+    let value = expand_vars(raw_value(key)?.trim());
+    if value.is_empty() {
+        return None;
+    }
+    let home = || std::env::var("HOME").unwrap_or_else(|_| get_var("HOME"));
+    let value = match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{}{}", home(), rest),
+        _ => value,
+    };
+    Some(PathBuf::from(value))
+}
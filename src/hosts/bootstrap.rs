@@ -1,7 +1,7 @@
 //! Host bootstrap orchestration
 //!
 //! Sequences environment import, XDG/RSB path setup, directory creation,
-//! mode flags, script context, and args context.
+//! mode flags, script context, args context, and logging setup.
 
 use crate::global;
 
@@ -64,6 +64,9 @@ pub fn bootstrap(args: &[String]) {
 
     // 7) Args context
     setup_args_context(args);
+
+    // 8) Logging (RSB_LOG, RSB_LOG_FILE, ...)
+    crate::log::init_from_env();
 }
 
 /// Bootstrap using `std::env::args()`.
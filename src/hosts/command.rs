@@ -9,6 +9,10 @@ use std::collections::HashMap;
 use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 
+// Argv-style process pipelines (no shell)
+pub mod pipeline;
+pub use pipeline::*;
+
 /// The result of a command execution, containing status, stdout, and stderr.
 #[derive(Debug, Clone)]
 pub struct CmdResult {
@@ -57,7 +61,8 @@ pub fn is_command(cmd: &str) -> bool {
 
 
 /// Executes a shell command and returns a `CmdResult`.
-/// Expands global variables in the command string.
+/// Expands global variables in the command string. In dry-run mode the
+/// command is only printed and reports status 0 with no output.
 pub fn run_cmd_with_status(cmd: &str) -> CmdResult {
     let expanded_cmd = global::expand_vars(cmd);
 
@@ -69,10 +74,18 @@ pub fn run_cmd_with_status(cmd: &str) -> CmdResult {
             error: String::new(),
         };
     }
+    if crate::hosts::dry_run_skip(&expanded_cmd) {
+        return CmdResult {
+            status: 0,
+            output: String::new(),
+            error: String::new(),
+        };
+    }
 
+    let started = std::time::Instant::now();
     let output = Command::new("sh").arg("-c").arg(&expanded_cmd).output();
 
-    match output {
+    let result = match output {
         Ok(out) => CmdResult {
             status: out.status.code().unwrap_or(1),
             output: String::from_utf8_lossy(&out.stdout).to_string(),
@@ -83,21 +96,56 @@ pub fn run_cmd_with_status(cmd: &str) -> CmdResult {
             output: String::new(),
             error: e.to_string(),
         },
-    }
+    };
+    crate::hosts::trace_done(&expanded_cmd, started, Some(result.status));
+    result
 }
 
 /// Executes a shell command and returns its stdout, exiting on error.
 pub fn run_cmd(cmd: &str) -> String {
     let result = run_cmd_with_status(cmd);
     if result.status != 0 {
-        crate::event!(emit "COMMAND_ERROR", "source" => "cmd!", "command" => cmd, "status" => &result.status.to_string(), "stderr" => &result.error);
-        eprintln!("Command failed: {}", cmd);
-        eprintln!("Stderr: {}", result.error);
-        std::process::exit(result.status);
+        fail_cmd(cmd, result.status, &result.error);
     }
     result.output
 }
 
+/// Reports a failed `cmd!` style command and exits with its status.
+fn fail_cmd(cmd: &str, status: i32, stderr: &str) -> ! {
+    crate::event!(emit "COMMAND_ERROR", "source" => "cmd!", "command" => cmd, "status" => &status.to_string(), "stderr" => stderr);
+    eprintln!("Command failed: {}", cmd);
+    eprintln!("Stderr: {}", stderr);
+    std::process::exit(status);
+}
+
+// === Streaming Execution ===
+
+/// Streams a shell command's stdout line by line, like a lazy `run_cmd`.
+/// Expands global variables and honors mocks; exits on failure once drained.
+pub fn stream_cmd(cmd: &str) -> CmdLines {
+    Pipeline::shell(&global::expand_vars(cmd)).strict().lines()
+}
+
+/// Executes a shell command, calling `on_line` for each stdout/stderr line as
+/// it arrives instead of after the command exits. Expands global variables.
+pub fn run_cmd_live<F>(cmd: &str, on_line: F) -> CmdResult
+where
+    F: FnMut(&OutputLine),
+{
+    Pipeline::shell(&global::expand_vars(cmd))
+        .run_live(on_line)
+        .into()
+}
+
+/// Executes a shell command with a wall-clock timeout. On expiry the whole
+/// process group is killed and the status is `TIMEOUT_STATUS` (124).
+pub fn run_cmd_with_timeout(cmd: &str, timeout: std::time::Duration) -> CmdResult {
+    Pipeline::shell(&global::expand_vars(cmd))
+        .timeout(timeout)
+        .run()
+        .into()
+}
+
 /// Executes a shell command and captures its output, similar to `$(...)` in bash.
 pub fn shell_exec(cmd: &str, silent: bool) -> Result<String, CmdResult> {
     let result = run_cmd_with_status(cmd);
@@ -0,0 +1,866 @@
+//! Process Pipelines
+//!
+//! Argv-style commands connected with OS pipes, the Rust side of bash's
+//! `a | b | c`. Nothing goes through `sh -c`, so arguments are passed
+//! verbatim (no `$VAR` expansion, globbing or injection). Results carry a
+//! bash-like `PIPESTATUS` vector and honor `pipefail`. Pipelines can also
+//! stream stdout/stderr lines to a callback as they arrive, tee them to a log
+//! file and enforce a wall-clock timeout on the whole process group.
+
+use super::{fail_cmd, CmdResult, MOCK_CMDS};
+use crate::streams::Stream;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Exit status reported when a pipeline hits its timeout (as coreutils `timeout`).
+pub const TIMEOUT_STATUS: i32 = 124;
+
+/// How long a timed-out process group gets between SIGTERM and SIGKILL.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// A single argv-style pipeline stage with its own env and working directory.
+#[derive(Debug, Clone)]
+pub struct PipeCmd {
+    program: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    cwd: Option<String>,
+}
+
+impl PipeCmd {
+    /// Creates a stage running `program` (looked up in PATH).
+    pub fn new(program: &str) -> Self {
+        PipeCmd {
+            program: program.to_string(),
+            args: Vec::new(),
+            env: Vec::new(),
+            cwd: None,
+        }
+    }
+
+    /// Creates a stage from an argv slice (`["grep", "-n", "TODO"]`).
+    pub fn from_argv(argv: &[&str]) -> Self {
+        let mut cmd = Self::new(argv.first().copied().unwrap_or_default());
+        cmd.args = argv.iter().skip(1).map(|a| a.to_string()).collect();
+        cmd
+    }
+
+    /// Appends one argument.
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// Appends several arguments.
+    pub fn args(mut self, args: &[&str]) -> Self {
+        self.args.extend(args.iter().map(|a| a.to_string()));
+        self
+    }
+
+    /// Sets an environment variable for this stage only.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Runs this stage in `dir` instead of the current directory.
+    pub fn cwd(mut self, dir: &str) -> Self {
+        self.cwd = Some(dir.to_string());
+        self
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+        if let Some(dir) = &self.cwd {
+            command.current_dir(dir);
+        }
+        command
+    }
+}
+
+impl fmt::Display for PipeCmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '\'') {
+                write!(f, " '{}'", arg.replace('\'', "'\\''"))?;
+            } else {
+                write!(f, " {}", arg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a pipeline's first stage reads stdin from.
+enum PipeInput {
+    Null,
+    Text(String),
+    Lines(Vec<String>),
+    Stream(Stream),
+}
+
+/// A line of live output, tagged with the stream it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputLine {
+    Stdout(String),
+    Stderr(String),
+}
+
+impl OutputLine {
+    /// The line without its trailing newline.
+    pub fn text(&self) -> &str {
+        match self {
+            OutputLine::Stdout(line) | OutputLine::Stderr(line) => line,
+        }
+    }
+
+    pub fn is_stderr(&self) -> bool {
+        matches!(self, OutputLine::Stderr(_))
+    }
+}
+
+/// The result of a pipeline run: final status, per-stage `PIPESTATUS`,
+/// stdout of the last stage and the combined stderr of every stage.
+/// `status` is `TIMEOUT_STATUS` (124) when the timeout killed the pipeline.
+#[derive(Debug, Clone)]
+pub struct PipeResult {
+    pub status: i32,
+    pub statuses: Vec<i32>,
+    pub output: String,
+    pub error: String,
+    pub timed_out: bool,
+}
+
+impl PipeResult {
+    /// True when the pipeline's effective status is 0.
+    pub fn success(&self) -> bool {
+        self.status == 0
+    }
+}
+
+impl From<PipeResult> for CmdResult {
+    fn from(result: PipeResult) -> Self {
+        CmdResult {
+            status: result.status,
+            output: result.output,
+            error: result.error,
+        }
+    }
+}
+
+/// Builder for a chain of processes connected stdout → stdin.
+///
+/// ```ignore
+/// let res = Pipeline::new()
+///     .argv(&["grep", "-v", "DEBUG"])
+///     .cmd(PipeCmd::new("sort").env("LC_ALL", "C"))
+///     .stdin_str(&log)
+///     .pipefail(true)
+///     .run();
+/// assert_eq!(res.statuses.len(), 2);
+/// ```
+pub struct Pipeline {
+    stages: Vec<PipeCmd>,
+    input: PipeInput,
+    pipefail: bool,
+    timeout: Option<Duration>,
+    tee: Option<String>,
+    label: Option<String>,
+    strict: bool,
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline.
+    pub fn new() -> Self {
+        Pipeline {
+            stages: Vec::new(),
+            input: PipeInput::Null,
+            pipefail: false,
+            timeout: None,
+            tee: None,
+            label: None,
+            strict: false,
+        }
+    }
+
+    /// Creates a single-stage `sh -c` pipeline for legacy shell strings.
+    /// The string is used verbatim; callers expand variables beforehand.
+    pub fn shell(cmd: &str) -> Self {
+        let mut pipeline = Self::new().cmd(PipeCmd::new("sh").arg("-c").arg(cmd));
+        pipeline.label = Some(cmd.to_string());
+        pipeline
+    }
+
+    /// Appends a stage.
+    pub fn cmd(mut self, cmd: PipeCmd) -> Self {
+        self.stages.push(cmd);
+        self
+    }
+
+    /// Appends a stage from an argv slice.
+    pub fn argv(self, argv: &[&str]) -> Self {
+        self.cmd(PipeCmd::from_argv(argv))
+    }
+
+    /// Feeds a string to the first stage's stdin.
+    pub fn stdin_str(mut self, input: &str) -> Self {
+        self.input = PipeInput::Text(input.to_string());
+        self
+    }
+
+    /// Feeds lines (newline-terminated) to the first stage's stdin.
+    pub fn stdin_lines(mut self, lines: Vec<String>) -> Self {
+        self.input = PipeInput::Lines(lines);
+        self
+    }
+
+    /// Feeds a `Stream` to the first stage's stdin. It is written lazily,
+    /// line by line as the stage reads, so large inputs are never buffered.
+    pub fn stdin_stream(mut self, stream: Stream) -> Self {
+        self.input = PipeInput::Stream(stream);
+        self
+    }
+
+    /// With pipefail the status is the rightmost non-zero stage status.
+    pub fn pipefail(mut self, on: bool) -> Self {
+        self.pipefail = on;
+        self
+    }
+
+    /// Kills the whole process group (SIGTERM, then SIGKILL after a short
+    /// grace period) once `limit` of wall-clock time has passed.
+    pub fn timeout(mut self, limit: Duration) -> Self {
+        self.timeout = Some(limit);
+        self
+    }
+
+    /// Copies every stdout/stderr line to `path` as it arrives (truncates).
+    pub fn tee(mut self, path: &str) -> Self {
+        self.tee = Some(path.to_string());
+        self
+    }
+
+    /// Exit like `run_cmd` when the pipeline fails (used by `cmd!`).
+    pub(crate) fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Runs the pipeline to completion and collects its output.
+    /// `Stream` input is written lazily from the calling thread.
+    /// Also records the per-stage statuses in the global `PIPESTATUS` array.
+    pub fn run(self) -> PipeResult {
+        if self.tee.is_some() {
+            return self.run_live(|_| {});
+        }
+        let label = self.to_string();
+        if let Some(output) = mocked(&label) {
+            return record(PipeResult {
+                status: 0,
+                statuses: vec![0; self.stages.len().max(1)],
+                output,
+                error: String::new(),
+                timed_out: false,
+            });
+        }
+
+        let started = Instant::now();
+        let mut procs = self.start(None);
+        let stdout = procs.stdout.take().map(|pipe| pump_in_background(pipe, None));
+        if let Some(stdin) = procs.stdin.take() {
+            write_input(&mut BufWriter::new(stdin), self.input);
+        }
+        let result = finish_collected(procs, None, stdout, self.pipefail);
+        crate::hosts::trace_done(&label, started, Some(result.status));
+        result
+    }
+
+    /// Runs the pipeline, calling `on_line` for every stdout and stderr line
+    /// as soon as it is produced (and teeing it when `tee` is set). Output is
+    /// still collected into the returned `PipeResult`.
+    pub fn run_live<F>(self, mut on_line: F) -> PipeResult
+    where
+        F: FnMut(&OutputLine),
+    {
+        let mut tee = self.tee.as_deref().and_then(open_tee);
+        let label = self.to_string();
+        if let Some(output) = mocked(&label) {
+            for line in output.lines() {
+                let line = OutputLine::Stdout(line.to_string());
+                tee_line(&mut tee, &line);
+                on_line(&line);
+            }
+            return record(PipeResult {
+                status: 0,
+                statuses: vec![0; self.stages.len().max(1)],
+                output,
+                error: String::new(),
+                timed_out: false,
+            });
+        }
+
+        let started = Instant::now();
+        let (tx, rx) = mpsc::channel();
+        let mut procs = self.start(Some(tx.clone()));
+        let stdin = procs
+            .stdin
+            .take()
+            .map(|pipe| feed_in_background(pipe, self.input));
+        let stdout = procs
+            .stdout
+            .take()
+            .map(|pipe| pump_in_background(pipe, Some(tx.clone())));
+        drop(tx);
+
+        // Ends once every stdout/stderr reader hits EOF.
+        for line in rx {
+            tee_line(&mut tee, &line);
+            on_line(&line);
+        }
+        let result = finish_collected(procs, stdin, stdout, self.pipefail);
+        crate::hosts::trace_done(&label, started, Some(result.status));
+        result
+    }
+
+    /// Starts the pipeline in the background and returns a handle to it.
+    /// Input is buffered and written from a helper thread.
+    pub fn spawn(self) -> PipeChild {
+        let label = self.to_string();
+        let pipefail = self.pipefail;
+        if let Some(output) = mocked(&label) {
+            return PipeChild {
+                label,
+                pipefail,
+                procs: None,
+                stdin: None,
+                stdout: None,
+                mocked: Some(output),
+            };
+        }
+
+        crate::hosts::trace_start(&label);
+        let mut procs = self.start(None);
+        let stdin = procs
+            .stdin
+            .take()
+            .map(|pipe| feed_in_background(pipe, self.input));
+        let stdout = procs.stdout.take().map(|pipe| pump_in_background(pipe, None));
+        PipeChild {
+            label,
+            pipefail,
+            procs: Some(procs),
+            stdin,
+            stdout,
+            mocked: None,
+        }
+    }
+
+    /// Starts the pipeline and lazily yields the last stage's stdout lines.
+    pub fn lines(self) -> CmdLines {
+        let label = self.to_string();
+        let strict = self.strict;
+        let pipefail = self.pipefail;
+        if let Some(output) = mocked(&label) {
+            let mut lines = CmdLines::idle(&label, strict, pipefail);
+            lines.mocked = output
+                .lines()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .into_iter();
+            return lines;
+        }
+
+        crate::hosts::trace_start(&label);
+        let mut procs = self.start(None);
+        let mut lines = CmdLines::idle(&label, strict, pipefail);
+        lines.stdin = procs
+            .stdin
+            .take()
+            .map(|pipe| feed_in_background(pipe, self.input));
+        lines.stdout = procs.stdout.take().map(BufReader::new);
+        lines.procs = Some(procs);
+        lines
+    }
+
+    fn start(&self, live: Option<Sender<OutputLine>>) -> Spawned {
+        let has_input = !matches!(self.input, PipeInput::Null);
+        Spawned::start(&self.stages, has_input, self.timeout, live)
+    }
+}
+
+/// Handle to a pipeline started with `Pipeline::spawn`.
+///
+/// Dropping it without `wait` kills the process group of stages that are
+/// still running and reaps them, like `CmdLines`.
+pub struct PipeChild {
+    label: String,
+    pipefail: bool,
+    procs: Option<Spawned>,
+    stdin: Option<JoinHandle<()>>,
+    stdout: Option<JoinHandle<Vec<u8>>>,
+    mocked: Option<String>,
+}
+
+impl PipeChild {
+    /// The pipeline as it would be written in a shell (or the shell string).
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// PIDs of the stages that started, in pipeline order.
+    pub fn pids(&self) -> Vec<u32> {
+        self.procs
+            .as_ref()
+            .map(|procs| procs.children.iter().flatten().map(|c| c.id()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Process group shared by every stage, if any stage started.
+    pub fn pgid(&self) -> Option<i32> {
+        self.procs.as_ref().and_then(|procs| procs.pgid)
+    }
+
+    /// Non-blocking check: true while any stage is still running.
+    pub fn is_running(&mut self) -> bool {
+        let Some(procs) = self.procs.as_mut() else {
+            return false;
+        };
+        procs
+            .children
+            .iter_mut()
+            .flatten()
+            .any(|child| matches!(child.try_wait(), Ok(None)))
+    }
+
+    /// Sends `signal` to every stage (the whole process group).
+    pub fn signal(&self, signal: i32) -> Result<(), String> {
+        let Some(pgid) = self.pgid() else {
+            return Err(format!("'{}' has no running process", self.label));
+        };
+        if unsafe { libc::kill(-pgid, signal) } == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error().to_string())
+        }
+    }
+
+    /// Waits for every stage and collects the result.
+    pub fn wait(mut self) -> PipeResult {
+        match self.procs.take() {
+            Some(procs) => {
+                finish_collected(procs, self.stdin.take(), self.stdout.take(), self.pipefail)
+            }
+            None => record(PipeResult {
+                status: 0,
+                statuses: vec![0],
+                output: self.mocked.take().unwrap_or_default(),
+                error: String::new(),
+                timed_out: false,
+            }),
+        }
+    }
+}
+
+impl Drop for PipeChild {
+    fn drop(&mut self) {
+        // Only signal a group that still has members; its id may be reused
+        // once every stage has been reaped.
+        if self.is_running() {
+            if let Some(mut procs) = self.procs.take() {
+                procs.kill();
+            }
+        }
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            return write!(f, "{}", label);
+        }
+        let stages: Vec<String> = self.stages.iter().map(|s| s.to_string()).collect();
+        write!(f, "{}", stages.join(" | "))
+    }
+}
+
+/// Running processes of a pipeline sharing one process group.
+struct Spawned {
+    children: Vec<Result<Child, i32>>,
+    pgid: Option<i32>,
+    stdin: Option<ChildStdin>,
+    stdout: Option<ChildStdout>,
+    stderr: Vec<JoinHandle<Vec<u8>>>,
+    spawn_errors: String,
+    watchdog: Option<Watchdog>,
+}
+
+impl Spawned {
+    /// Spawns every stage; a stage that fails to start gets status 127/126
+    /// like bash and the next stage reads an empty stdin. With `live`, stderr
+    /// lines are forwarded as they arrive.
+    fn start(
+        stages: &[PipeCmd],
+        has_input: bool,
+        timeout: Option<Duration>,
+        live: Option<Sender<OutputLine>>,
+    ) -> Self {
+        let mut procs = Spawned {
+            children: Vec::new(),
+            pgid: None,
+            stdin: None,
+            stdout: None,
+            stderr: Vec::new(),
+            spawn_errors: String::new(),
+            watchdog: None,
+        };
+
+        let mut upstream: Option<ChildStdout> = None;
+        for (i, stage) in stages.iter().enumerate() {
+            let stdin = match upstream.take() {
+                Some(pipe) => Stdio::from(pipe),
+                None if i == 0 && has_input => Stdio::piped(),
+                None => Stdio::null(),
+            };
+            let spawned = stage
+                .command()
+                .stdin(stdin)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .process_group(procs.pgid.unwrap_or(0))
+                .spawn();
+
+            match spawned {
+                Ok(mut child) => {
+                    procs.pgid.get_or_insert(child.id() as i32);
+                    if i == 0 {
+                        procs.stdin = child.stdin.take();
+                    }
+                    if let Some(pipe) = child.stderr.take() {
+                        let live = live.clone();
+                        procs.stderr.push(std::thread::spawn(move || {
+                            pump(pipe, live.as_ref(), OutputLine::Stderr)
+                        }));
+                    }
+                    upstream = child.stdout.take();
+                    procs.children.push(Ok(child));
+                }
+                Err(e) => {
+                    let status = if e.kind() == std::io::ErrorKind::NotFound {
+                        127
+                    } else {
+                        126
+                    };
+                    procs
+                        .spawn_errors
+                        .push_str(&format!("rsb: {}: {}\n", stage.program, e));
+                    procs.children.push(Err(status));
+                }
+            }
+        }
+        procs.stdout = upstream;
+        if let (Some(limit), Some(pgid)) = (timeout, procs.pgid) {
+            procs.watchdog = Some(Watchdog::arm(pgid, limit));
+        }
+        procs
+    }
+
+    /// Waits for every stage and returns (PIPESTATUS, combined stderr, timed out).
+    fn finish(&mut self) -> (Vec<i32>, String, bool) {
+        self.stdin = None;
+        let statuses = self
+            .children
+            .iter_mut()
+            .map(|child| match child {
+                Ok(child) => child.wait().map(exit_code).unwrap_or(1),
+                Err(status) => *status,
+            })
+            .collect();
+        let timed_out = self
+            .watchdog
+            .take()
+            .map(|watchdog| watchdog.disarm())
+            .unwrap_or(false);
+        let mut error = std::mem::take(&mut self.spawn_errors);
+        for handle in self.stderr.drain(..) {
+            error.push_str(&String::from_utf8_lossy(&handle.join().unwrap_or_default()));
+        }
+        self.children.clear();
+        (statuses, error, timed_out)
+    }
+
+    /// Kills the whole process group (used when output is abandoned early).
+    fn kill(&mut self) {
+        if let Some(pgid) = self.pgid {
+            unsafe {
+                libc::kill(-pgid, libc::SIGKILL);
+            }
+        }
+        for child in self.children.iter_mut().flatten() {
+            let _ = child.wait();
+        }
+        self.children.clear();
+    }
+}
+
+/// Kills a process group once its time limit passes, unless disarmed first.
+struct Watchdog {
+    cancel: Sender<()>,
+    fired: Arc<AtomicBool>,
+}
+
+impl Watchdog {
+    fn arm(pgid: i32, limit: Duration) -> Self {
+        let (cancel, cancelled) = mpsc::channel::<()>();
+        let fired = Arc::new(AtomicBool::new(false));
+        let flag = fired.clone();
+        std::thread::spawn(move || {
+            if cancelled.recv_timeout(limit) != Err(RecvTimeoutError::Timeout) {
+                return;
+            }
+            flag.store(true, Ordering::SeqCst);
+            unsafe {
+                libc::kill(-pgid, libc::SIGTERM);
+            }
+            if cancelled.recv_timeout(KILL_GRACE) == Err(RecvTimeoutError::Timeout) {
+                unsafe {
+                    libc::kill(-pgid, libc::SIGKILL);
+                }
+            }
+        });
+        Watchdog { cancel, fired }
+    }
+
+    /// Stops the timer and reports whether it already fired.
+    fn disarm(self) -> bool {
+        drop(self.cancel);
+        self.fired.load(Ordering::SeqCst)
+    }
+}
+
+/// Lazily yields the stdout lines of a running pipeline as they are produced.
+///
+/// All stages run in one process group. Dropping the iterator before the
+/// output is exhausted (e.g. after `Stream::head`) kills the whole group, so
+/// `cmd!("tail -f app.log").grep("ERROR").head(1)` does not hang.
+pub struct CmdLines {
+    label: String,
+    strict: bool,
+    pipefail: bool,
+    procs: Option<Spawned>,
+    stdout: Option<BufReader<ChildStdout>>,
+    stdin: Option<JoinHandle<()>>,
+    mocked: std::vec::IntoIter<String>,
+}
+
+impl CmdLines {
+    fn idle(label: &str, strict: bool, pipefail: bool) -> Self {
+        CmdLines {
+            label: label.to_string(),
+            strict,
+            pipefail,
+            procs: None,
+            stdout: None,
+            stdin: None,
+            mocked: Vec::new().into_iter(),
+        }
+    }
+
+    /// Reaps the stages once stdout hits EOF; strict pipelines exit on failure.
+    fn finish(&mut self) {
+        self.stdout = None;
+        let Some(mut procs) = self.procs.take() else {
+            return;
+        };
+        let (statuses, error, timed_out) = procs.finish();
+        if let Some(handle) = self.stdin.take() {
+            let _ = handle.join();
+        }
+        let status = effective_status(&statuses, self.pipefail, timed_out);
+        record(PipeResult {
+            status,
+            statuses,
+            output: String::new(),
+            error: error.clone(),
+            timed_out,
+        });
+        if self.strict && status != 0 {
+            fail_cmd(&self.label, status, &error);
+        }
+    }
+}
+
+impl Iterator for CmdLines {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if let Some(line) = self.mocked.next() {
+            return Some(line);
+        }
+        let reader = self.stdout.as_mut()?;
+        let mut buf = Vec::new();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(n) if n > 0 => Some(strip_line(&buf)),
+            _ => {
+                self.finish();
+                None
+            }
+        }
+    }
+}
+
+impl Drop for CmdLines {
+    fn drop(&mut self) {
+        self.stdout = None;
+        if let Some(mut procs) = self.procs.take() {
+            procs.kill();
+        }
+    }
+}
+
+// --- helpers ---
+
+/// Reads a pipe to EOF, forwarding each line to `live` as it arrives, and
+/// returns the raw bytes.
+fn pump<R: Read>(pipe: R, live: Option<&Sender<OutputLine>>, tag: fn(String) -> OutputLine) -> Vec<u8> {
+    let mut reader = BufReader::new(pipe);
+    let mut all = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(n) if n > 0 => {
+                all.extend_from_slice(&buf);
+                if let Some(tx) = live {
+                    let _ = tx.send(tag(strip_line(&buf)));
+                }
+            }
+            _ => break,
+        }
+    }
+    all
+}
+
+fn pump_in_background(pipe: ChildStdout, live: Option<Sender<OutputLine>>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || pump(pipe, live.as_ref(), OutputLine::Stdout))
+}
+
+/// Decodes a raw line lossily, dropping the `\n` / `\r\n` terminator.
+fn strip_line(buf: &[u8]) -> String {
+    let line = buf.strip_suffix(b"\n").unwrap_or(buf);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).to_string()
+}
+
+fn open_tee(path: &str) -> Option<BufWriter<File>> {
+    if let Some(parent) = Path::new(path).parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match File::create(path) {
+        Ok(file) => Some(BufWriter::new(file)),
+        Err(e) => {
+            eprintln!("rsb-error: Failed to open tee file '{}': {}", path, e);
+            None
+        }
+    }
+}
+
+fn tee_line(tee: &mut Option<BufWriter<File>>, line: &OutputLine) {
+    if let Some(writer) = tee {
+        // Flush per line so the log is a live tail, not a post-mortem.
+        let _ = writeln!(writer, "{}", line.text()).and_then(|_| writer.flush());
+    }
+}
+
+/// Writes the input from a thread; a `Stream` is pulled lazily as the
+/// first stage reads it. A closed pipe just means the reader stopped early.
+fn feed_in_background(stdin: ChildStdin, input: PipeInput) -> JoinHandle<()> {
+    std::thread::spawn(move || write_input(&mut BufWriter::new(stdin), input))
+}
+
+fn finish_collected(
+    mut procs: Spawned,
+    stdin: Option<JoinHandle<()>>,
+    stdout: Option<JoinHandle<Vec<u8>>>,
+    pipefail: bool,
+) -> PipeResult {
+    if let Some(handle) = stdin {
+        let _ = handle.join();
+    }
+    let output = stdout
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default();
+    let (statuses, error, timed_out) = procs.finish();
+    record(PipeResult {
+        status: effective_status(&statuses, pipefail, timed_out),
+        statuses,
+        output: String::from_utf8_lossy(&output).to_string(),
+        error,
+        timed_out,
+    })
+}
+
+/// Canned output for `label`: its mock, or nothing at all in dry-run mode.
+fn mocked(label: &str) -> Option<String> {
+    let mock = MOCK_CMDS.lock().unwrap().get(label).cloned();
+    mock.or_else(|| crate::hosts::dry_run_skip(label).then(String::new))
+}
+
+/// Writes pipeline input; a closed pipe just means the reader stopped early.
+fn write_input(writer: &mut impl Write, input: PipeInput) {
+    let _ = match input {
+        PipeInput::Null => Ok(()),
+        PipeInput::Text(text) => writer.write_all(text.as_bytes()),
+        PipeInput::Lines(lines) => lines
+            .into_iter()
+            .try_for_each(|line| writeln!(writer, "{}", line)),
+        PipeInput::Stream(stream) => stream
+            .into_iter()
+            .try_for_each(|line| writeln!(writer, "{}", line)),
+    };
+    let _ = writer.flush();
+}
+
+/// Maps an exit status to a shell-style code (128 + signal when killed).
+fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .or_else(|| status.signal().map(|sig| 128 + sig))
+        .unwrap_or(1)
+}
+
+fn effective_status(statuses: &[i32], pipefail: bool, timed_out: bool) -> i32 {
+    if timed_out {
+        TIMEOUT_STATUS
+    } else if pipefail {
+        statuses.iter().rev().copied().find(|s| *s != 0).unwrap_or(0)
+    } else {
+        statuses.last().copied().unwrap_or(0)
+    }
+}
+
+fn record(result: PipeResult) -> PipeResult {
+    let statuses: Vec<String> = result.statuses.iter().map(|s| s.to_string()).collect();
+    let items: Vec<&str> = statuses.iter().map(|s| s.as_str()).collect();
+    crate::global::set_array("PIPESTATUS", &items);
+    result
+}
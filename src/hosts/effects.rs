@@ -0,0 +1,85 @@
+//! Dry-run and trace modes for side-effecting helpers
+//!
+//! The fs helpers (`write_file`, `rm_rf`, `mv`, `chmod`, ...), command
+//! execution (`run_cmd`, `Pipeline`) and the archive helpers route through
+//! here, so a tool can be previewed before it runs for real:
+//!
+//! - `opt_dry_run` (`--dry-run`, or `DRY_RUN` in the environment) prints
+//!   `[dry-run] <op>` instead of doing the operation. Helpers report success
+//!   and commands return status 0 with no output.
+//! - `opt_trace` (`--trace`, `-t`) echoes each operation `set -x` style once
+//!   it finishes: `+ rm -rf build (1.2ms)`.
+//!
+//! Both write straight to stderr, ignoring log filters and quiet mode: the
+//! user asked for this output. Command mocks (`set_mock_cmds`) still win over
+//! dry-run so tests see their canned output.
+
+use std::time::{Duration, Instant};
+
+use crate::com::is_true;
+use crate::global::has_var;
+
+/// Prefix of dry-run notes.
+pub const DRY_RUN_PREFIX: &str = "[dry-run]";
+
+/// Whether side effects are being skipped (`opt_dry_run` or `DRY_RUN_MODE`).
+pub fn is_dry_run() -> bool {
+    is_true("opt_dry_run") || has_var("DRY_RUN_MODE")
+}
+
+/// Whether operations are echoed as they run (`opt_trace`).
+pub fn is_trace() -> bool {
+    is_true("opt_trace")
+}
+
+/// In dry-run mode, prints what would happen and returns `true`: the caller
+/// skips the operation. Returns `false` otherwise.
+pub fn dry_run_skip(what: &str) -> bool {
+    if !is_dry_run() {
+        return false;
+    }
+    eprintln!("{} {}", DRY_RUN_PREFIX, what);
+    true
+}
+
+/// Echoes a finished operation in trace mode: `+ what (status 1, 4.0ms)`.
+/// `status` is the exit status for commands, `None` for file operations.
+pub fn trace_done(what: &str, started: Instant, status: Option<i32>) {
+    if !is_trace() {
+        return;
+    }
+    let elapsed = format_elapsed(started.elapsed());
+    match status {
+        Some(status) => eprintln!("+ {} (status {}, {})", what, status, elapsed),
+        None => eprintln!("+ {} ({})", what, elapsed),
+    }
+}
+
+/// Echoes an operation that was only started (a spawned or streamed
+/// command) in trace mode: `+ what &`.
+pub fn trace_start(what: &str) {
+    if is_trace() {
+        eprintln!("+ {} &", what);
+    }
+}
+
+/// Runs `op`, echoing it with its timing in trace mode.
+pub fn traced<T>(what: &str, op: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let out = op();
+    trace_done(what, started, None);
+    out
+}
+
+/// The common wrapper: in dry-run mode prints `what` and returns `dry`
+/// without calling `op`; otherwise runs `op`, traced.
+pub fn side_effect<T>(what: &str, dry: T, op: impl FnOnce() -> T) -> T {
+    match dry_run_skip(what) {
+        true => dry,
+        false => traced(what, op),
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    format!("{:.1?}", elapsed)
+}
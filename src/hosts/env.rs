@@ -18,7 +18,7 @@ pub fn import_environment() {
 }
 
 /// Apply standard mode flags from environment into global (Rust-native booleans).
-/// Sets: DEBUG_MODE, DEV_MODE, QUIET_MODE, TRACE_MODE to "true" when present.
+/// Sets: DEBUG_MODE, DEV_MODE, DRY_RUN_MODE, QUIET_MODE, TRACE_MODE to "true" when present.
 pub fn setup_standard_modes() {
     if std::env::var("DEBUG").is_ok() {
         crate::global::set_var("DEBUG_MODE", crate::com::TRUE_STR);
@@ -26,6 +26,9 @@ pub fn setup_standard_modes() {
     if std::env::var("DEV").is_ok() {
         crate::global::set_var("DEV_MODE", crate::com::TRUE_STR);
     }
+    if std::env::var("DRY_RUN").is_ok() {
+        crate::global::set_var("DRY_RUN_MODE", crate::com::TRUE_STR);
+    }
     if std::env::var("QUIET").is_ok() {
         crate::global::set_var("QUIET_MODE", crate::com::TRUE_STR);
     }
@@ -168,22 +168,6 @@ macro_rules! require_command {
     };
 }
 
-// === JSON Macros (jq-backed helpers) ===
-
-#[macro_export]
-macro_rules! json_get {
-    ($json:expr, $path:expr) => {
-        $crate::bash::jq::jq_get($json, $path)
-    };
-}
-
-#[macro_export]
-macro_rules! json_get_file {
-    ($file:expr, $path:expr) => {
-        $crate::bash::jq::jq_get_file($file, $path)
-    };
-}
-
 // === Test Helpers ===
 
 #[macro_export]
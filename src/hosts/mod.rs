@@ -21,17 +21,21 @@ pub mod macros;
 
 // Implementation modules (kept private)
 mod bootstrap;
+mod effects;
 mod env;
 mod host_path;
 mod rsb_path;
+mod terminal;
 mod xdg_path;
 
 // Curated surface - re-export stable host helpers
 pub use bootstrap::*;
 pub use command::*;
+pub use effects::*;
 pub use env::*;
 pub use host_global::*;
 pub use host_path::*;
 pub use os::*;
 pub use rsb_path::*;
+pub use terminal::*;
 pub use xdg_path::*;
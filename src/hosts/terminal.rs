@@ -0,0 +1,139 @@
+//! Terminal Capability Detection
+//!
+//! What the attached terminal can show: color depth, unicode, width and
+//! whether stdout is a terminal at all. Detected once from the environment
+//! (`NO_COLOR`, `COLORTERM`, `TERM`, `LC_ALL`/`LC_CTYPE`/`LANG`, `COLUMNS`)
+//! and cached; `colors`, `glyphs` and the progress reporter consult it.
+//! `set_term_caps` overrides the detected values (tests, `--color=…` flags).
+
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref TERM_CAPS: RwLock<Option<TermCaps>> = RwLock::new(None);
+}
+
+/// How many colors the terminal can show, in increasing order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorDepth {
+    /// No color at all
+    None,
+    /// The 8/16 standard colors
+    Basic,
+    /// The 256-color palette
+    Ansi256,
+    /// 24-bit RGB
+    TrueColor,
+}
+
+/// Capabilities of the terminal attached to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermCaps {
+    pub color: ColorDepth,
+    pub unicode: bool,
+    /// Columns when detected, else 80
+    pub width: usize,
+    /// Whether stdout is a terminal
+    pub is_tty: bool,
+}
+
+/// Color depth implied by `TERM` and `COLORTERM` (`NO_COLOR` is handled by
+/// `detect_term_caps`).
+pub fn color_depth_from(term: &str, colorterm: &str) -> ColorDepth {
+    let colorterm = colorterm.to_ascii_lowercase();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return ColorDepth::TrueColor;
+    }
+    let term = term.to_ascii_lowercase();
+    if term.is_empty() || term == "dumb" {
+        return ColorDepth::None;
+    }
+    if term.contains("truecolor") || term.contains("direct") || term.starts_with("xterm-kitty") {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Basic
+    }
+}
+
+/// Whether a locale value (`en_US.UTF-8`, `C.utf8`) selects UTF-8.
+pub fn locale_is_utf8(locale: &str) -> bool {
+    let locale = locale.to_ascii_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+fn env(name: &str) -> String {
+    std::env::var(name).unwrap_or_default()
+}
+
+/// Detects the capabilities from the environment, ignoring the cache.
+pub fn detect_term_caps() -> TermCaps {
+    let color = if std::env::var_os("NO_COLOR").is_some() {
+        ColorDepth::None
+    } else {
+        color_depth_from(&env("TERM"), &env("COLORTERM"))
+    };
+    // The first locale variable that is set decides, as for libc
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .map(|name| env(name))
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    TermCaps {
+        color,
+        unicode: locale_is_utf8(&locale),
+        width: term_width(),
+        is_tty: stdout_is_tty(),
+    }
+}
+
+/// The cached capabilities, detected on first use.
+pub fn term_caps() -> TermCaps {
+    if let Some(caps) = *TERM_CAPS.read().unwrap() {
+        return caps;
+    }
+    let caps = detect_term_caps();
+    *TERM_CAPS.write().unwrap() = Some(caps);
+    caps
+}
+
+/// Replaces the cached capabilities.
+pub fn set_term_caps(caps: TermCaps) {
+    *TERM_CAPS.write().unwrap() = Some(caps);
+}
+
+/// Drops the cache so the next `term_caps` detects again.
+pub fn refresh_term_caps() {
+    *TERM_CAPS.write().unwrap() = None;
+}
+
+pub fn stdout_is_tty() -> bool {
+    // SAFETY: isatty only inspects the descriptor.
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+/// Terminal size as `(cols, rows)` from the stdout window, defaulting to
+/// 80x24.
+pub fn terminal_size() -> (usize, usize) {
+    // SAFETY: winsize is plain data filled by the ioctl.
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0
+            && size.ws_col > 0
+            && size.ws_row > 0
+        {
+            return (size.ws_col as usize, size.ws_row as usize);
+        }
+    }
+    (80, 24)
+}
+
+/// Current width in columns: `COLUMNS` when set, else the window size.
+pub fn term_width() -> usize {
+    match env("COLUMNS").trim().parse::<usize>() {
+        Ok(cols) if cols > 0 => cols,
+        _ => terminal_size().0,
+    }
+}
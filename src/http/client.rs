@@ -0,0 +1,540 @@
+//! HTTP/1.1 Client
+//!
+//! A small blocking client over `std::net::TcpStream`: request builder,
+//! redirects, timeouts, basic/bearer auth, JSON bodies, chunked responses
+//! and streaming downloads with a progress callback. Plain `http://` only;
+//! there is no TLS in this client.
+
+use crate::hosts::command::CmdResult;
+use base64::Engine;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Default connect/read/write timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default number of redirects followed before giving up.
+pub const DEFAULT_MAX_REDIRECTS: usize = 5;
+
+/// Why a request failed before a response could be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HttpError {
+    InvalidUrl(String),
+    Connect(String),
+    Timeout,
+    TooManyRedirects(usize),
+    Protocol(String),
+    Io(String),
+}
+
+impl HttpError {
+    /// The exit code `curl` uses for the same failure, for `CmdResult`
+    /// compatibility (7 connect, 28 timeout, 47 redirects, 3 bad URL).
+    pub fn curl_code(&self) -> i32 {
+        match self {
+            HttpError::InvalidUrl(_) => 3,
+            HttpError::Connect(_) => 7,
+            HttpError::Timeout => 28,
+            HttpError::TooManyRedirects(_) => 47,
+            HttpError::Protocol(_) => 8,
+            HttpError::Io(_) => 56,
+        }
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::InvalidUrl(msg) => write!(f, "invalid URL: {}", msg),
+            HttpError::Connect(msg) => write!(f, "connection failed: {}", msg),
+            HttpError::Timeout => write!(f, "request timed out"),
+            HttpError::TooManyRedirects(n) => write!(f, "too many redirects ({})", n),
+            HttpError::Protocol(msg) => write!(f, "bad HTTP response: {}", msg),
+            HttpError::Io(msg) => write!(f, "I/O error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+fn io_error(err: std::io::Error) -> HttpError {
+    match err.kind() {
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => HttpError::Timeout,
+        _ => HttpError::Io(err.to_string()),
+    }
+}
+
+/// A parsed `http://host[:port]/path?query` URL.
+#[derive(Debug, Clone, PartialEq)]
+struct Url {
+    host: String,
+    port: u16,
+    target: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> Result<Url, HttpError> {
+        let rest = match url.split_once("://") {
+            Some(("http", rest)) => rest,
+            Some((scheme, _)) => {
+                return Err(HttpError::InvalidUrl(format!(
+                    "unsupported scheme '{}' (native client speaks plain http)",
+                    scheme
+                )))
+            }
+            None => return Err(HttpError::InvalidUrl(url.to_string())),
+        };
+        let (authority, target) = match rest.find(['/', '?']) {
+            Some(i) => (&rest[..i], rest[i..].to_string()),
+            None => (rest, String::new()),
+        };
+        let target = match target.split('#').next().unwrap_or("") {
+            "" => "/".to_string(),
+            t if t.starts_with('?') => format!("/{}", t),
+            t => t.to_string(),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| HttpError::InvalidUrl(format!("bad port in '{}'", url)))?;
+                (host, port)
+            }
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(HttpError::InvalidUrl(format!("missing host in '{}'", url)));
+        }
+        Ok(Url {
+            host: host.to_string(),
+            port,
+            target,
+        })
+    }
+
+    fn host_header(&self) -> String {
+        if self.port == 80 {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Resolves a `Location` header against this URL.
+    fn join(&self, location: &str) -> String {
+        if location.contains("://") {
+            location.to_string()
+        } else if location.starts_with("//") {
+            format!("http:{}", location)
+        } else if location.starts_with('/') {
+            format!("http://{}{}", self.host_header(), location)
+        } else {
+            let path = self.target.split('?').next().unwrap_or("/");
+            let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+            format!("http://{}{}{}", self.host_header(), dir, location)
+        }
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "http://{}{}", self.host_header(), self.target)
+    }
+}
+
+/// A received response. `body` is empty for downloads (written to disk).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Final URL after redirects.
+    pub url: String,
+}
+
+impl HttpResponse {
+    /// First header with this name (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 2xx status.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Body as text (lossy UTF-8).
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Body parsed as JSON.
+    pub fn json(&self) -> Result<serde_json::Value, String> {
+        serde_json::from_slice(&self.body).map_err(|e| format!("invalid JSON body: {}", e))
+    }
+}
+
+/// Converts a request outcome into a `CmdResult` the way `curl -s` reports
+/// it: any HTTP response is status 0 with the body as output; transport
+/// failures use curl's exit codes.
+pub fn to_cmd_result(result: Result<HttpResponse, HttpError>) -> CmdResult {
+    match result {
+        Ok(response) => CmdResult {
+            status: 0,
+            output: response.text(),
+            error: String::new(),
+        },
+        Err(err) => CmdResult {
+            status: err.curl_code(),
+            output: String::new(),
+            error: err.to_string(),
+        },
+    }
+}
+
+/// Request builder; start with `http::get(url)`, `http::post(url)`, ...
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    timeout: Duration,
+    max_redirects: usize,
+}
+
+impl HttpRequest {
+    pub fn new(method: &str, url: &str) -> Self {
+        HttpRequest {
+            method: method.to_uppercase(),
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+        }
+    }
+
+    /// Adds a header (replacing one with the same name).
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn basic_auth(self, user: &str, password: &str) -> Self {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password));
+        self.header("Authorization", &format!("Basic {}", token))
+    }
+
+    pub fn bearer(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// Raw request body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// JSON request body with a matching `Content-Type`.
+    pub fn json(self, value: &serde_json::Value) -> Self {
+        self.header("Content-Type", "application/json")
+            .body(value.to_string())
+    }
+
+    /// URL-encoded form body.
+    pub fn form(self, fields: &[(&str, &str)]) -> Self {
+        let encoded = fields
+            .iter()
+            .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        self.header("Content-Type", "application/x-www-form-urlencoded")
+            .body(encoded)
+    }
+
+    /// Connect/read/write timeout (per operation).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Maximum redirects to follow; 0 returns the 3xx response as-is.
+    pub fn max_redirects(mut self, max: usize) -> Self {
+        self.max_redirects = max;
+        self
+    }
+
+    /// Sends the request and buffers the whole body.
+    pub fn send(&self) -> Result<HttpResponse, HttpError> {
+        let mut body = Vec::new();
+        let mut response = self.execute(&mut body, &mut |_, _| {})?;
+        response.body = body;
+        Ok(response)
+    }
+
+    /// Streams the response body to `path`. Non-2xx responses are returned
+    /// without touching the file.
+    pub fn download(&self, path: &str) -> Result<HttpResponse, HttpError> {
+        self.download_with_progress(path, |_, _| {})
+    }
+
+    /// Like `download`, calling `on_progress(bytes_so_far, total)` as data
+    /// arrives (`total` comes from `Content-Length` when present).
+    pub fn download_with_progress<F>(&self, path: &str, mut on_progress: F) -> Result<HttpResponse, HttpError>
+    where
+        F: FnMut(u64, Option<u64>),
+    {
+        let path = crate::global::expand_vars(path);
+        let mut sink = DownloadSink {
+            path: &path,
+            file: None,
+        };
+        self.execute(&mut sink, &mut on_progress)
+    }
+
+    fn execute(
+        &self,
+        sink: &mut dyn BodySink,
+        on_progress: &mut dyn FnMut(u64, Option<u64>),
+    ) -> Result<HttpResponse, HttpError> {
+        let mut method = self.method.clone();
+        let mut body = self.body.clone();
+        let mut headers = self.headers.clone();
+        let mut url = Url::parse(&self.url)?;
+        let mut redirects = 0;
+        loop {
+            let mut stream = self.connect(&url)?;
+            write_request(&mut stream, &method, &url, &headers, &body)?;
+            let mut reader = BufReader::new(stream);
+            let mut response = read_head(&mut reader, url.to_string())?;
+
+            let location = response.header("Location").map(str::to_string);
+            if let (Some(location), true) = (location, is_redirect(response.status)) {
+                if self.max_redirects > 0 {
+                    redirects += 1;
+                    if redirects > self.max_redirects {
+                        return Err(HttpError::TooManyRedirects(self.max_redirects));
+                    }
+                    let next = Url::parse(&url.join(&location))?;
+                    if next.host != url.host || next.port != url.port {
+                        headers.retain(|(key, _)| !key.eq_ignore_ascii_case("Authorization"));
+                    }
+                    if matches!(response.status, 301..=303) && method != "HEAD" {
+                        method = "GET".to_string();
+                        body.clear();
+                        headers.retain(|(key, _)| !key.eq_ignore_ascii_case("Content-Type"));
+                    }
+                    url = next;
+                    continue;
+                }
+            }
+
+            let has_body = method != "HEAD" && !matches!(response.status, 100..=199 | 204 | 304);
+            if has_body {
+                if sink.accepts(&response) {
+                    read_body(&mut reader, &response, sink, on_progress)?;
+                } else {
+                    let mut buffered = Vec::new();
+                    read_body(&mut reader, &response, &mut buffered, &mut |_, _| {})?;
+                    response.body = buffered;
+                }
+            }
+            return Ok(response);
+        }
+    }
+
+    fn connect(&self, url: &Url) -> Result<TcpStream, HttpError> {
+        let addrs = (url.host.as_str(), url.port)
+            .to_socket_addrs()
+            .map_err(|e| HttpError::Connect(format!("{}: {}", url.host, e)))?;
+        let mut last = HttpError::Connect(format!("{}: no addresses", url.host));
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout)).map_err(io_error)?;
+                    stream.set_write_timeout(Some(self.timeout)).map_err(io_error)?;
+                    return Ok(stream);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => last = HttpError::Timeout,
+                Err(e) => last = HttpError::Connect(format!("{}:{}: {}", url.host, url.port, e)),
+            }
+        }
+        Err(last)
+    }
+}
+
+fn is_redirect(status: u16) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+fn write_request(
+    stream: &mut TcpStream,
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<(), HttpError> {
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, url.target, url.host_header());
+    let has = |name: &str| headers.iter().any(|(key, _)| key.eq_ignore_ascii_case(name));
+    if !has("User-Agent") {
+        head.push_str(&format!("User-Agent: rsb/{}\r\n", env!("CARGO_PKG_VERSION")));
+    }
+    if !has("Accept") {
+        head.push_str("Accept: */*\r\n");
+    }
+    head.push_str("Connection: close\r\n");
+    for (key, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", key, value));
+    }
+    if !body.is_empty() || matches!(method, "POST" | "PUT" | "PATCH") {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).map_err(io_error)?;
+    stream.write_all(body).map_err(io_error)?;
+    stream.flush().map_err(io_error)
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String, HttpError> {
+    let mut line = String::new();
+    let n = reader.read_line(&mut line).map_err(io_error)?;
+    if n == 0 {
+        return Err(HttpError::Protocol("connection closed early".to_string()));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_head(reader: &mut impl BufRead, url: String) -> Result<HttpResponse, HttpError> {
+    let status_line = read_line(reader)?;
+    let mut parts = status_line.splitn(3, ' ');
+    let version = parts.next().unwrap_or("");
+    if !version.starts_with("HTTP/") {
+        return Err(HttpError::Protocol(format!("bad status line '{}'", status_line)));
+    }
+    let status = parts
+        .next()
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| HttpError::Protocol(format!("bad status line '{}'", status_line)))?;
+    let reason = parts.next().unwrap_or("").to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    Ok(HttpResponse {
+        status,
+        reason,
+        headers,
+        body: Vec::new(),
+        url,
+    })
+}
+
+/// Where a response body goes.
+trait BodySink {
+    /// Whether this sink wants the body of `response`.
+    fn accepts(&self, _response: &HttpResponse) -> bool {
+        true
+    }
+    fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), HttpError>;
+}
+
+impl BodySink for Vec<u8> {
+    fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), HttpError> {
+        self.extend_from_slice(chunk);
+        Ok(())
+    }
+}
+
+struct DownloadSink<'a> {
+    path: &'a str,
+    file: Option<std::fs::File>,
+}
+
+impl BodySink for DownloadSink<'_> {
+    fn accepts(&self, response: &HttpResponse) -> bool {
+        response.is_success()
+    }
+
+    fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), HttpError> {
+        if self.file.is_none() {
+            let file = std::fs::File::create(self.path)
+                .map_err(|e| HttpError::Io(format!("{}: {}", self.path, e)))?;
+            self.file = Some(file);
+        }
+        self.file
+            .as_mut()
+            .expect("file opened above")
+            .write_all(chunk)
+            .map_err(io_error)
+    }
+}
+
+fn read_body(
+    reader: &mut impl BufRead,
+    response: &HttpResponse,
+    sink: &mut dyn BodySink,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<(), HttpError> {
+    let chunked = response
+        .header("Transfer-Encoding")
+        .is_some_and(|te| te.to_ascii_lowercase().contains("chunked"));
+    let total = response
+        .header("Content-Length")
+        .and_then(|len| len.parse::<u64>().ok());
+    // Empty bodies still create the download target
+    sink.write_chunk(&[])?;
+
+    let mut done = 0u64;
+    let mut buf = [0u8; 16 * 1024];
+    let mut copy = |reader: &mut dyn Read, limit: Option<u64>, done: &mut u64| -> Result<(), HttpError> {
+        let mut remaining = limit;
+        loop {
+            let want = remaining.map_or(buf.len(), |r| (r as usize).min(buf.len()));
+            if want == 0 {
+                return Ok(());
+            }
+            let n = reader.read(&mut buf[..want]).map_err(io_error)?;
+            if n == 0 {
+                return match remaining {
+                    Some(_) => Err(HttpError::Protocol("body shorter than Content-Length".to_string())),
+                    None => Ok(()),
+                };
+            }
+            sink.write_chunk(&buf[..n])?;
+            *done += n as u64;
+            on_progress(*done, total);
+            remaining = remaining.map(|r| r - n as u64);
+        }
+    };
+
+    if chunked {
+        loop {
+            let size_line = read_line(reader)?;
+            let size_hex = size_line.split(';').next().unwrap_or("").trim();
+            let size = u64::from_str_radix(size_hex, 16)
+                .map_err(|_| HttpError::Protocol(format!("bad chunk size '{}'", size_line)))?;
+            if size == 0 {
+                // Trailers until the blank line
+                while !read_line(reader)?.is_empty() {}
+                return Ok(());
+            }
+            copy(&mut *reader, Some(size), &mut done)?;
+            read_line(reader)?;
+        }
+    }
+    copy(reader, total, &mut done)
+}
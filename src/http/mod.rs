@@ -0,0 +1,53 @@
+//! HTTP Module (MODULE_SPEC aligned, feature `http`)
+//!
+//! Native HTTP/1.1 client with no external binary: status, headers, body,
+//! redirects, timeouts, basic/bearer auth, JSON bodies and streaming
+//! downloads. When enabled, `curl!`/`get!` use it for `http://` URLs.
+//!
+//! # Usage
+//! ```ignore
+//! use std::time::Duration;
+//!
+//! let resp = rsb::http::get("http://localhost:8080/api/items")
+//!     .bearer("token")
+//!     .timeout(Duration::from_secs(5))
+//!     .send()?;
+//! assert_eq!(resp.status, 200);
+//! let items = resp.json()?;
+//!
+//! rsb::http::get("http://example.com/big.tar.gz").download("/tmp/big.tar.gz")?;
+//! ```
+
+pub mod client;
+
+pub use client::{to_cmd_result, HttpError, HttpRequest, HttpResponse};
+
+/// Starts a GET request.
+pub fn get(url: &str) -> HttpRequest {
+    HttpRequest::new("GET", url)
+}
+
+/// Starts a POST request.
+pub fn post(url: &str) -> HttpRequest {
+    HttpRequest::new("POST", url)
+}
+
+/// Starts a PUT request.
+pub fn put(url: &str) -> HttpRequest {
+    HttpRequest::new("PUT", url)
+}
+
+/// Starts a DELETE request.
+pub fn delete(url: &str) -> HttpRequest {
+    HttpRequest::new("DELETE", url)
+}
+
+/// Starts a HEAD request.
+pub fn head(url: &str) -> HttpRequest {
+    HttpRequest::new("HEAD", url)
+}
+
+/// Starts a request with any method.
+pub fn request(method: &str, url: &str) -> HttpRequest {
+    HttpRequest::new(method, url)
+}
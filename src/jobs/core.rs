@@ -1,9 +1,13 @@
 //! Background Job Control
 //!
-//! Background shell job execution, tracking, and waiting.
+//! Background jobs backed by real child processes: start, poll, signal,
+//! wait, and collect captured output.
 
-use crate::hosts::command::run_cmd_with_status;
-use super::process::{JobHandle, JOB_COUNTER, JOBS};
+use super::process::{
+    poll_job, reap_job, JobHandle, JobStatus, JOB_COUNTER, JOBS, POLL_INTERVAL,
+};
+use crate::global::expand_vars;
+use crate::hosts::command::{CmdResult, Pipeline};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -11,47 +15,156 @@ use std::time::Duration;
 /// Start a background job that runs the given shell command.
 /// Returns a job ID that can be waited on.
 pub fn start_background(command: &str) -> u32 {
+    let child = Pipeline::shell(&expand_vars(command)).spawn();
+    let mut job = JobHandle::new(next_job_id(), child);
+    job.command = command.to_string();
+    register_job(job)
+}
+
+/// Start a background job that runs an argv-style `Pipeline`.
+/// The processes are started immediately; the job reports the pipeline's
+/// effective status (honoring `pipefail`).
+pub fn start_pipeline(pipeline: Pipeline) -> u32 {
+    register_job(JobHandle::new(next_job_id(), pipeline.spawn()))
+}
+
+fn next_job_id() -> u32 {
     let mut counter = JOB_COUNTER.lock().unwrap();
     *counter += 1;
-    let job_id = *counter;
-    let cmd_string = command.to_string();
-    let (tx, rx) = std::sync::mpsc::channel();
-    let cmd_string_for_thread = cmd_string.clone();
-
-    let handle = thread::spawn(move || {
-        let result = run_cmd_with_status(&cmd_string_for_thread);
-        let _ = tx.send(result);
-    });
-
-    let job_handle = JobHandle {
-        id: job_id,
-        command: cmd_string,
-        handle: Some(handle),
-        rx,
-    };
+    *counter
+}
+
+fn register_job(job: JobHandle) -> u32 {
+    let job_id = job.id;
+    let pids = job
+        .pids
+        .iter()
+        .map(|pid| pid.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
     JOBS.lock()
         .unwrap()
-        .insert(job_id, Arc::new(Mutex::new(job_handle)));
-    crate::utils::stderrx("info", &format!("[{}] Started background job", job_id));
+        .insert(job_id, Arc::new(Mutex::new(job)));
+    crate::utils::stderrx(
+        "info",
+        &format!("[{}] {} Started background job", job_id, pids),
+    );
     job_id
 }
 
 /// Wait for a background job by ID. Optional timeout in seconds.
 pub fn wait(job_id: u32, timeout_secs: Option<u64>) -> Result<i32, String> {
+    wait_output(job_id, timeout_secs).map(|result| result.status)
+}
+
+/// Wait for a background job and return its captured output.
+/// The job is removed from the job table once it has been collected.
+pub fn wait_output(job_id: u32, timeout_secs: Option<u64>) -> Result<CmdResult, String> {
     let timeout = timeout_secs.map(Duration::from_secs);
-    match super::process::wait_on_job(job_id, timeout) {
-        Ok(result) => Ok(result.status),
-        Err(e) => Err(e),
+    super::process::wait_on_job(job_id, timeout)
+}
+
+/// Current status of a job without blocking; `None` for unknown IDs.
+pub fn job_status(job_id: u32) -> Option<JobStatus> {
+    poll_job(job_id)
+}
+
+/// Captured output of a finished job that has not been waited on yet.
+pub fn job_output(job_id: u32) -> Option<CmdResult> {
+    poll_job(job_id)?;
+    let job = JOBS.lock().unwrap().get(&job_id).cloned()?;
+    let result = job.lock().unwrap().result().cloned();
+    result
+}
+
+/// PIDs of the processes behind a job.
+pub fn job_pids(job_id: u32) -> Vec<u32> {
+    let job = JOBS.lock().unwrap().get(&job_id).cloned();
+    job.map(|job| job.lock().unwrap().pids.clone())
+        .unwrap_or_default()
+}
+
+/// Send a signal (name like "TERM"/"SIGKILL" or a number) to every process
+/// in a job. Defaults to TERM.
+pub fn kill_job(job_id: u32, signal: Option<&str>) -> Result<(), String> {
+    let name = signal.unwrap_or("TERM");
+    let signum =
+        super::signal::signal_number(name).ok_or_else(|| format!("Unknown signal '{}'", name))?;
+    let job = JOBS
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .cloned()
+        .ok_or_else(|| format!("Job {} not found", job_id))?;
+    let job = job.lock().unwrap();
+    job.signal(signum)
+}
+
+/// Wait until any of `job_ids` finishes (all jobs when empty) and reap it.
+/// Returns `None` when there is nothing to wait for.
+pub fn wait_any(job_ids: &[u32]) -> Option<(u32, CmdResult)> {
+    loop {
+        let candidates = if job_ids.is_empty() {
+            sorted_job_ids()
+        } else {
+            job_ids.to_vec()
+        };
+        let mut pending = false;
+        for id in candidates {
+            match poll_job(id) {
+                Some(JobStatus::Done(_)) => {
+                    if let Some(result) = reap_job(id) {
+                        return Some((id, result));
+                    }
+                }
+                Some(JobStatus::Running) => pending = true,
+                None => {}
+            }
+        }
+        if !pending {
+            return None;
+        }
+        thread::sleep(POLL_INTERVAL);
     }
 }
 
+/// Wait for every listed job (all jobs when empty) and reap them,
+/// in job ID order.
+pub fn wait_all(job_ids: &[u32]) -> Vec<(u32, CmdResult)> {
+    let ids = if job_ids.is_empty() {
+        sorted_job_ids()
+    } else {
+        job_ids.to_vec()
+    };
+    ids.into_iter()
+        .filter_map(|id| wait_output(id, None).ok().map(|result| (id, result)))
+        .collect()
+}
+
+fn sorted_job_ids() -> Vec<u32> {
+    let mut ids: Vec<u32> = JOBS.lock().unwrap().keys().copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
 /// List current background jobs as (id, command) pairs.
 pub fn list_jobs() -> Vec<(u32, String)> {
-    let jobs = JOBS.lock().unwrap();
-    let mut out = Vec::new();
-    for (id, job_mutex) in jobs.iter() {
-        let job = job_mutex.lock().unwrap();
-        out.push((*id, job.command.clone()));
-    }
-    out
+    list_job_status()
+        .into_iter()
+        .map(|(id, _, command)| (id, command))
+        .collect()
+}
+
+/// List jobs with their status (running or finished but not yet waited on),
+/// in job ID order.
+pub fn list_job_status() -> Vec<(u32, JobStatus, String)> {
+    sorted_job_ids()
+        .into_iter()
+        .filter_map(|id| {
+            let status = poll_job(id)?;
+            let job = JOBS.lock().unwrap().get(&id).cloned()?;
+            let command = job.lock().unwrap().command.clone();
+            Some((id, status, command))
+        })
+        .collect()
 }
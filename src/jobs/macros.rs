@@ -7,6 +7,9 @@ macro_rules! job {
     (background: $command:expr) => {{
         $crate::jobs::start_background($command)
     }};
+    (pipeline: $pipeline:expr) => {{
+        $crate::jobs::start_pipeline($pipeline)
+    }};
     (wait: $job_id:expr) => {{
         match $crate::jobs::wait($job_id, None) {
             Ok(status) => status,
@@ -31,13 +34,49 @@ macro_rules! job {
             }
         }
     }};
+    (status: $job_id:expr) => {{
+        $crate::jobs::job_status($job_id)
+    }};
+    (output: $job_id:expr) => {{
+        match $crate::jobs::wait_output($job_id, None) {
+            Ok(result) => result.output,
+            Err(e) => {
+                $crate::utils::stderrx(
+                    "error",
+                    &format!("Failed to wait for job {}: {}", $job_id, e),
+                );
+                String::new()
+            }
+        }
+    }};
+    (kill: $job_id:expr) => {{
+        $crate::job!(kill: $job_id, signal: "TERM")
+    }};
+    (kill: $job_id:expr, signal: $signal:expr) => {{
+        match $crate::jobs::kill_job($job_id, Some($signal)) {
+            Ok(()) => true,
+            Err(e) => {
+                $crate::utils::stderrx(
+                    "error",
+                    &format!("Failed to kill job {}: {}", $job_id, e),
+                );
+                false
+            }
+        }
+    }};
+    (wait_any) => {{
+        $crate::jobs::wait_any(&[])
+    }};
+    (wait_all) => {{
+        $crate::jobs::wait_all(&[])
+    }};
     (list) => {{
-        let jobs = $crate::jobs::list_jobs();
+        let jobs = $crate::jobs::list_job_status();
         if jobs.is_empty() {
             $crate::utils::stderrx("info", "No running jobs.");
         }
-        for (id, cmd) in jobs {
-            $crate::echo!("[{}] {}", id, cmd);
+        for (id, status, cmd) in jobs {
+            $crate::echo!("[{}] {:<8} {}", id, status.to_string(), cmd);
         }
     }};
 }
@@ -47,31 +86,21 @@ macro_rules! event {
     (register $event:expr, $handler:expr) => {{
         let mut handlers = $crate::jobs::EVENT_HANDLERS.lock().unwrap();
         let event_handlers = handlers.entry($event.to_string()).or_insert_with(Vec::new);
-        event_handlers.push(Box::new($handler));
+        event_handlers.push(::std::sync::Arc::new($handler));
     }};
     (emit $event:expr, $($key:expr => $value:expr),*) => {{
         let mut data = ::std::collections::HashMap::new();
         $( data.insert($key.to_string(), $value.to_string()); )*
         let event_data = $crate::jobs::EventData { event_type: $event.to_string(), data, };
-        if let Some(handlers) = $crate::jobs::EVENT_HANDLERS.lock().unwrap().get($event) {
-            for handler in handlers { handler(&event_data); }
-        }
+        $crate::jobs::dispatch_event(&event_data);
     }};
 }
 
 #[macro_export]
 macro_rules! trap {
     ($handler:expr, on: $signal:expr) => {{
-        let sig_name = $signal.to_uppercase();
-        match sig_name.as_str() {
-            "SIGINT" | "SIGTERM" | "EXIT" | "COMMAND_ERROR" => {
-                $crate::jobs::install_signal_handlers();
-                $crate::event!(register & sig_name, $handler);
-            }
-            _ => {
-                $crate::event!(register & sig_name, $handler);
-            }
-        }
+        let event_name = $crate::jobs::install_trap($signal);
+        $crate::event!(register & event_name, $handler);
     }};
 }
 
@@ -6,12 +6,14 @@
 
 pub mod core;
 pub mod macros;
+pub mod pool;
 pub mod process;
 pub mod signal;
 pub mod utils;
 
 // Flatten exports
 pub use core::*;
+pub use pool::*;
 pub use process::*;
 pub use signal::*;
 pub use utils::*;
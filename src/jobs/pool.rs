@@ -0,0 +1,76 @@
+//! Bounded Job Pools
+//!
+//! `xargs -P` style concurrency: at most N background jobs at a time.
+
+use super::core::{start_background, start_pipeline, wait_all};
+use super::process::{poll_job, JobStatus, POLL_INTERVAL};
+use crate::hosts::command::{CmdResult, Pipeline};
+use std::thread;
+
+/// A set of background jobs limited to `max` running at once.
+/// Submitting blocks while the pool is full.
+#[derive(Debug)]
+pub struct JobPool {
+    max: usize,
+    ids: Vec<u32>,
+}
+
+impl JobPool {
+    /// Creates a pool; `max` is clamped to at least 1.
+    pub fn new(max: usize) -> Self {
+        JobPool {
+            max: max.max(1),
+            ids: Vec::new(),
+        }
+    }
+
+    /// Starts a shell command once a slot is free and returns its job ID.
+    pub fn submit(&mut self, command: &str) -> u32 {
+        self.wait_for_slot();
+        let id = start_background(command);
+        self.ids.push(id);
+        id
+    }
+
+    /// Starts a pipeline once a slot is free and returns its job ID.
+    pub fn submit_pipeline(&mut self, pipeline: Pipeline) -> u32 {
+        self.wait_for_slot();
+        let id = start_pipeline(pipeline);
+        self.ids.push(id);
+        id
+    }
+
+    /// Job IDs submitted so far, in submission order.
+    pub fn ids(&self) -> &[u32] {
+        &self.ids
+    }
+
+    /// Number of submitted jobs still running.
+    pub fn running(&self) -> usize {
+        self.ids
+            .iter()
+            .filter(|id| poll_job(**id) == Some(JobStatus::Running))
+            .count()
+    }
+
+    /// Waits for every submitted job and returns results in submission order.
+    pub fn wait_all(self) -> Vec<(u32, CmdResult)> {
+        wait_all(&self.ids)
+    }
+
+    fn wait_for_slot(&self) {
+        while self.running() >= self.max {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Runs shell commands with at most `max` in parallel and returns their
+/// results in input order.
+pub fn run_parallel(commands: &[&str], max: usize) -> Vec<CmdResult> {
+    let mut pool = JobPool::new(max);
+    for command in commands {
+        pool.submit(command);
+    }
+    pool.wait_all().into_iter().map(|(_, result)| result).collect()
+}
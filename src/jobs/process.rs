@@ -2,12 +2,13 @@
 //!
 //! Process queries, job tracking, locking mechanisms, and event registry.
 
-use crate::hosts::command::{run_cmd, run_cmd_with_status, CmdResult};
+use crate::hosts::command::{run_cmd, run_cmd_with_status, CmdResult, PipeChild};
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 use std::io::Write;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 // === Event System ===
 
@@ -17,68 +18,178 @@ pub struct EventData {
     pub data: HashMap<String, String>,
 }
 
+pub type EventHandler = Arc<dyn Fn(&EventData) + Send + Sync>;
+
 lazy_static! {
     // A registry for event handlers.
-    pub static ref EVENT_HANDLERS: Arc<Mutex<HashMap<String, Vec<Box<dyn Fn(&EventData) + Send + Sync>>>>> =
+    pub static ref EVENT_HANDLERS: Arc<Mutex<HashMap<String, Vec<EventHandler>>>> =
         Arc::new(Mutex::new(HashMap::new()));
 }
 
+/// Calls every handler registered for `event.event_type`.
+/// The registry is released before handlers run, so handlers may register
+/// or emit events themselves (or exit the process).
+pub fn dispatch_event(event: &EventData) {
+    let handlers: Vec<EventHandler> = match EVENT_HANDLERS.lock() {
+        Ok(registry) => registry.get(&event.event_type).cloned().unwrap_or_default(),
+        Err(poisoned) => poisoned
+            .into_inner()
+            .get(&event.event_type)
+            .cloned()
+            .unwrap_or_default(),
+    };
+    for handler in handlers {
+        handler(event);
+    }
+}
+
 // === Job Control ===
 
+/// How often job polling (`wait`, `wait_any`, pools) re-checks children.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// State of a background job, in the spirit of bash's `jobs` listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    /// Finished with the given exit status (128+N when killed by signal N).
+    Done(i32),
+}
+
+impl JobStatus {
+    pub fn is_running(&self) -> bool {
+        matches!(self, JobStatus::Running)
+    }
+
+    /// Exit status once finished.
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            JobStatus::Running => None,
+            JobStatus::Done(code) => Some(*code),
+        }
+    }
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "Running"),
+            JobStatus::Done(0) => write!(f, "Done"),
+            JobStatus::Done(code) => write!(f, "Exit {}", code),
+        }
+    }
+}
+
+/// A background job backed by real child processes.
+///
+/// The job keeps its processes until it is polled as finished; from then on
+/// the captured output is held in `result` until the job is reaped by `wait`.
 pub struct JobHandle {
     pub id: u32,
     pub command: String,
-    pub handle: Option<thread::JoinHandle<()>>,
-    pub rx: std::sync::mpsc::Receiver<CmdResult>,
+    pub pids: Vec<u32>,
+    pub started: Instant,
+    child: Option<PipeChild>,
+    result: Option<CmdResult>,
+}
+
+impl JobHandle {
+    pub fn new(id: u32, child: PipeChild) -> Self {
+        JobHandle {
+            id,
+            command: child.label().to_string(),
+            pids: child.pids(),
+            started: Instant::now(),
+            child: Some(child),
+            result: None,
+        }
+    }
+
+    /// Non-blocking status check; collects the output once the processes exit.
+    pub fn poll(&mut self) -> JobStatus {
+        if let Some(result) = &self.result {
+            return JobStatus::Done(result.status);
+        }
+        let running = self.child.as_mut().is_some_and(|c| c.is_running());
+        if running {
+            return JobStatus::Running;
+        }
+        let result = match self.child.take() {
+            Some(child) => CmdResult::from(child.wait()),
+            None => CmdResult {
+                status: -1,
+                output: String::new(),
+                error: "job has no process".to_string(),
+            },
+        };
+        let status = result.status;
+        self.result = Some(result);
+        JobStatus::Done(status)
+    }
+
+    /// Sends `signal` to every process in the job.
+    pub fn signal(&self, signal: i32) -> Result<(), String> {
+        match &self.child {
+            Some(child) => child.signal(signal),
+            None => Err(format!("job {} has already finished", self.id)),
+        }
+    }
+
+    /// Captured output, available once the job has finished.
+    pub fn result(&self) -> Option<&CmdResult> {
+        self.result.as_ref()
+    }
 }
 
 lazy_static! {
     pub static ref JOBS: Arc<Mutex<HashMap<u32, Arc<Mutex<JobHandle>>>>> =
         Arc::new(Mutex::new(HashMap::new()));
     pub static ref JOB_COUNTER: Arc<Mutex<u32>> = Arc::new(Mutex::new(0));
+    static ref HELD_LOCKS: Mutex<Vec<String>> = Mutex::new(Vec::new());
 }
 
-/// Waits for a specific job to complete and returns its exit status.
-/// This function will remove the job from the global JOBS map.
-pub fn wait_on_job(
-    job_id: u32,
-    timeout: Option<std::time::Duration>,
-) -> Result<CmdResult, String> {
-    let job_arc = JOBS.lock().unwrap().remove(&job_id);
-
-    if let Some(job_arc) = job_arc {
-        if let Ok(job_handle) = Arc::try_unwrap(job_arc).map(|mutex| mutex.into_inner().unwrap()) {
-            let result = if let Some(t) = timeout {
-                job_handle.rx.recv_timeout(t)
-            } else {
-                job_handle
-                    .rx
-                    .recv()
-                    .map_err(|_| std::sync::mpsc::RecvTimeoutError::Disconnected)
-            };
-
-            return match result {
-                Ok(cmd_result) => {
-                    if let Some(h) = job_handle.handle {
-                        let _ = h.join(); // Join only on success
-                    }
-                    Ok(cmd_result)
-                }
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                    // On timeout, we don't join the handle. The job is orphaned.
-                    Err("Timeout".to_string())
-                }
-                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-                    if let Some(h) = job_handle.handle {
-                        let _ = h.join(); // Join on disconnect
-                    }
-                    Err("Job channel disconnected".to_string())
-                }
-            };
-        }
+/// Polls a job by ID without blocking. Returns `None` for unknown jobs.
+/// Emits a `JOB_DONE` event the first time a job is seen finished.
+pub fn poll_job(job_id: u32) -> Option<JobStatus> {
+    let job = JOBS.lock().unwrap().get(&job_id).cloned()?;
+    let (status, finished_now, command) = {
+        let mut job = job.lock().unwrap();
+        let was_running = job.result.is_none();
+        let status = job.poll();
+        (status, was_running && !status.is_running(), job.command.clone())
+    };
+    if let (true, JobStatus::Done(code)) = (finished_now, status) {
+        crate::event!(emit "JOB_DONE", "id" => job_id, "command" => command, "status" => code);
     }
+    Some(status)
+}
 
-    Err(format!("Job {} not found", job_id))
+/// Removes a finished job from the table and returns its result.
+pub(crate) fn reap_job(job_id: u32) -> Option<CmdResult> {
+    let mut jobs = JOBS.lock().unwrap();
+    let result = jobs.get(&job_id)?.lock().unwrap().result().cloned()?;
+    jobs.remove(&job_id);
+    Some(result)
+}
+
+/// Waits for a specific job to complete and returns its result.
+/// On success the job is removed from the global JOBS map; on timeout it
+/// keeps running and stays listed.
+pub fn wait_on_job(job_id: u32, timeout: Option<Duration>) -> Result<CmdResult, String> {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    loop {
+        match poll_job(job_id) {
+            None => return Err(format!("Job {} not found", job_id)),
+            Some(JobStatus::Done(_)) => {
+                return reap_job(job_id).ok_or_else(|| format!("Job {} not found", job_id));
+            }
+            Some(JobStatus::Running) => {}
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            return Err("Timeout".to_string());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
 }
 
 // === Process Management ===
@@ -145,10 +256,26 @@ pub fn create_lock(lock_path: &str) -> Result<(), String> {
     let pid = std::process::id();
     write!(file, "{}", pid).map_err(|e| format!("Failed to write PID to lock file: {}", e))?;
 
+    HELD_LOCKS.lock().unwrap().push(lock_path.to_string());
+    super::signal::install_exit_hook();
     Ok(())
 }
 
 /// Remove a lock file.
 pub fn remove_lock(lock_path: &str) {
     let _ = std::fs::remove_file(lock_path);
+    if let Ok(mut held) = HELD_LOCKS.lock() {
+        held.retain(|path| path != lock_path);
+    }
+}
+
+/// Remove every lock file this process still holds (run by the exit hook).
+pub fn release_held_locks() {
+    let held = match HELD_LOCKS.lock() {
+        Ok(mut held) => std::mem::take(&mut *held),
+        Err(_) => return,
+    };
+    for path in held {
+        let _ = std::fs::remove_file(path);
+    }
 }
@@ -1,31 +1,229 @@
 //! Signal Handling
 //!
-//! Install trap handlers for common termination signals.
+//! Self-pipe signal subsystem behind `trap!`. The C-level handler only writes
+//! the signal number into a pipe; a dispatcher thread reads it and runs the
+//! registered trap handlers (as `SIG*` events) outside signal context.
+//! Terminating signals then exit with 128+N, which fires `EXIT` traps and
+//! the automatic cleanup of `tmp!` files and held lock files.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use super::process::{dispatch_event, release_held_locks, EventData};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Mutex;
 
-static TRAP_INSTALLED: AtomicBool = AtomicBool::new(false);
+static DISPATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+static EXIT_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+static EXIT_TRAPS_RAN: AtomicBool = AtomicBool::new(false);
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+static LAST_SIGNAL: AtomicI32 = AtomicI32::new(0);
+static INSTALLED: Mutex<Vec<i32>> = Mutex::new(Vec::new());
 
-/// The actual C-style signal handler.
-extern "C" fn signal_handler(signal: i32) {
-    let event_name = match signal {
-        libc::SIGINT => "SIGINT",
-        libc::SIGTERM => "SIGTERM",
-        _ => "UNKNOWN_SIGNAL",
-    };
-    eprintln!("\nrsb-trap: Caught signal {}, exiting.", event_name);
-    std::process::exit(128 + signal);
+/// Signals that end the process once their traps have run.
+const TERMINATING: [i32; 4] = [libc::SIGHUP, libc::SIGINT, libc::SIGQUIT, libc::SIGTERM];
+
+/// The C-level handler: async-signal-safe, it only writes to the self-pipe.
+extern "C" fn signal_handler(signal: libc::c_int) {
+    let fd = SIGNAL_PIPE.load(Ordering::Relaxed);
+    if fd >= 0 {
+        let byte = signal as u8;
+        unsafe {
+            libc::write(fd, &byte as *const u8 as *const libc::c_void, 1);
+        }
+    }
 }
 
-/// Installs the signal handlers for common termination signals.
+/// Runs on process exit (`std::process::exit`, returning from `main`,
+/// `validate!` failures, terminating signals).
+extern "C" fn exit_hook() {
+    run_exit_traps();
+}
+
+/// Installs the handlers for the common termination signals
+/// (SIGHUP, SIGINT, SIGQUIT, SIGTERM) so they run traps and cleanup.
 pub fn install_signal_handlers() {
-    if TRAP_INSTALLED
+    for signal in TERMINATING {
+        install_signal(signal);
+    }
+}
+
+/// Routes `signal` through the trap dispatcher. Returns false if the signal
+/// cannot be caught (SIGKILL, SIGSTOP) or the handler could not be set.
+pub fn install_signal(signal: i32) -> bool {
+    let mut installed = INSTALLED.lock().unwrap();
+    if installed.contains(&signal) {
+        return true;
+    }
+    if !start_dispatcher() {
+        return false;
+    }
+    let ok = unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = signal_handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(signal, &action, std::ptr::null_mut()) == 0
+    };
+    if ok {
+        installed.push(signal);
+        if TERMINATING.contains(&signal) {
+            install_exit_hook();
+        }
+    }
+    ok
+}
+
+/// Registers the process exit hook that runs `EXIT` traps and cleanup.
+/// Called automatically by `trap!(.., on: "EXIT")`, `tmp!` and `create_lock`.
+pub fn install_exit_hook() {
+    if EXIT_HOOK_INSTALLED
         .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
         .is_ok()
     {
         unsafe {
-            libc::signal(libc::SIGINT, signal_handler as usize);
-            libc::signal(libc::SIGTERM, signal_handler as usize);
+            libc::atexit(exit_hook);
         }
     }
 }
+
+/// Prepares a `trap!` target and returns the event name handlers are
+/// registered under: "EXIT", canonical signal names ("INT", "sigint" and
+/// "2" all become "SIGINT"), or the upper-cased name for other events
+/// such as "COMMAND_ERROR".
+pub fn install_trap(target: &str) -> String {
+    let upper = target.trim().to_uppercase();
+    if upper == "EXIT" {
+        install_exit_hook();
+        return upper;
+    }
+    let Some(signal) = signal_number(&upper) else {
+        return upper;
+    };
+    if !install_signal(signal) {
+        crate::utils::stderrx("warn", &format!("Cannot trap signal {}", upper));
+    }
+    signal_name(signal).unwrap_or(upper)
+}
+
+/// Runs `EXIT` traps and removes `tmp!` files and held locks. Runs at most
+/// once per process; the exit hook calls it automatically.
+pub fn run_exit_traps() {
+    if EXIT_TRAPS_RAN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut data = HashMap::new();
+    let signal = LAST_SIGNAL.load(Ordering::SeqCst);
+    if let Some(name) = signal_name(signal) {
+        data.insert("signal".to_string(), name);
+    }
+    dispatch_event(&EventData {
+        event_type: "EXIT".to_string(),
+        data,
+    });
+    crate::fs::cleanup_temp_files();
+    release_held_locks();
+}
+
+fn start_dispatcher() -> bool {
+    if DISPATCHER_STARTED.load(Ordering::SeqCst) {
+        return true;
+    }
+    let mut fds = [0 as libc::c_int; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    let (read_fd, write_fd) = (fds[0], fds[1]);
+    unsafe {
+        libc::fcntl(read_fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        libc::fcntl(write_fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        let flags = libc::fcntl(write_fd, libc::F_GETFL);
+        libc::fcntl(write_fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+    }
+    SIGNAL_PIPE.store(write_fd, Ordering::SeqCst);
+    let spawned = std::thread::Builder::new()
+        .name("rsb-signals".to_string())
+        .spawn(move || dispatch_loop(read_fd));
+    DISPATCHER_STARTED.store(spawned.is_ok(), Ordering::SeqCst);
+    spawned.is_ok()
+}
+
+fn dispatch_loop(read_fd: libc::c_int) {
+    let mut buf = [0u8; 64];
+    loop {
+        let n = unsafe { libc::read(read_fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if n < 0 && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+            continue;
+        }
+        if n <= 0 {
+            return;
+        }
+        for &byte in &buf[..n as usize] {
+            handle_signal(byte as i32);
+        }
+    }
+}
+
+fn handle_signal(signal: i32) {
+    let name = signal_name(signal).unwrap_or_else(|| format!("SIG{}", signal));
+    let mut data = HashMap::new();
+    data.insert("signal".to_string(), name.clone());
+    data.insert("number".to_string(), signal.to_string());
+    dispatch_event(&EventData {
+        event_type: name.clone(),
+        data,
+    });
+    if TERMINATING.contains(&signal) {
+        eprintln!("\nrsb-trap: Caught signal {}, exiting.", name);
+        LAST_SIGNAL.store(signal, Ordering::SeqCst);
+        std::process::exit(128 + signal);
+    }
+}
+
+/// Canonical "SIG*" name for a signal number.
+pub fn signal_name(signal: i32) -> Option<String> {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGCHLD => "SIGCHLD",
+        libc::SIGCONT => "SIGCONT",
+        libc::SIGSTOP => "SIGSTOP",
+        libc::SIGTSTP => "SIGTSTP",
+        libc::SIGWINCH => "SIGWINCH",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Resolves a signal name ("TERM", "SIGTERM", "term") or number ("15").
+pub fn signal_number(name: &str) -> Option<i32> {
+    let name = name.trim();
+    if let Ok(num) = name.parse::<i32>() {
+        return Some(num);
+    }
+    let upper = name.to_uppercase();
+    let bare = upper.strip_prefix("SIG").unwrap_or(&upper);
+    let signum = match bare {
+        "HUP" => libc::SIGHUP,
+        "INT" => libc::SIGINT,
+        "QUIT" => libc::SIGQUIT,
+        "KILL" => libc::SIGKILL,
+        "USR1" => libc::SIGUSR1,
+        "USR2" => libc::SIGUSR2,
+        "PIPE" => libc::SIGPIPE,
+        "ALRM" => libc::SIGALRM,
+        "TERM" => libc::SIGTERM,
+        "CHLD" => libc::SIGCHLD,
+        "CONT" => libc::SIGCONT,
+        "STOP" => libc::SIGSTOP,
+        "TSTP" => libc::SIGTSTP,
+        "WINCH" => libc::SIGWINCH,
+        _ => return None,
+    };
+    Some(signum)
+}
@@ -0,0 +1,71 @@
+//! JSON Macros
+//!
+//! String-first wrappers over `json::utils`. Query and string edits log
+//! errors and fall back (empty output / unchanged input); file edits are
+//! fail-fast.
+
+#[macro_export]
+macro_rules! json_get {
+    ($json:expr, $path:expr) => {
+        match $crate::json::json_get($json, $path) {
+            Ok(value) => value,
+            Err(e) => {
+                $crate::error!("json_get: {}", e);
+                String::new()
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! json_get_file {
+    ($file:expr, $path:expr) => {
+        match $crate::json::json_get_file($file, $path) {
+            Ok(value) => value,
+            Err(e) => {
+                $crate::error!("json_get_file: {}", e);
+                String::new()
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! json_set {
+    (file: $file:expr, $path:expr, $value:expr) => {{
+        if let Err(e) = $crate::json::json_set_file($file, $path, $value) {
+            $crate::error!("json_set: {}", e);
+            std::process::exit(1);
+        }
+    }};
+    ($json:expr, $path:expr, $value:expr) => {{
+        let json: &str = $json;
+        match $crate::json::json_set(json, $path, $value) {
+            Ok(updated) => updated,
+            Err(e) => {
+                $crate::error!("json_set: {}", e);
+                json.to_string()
+            }
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! json_del {
+    (file: $file:expr, $path:expr) => {{
+        if let Err(e) = $crate::json::json_delete_file($file, $path) {
+            $crate::error!("json_del: {}", e);
+            std::process::exit(1);
+        }
+    }};
+    ($json:expr, $path:expr) => {{
+        let json: &str = $json;
+        match $crate::json::json_delete(json, $path) {
+            Ok(updated) => updated,
+            Err(e) => {
+                $crate::error!("json_del: {}", e);
+                json.to_string()
+            }
+        }
+    }};
+}
@@ -0,0 +1,23 @@
+//! JSON Module (MODULE_SPEC aligned)
+//!
+//! Native, in-process JSON querying and editing on top of `serde_json`:
+//! a jq-flavoured query language (paths, indexes, wildcards, `select`,
+//! `length`, `keys`), set/delete with write-back to files, and flattening
+//! into the global store.
+//!
+//! # Usage
+//! ```ignore
+//! use rsb::prelude::*;
+//!
+//! let doc = r#"{"users": [{"name": "Alice", "age": 31}, {"name": "Bob", "age": 25}]}"#;
+//! let names = json_get!(doc, ".users[] | select(.age > 30) | .name"); // "Alice"
+//! let doc = json_set!(doc, ".users[1].age", "26");
+//! rsb::json::json_to_global(&doc, "cfg").unwrap(); // cfg_users_LENGTH=2, cfg_users_0_name=Alice
+//! ```
+
+pub mod macros;
+pub mod query;
+pub mod utils;
+
+pub use query::Query;
+pub use utils::*;
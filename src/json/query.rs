@@ -0,0 +1,474 @@
+//! JSON Query Engine
+//!
+//! A small jq-flavoured path language evaluated in-process over
+//! `serde_json::Value`:
+//!
+//! - paths: `.a.b`, `a.b`, `.a[0]`, `.a[-1]`, `."odd key"`, `.["odd key"]`
+//! - wildcards: `.a[]`, `.a[*]`, `.a.*` (every element / every value)
+//! - pipes: `.users[] | select(.age >= 30) | .name`
+//! - functions: `length`, `keys`, `select(cond)` where `cond` is a path
+//!   (truthy test) or `path <op> literal` with `== != < <= > >=`, joined
+//!   with `and` / `or`
+
+use serde_json::Value;
+
+/// One navigation step of a path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Key(String),
+    Index(i64),
+    /// Every element of an array or every value of an object.
+    Each,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cond {
+    Truthy(Vec<Step>),
+    Cmp(Vec<Step>, CmpOp, Value),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Path(Vec<Step>),
+    Length,
+    Keys,
+    Select(Cond),
+}
+
+/// A parsed query: filters joined by `|`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    filters: Vec<Filter>,
+}
+
+impl Query {
+    pub fn parse(src: &str) -> Result<Query, String> {
+        let mut parser = Parser::new(src);
+        let mut filters = vec![parser.filter()?];
+        loop {
+            parser.skip_ws();
+            if parser.eat('|') {
+                filters.push(parser.filter()?);
+            } else if parser.at_end() {
+                break;
+            } else {
+                return Err(parser.error("expected '|' or end of query"));
+            }
+        }
+        Ok(Query { filters })
+    }
+
+    /// The steps of a plain path query (no pipes or functions), as needed
+    /// by set/delete.
+    pub fn as_path(&self) -> Option<&[Step]> {
+        match self.filters.as_slice() {
+            [Filter::Path(steps)] => Some(steps),
+            _ => None,
+        }
+    }
+
+    /// Evaluates the query; every matching value is returned in order.
+    pub fn eval(&self, doc: &Value) -> Result<Vec<Value>, String> {
+        let mut current = vec![doc.clone()];
+        for filter in &self.filters {
+            let mut next = Vec::new();
+            for value in &current {
+                apply_filter(filter, value, &mut next)?;
+            }
+            current = next;
+        }
+        Ok(current)
+    }
+}
+
+fn apply_filter(filter: &Filter, value: &Value, out: &mut Vec<Value>) -> Result<(), String> {
+    match filter {
+        Filter::Path(steps) => walk(value, steps, out),
+        Filter::Length => {
+            let len = match value {
+                Value::Null => 0,
+                Value::String(s) => s.chars().count(),
+                Value::Array(items) => items.len(),
+                Value::Object(map) => map.len(),
+                Value::Number(n) => {
+                    out.push(Value::from(n.as_f64().unwrap_or(0.0).abs()));
+                    return Ok(());
+                }
+                Value::Bool(_) => return Err("boolean has no length".to_string()),
+            };
+            out.push(Value::from(len));
+            Ok(())
+        }
+        Filter::Keys => match value {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                out.push(Value::from(
+                    keys.into_iter().map(|k| Value::from(k.as_str())).collect::<Vec<_>>(),
+                ));
+                Ok(())
+            }
+            Value::Array(items) => {
+                out.push(Value::from((0..items.len()).collect::<Vec<_>>()));
+                Ok(())
+            }
+            other => Err(format!("{} has no keys", type_name(other))),
+        },
+        Filter::Select(cond) => {
+            if test(cond, value)? {
+                out.push(value.clone());
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Follows `steps` from `value`, collecting every reached value.
+/// Missing keys and out-of-range indexes yield `null`, like jq.
+pub(crate) fn walk(value: &Value, steps: &[Step], out: &mut Vec<Value>) -> Result<(), String> {
+    let Some((step, rest)) = steps.split_first() else {
+        out.push(value.clone());
+        return Ok(());
+    };
+    match (step, value) {
+        (Step::Key(key), Value::Object(map)) => {
+            walk(map.get(key).unwrap_or(&Value::Null), rest, out)
+        }
+        (Step::Index(idx), Value::Array(items)) => {
+            let item = resolve_index(*idx, items.len()).and_then(|i| items.get(i));
+            walk(item.unwrap_or(&Value::Null), rest, out)
+        }
+        (Step::Key(_) | Step::Index(_), Value::Null) => walk(&Value::Null, rest, out),
+        (Step::Each, Value::Array(items)) => {
+            items.iter().try_for_each(|item| walk(item, rest, out))
+        }
+        (Step::Each, Value::Object(map)) => map.values().try_for_each(|item| walk(item, rest, out)),
+        (Step::Key(key), other) => Err(format!(
+            "cannot index {} with \"{}\"",
+            type_name(other),
+            key
+        )),
+        (Step::Index(idx), other) => Err(format!("cannot index {} with {}", type_name(other), idx)),
+        (Step::Each, other) => Err(format!("cannot iterate over {}", type_name(other))),
+    }
+}
+
+/// Maps a possibly negative index onto `0..len`.
+pub(crate) fn resolve_index(idx: i64, len: usize) -> Option<usize> {
+    if idx < 0 {
+        len.checked_sub(idx.unsigned_abs() as usize)
+    } else {
+        Some(idx as usize)
+    }
+}
+
+fn test(cond: &Cond, value: &Value) -> Result<bool, String> {
+    match cond {
+        Cond::Truthy(steps) => {
+            let mut found = Vec::new();
+            walk(value, steps, &mut found)?;
+            Ok(found.iter().any(truthy))
+        }
+        Cond::Cmp(steps, op, literal) => {
+            let mut found = Vec::new();
+            walk(value, steps, &mut found)?;
+            Ok(found.iter().any(|v| compare(v, *op, literal)))
+        }
+        Cond::And(a, b) => Ok(test(a, value)? && test(b, value)?),
+        Cond::Or(a, b) => Ok(test(a, value)? || test(b, value)?),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn compare(left: &Value, op: CmpOp, right: &Value) -> bool {
+    use std::cmp::Ordering;
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    };
+    match op {
+        CmpOp::Eq => left == right || ordering == Some(Ordering::Equal),
+        CmpOp::Ne => !(left == right || ordering == Some(Ordering::Equal)),
+        CmpOp::Lt => ordering == Some(Ordering::Less),
+        CmpOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CmpOp::Gt => ordering == Some(Ordering::Greater),
+        CmpOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+// --- Parser ---
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Parser { src, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn at_end(&self) -> bool {
+        self.rest().is_empty()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let boundary = match self.rest().strip_prefix(word) {
+            Some(after) => after.chars().next().is_none_or(|c| !is_ident(c)),
+            None => false,
+        };
+        if boundary {
+            self.pos += word.len();
+        }
+        boundary
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!("invalid query '{}' at {}: {}", self.src, self.pos, msg)
+    }
+
+    fn filter(&mut self) -> Result<Filter, String> {
+        self.skip_ws();
+        if self.eat_word("length") {
+            return Ok(Filter::Length);
+        }
+        if self.eat_word("keys") {
+            return Ok(Filter::Keys);
+        }
+        if self.eat_word("select") {
+            self.skip_ws();
+            if !self.eat('(') {
+                return Err(self.error("expected '(' after select"));
+            }
+            let cond = self.cond()?;
+            self.skip_ws();
+            if !self.eat(')') {
+                return Err(self.error("expected ')'"));
+            }
+            return Ok(Filter::Select(cond));
+        }
+        self.path().map(Filter::Path)
+    }
+
+    fn path(&mut self) -> Result<Vec<Step>, String> {
+        self.skip_ws();
+        let mut steps = Vec::new();
+        // A leading bare key is allowed: `a.b` == `.a.b`
+        if self.peek().is_some_and(is_ident) {
+            steps.push(Step::Key(self.ident()));
+        } else if self.peek() != Some('.') && self.peek() != Some('[') {
+            return Err(self.error("expected a path"));
+        }
+        loop {
+            match self.peek() {
+                Some('.') => {
+                    self.bump();
+                    match self.peek() {
+                        Some('*') => {
+                            self.bump();
+                            steps.push(Step::Each);
+                        }
+                        Some('"') => steps.push(Step::Key(self.quoted()?)),
+                        Some('[') => {}
+                        Some(c) if is_ident(c) => steps.push(Step::Key(self.ident())),
+                        // Bare `.` is the identity path
+                        _ => {}
+                    }
+                }
+                Some('[') => {
+                    self.bump();
+                    self.skip_ws();
+                    let step = match self.peek() {
+                        Some(']') => Step::Each,
+                        Some('*') => {
+                            self.bump();
+                            Step::Each
+                        }
+                        Some('"') => Step::Key(self.quoted()?),
+                        _ => Step::Index(self.integer()?),
+                    };
+                    self.skip_ws();
+                    if !self.eat(']') {
+                        return Err(self.error("expected ']'"));
+                    }
+                    steps.push(step);
+                }
+                _ => return Ok(steps),
+            }
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(is_ident) {
+            self.bump();
+        }
+        self.src[start..self.pos].to_string()
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        match self.literal()? {
+            Value::String(s) => Ok(s),
+            _ => Err(self.error("expected a quoted key")),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, String> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        self.src[start..self.pos]
+            .parse()
+            .map_err(|_| self.error("expected an index"))
+    }
+
+    /// A JSON scalar literal: string, number, `true`, `false` or `null`.
+    fn literal(&mut self) -> Result<Value, String> {
+        self.skip_ws();
+        let start = self.pos;
+        if self.eat('"') {
+            while let Some(c) = self.bump() {
+                match c {
+                    '\\' => {
+                        self.bump();
+                    }
+                    '"' => {
+                        return serde_json::from_str(&self.src[start..self.pos])
+                            .map_err(|_| self.error("invalid string literal"));
+                    }
+                    _ => {}
+                }
+            }
+            return Err(self.error("unterminated string"));
+        }
+        for (word, value) in [
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+            ("null", Value::Null),
+        ] {
+            if self.eat_word(word) {
+                return Ok(value);
+            }
+        }
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.bump();
+        }
+        serde_json::from_str::<serde_json::Number>(&self.src[start..self.pos])
+            .map(Value::Number)
+            .map_err(|_| self.error("expected a literal"))
+    }
+
+    fn cond(&mut self) -> Result<Cond, String> {
+        let mut left = self.cond_and()?;
+        loop {
+            self.skip_ws();
+            if self.eat_word("or") {
+                left = Cond::Or(Box::new(left), Box::new(self.cond_and()?));
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    fn cond_and(&mut self) -> Result<Cond, String> {
+        let mut left = self.comparison()?;
+        loop {
+            self.skip_ws();
+            if self.eat_word("and") {
+                left = Cond::And(Box::new(left), Box::new(self.comparison()?));
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Cond, String> {
+        let steps = self.path()?;
+        self.skip_ws();
+        let op = if self.rest().starts_with("==") {
+            CmpOp::Eq
+        } else if self.rest().starts_with("!=") {
+            CmpOp::Ne
+        } else if self.rest().starts_with("<=") {
+            CmpOp::Le
+        } else if self.rest().starts_with(">=") {
+            CmpOp::Ge
+        } else if self.rest().starts_with('<') {
+            CmpOp::Lt
+        } else if self.rest().starts_with('>') {
+            CmpOp::Gt
+        } else {
+            return Ok(Cond::Truthy(steps));
+        };
+        self.pos += if matches!(op, CmpOp::Lt | CmpOp::Gt) { 1 } else { 2 };
+        let literal = self.literal()?;
+        Ok(Cond::Cmp(steps, op, literal))
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
@@ -0,0 +1,271 @@
+//! JSON Utilities
+//!
+//! String-first helpers over the query engine: get, set, delete, write back
+//! to files, and flatten into the global store.
+
+use super::query::{resolve_index, type_name, Query, Step};
+use crate::global::{expand_vars, set_var};
+use serde_json::Value;
+
+// --- Parsing / formatting ---
+
+/// Parses a JSON document.
+pub fn json_parse(json: &str) -> Result<Value, String> {
+    serde_json::from_str(json).map_err(|e| format!("invalid JSON: {}", e))
+}
+
+/// Reads and parses a JSON file (path is variable-expanded).
+pub fn json_read_file(path: &str) -> Result<Value, String> {
+    let path = expand_vars(path);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read JSON file '{}': {}", path, e))?;
+    json_parse(&text)
+}
+
+/// Writes `doc` pretty-printed (with a trailing newline) to `path`.
+pub fn json_write_file(path: &str, doc: &Value) -> Result<(), String> {
+    let path = expand_vars(path);
+    let mut text = serde_json::to_string_pretty(doc).map_err(|e| e.to_string())?;
+    text.push('\n');
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write JSON file '{}': {}", path, e))
+}
+
+/// Formats a value like `jq -r`: strings raw, everything else as compact JSON.
+pub fn json_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Interprets a string-first value: valid JSON (`42`, `true`, `[1]`,
+/// `"quoted"`) is used as-is, anything else becomes a JSON string.
+pub fn json_value_from_str(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+// --- Query ---
+
+/// Runs a query against a parsed document.
+pub fn json_query(doc: &Value, query: &str) -> Result<Vec<Value>, String> {
+    Query::parse(query)?.eval(doc)
+}
+
+/// Queries a JSON string; results are `jq -r` formatted, one per line.
+/// Missing paths yield `null`.
+pub fn json_get(json: &str, query: &str) -> Result<String, String> {
+    let results = json_query(&json_parse(json)?, query)?;
+    Ok(results.iter().map(json_to_text).collect::<Vec<_>>().join("\n"))
+}
+
+/// Queries a JSON file; see `json_get`.
+pub fn json_get_file(path: &str, query: &str) -> Result<String, String> {
+    let results = json_query(&json_read_file(path)?, query)?;
+    Ok(results.iter().map(json_to_text).collect::<Vec<_>>().join("\n"))
+}
+
+// --- Set / delete ---
+
+fn plain_path(path: &str) -> Result<Vec<Step>, String> {
+    let query = Query::parse(path)?;
+    query
+        .as_path()
+        .map(|steps| steps.to_vec())
+        .ok_or_else(|| format!("'{}' is not a plain path", path))
+}
+
+/// Sets every location matched by `path` to `value`, creating missing
+/// objects and padding arrays with `null` along the way. Returns how many
+/// locations were written.
+pub fn json_set_path(doc: &mut Value, path: &str, value: Value) -> Result<usize, String> {
+    let steps = plain_path(path)?;
+    set_steps(doc, &steps, &value)
+}
+
+fn set_steps(target: &mut Value, steps: &[Step], value: &Value) -> Result<usize, String> {
+    let Some((step, rest)) = steps.split_first() else {
+        *target = value.clone();
+        return Ok(1);
+    };
+    if target.is_null() {
+        *target = match step {
+            Step::Index(_) => Value::Array(Vec::new()),
+            _ => Value::Object(Default::default()),
+        };
+    }
+    match (step, target) {
+        (Step::Key(key), Value::Object(map)) => {
+            set_steps(map.entry(key.clone()).or_insert(Value::Null), rest, value)
+        }
+        (Step::Index(idx), Value::Array(items)) => {
+            let i = resolve_index(*idx, items.len())
+                .ok_or_else(|| format!("index {} out of range", idx))?;
+            if i >= items.len() {
+                items.resize(i + 1, Value::Null);
+            }
+            set_steps(&mut items[i], rest, value)
+        }
+        (Step::Each, Value::Array(items)) => items
+            .iter_mut()
+            .try_fold(0, |n, item| Ok(n + set_steps(item, rest, value)?)),
+        (Step::Each, Value::Object(map)) => map
+            .values_mut()
+            .try_fold(0, |n, item| Ok(n + set_steps(item, rest, value)?)),
+        (_, other) => Err(format!("cannot set a path inside {}", type_name(other))),
+    }
+}
+
+/// Removes every location matched by `path`. Returns how many were removed.
+pub fn json_delete_path(doc: &mut Value, path: &str) -> Result<usize, String> {
+    let steps = plain_path(path)?;
+    if steps.is_empty() {
+        return Err("cannot delete the document root".to_string());
+    }
+    delete_steps(doc, &steps)
+}
+
+fn delete_steps(target: &mut Value, steps: &[Step]) -> Result<usize, String> {
+    let (step, rest) = steps.split_first().expect("non-empty path");
+    if rest.is_empty() {
+        return Ok(match (step, target) {
+            (Step::Key(key), Value::Object(map)) => map.remove(key).map_or(0, |_| 1),
+            (Step::Index(idx), Value::Array(items)) => match resolve_index(*idx, items.len()) {
+                Some(i) if i < items.len() => {
+                    items.remove(i);
+                    1
+                }
+                _ => 0,
+            },
+            (Step::Each, Value::Array(items)) => std::mem::take(items).len(),
+            (Step::Each, Value::Object(map)) => std::mem::take(map).len(),
+            _ => 0,
+        });
+    }
+    match (step, target) {
+        (Step::Key(key), Value::Object(map)) => match map.get_mut(key) {
+            Some(child) => delete_steps(child, rest),
+            None => Ok(0),
+        },
+        (Step::Index(idx), Value::Array(items)) => {
+            let len = items.len();
+            match resolve_index(*idx, len).and_then(|i| items.get_mut(i)) {
+                Some(child) => delete_steps(child, rest),
+                None => Ok(0),
+            }
+        }
+        (Step::Each, Value::Array(items)) => items
+            .iter_mut()
+            .try_fold(0, |n, item| Ok(n + delete_steps(item, rest)?)),
+        (Step::Each, Value::Object(map)) => map
+            .values_mut()
+            .try_fold(0, |n, item| Ok(n + delete_steps(item, rest)?)),
+        _ => Ok(0),
+    }
+}
+
+/// Sets `path` in a JSON string and returns the updated document (compact).
+/// `value` is string-first; see `json_value_from_str`.
+pub fn json_set(json: &str, path: &str, value: &str) -> Result<String, String> {
+    let mut doc = json_parse(json)?;
+    json_set_path(&mut doc, path, json_value_from_str(value))?;
+    Ok(doc.to_string())
+}
+
+/// Deletes `path` from a JSON string and returns the updated document (compact).
+pub fn json_delete(json: &str, path: &str) -> Result<String, String> {
+    let mut doc = json_parse(json)?;
+    json_delete_path(&mut doc, path)?;
+    Ok(doc.to_string())
+}
+
+/// Sets `path` in a JSON file and writes it back.
+pub fn json_set_file(file: &str, path: &str, value: &str) -> Result<(), String> {
+    let mut doc = json_read_file(file)?;
+    json_set_path(&mut doc, path, json_value_from_str(value))?;
+    json_write_file(file, &doc)
+}
+
+/// Deletes `path` from a JSON file and writes it back.
+pub fn json_delete_file(file: &str, path: &str) -> Result<(), String> {
+    let mut doc = json_read_file(file)?;
+    json_delete_path(&mut doc, path)?;
+    json_write_file(file, &doc)
+}
+
+// --- Global store ---
+
+/// Flattens a document into `(key, value)` pairs using RSB naming:
+/// nested keys are joined with `_` (non-alphanumerics become `_`), arrays
+/// use the `KEY_LENGTH` + `KEY_<i>` convention, `null` becomes "".
+pub fn json_flatten(doc: &Value, prefix: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    flatten_into(doc, &sanitize_key(prefix), &mut out);
+    out
+}
+
+fn flatten_into(value: &Value, key: &str, out: &mut Vec<(String, String)>) {
+    let join = |child: &str| {
+        if key.is_empty() {
+            child.to_string()
+        } else {
+            format!("{}_{}", key, child)
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            for (child, item) in map {
+                flatten_into(item, &join(&sanitize_key(child)), out);
+            }
+        }
+        Value::Array(items) => {
+            out.push((join("LENGTH"), items.len().to_string()));
+            for (i, item) in items.iter().enumerate() {
+                flatten_into(item, &join(&i.to_string()), out);
+            }
+            // Scalar arrays also get the space-joined form, like `set_array`
+            if !key.is_empty() && items.iter().all(|item| !item.is_object() && !item.is_array()) {
+                let joined = items.iter().map(scalar_text).collect::<Vec<_>>().join(" ");
+                out.push((key.to_string(), joined));
+            }
+        }
+        scalar => {
+            if !key.is_empty() {
+                out.push((key.to_string(), scalar_text(scalar)));
+            }
+        }
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        other => json_to_text(other),
+    }
+}
+
+fn sanitize_key(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Flattens a JSON string into the global store under `prefix`
+/// (see `json_flatten`). Returns the keys written.
+pub fn json_to_global(json: &str, prefix: &str) -> Result<Vec<String>, String> {
+    Ok(store_flat(json_flatten(&json_parse(json)?, prefix)))
+}
+
+/// Flattens a JSON file into the global store under `prefix`.
+pub fn json_file_to_global(path: &str, prefix: &str) -> Result<Vec<String>, String> {
+    Ok(store_flat(json_flatten(&json_read_file(path)?, prefix)))
+}
+
+fn store_flat(pairs: Vec<(String, String)>) -> Vec<String> {
+    pairs
+        .into_iter()
+        .map(|(key, value)| {
+            set_var(&key, &value);
+            key
+        })
+        .collect()
+}
@@ -8,6 +8,7 @@ pub mod date;
 pub mod deps;
 pub mod global;
 pub mod jobs;
+pub mod log;
 pub mod math;
 pub mod string;
 pub mod utils;
@@ -34,8 +35,8 @@ pub mod visual;
 #[cfg(feature = "progress")]
 pub mod progress;
 
-// Optional dev utilities (e.g., PTY wrapper for tests)
-#[cfg(feature = "dev-pty")]
+// Optional dev utilities (e.g., PTY wrapper, loopback HTTP server for tests)
+#[cfg(any(feature = "dev-pty", feature = "dev-http"))]
 pub mod dev;
 
 // Param helpers namespace (non-macro implementation details)
@@ -54,6 +55,13 @@ pub mod parse;
 #[cfg(feature = "object")]
 pub mod object;
 
+// Native JSON query/edit engine (serde_json-backed)
+pub mod json;
+
+// Native HTTP client (feature-gated)
+#[cfg(feature = "http")]
+pub mod http;
+
 // TOML snooping module for extracting Cargo.toml metadata
 pub mod toml;
 
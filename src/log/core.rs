@@ -0,0 +1,221 @@
+//! The logger: a filter plus named sinks
+//!
+//! Until a filter is set (`RSB_LOG` at bootstrap, or `set_log_filter`),
+//! records follow the legacy `stderrx` gating (`should_print_level`, driven
+//! by the quiet/debug/trace modes) and span records are dropped. A sink can
+//! carry its own filter, e.g. a log file at `info` while the terminal stays
+//! quiet.
+
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+use crate::global;
+use crate::log::{
+    default_log_path, FileSink, Level, LogFilter, LogFormat, LogSink, Record, StderrSink,
+};
+
+/// Target of the span enter/exit records.
+pub const SPAN_TARGET: &str = "rsb::span";
+
+struct SinkEntry {
+    name: String,
+    filter: Option<LogFilter>,
+    sink: Box<dyn LogSink>,
+}
+
+struct Logger {
+    filter: Option<LogFilter>,
+    sinks: Vec<SinkEntry>,
+}
+
+/// A sink's own filter, else the logger's, else the legacy gating.
+fn passes(own: Option<&LogFilter>, logger: Option<&LogFilter>, level: Level, target: &str) -> bool {
+    match own.or(logger) {
+        Some(filter) => filter.enabled(level, target),
+        None => target != SPAN_TARGET && crate::utils::should_print_level(level.as_str()),
+    }
+}
+
+lazy_static! {
+    static ref LOGGER: Mutex<Logger> = Mutex::new(Logger {
+        filter: None,
+        sinks: vec![SinkEntry {
+            name: "stderr".to_string(),
+            filter: None,
+            sink: Box::new(StderrSink::new()),
+        }],
+    });
+}
+
+/// Sets the filter for sinks without their own (`"warn,myapp::db=debug"`).
+pub fn set_log_filter(spec: &str) -> Result<(), String> {
+    let filter = LogFilter::parse(spec)?;
+    LOGGER.lock().unwrap().filter = Some(filter);
+    Ok(())
+}
+
+/// Back to the legacy quiet/debug/trace gating.
+pub fn clear_log_filter() {
+    LOGGER.lock().unwrap().filter = None;
+}
+
+/// Adds (or replaces) a sink under `name`; it uses the logger's filter.
+/// The default stderr sink is named `"stderr"`.
+pub fn add_sink(name: &str, sink: impl LogSink + 'static) {
+    insert_sink(name, None, Box::new(sink));
+}
+
+/// Adds (or replaces) a sink that uses its own filter.
+pub fn add_sink_with_filter(name: &str, sink: impl LogSink + 'static, filter: LogFilter) {
+    insert_sink(name, Some(filter), Box::new(sink));
+}
+
+fn insert_sink(name: &str, filter: Option<LogFilter>, sink: Box<dyn LogSink>) {
+    let mut logger = LOGGER.lock().unwrap();
+    logger.sinks.retain(|entry| entry.name != name);
+    logger.sinks.push(SinkEntry {
+        name: name.to_string(),
+        filter,
+        sink,
+    });
+}
+
+/// Removes a sink, flushing it first. Returns whether it existed.
+pub fn remove_sink(name: &str) -> bool {
+    let mut logger = LOGGER.lock().unwrap();
+    match logger.sinks.iter().position(|entry| entry.name == name) {
+        Some(index) => {
+            logger.sinks.remove(index).sink.flush();
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn sink_names() -> Vec<String> {
+    let logger = LOGGER.lock().unwrap();
+    logger
+        .sinks
+        .iter()
+        .map(|entry| entry.name.clone())
+        .collect()
+}
+
+pub fn flush_logs() {
+    for entry in LOGGER.lock().unwrap().sinks.iter_mut() {
+        entry.sink.flush();
+    }
+}
+
+/// Whether any sink would take a record at `level` for `target`.
+pub fn log_enabled(level: Level, target: &str) -> bool {
+    let logger = LOGGER.lock().unwrap();
+    logger
+        .sinks
+        .iter()
+        .any(|entry| passes(entry.filter.as_ref(), logger.filter.as_ref(), level, target))
+}
+
+/// Logs `message` at the named level (`"info"`, `"warn"`, ...; unknown names
+/// log at info). `$VARS` in the message are expanded, as in `stderrx`.
+pub fn log(level: &str, target: &str, message: &str) {
+    log_fields(level, target, message, &[]);
+}
+
+/// Logs with key/value fields attached.
+pub fn log_fields(level: &str, target: &str, message: &str, fields: &[(&str, String)]) {
+    let level = Level::from_name(level).unwrap_or(Level::Info);
+    if !log_enabled(level, target) {
+        return;
+    }
+    let record = Record {
+        timestamp: chrono::Local::now(),
+        level,
+        target: target.to_string(),
+        message: global::expand_vars(message),
+        fields: fields
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect(),
+        span: global::call_path(),
+    };
+    let mut logger = LOGGER.lock().unwrap();
+    let Logger { filter, sinks } = &mut *logger;
+    for entry in sinks.iter_mut() {
+        if passes(entry.filter.as_ref(), filter.as_ref(), level, target) {
+            entry.sink.write(&record);
+        }
+    }
+}
+
+/// Configures logging from Global (called by `hosts::bootstrap`):
+/// - `RSB_LOG` — filter spec for all sinks
+/// - `RSB_LOG_FILE` — log file path; `1`/`true`/`auto` for
+///   `$XDG_STATE_HOME/rsb/<tool>.log`. The file uses `RSB_LOG`, or `info`
+///   when it isn't set.
+/// - `RSB_LOG_FORMAT=json` — JSON lines in the log file
+/// - `RSB_LOG_MAX_BYTES` / `RSB_LOG_KEEP` — rotation size and file count
+pub fn init_from_env() {
+    let spec = global::get_var("RSB_LOG");
+    if !spec.is_empty() {
+        if let Err(e) = set_log_filter(&spec) {
+            crate::utils::stderrx("warn", &format!("RSB_LOG: {}", e));
+        }
+    }
+
+    let file = global::get_var("RSB_LOG_FILE");
+    if file.is_empty() {
+        return;
+    }
+    let path = match file.as_str() {
+        "1" | "true" | "auto" => default_log_path(&crate::cli::program_name()),
+        path => std::path::PathBuf::from(path),
+    };
+    let mut sink = FileSink::new(path);
+    if global::get_var("RSB_LOG_FORMAT") == "json" {
+        sink = sink.format(LogFormat::Json);
+    }
+    if let Ok(max_bytes) = global::get_var("RSB_LOG_MAX_BYTES").parse::<u64>() {
+        sink = sink.max_bytes(max_bytes);
+    }
+    if let Ok(keep) = global::get_var("RSB_LOG_KEEP").parse::<usize>() {
+        sink = sink.keep(keep);
+    }
+    match spec.is_empty() {
+        true => add_sink_with_filter("file", sink, LogFilter::level(Level::Info)),
+        false => add_sink("file", sink),
+    }
+}
+
+/// Called by `global::push_call` / `pop_call`: trace records on the
+/// `rsb::span` target for entering and leaving a frame.
+pub(crate) fn span_event(enter: bool, function: &str, fields: &[(&str, String)]) {
+    let message = match enter {
+        true => format!("enter {}", function),
+        false => format!("exit {}", function),
+    };
+    log_fields("trace", SPAN_TARGET, &message, fields);
+}
+
+/// A call frame that is popped when dropped: `let _span = log::span("load");`
+/// Records logged while it lives carry it in their span.
+#[must_use = "the span ends when this guard is dropped"]
+pub struct Span {
+    name: String,
+}
+
+pub fn span(name: &str) -> Span {
+    global::push_call(name, &[]);
+    Span {
+        name: name.to_string(),
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let top = global::call_path().last().cloned();
+        if top.as_deref() == Some(self.name.as_str()) {
+            global::pop_call();
+        }
+    }
+}
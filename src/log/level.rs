@@ -0,0 +1,136 @@
+//! Log levels and `RSB_LOG`-style filters
+//!
+//! A filter is a comma-separated list of directives: a bare level sets the
+//! default, `target=level` sets the level for a module target and everything
+//! beneath it (`rsb::fs` covers `rsb::fs::utils`). The most specific target
+//! wins; `off` silences a target.
+//!
+//! ```text
+//! RSB_LOG=warn,myapp::db=debug,rsb::span=trace
+//! ```
+
+/// Severity of a log record. `Okay` is a success note at `Info` severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Okay,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    /// Parses a level name as used by `stderrx` (`think` is an alias for
+    /// `trace`).
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name.trim().to_lowercase().as_str() {
+            "trace" | "think" => Some(Level::Trace),
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "okay" => Some(Level::Okay),
+            "warn" | "warning" => Some(Level::Warn),
+            "error" => Some(Level::Error),
+            "fatal" => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Okay => "okay",
+            Level::Warn => "warn",
+            Level::Error => "error",
+            Level::Fatal => "fatal",
+        }
+    }
+
+    fn severity(&self) -> u8 {
+        match self {
+            Level::Trace => 0,
+            Level::Debug => 1,
+            Level::Info | Level::Okay => 2,
+            Level::Warn => 3,
+            Level::Error => 4,
+            Level::Fatal => 5,
+        }
+    }
+
+    /// Whether a record at this level passes a `min` threshold.
+    pub fn at_least(&self, min: Level) -> bool {
+        self.severity() >= min.severity()
+    }
+}
+
+/// A parsed filter: a default threshold plus per-target overrides. `None`
+/// means off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: Option<Level>,
+    targets: Vec<(String, Option<Level>)>,
+}
+
+impl LogFilter {
+    /// A filter passing `min` and above for every target.
+    pub fn level(min: Level) -> Self {
+        LogFilter {
+            default: Some(min),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Parses `info,rsb::fs=debug,noisy=off`. An empty spec means `info`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut filter = LogFilter::level(Level::Info);
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let (target, level) = match directive.split_once('=') {
+                Some((target, level)) => (Some(target.trim()), level),
+                None => (None, directive),
+            };
+            let level = match level.trim() {
+                "off" | "none" => None,
+                name => Some(
+                    Level::from_name(name)
+                        .ok_or_else(|| format!("unknown log level '{}'", name))?,
+                ),
+            };
+            match target {
+                Some("") => return Err(format!("missing target in '{}'", directive)),
+                Some(target) => {
+                    filter.targets.retain(|(t, _)| t != target);
+                    filter.targets.push((target.to_string(), level));
+                }
+                None => filter.default = level,
+            }
+        }
+        // Most specific targets first
+        filter
+            .targets
+            .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        Ok(filter)
+    }
+
+    /// The threshold for `target`: its most specific directive, else the
+    /// default.
+    pub fn threshold(&self, target: &str) -> Option<Level> {
+        self.targets
+            .iter()
+            .find(|(t, _)| {
+                target == t
+                    || target
+                        .strip_prefix(t.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    pub fn enabled(&self, level: Level, target: &str) -> bool {
+        self.threshold(target)
+            .is_some_and(|min| level.at_least(min))
+    }
+}
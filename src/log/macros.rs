@@ -0,0 +1,21 @@
+//! Logging macros
+//!
+//! `log!` takes a level name, an optional `target:`, a format string and,
+//! after a `;`, key/value fields. The target defaults to the caller's
+//! module path.
+
+/// `log!(warn, "retrying {}", host; attempt = 2)`
+#[macro_export]
+macro_rules! log {
+    ($level:ident, target: $target:expr, $fmt:literal $(, $arg:expr)* $(; $($key:ident = $value:expr),+ )?) => {
+        $crate::log::log_fields(
+            stringify!($level),
+            $target,
+            &format!($fmt $(, $arg)*),
+            &[$($((stringify!($key), $value.to_string())),+)?],
+        )
+    };
+    ($level:ident, $fmt:literal $(, $arg:expr)* $(; $($key:ident = $value:expr),+ )?) => {
+        $crate::log!($level, target: module_path!(), $fmt $(, $arg)* $(; $($key = $value),+ )?)
+    };
+}
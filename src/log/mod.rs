@@ -0,0 +1,31 @@
+//! Logging Module (MODULE_SPEC aligned)
+//!
+//! Leveled, structured logging behind `utils::stderrx` and the `info!` /
+//! `warn!` / ... macros. Records carry a timestamp, a module target,
+//! key/value fields and the current `global::push_call` frames (the span),
+//! and go to named sinks: colored stderr by default, plus rotating files
+//! (text or JSON lines) and JSON writers.
+//!
+//! Filtering uses `RSB_LOG`-style specs (`warn,myapp::db=debug`). Without
+//! one, output follows the quiet/debug/trace modes exactly as `stderrx`
+//! always has. Pushing and popping call frames logs `trace` records on the
+//! `rsb::span` target, so a log file at `trace` shows where a failed run was.
+//!
+//! # Usage
+//! ```ignore
+//! use rsb::prelude::*;
+//!
+//! // RSB_LOG=info,rsb::span=trace RSB_LOG_FILE=auto mytool deploy
+//! rsb::log!(warn, "retrying {}", host; attempt = 2, delay_ms = 500);
+//! let _span = rsb::log::span("sync");
+//! rsb::log::add_sink("audit", rsb::log::FileSink::new("audit.jsonl").json());
+//! ```
+
+pub mod core;
+pub mod level;
+pub mod macros;
+pub mod sink;
+
+pub use core::*;
+pub use level::*;
+pub use sink::*;
@@ -0,0 +1,369 @@
+//! Log records and sinks
+//!
+//! A sink receives every record that passes its filter. Built-in sinks:
+//! `StderrSink` (the colored `stderrx` look), `FileSink` (text or JSON
+//! lines, rotated by size) and `JsonSink` (JSON lines to any writer).
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Map, Value};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::log::Level;
+
+/// One log event.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub timestamp: DateTime<Local>,
+    pub level: Level,
+    /// Module path of the caller (`""` for plain `stderrx` calls)
+    pub target: String,
+    pub message: String,
+    pub fields: Vec<(String, String)>,
+    /// Function names of the `global::push_call` frames, outermost first
+    pub span: Vec<String>,
+}
+
+impl Record {
+    /// Message with inline color tags expanded and any ANSI codes removed.
+    pub fn plain_message(&self) -> String {
+        strip_ansi(&crate::utils::expand_colors_unified(&self.message))
+    }
+
+    /// `2026-01-02T03:04:05.678+00:00 WARN  target [a>b] message key=value`
+    pub fn to_text(&self) -> String {
+        let mut line = format!(
+            "{} {:<5}",
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            self.level.as_str().to_uppercase()
+        );
+        if !self.target.is_empty() {
+            line.push(' ');
+            line.push_str(&self.target);
+        }
+        if !self.span.is_empty() {
+            line.push_str(&format!(" [{}]", self.span.join(">")));
+        }
+        line.push(' ');
+        line.push_str(&self.plain_message());
+        line.push_str(&self.fields_text());
+        line
+    }
+
+    /// One JSON object: `ts`, `level`, `target`, `msg`, `span`, `fields`.
+    pub fn to_json(&self) -> String {
+        let fields: Map<String, Value> = self
+            .fields
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+        json!({
+            "ts": self.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            "level": self.level.as_str(),
+            "target": self.target,
+            "msg": self.plain_message(),
+            "span": self.span,
+            "fields": fields,
+        })
+        .to_string()
+    }
+
+    /// ` key=value ...`, quoting values with spaces.
+    fn fields_text(&self) -> String {
+        self.fields
+            .iter()
+            .map(
+                |(k, v)| match v.contains(char::is_whitespace) || v.is_empty() {
+                    true => format!(" {}={:?}", k, v),
+                    false => format!(" {}={}", k, v),
+                },
+            )
+            .collect()
+    }
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            for nc in chars.by_ref() {
+                if nc.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Receives log records that passed its filter.
+pub trait LogSink: Send {
+    fn write(&mut self, record: &Record);
+    fn flush(&mut self) {}
+}
+
+/// Line format for file and writer sinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Colored, glyph-prefixed lines on stderr (the `stderrx` look). Quiet mode
+/// (`QUIET_MODE`) keeps only errors here, whatever the filter says.
+#[derive(Debug, Clone, Default)]
+pub struct StderrSink {
+    timestamps: bool,
+    targets: bool,
+}
+
+impl StderrSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timestamps(mut self) -> Self {
+        self.timestamps = true;
+        self
+    }
+
+    pub fn with_targets(mut self) -> Self {
+        self.targets = true;
+        self
+    }
+}
+
+impl LogSink for StderrSink {
+    fn write(&mut self, record: &Record) {
+        let level = record.level.as_str();
+        if crate::global::has_var("QUIET_MODE") && !["error", "fatal"].contains(&level) {
+            return;
+        }
+        let mut prefix = String::new();
+        if self.timestamps {
+            prefix.push_str(&record.timestamp.format("%H:%M:%S%.3f ").to_string());
+        }
+        if self.targets && !record.target.is_empty() {
+            prefix.push_str(&format!("{}: ", record.target));
+        }
+        let text = format!("{}{}{}", prefix, record.message, record.fields_text());
+        eprintln!("{}", styled_line(level, &text));
+    }
+}
+
+/// `text` with the color and glyph of `level`, as `StderrSink` prints it.
+/// Names it doesn't know get the plain bullet.
+pub(crate) fn styled_line(level: &str, text: &str) -> String {
+    let line = format!(
+        "{{{}}}{} {}{{reset}}",
+        level_color(level),
+        level_glyph(level),
+        text
+    );
+    crate::utils::expand_colors_unified(&line)
+}
+
+fn level_glyph(level: &str) -> String {
+    #[cfg(feature = "glyphs")]
+    {
+        if crate::visual::glyphs::glyphs_enabled() {
+            // Map levels to explicit glyph names (non-emoji)
+            let key = match level {
+                "info" => "info",
+                "okay" => "pass",
+                "warn" => "delta",
+                "error" | "fatal" => "fail",
+                "debug" => "bolt",
+                "trace" => "dots",
+                _ => "bullet",
+            };
+            let g = crate::visual::glyphs::glyph(key);
+            if !g.is_empty() {
+                return g.to_string();
+            }
+        }
+    }
+    let _ = level;
+    "•".to_string()
+}
+
+/// Color tag for a level: the visual registry's entry if it has one,
+/// otherwise a fixed mapping.
+fn level_color(level: &str) -> &str {
+    #[cfg(feature = "colors-core")]
+    {
+        if !crate::colors::color(level).is_empty() {
+            return level;
+        }
+    }
+    match level {
+        "info" => "cyan",
+        "okay" => "green",
+        "warn" => "yellow",
+        "error" | "fatal" => "red",
+        "debug" => "grey",
+        "trace" => "magenta",
+        _ => "reset",
+    }
+}
+
+/// Appends records to a file, rotating it when it would grow past
+/// `max_bytes`: `app.log` becomes `app.log.1`, `app.log.1` becomes
+/// `app.log.2`, and so on up to `keep` old files.
+#[derive(Debug)]
+pub struct FileSink {
+    path: PathBuf,
+    format: LogFormat,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl FileSink {
+    /// Text lines, rotated at 1 MiB, keeping 3 old files.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        FileSink {
+            path: path.as_ref().to_path_buf(),
+            format: LogFormat::Text,
+            max_bytes: 1024 * 1024,
+            keep: 3,
+            file: None,
+            size: 0,
+        }
+    }
+
+    /// Writes JSON lines instead of text.
+    pub fn json(mut self) -> Self {
+        self.format = LogFormat::Json;
+        self
+    }
+
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Size that triggers rotation (0 disables rotation).
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Number of rotated files to keep.
+    pub fn keep(mut self, keep: usize) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(&mut self) -> std::io::Result<&mut File> {
+        if self.file.is_none() {
+            if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.size = file.metadata().map(|m| m.len()).unwrap_or(0);
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+        let rotated = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        if self.keep == 0 {
+            return std::fs::remove_file(&self.path);
+        }
+        let _ = std::fs::remove_file(rotated(self.keep));
+        for n in (1..self.keep).rev() {
+            if rotated(n).exists() {
+                std::fs::rename(rotated(n), rotated(n + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, rotated(1))
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        self.open()?;
+        if self.max_bytes > 0 && self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        let file = self.open()?;
+        writeln!(file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+impl LogSink for FileSink {
+    fn write(&mut self, record: &Record) {
+        let line = match self.format {
+            LogFormat::Text => record.to_text(),
+            LogFormat::Json => record.to_json(),
+        };
+        // Report directly: logging the failure would come back here
+        if let Err(e) = self.write_line(&line) {
+            eprintln!("log: cannot write {}: {}", self.path.display(), e);
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(file) = self.file.as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// JSON lines to any writer (stdout by default).
+pub struct JsonSink {
+    out: Box<dyn Write + Send>,
+}
+
+impl JsonSink {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        JsonSink { out }
+    }
+
+    pub fn stdout() -> Self {
+        JsonSink::new(Box::new(std::io::stdout()))
+    }
+}
+
+impl LogSink for JsonSink {
+    fn write(&mut self, record: &Record) {
+        let _ = writeln!(self.out, "{}", record.to_json());
+    }
+
+    fn flush(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+/// Default log file: `$XDG_STATE_HOME/rsb/<tool>.log`, with
+/// `XDG_STATE_HOME` falling back to `$HOME/.local/state`.
+pub fn default_log_path(tool: &str) -> PathBuf {
+    let mut state = crate::global::get_var("XDG_STATE_HOME");
+    if state.is_empty() {
+        state = std::env::var("XDG_STATE_HOME").unwrap_or_default();
+    }
+    if state.is_empty() {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        state = format!("{}/.local/state", home);
+    }
+    PathBuf::from(state)
+        .join("rsb")
+        .join(format!("{}.log", tool))
+}
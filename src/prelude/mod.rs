@@ -27,6 +27,8 @@ pub use crate::global::{
 };
 // OS/host functions now under hosts module
 pub use crate::hosts::*;
+// Event payload passed to trap!/event! handlers
+pub use crate::jobs::EventData;
 pub use crate::streamable::{
     Base64Decode,
     Base64Encode,
@@ -110,8 +112,10 @@ pub use crate::{
     // boolean helpers
     is_true,
     job,
+    json_del,
     json_get,
     json_get_file,
+    json_set,
     kebab,
     kebab_var,
     kill_pid,
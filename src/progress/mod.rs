@@ -32,10 +32,26 @@ pub mod terminal;
 // Cross-module adapter for RSB colors integration
 pub mod progress_colors;
 
+// Cross-module adapter for live command output under a task
+pub mod progress_command;
+
+// Cross-module adapter for HTTP downloads under a task
+#[cfg(feature = "http")]
+pub mod progress_http;
+
+// Cross-module adapter for archive create/extract under a task
+#[cfg(feature = "archive")]
+pub mod progress_archive;
+
 // Re-exports for convenience
 pub use core::{ProgressEvent, ProgressReporter, ProgressState, ProgressTask};
 pub use manager::ProgressManager;
+#[cfg(feature = "archive")]
+pub use progress_archive::{create_with_task, extract_with_task};
 pub use progress_colors::ProgressColorScheme;
+pub use progress_command::run_with_task;
+#[cfg(feature = "http")]
+pub use progress_http::download_with_task;
 pub use styles::{BarStyle, MessagePosition, ProgressStyle, SpinnerStyle};
 pub use terminal::{TerminalConfig, TerminalReporter};
 
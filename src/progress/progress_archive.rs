@@ -0,0 +1,55 @@
+//! Progress Archive Adapter
+//!
+//! Cross-module integration between progress and `rsb::fs::archive`
+//! (feature `archive`). Reports one step per entry on a task, then
+//! completes or fails it from the result.
+//!
+//! Following RSB MODULE_SPEC for cross-module integration patterns.
+
+use crate::fs::archive::{
+    create_archive_with_progress, extract_archive_with_progress, ArchiveEntry, ArchiveOptions,
+};
+use crate::progress::ProgressTask;
+
+fn report(task: &ProgressTask, entry: &ArchiveEntry, done: usize, total: Option<usize>) {
+    let message = match total {
+        Some(total) => format!("[{}/{}] {}", done, total, entry.path),
+        None => entry.path.clone(),
+    };
+    task.update(done as u64, &message);
+}
+
+fn finish(task: &ProgressTask, verb: &str, result: &Result<Vec<ArchiveEntry>, String>) {
+    match result {
+        Ok(entries) => task.complete(&format!("{} {} entries", verb, entries.len())),
+        Err(err) => task.fail(err),
+    }
+}
+
+/// Create `archive` from `sources` under `task`: progress is the entry count.
+pub fn create_with_task(
+    archive: &str,
+    sources: &[&str],
+    opts: &ArchiveOptions,
+    task: &ProgressTask,
+) -> Result<Vec<ArchiveEntry>, String> {
+    let result = create_archive_with_progress(archive, sources, opts, &mut |entry, done, total| {
+        report(task, entry, done, total)
+    });
+    finish(task, "archived", &result);
+    result
+}
+
+/// Extract `archive` into `dest` under `task`: progress is the entry count.
+pub fn extract_with_task(
+    archive: &str,
+    dest: &str,
+    opts: &ArchiveOptions,
+    task: &ProgressTask,
+) -> Result<Vec<ArchiveEntry>, String> {
+    let result = extract_archive_with_progress(archive, dest, opts, &mut |entry, done, total| {
+        report(task, entry, done, total)
+    });
+    finish(task, "extracted", &result);
+    result
+}
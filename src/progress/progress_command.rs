@@ -0,0 +1,30 @@
+//! Progress Command Adapter
+//!
+//! Cross-module integration between progress and `hosts::command`.
+//! Runs a pipeline with live output and shows the latest line as the
+//! task message, so long builds display a live tail under their task.
+//!
+//! Following RSB MODULE_SPEC for cross-module integration patterns.
+
+use crate::hosts::command::{PipeResult, Pipeline};
+use crate::progress::ProgressTask;
+
+/// Run `pipeline` under `task`: every output line becomes the task message,
+/// then the task completes or fails based on the pipeline status.
+pub fn run_with_task(pipeline: Pipeline, task: &ProgressTask) -> PipeResult {
+    let result = pipeline.run_live(|line| {
+        let text = line.text().trim();
+        if !text.is_empty() {
+            task.update_message(text);
+        }
+    });
+
+    if result.success() {
+        task.complete("done");
+    } else if result.timed_out {
+        task.fail("timed out");
+    } else {
+        task.fail(&format!("exit status {}", result.status));
+    }
+    result
+}
@@ -0,0 +1,34 @@
+//! Progress HTTP Adapter
+//!
+//! Cross-module integration between progress and `rsb::http` (feature
+//! `http`). Streams a download to disk while reporting bytes received on a
+//! task, then completes or fails it from the response.
+//!
+//! Following RSB MODULE_SPEC for cross-module integration patterns.
+
+use crate::http::{HttpError, HttpRequest, HttpResponse};
+use crate::progress::core::utils::format_bytes;
+use crate::progress::ProgressTask;
+
+/// Download `request` to `path` under `task`: progress is the byte count
+/// (with a "received / total" message when the size is known).
+pub fn download_with_task(
+    request: &HttpRequest,
+    path: &str,
+    task: &ProgressTask,
+) -> Result<HttpResponse, HttpError> {
+    let result = request.download_with_progress(path, |done, total| {
+        let message = match total {
+            Some(total) => format!("{} / {}", format_bytes(done), format_bytes(total)),
+            None => format_bytes(done),
+        };
+        task.update(done, &message);
+    });
+
+    match &result {
+        Ok(response) if response.is_success() => task.complete("done"),
+        Ok(response) => task.fail(&format!("HTTP {}", response.status)),
+        Err(err) => task.fail(&err.to_string()),
+    }
+    result
+}
@@ -13,6 +13,7 @@ use super::core::{ProgressEvent, ProgressReporter, ProgressState};
 use super::progress_colors::ProgressColorScheme;
 #[allow(unused_imports)]
 use super::styles::{BarStyle, MessagePosition, ProgressStyle, SpinnerStyle};
+use crate::hosts::{term_caps, ColorDepth};
 
 /// Configuration for terminal progress display
 #[derive(Debug, Clone)]
@@ -31,21 +32,24 @@ pub struct TerminalConfig {
     pub spinner_refresh_ms: u64,
     /// Whether to clear completed tasks
     pub clear_on_complete: bool,
-    /// Maximum width for progress display
+    /// Maximum width for progress display; longer lines are cut so they
+    /// don't wrap and break redraws
     pub max_width: Option<usize>,
 }
 
 impl Default for TerminalConfig {
+    /// Colors, unicode and width follow the detected terminal (`hosts::term_caps`)
     fn default() -> Self {
+        let caps = term_caps();
         Self {
-            use_colors: true,
+            use_colors: caps.color != ColorDepth::None,
             color_scheme: ProgressColorScheme::default(),
-            use_unicode: true,
+            use_unicode: caps.unicode,
             use_stderr: false,
             update_interval_ms: 50,
             spinner_refresh_ms: 80,  // ~12 FPS for smooth spinner animation
             clear_on_complete: false,
-            max_width: None,
+            max_width: caps.is_tty.then_some(caps.width),
         }
     }
 }
@@ -743,6 +747,14 @@ impl TerminalReporter {
     /// Write output with control over line ending
     fn write_output_with_ending(&self, text: &str, use_newline: bool) {
         let ending = if use_newline { b"\n" } else { b"\r" };
+        let fitted;
+        let text = match self.config.max_width {
+            Some(width) => {
+                fitted = fit_width(text, width);
+                fitted.as_str()
+            }
+            None => text,
+        };
 
         if self.config.use_stderr {
             let _ = io::stderr().write_all(text.as_bytes());
@@ -895,6 +907,9 @@ impl ProgressReporter for TerminalReporter {
         if let Some(value) = config.get("update_interval_ms") {
             self.config.update_interval_ms = value.parse().unwrap_or(50);
         }
+        if let Some(value) = config.get("max_width") {
+            self.config.max_width = value.parse().ok();
+        }
     }
 }
 
@@ -904,6 +919,40 @@ impl Default for TerminalReporter {
     }
 }
 
+/// Cuts each line to fewer than `width` visible columns (the last column
+/// would wrap on some terminals). Escape sequences are kept and don't count;
+/// a cut line ends with a reset.
+fn fit_width(text: &str, width: usize) -> String {
+    let limit = width.saturating_sub(1).max(1);
+    let mut out = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let mut visible = 0;
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                out.push(c);
+                for c in chars.by_ref() {
+                    out.push(c);
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+                continue;
+            }
+            if visible == limit {
+                out.push_str("\x1b[0m");
+                break;
+            }
+            out.push(c);
+            visible += 1;
+        }
+    }
+    out
+}
+
 impl Drop for TerminalReporter {
     fn drop(&mut self) {
         // Always show cursor when reporter is dropped to avoid leaving cursor hidden
@@ -210,6 +210,22 @@ impl Repl {
         }
     }
 
+    /// Run a command from the shared handler table
+    ///
+    /// Commands registered by `dispatch!` / `pre_dispatch!` or
+    /// `global::register_handler` run with the same routing as on the command
+    /// line (aliases, nested paths, spec validation). Handlers see the words
+    /// after the command as `args.get(1)..`, exactly as under `dispatch!`.
+    ///
+    /// # Returns
+    /// * `Some(code)` - the handler's exit code
+    /// * `None` - no registered command matches
+    pub fn run_command(&self, args: &Args) -> Option<i32> {
+        let mut words = vec![crate::cli::program_name()];
+        words.extend(args.all().iter().cloned());
+        crate::cli::run_command(&Args::new(&words))
+    }
+
     /// Show command history
     fn show_history(&self) {
         if self.history.is_empty() {
@@ -231,6 +247,20 @@ impl Repl {
         println!("  clear       - Clear REPL context variables");
         println!("  history     - Show command history");
         println!("  help        - Show this help message");
+
+        let commands = crate::global::list_handlers();
+        if !commands.is_empty() {
+            let descriptions = crate::global::list_functions();
+            println!("\nCommands:");
+            for name in commands {
+                let desc = descriptions
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, d)| d.as_str())
+                    .unwrap_or("");
+                println!("  {:<11} - {}", name, desc);
+            }
+        }
         println!();
     }
 }
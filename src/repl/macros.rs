@@ -78,6 +78,8 @@ macro_rules! repl_argv {
 /// Interactive REPL loop with command routing
 ///
 /// Creates a REPL loop that reads commands, dispatches to handlers, and manages state.
+/// Commands not listed in the table fall back to the shared handler table, so
+/// everything registered by `dispatch!` is available in the REPL too.
 ///
 /// # Example
 /// ```rust,ignore
@@ -126,9 +128,13 @@ macro_rules! repl_dispatch {
                                     }
                                 },)*
                                 "" => continue,
-                                unknown => {
-                                    eprintln!("Unknown command: {}", unknown);
-                                    eprintln!("Type 'help' for available commands");
+                                unknown => match repl.run_command(&cmd_args) {
+                                    Some(0) => {},
+                                    Some(code) => eprintln!("Error: '{}' exited with code {}", unknown, code),
+                                    None => {
+                                        eprintln!("Unknown command: {}", unknown);
+                                        eprintln!("Type 'help' for available commands");
+                                    }
                                 }
                             }
                         },
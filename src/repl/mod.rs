@@ -5,7 +5,8 @@
 //! - Global argument storage (repl_arg_* pattern)
 //! - Built-in commands (exit, quit, clear, history, help)
 //! - Dynamic prompt configuration
-//! - Integration with dispatch! system
+//! - Integration with dispatch! system (shared command table: commands
+//!   registered by `dispatch!` run in the REPL without a second table)
 //!
 //! # Example Usage
 //!
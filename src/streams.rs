@@ -1,18 +1,33 @@
 // src/streams.rs
 
-use crate::fs::{append_file, read_file, write_file};
+use crate::com::ErrorCode;
 use crate::global::{expand_vars, get_var, set_var};
-use crate::hosts::command::run_cmd;
-use std::collections::HashSet;
-use std::io::Write;
+use crate::hosts::command::{stream_cmd, Pipeline};
+use std::cell::{OnceCell, RefCell};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
 
 // Module-owned macros for stream constructors and shell helpers
 pub mod macros;
 
+/// Boxed line source behind a lazy stream.
+type LineIter = Box<dyn Iterator<Item = String> + Send>;
+
 /// A struct for building and executing bash-like data processing pipelines.
-#[derive(Debug, Clone)]
+///
+/// Streams are lazy: `from_file`/`from_cmd` pull lines on demand and most
+/// operations wrap the underlying iterator, so `cat!(log).grep("x").head(10)`
+/// stops reading (and stops the child process) after ten matches. Only
+/// operations that need the whole input (`sort`, `tail`, `sed_block`,
+/// `sed_around`, `sed_insert`) buffer. Borrowing sinks such as `to_string`,
+/// `first` and `last` materialize the remaining lines once and cache them.
+/// Streams are `Send`, so sources and closures passed in must be too.
 pub struct Stream {
-    lines: Vec<String>,
+    source: RefCell<Option<LineIter>>,
+    lines: OnceCell<Vec<String>>,
 }
 
 impl Stream {
@@ -20,39 +35,49 @@ impl Stream {
 
     /// Creates a new, empty stream.
     pub fn new() -> Self {
-        Stream { lines: Vec::new() }
+        Self::buffered(Vec::new())
     }
 
     /// Creates a stream from a string.
     pub fn from_string(content: &str) -> Self {
-        Stream {
-            lines: content.lines().map(|s| s.to_string()).collect(),
-        }
+        Self::buffered(content.lines().map(|s| s.to_string()).collect())
     }
 
-    /// Creates a stream from the contents of a file.
+    /// Creates a lazy stream over the lines of a file.
     pub fn from_file(path: &str) -> Self {
-        let content = read_file(&expand_vars(path));
-        Self::from_string(&content)
+        Self::from_reader(open_file(path))
     }
 
-    /// Creates a stream from the contents of multiple files.
+    /// Creates a lazy stream over the lines of multiple files, in order.
     pub fn from_files(paths: &[&str]) -> Self {
-        let mut content = String::new();
-        for path in paths {
-            let file_content = read_file(&expand_vars(path));
-            if !content.is_empty() {
-                content.push('\n');
-            }
-            content.push_str(&file_content);
-        }
-        Self::from_string(&content)
+        let readers: Vec<BufReader<File>> = paths.iter().map(|path| open_file(path)).collect();
+        Self::lazy(readers.into_iter().flat_map(ReaderLines::new))
     }
 
-    /// Creates a stream from the stdout of a shell command.
+    /// Creates a lazy stream from the stdout of a shell command.
+    /// The command is killed if the stream is dropped before it finishes.
     pub fn from_cmd(cmd: &str) -> Self {
-        let output = run_cmd(&expand_vars(cmd));
-        Self::from_string(&output)
+        Self::lazy(stream_cmd(cmd))
+    }
+
+    /// Creates a lazy stream from the stdout of an argv-style `Pipeline`.
+    pub fn from_pipeline(pipeline: Pipeline) -> Self {
+        Self::lazy(pipeline.lines())
+    }
+
+    /// Creates a lazy stream from any buffered reader (stdin, sockets, ...).
+    /// Invalid UTF-8 is replaced rather than ending the stream.
+    pub fn from_reader<R: BufRead + Send + 'static>(reader: R) -> Self {
+        Self::lazy(ReaderLines::new(reader))
+    }
+
+    /// Creates a lazy stream from an iterator of lines.
+    pub fn from_lines<I>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+        I::IntoIter: Send + 'static,
+    {
+        Self::lazy(lines.into_iter())
     }
 
     /// Creates a stream from an RSB context variable.
@@ -63,97 +88,151 @@ impl Stream {
 
     /// Creates a stream from a vector of strings.
     pub fn from_vec(lines: &[String]) -> Self {
-        Stream {
-            lines: lines.to_vec(),
-        }
+        Self::buffered(lines.to_vec())
     }
 
     /// Creates a stream from a single string by splitting it with a delimiter.
     pub fn from_delimited_string(content: &str, delimiter: &str) -> Self {
+        Self::buffered(content.split(delimiter).map(|s| s.to_string()).collect())
+    }
+
+    // --- Internal plumbing ---
+
+    fn lazy<I: Iterator<Item = String> + Send + 'static>(iter: I) -> Self {
         Stream {
-            lines: content.split(delimiter).map(|s| s.to_string()).collect(),
+            source: RefCell::new(Some(Box::new(iter))),
+            lines: OnceCell::new(),
         }
     }
 
-    // --- Chainable Operations ---
+    fn buffered(lines: Vec<String>) -> Self {
+        Stream {
+            source: RefCell::new(None),
+            lines: OnceCell::from(lines),
+        }
+    }
 
-    /// Filters lines in the stream, keeping only those that contain the pattern.
-    pub fn grep(mut self, pattern: &str) -> Self {
-        self.lines.retain(|line| line.contains(pattern));
-        self
+    /// Drains any pending source into the cache and returns all lines.
+    fn buffer(&self) -> &Vec<String> {
+        self.lines.get_or_init(|| {
+            self.source
+                .borrow_mut()
+                .take()
+                .map(|iter| iter.collect())
+                .unwrap_or_default()
+        })
+    }
+
+    fn into_lines(self) -> LineIter {
+        match self.lines.into_inner() {
+            Some(lines) => Box::new(lines.into_iter()),
+            None => self
+                .source
+                .into_inner()
+                .unwrap_or_else(|| Box::new(std::iter::empty())),
+        }
     }
 
-    /// Replaces all occurrences of a pattern in the stream.
-    pub fn sed(mut self, from: &str, to: &str) -> Self {
-        self.lines = self
-            .lines
-            .iter()
-            .map(|line| line.replace(from, to))
-            .collect();
-        self
+    fn into_buffer(self) -> Vec<String> {
+        match self.lines.into_inner() {
+            Some(lines) => lines,
+            None => self
+                .source
+                .into_inner()
+                .map(|iter| iter.collect())
+                .unwrap_or_default(),
+        }
     }
 
-    /// Extracts a specific field from each line, based on a delimiter. (1-indexed)
-    pub fn cut(mut self, field: usize, delimiter: &str) -> Self {
-        self.lines = self
-            .lines
-            .iter()
-            .filter_map(|line| line.split(delimiter).nth(field.saturating_sub(1)))
-            .map(|s| s.to_string())
-            .collect();
-        self
+    /// Returns true once the stream has been materialized into memory.
+    pub fn is_buffered(&self) -> bool {
+        self.lines.get().is_some()
     }
 
-    /// Takes the first `n` lines of the stream.
-    pub fn head(mut self, n: usize) -> Self {
-        self.lines.truncate(n);
-        self
+    // --- Chainable Operations ---
+
+    /// Filters lines in the stream, keeping only those that contain the pattern.
+    pub fn grep(self, pattern: &str) -> Self {
+        let pattern = pattern.to_string();
+        Self::lazy(self.into_lines().filter(move |line| line.contains(&pattern)))
+    }
+
+    /// Replaces all occurrences of a pattern in the stream.
+    pub fn sed(self, from: &str, to: &str) -> Self {
+        let (from, to) = (from.to_string(), to.to_string());
+        Self::lazy(self.into_lines().map(move |line| line.replace(&from, &to)))
     }
 
-    /// Takes the last `n` lines of the stream.
-    pub fn tail(mut self, n: usize) -> Self {
-        let len = self.lines.len();
-        if len > n {
-            self.lines = self.lines.into_iter().skip(len - n).collect();
+    /// Extracts a specific field from each line, based on a delimiter. (1-indexed)
+    pub fn cut(self, field: usize, delimiter: &str) -> Self {
+        let delimiter = delimiter.to_string();
+        Self::lazy(self.into_lines().filter_map(move |line| {
+            line.split(delimiter.as_str())
+                .nth(field.saturating_sub(1))
+                .map(|s| s.to_string())
+        }))
+    }
+
+    /// Takes the first `n` lines of the stream. Upstream sources stop being
+    /// read once `n` lines have been produced.
+    pub fn head(self, n: usize) -> Self {
+        Self::lazy(self.into_lines().take(n))
+    }
+
+    /// Takes the last `n` lines of the stream (buffers at most `n` lines).
+    pub fn tail(self, n: usize) -> Self {
+        let mut window = VecDeque::with_capacity(n);
+        for line in self.into_lines() {
+            if n == 0 {
+                continue;
+            }
+            if window.len() == n {
+                window.pop_front();
+            }
+            window.push_back(line);
         }
-        self
+        Self::buffered(window.into())
     }
 
     /// Sorts the lines in the stream alphabetically.
-    pub fn sort(mut self) -> Self {
-        self.lines.sort();
-        self
+    pub fn sort(self) -> Self {
+        let mut lines = self.into_buffer();
+        lines.sort();
+        Self::buffered(lines)
     }
 
     /// Removes duplicate consecutive lines from the stream.
-    pub fn uniq(mut self) -> Self {
-        self.lines.dedup();
-        self
+    pub fn uniq(self) -> Self {
+        let mut previous: Option<String> = None;
+        Self::lazy(self.into_lines().filter(move |line| {
+            if previous.as_ref() == Some(line) {
+                return false;
+            }
+            previous = Some(line.clone());
+            true
+        }))
     }
 
     /// Removes all duplicate lines from the stream, regardless of order.
-    pub fn unique(mut self) -> Self {
+    pub fn unique(self) -> Self {
         let mut seen = HashSet::new();
-        self.lines.retain(|line| seen.insert(line.clone()));
-        self
+        Self::lazy(self.into_lines().filter(move |line| seen.insert(line.clone())))
     }
 
     /// Applies a custom filter function to each line.
-    pub fn filter<F>(mut self, predicate: F) -> Self
+    pub fn filter<F>(self, predicate: F) -> Self
     where
-        F: Fn(&str) -> bool,
+        F: Fn(&str) -> bool + Send + 'static,
     {
-        self.lines.retain(|line| predicate(line));
-        self
+        Self::lazy(self.into_lines().filter(move |line| predicate(line)))
     }
 
     /// Applies a custom mapping function to each line.
-    pub fn map<F>(mut self, mapper: F) -> Self
+    pub fn map<F>(self, mapper: F) -> Self
     where
-        F: Fn(&str) -> String,
+        F: Fn(&str) -> String + Send + 'static,
     {
-        self.lines = self.lines.iter().map(|line| mapper(line)).collect();
-        self
+        Self::lazy(self.into_lines().map(move |line| mapper(&line)))
     }
 
     /// Case conversions per-line using string::case helpers (line-size safe)
@@ -183,12 +262,12 @@ impl Stream {
     }
 
     /// Replaces a block of text between two patterns.
-    pub fn sed_block(mut self, start_pattern: &str, end_pattern: &str, replacement: &str) -> Self {
+    pub fn sed_block(self, start_pattern: &str, end_pattern: &str, replacement: &str) -> Self {
         let mut result_lines = Vec::new();
         let mut buffer = Vec::new();
         let mut in_block = false;
 
-        for line in self.lines {
+        for line in self.into_lines() {
             if !in_block && line.contains(start_pattern) {
                 in_block = true;
                 // The line that starts the block is part of the block
@@ -215,45 +294,36 @@ impl Stream {
             result_lines.extend(buffer);
         }
 
-        self.lines = result_lines;
-        self
+        Self::buffered(result_lines)
     }
 
     /// Returns lines between two line numbers (1-indexed, inclusive).
-    pub fn sed_lines(mut self, start_line: usize, end_line: usize) -> Self {
+    pub fn sed_lines(self, start_line: usize, end_line: usize) -> Self {
         if start_line == 0 || end_line == 0 || start_line > end_line {
-            self.lines.clear();
-            return self;
+            return Self::new();
         }
 
-        let start_idx = start_line.saturating_sub(1);
-        let end_idx = std::cmp::min(end_line, self.lines.len());
-
-        if start_idx >= self.lines.len() {
-            self.lines.clear();
-        } else {
-            self.lines = self.lines[start_idx..end_idx].to_vec();
-        }
-        self
+        Self::lazy(
+            self.into_lines()
+                .skip(start_line - 1)
+                .take(end_line - start_line + 1),
+        )
     }
 
     /// Returns N lines before and after a matching string.
-    pub fn sed_around(mut self, pattern: &str, context_lines: usize) -> Self {
+    pub fn sed_around(self, pattern: &str, context_lines: usize) -> Self {
+        let lines = self.into_buffer();
         let mut result_lines = Vec::new();
-        let total_lines = self.lines.len();
+        let total_lines = lines.len();
 
-        for (i, line) in self.lines.iter().enumerate() {
+        for (i, line) in lines.iter().enumerate() {
             if line.contains(pattern) {
                 // Calculate range with context
                 let start_idx = i.saturating_sub(context_lines);
                 let end_idx = std::cmp::min(i + context_lines + 1, total_lines);
 
                 // Add context lines
-                for j in start_idx..end_idx {
-                    if j < total_lines {
-                        result_lines.push(self.lines[j].clone());
-                    }
-                }
+                result_lines.extend(lines[start_idx..end_idx].iter().cloned());
             }
         }
 
@@ -266,16 +336,13 @@ impl Stream {
                 unique_lines.push(line);
             }
         }
-        result_lines = unique_lines;
-
-        self.lines = result_lines;
-        self
+        Self::buffered(unique_lines)
     }
 
     /// Inserts content at a unique sentinel location. Errors if sentinel is not unique.
-    pub fn sed_insert(mut self, content: &str, sentinel: &str) -> Result<Self, String> {
-        let matches: Vec<usize> = self
-            .lines
+    pub fn sed_insert(self, content: &str, sentinel: &str) -> Result<Self, String> {
+        let mut lines = self.into_buffer();
+        let matches: Vec<usize> = lines
             .iter()
             .enumerate()
             .filter(|(_, line)| line.contains(sentinel))
@@ -298,60 +365,41 @@ impl Stream {
         let content_lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
 
         // Replace the sentinel line with the content
-        self.lines.splice(insert_idx..=insert_idx, content_lines);
-        Ok(self)
+        lines.splice(insert_idx..=insert_idx, content_lines);
+        Ok(Self::buffered(lines))
     }
 
     /// Replaces all occurrences of a sentinel with content (template mode).
-    pub fn sed_template(mut self, content: &str, sentinel: &str) -> Self {
-        for line in &mut self.lines {
-            if line.contains(sentinel) {
-                *line = line.replace(sentinel, content);
-            }
-        }
-        self
+    pub fn sed_template(self, content: &str, sentinel: &str) -> Self {
+        self.sed(sentinel, content)
     }
 
     /// Pipes the stream's content as stdin to another shell command.
+    /// The command's stdout is read lazily as a new stream.
     pub fn pipe_to_cmd(self, command: &str) -> Self {
-        let input = self.to_string();
-        let expanded_cmd = expand_vars(command);
-
-        let mut child = std::process::Command::new("sh")
-            .arg("-c")
-            .arg(&expanded_cmd)
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
-            .spawn()
-            .expect("Failed to spawn command for pipe_to_cmd");
-
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin
-                .write_all(input.as_bytes())
-                .expect("Failed to write to stdin");
-        }
+        self.pipe_to(Pipeline::shell(&expand_vars(command)))
+    }
 
-        let output = child
-            .wait_with_output()
-            .expect("Failed to read stdout from piped command");
-        Self::from_string(&String::from_utf8_lossy(&output.stdout))
+    /// Pipes the stream's content into an argv-style `Pipeline` (no shell).
+    pub fn pipe_to(self, pipeline: Pipeline) -> Self {
+        Self::lazy(pipeline.stdin_stream(self).lines())
     }
 
     // --- Sink (Consuming) Operations ---
 
     /// Returns the stream's contents as a single string.
     pub fn to_string(&self) -> String {
-        self.lines.join("\n")
+        self.buffer().join("\n")
     }
 
     /// Consumes the stream and returns its contents as a vector of strings.
     pub fn to_vec(self) -> Vec<String> {
-        self.lines
+        self.into_buffer()
     }
 
-    /// Writes the stream's contents to a file, then consumes the stream.
+    /// Writes the stream's contents to a file line by line, then consumes the stream.
     pub fn to_file(self, path: &str) {
-        write_file(&expand_vars(path), &self.to_string());
+        write_lines(path, self.into_lines(), false);
     }
 
     /// Writes the stream's contents to a variable, then returns the stream.
@@ -362,12 +410,12 @@ impl Stream {
 
     /// Appends the stream's contents to a file, then consumes the stream.
     pub fn append_to_file(self, path: &str) {
-        append_file(&expand_vars(path), &self.to_string());
+        write_lines(path, self.into_lines(), true);
     }
 
     /// Writes the stream's contents to a file, but returns the stream for further processing.
     pub fn tee(&self, path: &str) -> Self {
-        write_file(&expand_vars(path), &self.to_string());
+        crate::fs::write_file(&expand_vars(path), &self.to_string());
         self.clone()
     }
 
@@ -376,7 +424,7 @@ impl Stream {
     where
         F: Fn(&str),
     {
-        for line in &self.lines {
+        for line in self.buffer() {
             action(line);
         }
         self
@@ -384,17 +432,17 @@ impl Stream {
 
     /// Consumes the stream and returns the number of lines.
     pub fn count(self) -> usize {
-        self.lines.len()
+        self.into_lines().count()
     }
 
     /// Returns the first line of the stream, if any.
     pub fn first(&self) -> Option<&String> {
-        self.lines.first()
+        self.buffer().first()
     }
 
     /// Returns the last line of the stream, if any.
     pub fn last(&self) -> Option<&String> {
-        self.lines.last()
+        self.buffer().last()
     }
 }
 
@@ -403,3 +451,142 @@ impl Default for Stream {
         Self::new()
     }
 }
+
+impl Clone for Stream {
+    fn clone(&self) -> Self {
+        Self::buffered(self.buffer().clone())
+    }
+}
+
+impl fmt::Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.lines.get() {
+            Some(lines) => f.debug_struct("Stream").field("lines", lines).finish(),
+            None => f.debug_struct("Stream").field("lines", &"<lazy>").finish(),
+        }
+    }
+}
+
+impl IntoIterator for Stream {
+    type Item = String;
+    type IntoIter = LineIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_lines()
+    }
+}
+
+impl FromIterator<String> for Stream {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self::buffered(iter.into_iter().collect())
+    }
+}
+
+// --- Line I/O helpers ---
+
+/// Lossy line iterator over a reader (strips `\n` / `\r\n`).
+struct ReaderLines<R> {
+    reader: R,
+}
+
+impl<R: BufRead> ReaderLines<R> {
+    fn new(reader: R) -> Self {
+        ReaderLines { reader }
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderLines<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let mut buf = Vec::new();
+        match self.reader.read_until(b'\n', &mut buf) {
+            Ok(n) if n > 0 => {
+                if buf.ends_with(b"\n") {
+                    buf.pop();
+                    if buf.ends_with(b"\r") {
+                        buf.pop();
+                    }
+                }
+                Some(String::from_utf8_lossy(&buf).to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Opens a file for streaming, exiting like `fs::read_file` when it can't.
+fn open_file(path: &str) -> BufReader<File> {
+    let expanded_path = expand_vars(path);
+    match File::open(&expanded_path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            eprintln!("rsb-error: Failed to read file '{}': {}", expanded_path, e);
+            ErrorCode(1).exit();
+        }
+    }
+}
+
+/// Writes lines joined by newlines without buffering the whole stream.
+/// Append mode adds a trailing newline, matching `fs::append_file`.
+/// Writes `lines` to `path`, as a dry-run/trace aware side effect like
+/// `fs::write_file`.
+fn write_lines(path: &str, lines: LineIter, append: bool) {
+    let expanded_path = expand_vars(path);
+    let what = match append {
+        true => format!("append {}", expanded_path),
+        false => format!("write {}", expanded_path),
+    };
+    crate::hosts::side_effect(&what, (), || write_lines_now(&expanded_path, lines, append))
+}
+
+fn write_lines_now(expanded_path: &str, lines: LineIter, append: bool) {
+    if let Some(parent) = Path::new(&expanded_path).parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            eprintln!(
+                "rsb-error: Failed to create directory '{}': {}",
+                parent.display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(expanded_path);
+    let mut writer = match file {
+        Ok(f) => BufWriter::new(f),
+        Err(e) => {
+            eprintln!(
+                "rsb-error: Failed to write to file '{}': {}",
+                expanded_path, e
+            );
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = emit_lines(&mut writer, lines, append) {
+        eprintln!(
+            "rsb-error: Failed to write to file '{}': {}",
+            expanded_path, e
+        );
+        std::process::exit(1);
+    }
+}
+
+fn emit_lines(writer: &mut impl Write, lines: LineIter, append: bool) -> std::io::Result<()> {
+    for (i, line) in lines.enumerate() {
+        if i > 0 {
+            writer.write_all(b"\n")?;
+        }
+        writer.write_all(line.as_bytes())?;
+    }
+    if append {
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
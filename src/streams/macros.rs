@@ -13,6 +13,15 @@ macro_rules! cat {
 
 #[macro_export]
 macro_rules! cmd {
+    (argv: $($arg:expr),+ $(,)?) => {
+        $crate::streams::Stream::from_pipeline(
+            $crate::hosts::command::Pipeline::new()
+                .argv(&[$(::std::convert::AsRef::<str>::as_ref(&$arg)),+]),
+        )
+    };
+    (pipeline: $pipeline:expr) => {
+        $crate::streams::Stream::from_pipeline($pipeline)
+    };
     ($command:expr) => {
         $crate::streams::Stream::from_cmd($command)
     };
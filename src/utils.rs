@@ -1,4 +1,4 @@
-use crate::global::{expand_vars, has_var};
+use crate::global::has_var;
 
 // This module will contain miscellaneous utilities, such as the
 // StringExt trait, array operations, and user interaction functions.
@@ -56,78 +56,17 @@ pub fn expand_colors_unified(text: &str) -> String {
     }
 }
 
+/// Level-prefixed message on stderr, routed through `crate::log` so file
+/// and JSON sinks see it too. `$VARS` and color tags are expanded. Level
+/// names the logger doesn't know print straight to stderr with a plain
+/// bullet, unless in quiet mode, as they always have.
 pub fn stderrx(level: &str, message: &str) {
-    if !should_print_level(level) {
-        return;
+    if crate::log::Level::from_name(level).is_some() {
+        crate::log::log(level, "", message);
+    } else if should_print_level(level) {
+        let message = crate::global::expand_vars(message);
+        eprintln!("{}", crate::log::styled_line(level, &message));
     }
-    let glyph = {
-        #[cfg(feature = "glyphs")]
-        {
-            if crate::visual::glyphs::glyphs_enabled() {
-                // Map levels to explicit glyph names (non-emoji)
-                let key = match level {
-                    "info" => "info",
-                    "okay" => "pass",
-                    "warn" => "delta",
-                    "error" | "fatal" => "fail",
-                    "debug" => "bolt",
-                    "trace" => "dots",
-                    _ => "bullet",
-                };
-                let g = crate::visual::glyphs::glyph(key);
-                if !g.is_empty() {
-                    g.to_string()
-                } else {
-                    "•".to_string()
-                }
-            } else {
-                "•".to_string()
-            }
-        }
-        #[cfg(not(feature = "glyphs"))]
-        {
-            "•".to_string()
-        }
-    };
-
-    // Decide color name for level via visual registry if available, otherwise fallback mapping
-    let color_name = {
-        #[cfg(feature = "colors-core")]
-        {
-            if !crate::colors::color(level).is_empty() {
-                level
-            } else {
-                match level {
-                    "info" => "cyan",
-                    "okay" => "green",
-                    "warn" => "yellow",
-                    "error" | "fatal" => "red",
-                    "debug" => "grey",
-                    "trace" => "magenta",
-                    _ => "reset",
-                }
-            }
-        }
-        #[cfg(not(feature = "colors-core"))]
-        {
-            match level {
-                "info" => "cyan",
-                "okay" => "green",
-                "warn" => "yellow",
-                "error" | "fatal" => "red",
-                "debug" => "grey",
-                "trace" => "magenta",
-                _ => "reset",
-            }
-        }
-    };
-
-    // Construct a format string with placeholders
-    let format_string = format!("{{{}}}{} {}", color_name, glyph, message);
-
-    // Expand variables and then colors (mutexes are now released)
-    let expanded_vars = expand_vars(&format_string);
-    eprintln!("{}", expand_colors_unified(&expanded_vars));
 }
 
 // Note: Math comparison functions (num_eq, num_lt, num_gt) moved to math::comparison module
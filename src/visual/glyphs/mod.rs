@@ -2,9 +2,13 @@
 //!
 //! String-first, case-insensitive glyphs with a tiny footprint.
 //! Enable at runtime via `glyph_enable()` or include "glyphs" in visuals features.
+//! When the terminal can't show unicode (see `hosts::term_caps`), glyphs with
+//! an ASCII stand-in return that instead and the rest return "".
 
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use crate::hosts::term_caps;
+
 static GLYPHS_ENABLED: AtomicBool = AtomicBool::new(false);
 
 // Full set ported from src/ref/glyphs.rs (case-insensitive names lowered)
@@ -118,6 +122,44 @@ const GLYPHS: &[(&str, &str)] = &[
     ("box", "\u{25A0}"),
 ];
 
+// ASCII stand-ins for non-unicode terminals
+const ASCII_FALLBACKS: &[(&str, &str)] = &[
+    ("usage", "*"),
+    ("info", "i"),
+    ("ellipsis", "..."),
+    ("pass", "+"),
+    ("fail", "x"),
+    ("mark", "x"),
+    ("dots", "..."),
+    ("bolt", "!"),
+    ("bullet", "*"),
+    ("dot", "."),
+    ("target", "o"),
+    ("radio_on", "(*)"),
+    ("radio_off", "( )"),
+    ("square_small", "-"),
+    ("pointer", ">"),
+    ("up", "^"),
+    ("down", "v"),
+    ("right", "->"),
+    ("left", "<-"),
+    ("heavy_arrow_right", "=>"),
+    ("return", "<-'"),
+    ("star", "*"),
+    ("delta", "^"),
+    ("tri_down", "v"),
+    ("diamond", "<>"),
+    ("spark", "*"),
+    ("sect", "S"),
+    ("sum", "E"),
+    ("note", "#"),
+    ("hline", "-"),
+    ("vline", "|"),
+    ("t_right", "+"),
+    ("corner_ur", "`"),
+    ("box", "#"),
+];
+
 pub fn glyph_enable() {
     GLYPHS_ENABLED.store(true, Ordering::SeqCst);
 }
@@ -128,13 +170,19 @@ pub fn glyphs_enabled() -> bool {
     GLYPHS_ENABLED.load(Ordering::SeqCst)
 }
 
-/// Lookup a glyph by name (case-insensitive). Returns empty string if disabled/unknown.
+/// Lookup a glyph by name (case-insensitive). Returns empty string if disabled/unknown,
+/// or the ASCII fallback on a non-unicode terminal.
 pub fn glyph(name: &str) -> &'static str {
     if !glyphs_enabled() {
         return "";
     }
     let key = name.to_ascii_lowercase();
-    for (n, v) in GLYPHS.iter() {
+    let table = if term_caps().unicode {
+        GLYPHS
+    } else {
+        ASCII_FALLBACKS
+    };
+    for (n, v) in table.iter() {
         if *n == key {
             return v;
         }
@@ -142,6 +190,12 @@ pub fn glyph(name: &str) -> &'static str {
     ""
 }
 
+/// Whether `name` is a known glyph, whatever the terminal can show.
+pub fn has_glyph(name: &str) -> bool {
+    let key = name.to_ascii_lowercase();
+    GLYPHS.iter().any(|(n, _)| *n == key)
+}
+
 pub fn get_all_glyphs() -> Vec<(String, &'static str)> {
     GLYPHS.iter().map(|(k, v)| (k.to_string(), *v)).collect()
 }
@@ -10,16 +10,16 @@
 
 /// Emit visual log lines with severity tags routed through stderr.
 #[macro_export]
-macro_rules! info { ($($arg:tt)*) => { $crate::utils::stderrx("info", &format!($($arg)*)); }; }
+macro_rules! info { ($($arg:tt)*) => { $crate::log::log("info", module_path!(), &format!($($arg)*)); }; }
 #[macro_export]
-macro_rules! okay { ($($arg:tt)*) => { $crate::utils::stderrx("okay", &format!($($arg)*)); }; }
+macro_rules! okay { ($($arg:tt)*) => { $crate::log::log("okay", module_path!(), &format!($($arg)*)); }; }
 #[macro_export]
-macro_rules! warn { ($($arg:tt)*) => { $crate::utils::stderrx("warn", &format!($($arg)*)); }; }
+macro_rules! warn { ($($arg:tt)*) => { $crate::log::log("warn", module_path!(), &format!($($arg)*)); }; }
 #[macro_export]
-macro_rules! error { ($($arg:tt)*) => { $crate::utils::stderrx("error", &format!($($arg)*)); }; }
+macro_rules! error { ($($arg:tt)*) => { $crate::log::log("error", module_path!(), &format!($($arg)*)); }; }
 #[macro_export]
-macro_rules! fatal { ($($arg:tt)*) => { $crate::utils::stderrx("fatal", &format!($($arg)*)); }; }
+macro_rules! fatal { ($($arg:tt)*) => { $crate::log::log("fatal", module_path!(), &format!($($arg)*)); }; }
 #[macro_export]
-macro_rules! debug { ($($arg:tt)*) => { $crate::utils::stderrx("debug", &format!($($arg)*)); }; }
+macro_rules! debug { ($($arg:tt)*) => { $crate::log::log("debug", module_path!(), &format!($($arg)*)); }; }
 #[macro_export]
-macro_rules! trace { ($($arg:tt)*) => { $crate::utils::stderrx("trace", &format!($($arg)*)); }; }
+macro_rules! trace { ($($arg:tt)*) => { $crate::log::log("trace", module_path!(), &format!($($arg)*)); }; }
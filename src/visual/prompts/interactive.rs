@@ -2,18 +2,22 @@
 //!
 //! This module contains the actual prompt function implementations that handle
 //! TTY detection, user input, and integration with global context flags.
+//! On a terminal, `select`, `multi_select`, `fuzzy_select`, `password` and
+//! `ask_edit` use the raw-mode widgets (arrow keys, masking, editing); they
+//! fall back to reading a line when raw mode can't be set up.
 
 use std::io::{self, Write};
 
+use super::widgets::{self, Echo};
 use crate::global::{expand_vars, get_var, is_true};
 use crate::utils::expand_colors_unified;
 
-fn stdin_is_tty() -> bool {
+pub(crate) fn stdin_is_tty() -> bool {
     // Use libc directly to avoid adding new deps.
     unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
 }
 
-fn render_prompt(prefix: &str, message: &str, suffix: &str) -> String {
+pub(crate) fn render_prompt(prefix: &str, message: &str, suffix: &str) -> String {
     // Minimal, color-friendly prefix using simple colors only
     // Examples: "{yellow}?{reset}", "{green}›{reset}"
     let s = format!("{} {}{} ", prefix, message, suffix);
@@ -107,7 +111,8 @@ pub fn ask(message: &str, default: Option<&str>) -> String {
     }
 }
 
-/// Present a simple selection list. Returns selected item or default/first.
+/// Present a selection list (arrow keys, Enter to choose). Returns the
+/// selected item, or default/first in quiet or non‑TTY.
 pub fn select(message: &str, options: &[&str], default_index: Option<usize>) -> String {
     if options.is_empty() {
         return String::new();
@@ -120,6 +125,21 @@ pub fn select(message: &str, options: &[&str], default_index: Option<usize>) ->
     if is_true("opt_quiet") || !stdin_is_tty() {
         return options[idx_default].to_string();
     }
+    if let Some(index) = widgets::select_list(message, options, idx_default) {
+        return options[index].to_string();
+    }
+    select_line(message, options, idx_default)
+}
+
+/// Line-based `select`: numbered options inline, answered by number or text.
+pub(crate) fn select_line(message: &str, options: &[&str], idx_default: usize) -> String {
+    if options.is_empty() {
+        return String::new();
+    }
+    let idx_default = idx_default.min(options.len() - 1);
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return options[idx_default].to_string();
+    }
 
     // Show inline options for brevity: "[1] one  [2] two  [3] three"
     let opts_inline = options
@@ -161,6 +181,103 @@ pub fn select(message: &str, options: &[&str], default_index: Option<usize>) ->
     }
 }
 
+/// Checkbox list (Space toggles, `a` toggles all, Enter confirms). Returns
+/// the checked items in list order; `defaults` (indexes) start checked and
+/// are returned as-is in quiet or non‑TTY.
+pub fn multi_select(message: &str, options: &[&str], defaults: &[usize]) -> Vec<String> {
+    let pick = |indexes: &[usize]| -> Vec<String> {
+        let mut indexes: Vec<usize> = indexes
+            .iter()
+            .copied()
+            .filter(|&i| i < options.len())
+            .collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+        indexes.iter().map(|&i| options[i].to_string()).collect()
+    };
+    if options.is_empty() || is_true("opt_quiet") || !stdin_is_tty() {
+        return pick(defaults);
+    }
+    if let Some(chosen) = widgets::multi_select_list(message, options, defaults) {
+        return pick(&chosen);
+    }
+
+    // Line fallback: "1,3" picks the first and third option
+    let opts_inline = options
+        .iter()
+        .enumerate()
+        .map(|(i, o)| format!("[{}]{}", i + 1, o))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let prompt = render_prompt(
+        "{cyan}●{reset}",
+        message,
+        &format!("  {}  (e.g. 1,3)", opts_inline),
+    );
+    loop {
+        print!("{}", prompt);
+        io::stdout().flush().ok();
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() || input.trim().is_empty() {
+            return pick(defaults);
+        }
+        let parsed: Option<Vec<usize>> = input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .map(|t| {
+                t.parse::<usize>()
+                    .ok()
+                    .filter(|n| (1..=options.len()).contains(n))
+                    .map(|n| n - 1)
+            })
+            .collect();
+        if let Some(indexes) = parsed {
+            return pick(&indexes);
+        }
+    }
+}
+
+/// Selection list filtered as you type (fuzzy, case-insensitive). Returns
+/// the chosen item, or default/first in quiet or non‑TTY.
+pub fn fuzzy_select(message: &str, options: &[&str], default_index: Option<usize>) -> String {
+    if options.is_empty() {
+        return String::new();
+    }
+    let idx_default = default_index
+        .unwrap_or(0)
+        .min(options.len().saturating_sub(1));
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return options[idx_default].to_string();
+    }
+    match widgets::fuzzy_list(message, options, idx_default) {
+        Some(index) => options[index].to_string(),
+        None => select_line(message, options, idx_default),
+    }
+}
+
+/// Ask for a secret. Typed characters are echoed as `mask` (`Some('*')`),
+/// or not at all with `None`. Returns empty in quiet or non‑TTY.
+pub fn password(message: &str, mask: Option<char>) -> String {
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return String::new();
+    }
+    let echo = match mask {
+        Some(mask) => Echo::Mask(mask),
+        None => Echo::Hidden,
+    };
+    widgets::edit_line(message, "", echo).unwrap_or_else(|| ask(message, None))
+}
+
+/// Ask for a line of input with `initial` already typed in and editable
+/// (arrows, Home/End, Backspace, Ctrl-U/K/W). Returns `initial` in quiet or
+/// non‑TTY.
+pub fn ask_edit(message: &str, initial: &str) -> String {
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return initial.to_string();
+    }
+    widgets::edit_line(message, initial, Echo::Plain).unwrap_or_else(|| ask(message, Some(initial)))
+}
+
 /// Helper: fetch default for prompts from context by key, fallback to provided value.
 pub fn default_from(key: &str, fallback: &str) -> String {
     let v = get_var(key);
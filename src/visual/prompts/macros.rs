@@ -41,6 +41,47 @@ macro_rules! select {
     };
 }
 
+#[cfg(feature = "prompts")]
+#[macro_export]
+macro_rules! multi_select {
+    ($msg:expr, $options:expr) => {
+        $crate::visual::prompts::multi_select($msg, $options, &[])
+    };
+    ($msg:expr, $options:expr, $defaults:expr) => {
+        $crate::visual::prompts::multi_select($msg, $options, $defaults)
+    };
+}
+
+#[cfg(feature = "prompts")]
+#[macro_export]
+macro_rules! fuzzy_select {
+    ($msg:expr, $options:expr) => {
+        $crate::visual::prompts::fuzzy_select($msg, $options, None)
+    };
+    ($msg:expr, $options:expr, $default_idx:expr) => {
+        $crate::visual::prompts::fuzzy_select($msg, $options, Some($default_idx))
+    };
+}
+
+#[cfg(feature = "prompts")]
+#[macro_export]
+macro_rules! password {
+    ($msg:expr) => {
+        $crate::visual::prompts::password($msg, Some('*'))
+    };
+    ($msg:expr, $mask:expr) => {
+        $crate::visual::prompts::password($msg, $mask)
+    };
+}
+
+#[cfg(feature = "prompts")]
+#[macro_export]
+macro_rules! ask_edit {
+    ($msg:expr, $initial:expr) => {
+        $crate::visual::prompts::ask_edit($msg, $initial)
+    };
+}
+
 #[cfg(feature = "prompts")]
 #[macro_export]
 macro_rules! prompt {
@@ -6,18 +6,25 @@
 //! ## Architecture (MODULE_SPEC compliant)
 //! - `mod.rs` - Orchestrator and curated public surface (this file)
 //! - `interactive.rs` - Core prompt function implementations
+//! - `wizard.rs` - Multi-step `Wizard` flows with a confirmation summary
+//! - `typed.rs` - Validated prompts (`ask_int`, `ask_path`, ...) with answers from flags or a file
+//! - `widgets.rs` - Raw-mode widgets (arrow-key lists, fuzzy filter, line editor)
+//! - `term.rs` - termios raw mode and key decoding
 //! - `utils.rs` - Curated timeout functions (exposed via `visual::utils`)
 //! - `macros.rs` - Prompt macros (confirm!, ask!, select!, etc.)
 //!
 //! ## Global Context Integration
 //! - `opt_yes` → All confirmations return `true`
 //! - `opt_quiet` → All prompts return defaults without interaction
-//! - `opt_prompt_timeout` → Timeout for enhanced functions
+//! - `opt_prompt_timeout` → Timeout for enhanced functions (and typed prompts)
+//! - `opt_<key>` / `opt_answers` → Answers for typed prompts, no interaction
 //! - `PROMPT_TIMEOUT` → Environment variable fallback
 //!
 //! ## TTY Behavior
-//! - Interactive TTY: Shows prompts and waits for input
+//! - Interactive TTY: Shows prompts and waits for input; list, password and
+//!   edit prompts switch the terminal to raw mode while they run
 //! - Non-TTY/CI: Returns defaults immediately (non-blocking)
+//! - Ctrl-C in a raw-mode prompt restores the terminal and exits with 130
 //!
 //! ## Usage
 //! ```rust
@@ -27,6 +34,14 @@
 //! let answer = confirm("Continue?");
 //! let name = ask("Your name", Some("anonymous"));
 //! let choice = select("Pick one", &["a", "b", "c"], Some(0));
+//! let picked = multi_select("Features", &["tls", "gzip", "cache"], &[0]);
+//! let branch = fuzzy_select("Branch", &["main", "develop", "release/1.2"], None);
+//! let secret = password("Token", Some('*'));
+//! let path = ask_edit("Install to", "/usr/local/bin");
+//!
+//! // Typed: re-asks until valid; `--port=8080` or an answers file skips it
+//! let port = ask_int("port", "Port", Some(8080), 1..=65535).unwrap();
+//! let root = ask_path("root", "Install root", Some("~/.local"), PathCheck::CreateDir);
 //!
 //! // Timeout-enhanced (via helpers module)
 //! use rsb::{confirm_timeout, ask_timeout}; // Macros
@@ -36,10 +51,20 @@
 // MODULE_SPEC: Implementation modules
 pub mod interactive;
 pub mod macros;
+mod term;
+mod typed;
 pub mod utils; // Curated functions - exposed via visual::utils
+mod widgets;
+mod wizard;
 
 // MODULE_SPEC: Curated public surface - re-export core functions
-pub use interactive::{ask, confirm, confirm_default, default_from, select};
+pub use interactive::{
+    ask, ask_edit, confirm, confirm_default, default_from, fuzzy_select, multi_select, password,
+    select,
+};
+pub use typed::{answer_for, ask_choice, ask_int, ask_path, ask_regex, ask_with, PathCheck};
+pub use widgets::{fuzzy_filter, fuzzy_score, INTERRUPT_STATUS};
+pub use wizard::{Step, Wizard, WizardAnswers, WIZARD_BACK};
 
 // Note: Timeout-enhanced functions are available via:
 // 1. Ergonomic macros: confirm_timeout!, ask_timeout!, etc. (recommended)
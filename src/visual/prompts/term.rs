@@ -0,0 +1,179 @@
+//! Raw terminal input for the interactive prompts
+//!
+//! `RawMode` switches stdin to non-canonical, no-echo mode (termios via
+//! `libc`) and restores the saved settings when dropped. `read_key` decodes
+//! one keypress: control keys, arrow/home/end/delete escape sequences and
+//! UTF-8 characters. Bytes are read straight from the file descriptor so
+//! nothing is left sitting in a userspace buffer between prompts.
+
+use std::time::Duration;
+
+pub use crate::hosts::terminal_size;
+
+/// How long to wait for the rest of an escape sequence before treating ESC
+/// as a key of its own.
+const ESC_WAIT: Duration = Duration::from_millis(50);
+
+/// A decoded keypress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Esc,
+    /// Ctrl-C (signals are off in raw mode)
+    Interrupt,
+    /// Ctrl-D
+    Eof,
+    /// Any other Ctrl-<letter>
+    Ctrl(char),
+    Char(char),
+    /// An escape sequence with no meaning here
+    Unknown,
+}
+
+/// Stdin in raw mode for as long as this lives.
+pub struct RawMode {
+    saved: libc::termios,
+}
+
+impl RawMode {
+    /// `None` when stdin is not a terminal (or refuses the change).
+    pub fn enable() -> Option<RawMode> {
+        // SAFETY: termios is plain data; tcgetattr fills it before use.
+        unsafe {
+            let mut term: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut term) != 0 {
+                return None;
+            }
+            let saved = term;
+            term.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            term.c_iflag &= !(libc::IXON | libc::ICRNL);
+            term.c_cc[libc::VMIN] = 1;
+            term.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &term) != 0 {
+                return None;
+            }
+            Some(RawMode { saved })
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: restores the settings read in `enable`.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved);
+        }
+    }
+}
+
+/// Whether stdin has a byte ready within `timeout`.
+pub fn stdin_ready(timeout: Duration) -> bool {
+    let mut fds = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+    // SAFETY: one valid pollfd.
+    unsafe { libc::poll(&mut fds, 1, ms) > 0 }
+}
+
+/// One byte from stdin; `None` on EOF, error, or when `timeout` passes
+/// first.
+fn read_byte(timeout: Option<Duration>) -> Option<u8> {
+    if let Some(timeout) = timeout {
+        if !stdin_ready(timeout) {
+            return None;
+        }
+    }
+    let mut byte = 0u8;
+    loop {
+        // SAFETY: reads at most one byte into `byte`.
+        let n = unsafe { libc::read(libc::STDIN_FILENO, &mut byte as *mut u8 as *mut _, 1) };
+        match n {
+            1 => return Some(byte),
+            -1 if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted => {
+                continue
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Reads one keypress; `None` when stdin is closed.
+pub fn read_key() -> Option<Key> {
+    let byte = read_byte(None)?;
+    Some(match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x03 => Key::Interrupt,
+        0x04 => Key::Eof,
+        0x1b => read_escape(),
+        0x01..=0x1a => Key::Ctrl((b'a' + byte - 1) as char),
+        0x00..=0x1f => Key::Unknown,
+        _ => read_char(byte),
+    })
+}
+
+fn read_escape() -> Key {
+    match read_byte(Some(ESC_WAIT)) {
+        Some(b'[') | Some(b'O') => {}
+        _ => return Key::Esc,
+    }
+    let mut params = String::new();
+    let last = loop {
+        match read_byte(Some(ESC_WAIT)) {
+            Some(b @ 0x40..=0x7e) => break b,
+            Some(b) => params.push(b as char),
+            None => return Key::Unknown,
+        }
+    };
+    match (params.as_str(), last) {
+        ("", b'A') => Key::Up,
+        ("", b'B') => Key::Down,
+        ("", b'C') => Key::Right,
+        ("", b'D') => Key::Left,
+        ("", b'H') | ("1", b'~') | ("7", b'~') => Key::Home,
+        ("", b'F') | ("4", b'~') | ("8", b'~') => Key::End,
+        ("3", b'~') => Key::Delete,
+        ("5", b'~') => Key::PageUp,
+        ("6", b'~') => Key::PageDown,
+        _ => Key::Unknown,
+    }
+}
+
+/// Completes a UTF-8 character starting with `first`.
+fn read_char(first: u8) -> Key {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+    let mut bytes = vec![first];
+    for _ in 1..len {
+        match read_byte(Some(ESC_WAIT)) {
+            Some(b) => bytes.push(b),
+            None => break,
+        }
+    }
+    match std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|s| s.chars().next())
+    {
+        Some(c) => Key::Char(c),
+        None => Key::Unknown,
+    }
+}
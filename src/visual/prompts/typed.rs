@@ -0,0 +1,262 @@
+//! Typed, validated prompts built on `ask`
+//!
+//! Every prompt names its answer with a `key`. An answer supplied up front is
+//! used without asking, so the same installer runs unattended in CI:
+//! 1. `opt_<key>` (`--<key>=value`; dashes in `key` become underscores)
+//! 2. the answers file named by `opt_answers` (`--answers=FILE`) or
+//!    `PROMPT_ANSWERS`: a config file of `key=value` lines
+//!
+//! Otherwise the user is asked; invalid input is reported inline and asked
+//! again. `--prompt-timeout` / `PROMPT_TIMEOUT` bound each attempt, falling
+//! back to the default. Quiet and non‑TTY runs use the default directly.
+//! Supplied answers and defaults go through the same validation; when
+//! nothing valid is left, the prompt returns `Err` instead of guessing.
+
+use std::io::{self, Write};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use regex::Regex;
+
+use super::interactive::{ask, stdin_is_tty};
+use super::utils::{ask_within, parse_timeout_from_context};
+use crate::global::{expand_vars, get_var, is_true, ConfigDoc};
+use crate::utils::expand_colors_unified;
+
+/// What `ask_path` requires of the path it returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathCheck {
+    /// Any non-empty path
+    Any,
+    /// Something must exist there
+    Exists,
+    /// An existing regular file
+    File,
+    /// An existing directory
+    Dir,
+    /// A directory, created (with parents) if missing
+    CreateDir,
+}
+
+fn answer_key(key: &str) -> String {
+    key.replace('-', "_")
+}
+
+/// The answer supplied for `key` up front: `opt_<key>`, else the answers
+/// file. Fails when an answers file is configured but can't be read.
+pub fn answer_for(key: &str) -> Result<Option<String>, String> {
+    let key = answer_key(key);
+    let value = get_var(&format!("opt_{}", key));
+    if !value.is_empty() {
+        return Ok(Some(value));
+    }
+    let path = match get_var("opt_answers") {
+        path if !path.is_empty() => path,
+        _ => get_var("PROMPT_ANSWERS"),
+    };
+    if path.is_empty() {
+        return Ok(None);
+    }
+    let path = expand_vars(&path);
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("cannot read answers file {}: {}", path, e))?;
+    let doc = ConfigDoc::parse(&content).map_err(|e| format!("answers file {}: {}", path, e))?;
+    Ok(doc.get(&key))
+}
+
+/// Asks until `validate` accepts the input (see the module docs for where
+/// answers come from). `default` is offered on an empty answer and must
+/// pass `validate` too.
+pub fn ask_with<T>(
+    key: &str,
+    message: &str,
+    default: Option<&str>,
+    validate: impl Fn(&str) -> Result<T, String>,
+) -> Result<T, String> {
+    if let Some(answer) = answer_for(key)? {
+        return validate(&answer).map_err(|e| format!("{}: '{}' {}", key, answer, e));
+    }
+    let fallback = || match default {
+        Some(default) => {
+            validate(default).map_err(|e| format!("{}: default '{}' {}", key, default, e))
+        }
+        None => Err(format!(
+            "{}: no answer (pass --{} or add it to the answers file)",
+            key,
+            key.replace('_', "-")
+        )),
+    };
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return fallback();
+    }
+
+    let timeout = parse_timeout_from_context().map(Duration::from_secs);
+    loop {
+        let input = match timeout {
+            Some(timeout) => match ask_within(message, default, timeout) {
+                Some(input) => input,
+                None => {
+                    println!();
+                    return fallback();
+                }
+            },
+            None => ask(message, default),
+        };
+        if input.is_empty() && default.is_none() {
+            report("an answer is required");
+            continue;
+        }
+        match validate(&input) {
+            Ok(value) => return Ok(value),
+            Err(e) => report(&format!("'{}' {}", input, e)),
+        }
+    }
+}
+
+/// Shows a rejected answer's reason under the prompt.
+pub(crate) fn report(reason: &str) {
+    println!(
+        "{}",
+        expand_colors_unified(&format!("  {{red}}✗ {}{{reset}}", reason))
+    );
+    io::stdout().flush().ok();
+}
+
+/// Asks for a whole number within `range` (`..` for any).
+pub fn ask_int(
+    key: &str,
+    message: &str,
+    default: Option<i64>,
+    range: impl RangeBounds<i64>,
+) -> Result<i64, String> {
+    let default = default.map(|d| d.to_string());
+    ask_with(key, message, default.as_deref(), |input| {
+        let n: i64 = input
+            .trim()
+            .parse()
+            .map_err(|_| "is not a whole number".to_string())?;
+        match range.contains(&n) {
+            true => Ok(n),
+            false => Err(describe_range(&range)),
+        }
+    })
+}
+
+fn describe_range(range: &impl RangeBounds<i64>) -> String {
+    let min = match range.start_bound() {
+        Bound::Included(&n) => Some(n),
+        Bound::Excluded(&n) => Some(n.saturating_add(1)),
+        Bound::Unbounded => None,
+    };
+    let max = match range.end_bound() {
+        Bound::Included(&n) => Some(n),
+        Bound::Excluded(&n) => Some(n.saturating_sub(1)),
+        Bound::Unbounded => None,
+    };
+    match (min, max) {
+        (Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+        (Some(min), None) => format!("must be at least {}", min),
+        (None, Some(max)) => format!("must be at most {}", max),
+        (None, None) => "is out of range".to_string(),
+    }
+}
+
+/// Asks for a path (variables and a leading `~` are expanded) that passes
+/// `check`. `PathCheck::CreateDir` creates the directory once accepted.
+pub fn ask_path(
+    key: &str,
+    message: &str,
+    default: Option<&str>,
+    check: PathCheck,
+) -> Result<PathBuf, String> {
+    ask_with(key, message, default, |input| {
+        let path = check_path(input, check)?;
+        if check == PathCheck::CreateDir && !path.exists() {
+            create_dir(&path)?;
+        }
+        Ok(path)
+    })
+}
+
+/// Expands `input` and checks it against `check`. A `CreateDir` path is only
+/// checked here; see `create_dir`.
+pub(crate) fn check_path(input: &str, check: PathCheck) -> Result<PathBuf, String> {
+    let path = expand_path(input.trim());
+    if path.as_os_str().is_empty() {
+        return Err("is not a path".to_string());
+    }
+    match check {
+        PathCheck::Exists if !path.exists() => Err("does not exist".to_string()),
+        PathCheck::File if !path.is_file() => Err("is not a file".to_string()),
+        PathCheck::Dir if !path.is_dir() => Err("is not a directory".to_string()),
+        PathCheck::CreateDir if path.exists() && !path.is_dir() => {
+            Err("exists and is not a directory".to_string())
+        }
+        _ => Ok(path),
+    }
+}
+
+pub(crate) fn create_dir(path: &Path) -> Result<(), String> {
+    match crate::fs::mkdir_p(&path.display().to_string()) {
+        true => Ok(()),
+        false => Err("could not be created".to_string()),
+    }
+}
+
+fn expand_path(input: &str) -> PathBuf {
+    let value = expand_vars(input);
+    match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = std::env::var("HOME").unwrap_or_else(|_| get_var("HOME"));
+            PathBuf::from(format!("{}{}", home, rest))
+        }
+        _ => PathBuf::from(value),
+    }
+}
+
+/// Asks for text matching `pattern` in full. Fails straight away if the
+/// pattern doesn't compile.
+pub fn ask_regex(
+    key: &str,
+    message: &str,
+    default: Option<&str>,
+    pattern: &str,
+) -> Result<String, String> {
+    let re = Regex::new(&format!("^(?:{})$", pattern))
+        .map_err(|e| format!("{}: invalid pattern '{}': {}", key, pattern, e))?;
+    ask_with(key, message, default, |input| {
+        let input = input.trim();
+        match re.is_match(input) {
+            true => Ok(input.to_string()),
+            false => Err(format!("does not match {}", pattern)),
+        }
+    })
+}
+
+/// Asks for one of `choices` by name, ignoring case; a unique prefix is
+/// enough. Returns the choice as written in `choices`.
+pub fn ask_choice(
+    key: &str,
+    message: &str,
+    choices: &[&str],
+    default: Option<&str>,
+) -> Result<String, String> {
+    let message = format!("{} ({})", message, choices.join("/"));
+    ask_with(key, &message, default, |input| {
+        let input = input.trim().to_lowercase();
+        if let Some(exact) = choices.iter().find(|c| c.to_lowercase() == input) {
+            return Ok(exact.to_string());
+        }
+        let matches: Vec<&str> = choices
+            .iter()
+            .copied()
+            .filter(|c| !input.is_empty() && c.to_lowercase().starts_with(&input))
+            .collect();
+        match matches.as_slice() {
+            [only] => Ok(only.to_string()),
+            [] => Err(format!("is not one of: {}", choices.join(", "))),
+            many => Err(format!("is ambiguous: {}", many.join(", "))),
+        }
+    })
+}
@@ -5,18 +5,22 @@
 //! 1. --prompt-timeout flag (opt_prompt_timeout)
 //! 2. PROMPT_TIMEOUT environment variable
 //!
-//! Timeout implementation uses thread-based approach for cross-platform compatibility.
+//! Timeouts wait on `poll` for stdin rather than a blocking read, so a prompt
+//! that times out leaves nothing behind to swallow the program's next input.
+//! On a terminal the prompt shows a countdown and the answer it will fall
+//! back to (`(n in 9s)`); that per-prompt default is returned on expiry.
 
-use std::sync::{Arc, Mutex};
-use std::thread;
+use std::io::{self, Write};
 use std::time::{Duration, Instant};
 
-use super::interactive::{ask, confirm, confirm_default, select};
-use crate::global::get_var;
+use super::interactive::{render_prompt, stdin_is_tty};
+use super::term::stdin_ready;
+use super::widgets::{self, Countdown, TimedOut};
+use crate::global::{get_var, is_true};
 
 /// Parse timeout from global context
 /// Priority: opt_prompt_timeout (CLI flag) > PROMPT_TIMEOUT (env var) > None
-fn parse_timeout_from_context() -> Option<u64> {
+pub(crate) fn parse_timeout_from_context() -> Option<u64> {
     // Check --prompt-timeout flag first
     let opt_val = get_var("opt_prompt_timeout");
     if !opt_val.is_empty() {
@@ -36,37 +40,59 @@ fn parse_timeout_from_context() -> Option<u64> {
     None
 }
 
-/// Execute a function with timeout using a simpler polling approach
-/// Returns Some(result) if completed within timeout, None if timed out
-fn with_timeout<F, R>(timeout: Duration, f: F) -> Option<R>
-where
-    F: FnOnce() -> R + Send + 'static,
-    R: Send + 'static,
-{
-    let result = Arc::new(Mutex::new(None));
-    let result_clone = Arc::clone(&result);
-    let completed = Arc::new(Mutex::new(false));
-    let completed_clone = Arc::clone(&completed);
-
-    let _handle = thread::spawn(move || {
-        let value = f();
-        *result_clone.lock().unwrap() = Some(value);
-        *completed_clone.lock().unwrap() = true;
-    });
-
-    // Poll for completion with timeout
-    let start = Instant::now();
-    while start.elapsed() < timeout {
-        if *completed.lock().unwrap() {
-            // Extract result
-            if let Ok(mut result_guard) = result.lock() {
-                return result_guard.take();
-            }
-        }
-        thread::sleep(Duration::from_millis(10));
+/// Seconds a timed prompt waits: the override, else the context, else 30.
+fn timeout_secs(timeout_override: Option<u64>) -> u64 {
+    timeout_override
+        .or_else(parse_timeout_from_context)
+        .unwrap_or(30)
+}
+
+fn countdown(timeout: Duration, action: &str) -> Countdown<'_> {
+    Countdown {
+        deadline: Instant::now() + timeout,
+        action,
+    }
+}
+
+/// Reads one line before the countdown runs out; `None` if it does. Uses the
+/// raw-mode editor with a live countdown, else a plain line read once `poll`
+/// says one is waiting. Either way nothing is left reading stdin afterwards.
+fn read_line_until(message: &str, suffix: &str, countdown: &Countdown) -> Option<String> {
+    if let Some(answer) = widgets::timed_line(message, suffix, countdown) {
+        return answer.ok();
+    }
+    let suffix = format!("{} {}", suffix, countdown.hint());
+    print!("{}", render_prompt("{green}›{reset}", message, &suffix));
+    io::stdout().flush().ok();
+    if !stdin_ready(countdown.deadline.saturating_duration_since(Instant::now())) {
+        println!();
+        return None;
+    }
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).ok()?;
+    Some(input.trim().to_string())
+}
+
+/// One `ask` bounded by `timeout`; `None` when it runs out (unlike
+/// `ask_with_timeout`, which can't tell a timeout from an empty answer).
+pub(crate) fn ask_within(
+    message: &str,
+    default: Option<&str>,
+    timeout: Duration,
+) -> Option<String> {
+    let suffix = default.map(|d| format!(" [{}]", d)).unwrap_or_default();
+    let input = read_line_until(message, &suffix, &countdown(timeout, default.unwrap_or("")))?;
+    match input.is_empty() {
+        true => Some(default.unwrap_or("").to_string()),
+        false => Some(input),
     }
+}
 
-    None // Timeout occurred
+fn yes_no(answer: bool) -> &'static str {
+    match answer {
+        true => "y",
+        false => "n",
+    }
 }
 
 /// Confirm with timeout support
@@ -76,16 +102,24 @@ pub fn confirm_with_timeout(
     timeout_override: Option<u64>,
     default_value: bool,
 ) -> bool {
-    let timeout_secs = timeout_override
-        .or_else(parse_timeout_from_context)
-        .unwrap_or(30); // 30 second default
-
-    let timeout_duration = Duration::from_secs(timeout_secs);
-    let message_owned = message.to_string();
-
-    // Use timeout wrapper
-    with_timeout(timeout_duration, move || confirm(&message_owned)).unwrap_or(default_value)
-    // Return default on timeout
+    if is_true("opt_yes") {
+        return true;
+    }
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return false;
+    }
+    let timeout = Duration::from_secs(timeout_secs(timeout_override));
+    let countdown = countdown(timeout, yes_no(default_value));
+    loop {
+        let Some(input) = read_line_until(message, " [y/n]", &countdown) else {
+            return default_value;
+        };
+        match input.to_lowercase().as_str() {
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => continue,
+        }
+    }
 }
 
 /// Confirm with default and timeout support
@@ -94,17 +128,29 @@ pub fn confirm_default_with_timeout(
     default: bool,
     timeout_override: Option<u64>,
 ) -> bool {
-    let timeout_secs = timeout_override
-        .or_else(parse_timeout_from_context)
-        .unwrap_or(30);
-
-    let timeout_duration = Duration::from_secs(timeout_secs);
-    let message_owned = message.to_string();
-
-    with_timeout(timeout_duration, move || {
-        confirm_default(&message_owned, default)
-    })
-    .unwrap_or(default) // Return provided default on timeout
+    if is_true("opt_yes") {
+        return true;
+    }
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return default;
+    }
+    let suffix = match default {
+        true => " [Y/n]",
+        false => " [y/N]",
+    };
+    let timeout = Duration::from_secs(timeout_secs(timeout_override));
+    let countdown = countdown(timeout, yes_no(default));
+    loop {
+        let Some(input) = read_line_until(message, suffix, &countdown) else {
+            return default; // Return provided default on timeout
+        };
+        match input.to_lowercase().as_str() {
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            "" => return default,
+            _ => continue,
+        }
+    }
 }
 
 /// Ask with timeout support
@@ -114,18 +160,11 @@ pub fn ask_with_timeout(
     default: Option<&str>,
     timeout_override: Option<u64>,
 ) -> String {
-    let timeout_secs = timeout_override
-        .or_else(parse_timeout_from_context)
-        .unwrap_or(30);
-
-    let timeout_duration = Duration::from_secs(timeout_secs);
-    let message_owned = message.to_string();
-    let default_owned = default.map(|s| s.to_string());
-
-    with_timeout(timeout_duration, move || {
-        ask(&message_owned, default_owned.as_deref())
-    })
-    .unwrap_or_else(|| default.unwrap_or("").to_string()) // Return default on timeout
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return default.unwrap_or("").to_string();
+    }
+    let timeout = Duration::from_secs(timeout_secs(timeout_override));
+    ask_within(message, default, timeout).unwrap_or_else(|| default.unwrap_or("").to_string())
 }
 
 /// Select with timeout support
@@ -140,22 +179,44 @@ pub fn select_with_timeout(
         return String::new();
     }
 
-    let timeout_secs = timeout_override
-        .or_else(parse_timeout_from_context)
-        .unwrap_or(30);
-
-    let timeout_duration = Duration::from_secs(timeout_secs);
     let default_idx = default_index
         .unwrap_or(0)
         .min(options.len().saturating_sub(1));
-    let message_owned = message.to_string();
-    let options_owned: Vec<String> = options.iter().map(|s| s.to_string()).collect();
-
-    with_timeout(timeout_duration, move || {
-        let options_refs: Vec<&str> = options_owned.iter().map(|s| s.as_str()).collect();
-        select(&message_owned, &options_refs, default_index)
-    })
-    .unwrap_or_else(|| options[default_idx].to_string()) // Return default option on timeout
+    if is_true("opt_quiet") || !stdin_is_tty() {
+        return options[default_idx].to_string();
+    }
+    let timeout = Duration::from_secs(timeout_secs(timeout_override));
+    let countdown = countdown(timeout, options[default_idx]);
+    match widgets::timed_select_list(message, options, default_idx, &countdown) {
+        Some(Ok(index)) => return options[index].to_string(),
+        Some(Err(TimedOut)) => return options[default_idx].to_string(),
+        None => {}
+    }
+
+    // Line fallback: numbered options inline, answered by number or text
+    let opts_inline = options
+        .iter()
+        .enumerate()
+        .map(|(i, o)| format!("[{}]{}", i + 1, o))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let suffix = format!("  {}", opts_inline);
+    loop {
+        let Some(input) = read_line_until(message, &suffix, &countdown) else {
+            return options[default_idx].to_string(); // Return default option on timeout
+        };
+        if input.is_empty() {
+            return options[default_idx].to_string();
+        }
+        if let Ok(n) = input.parse::<usize>() {
+            if n >= 1 && n <= options.len() {
+                return options[n - 1].to_string();
+            }
+        }
+        if let Some(pos) = options.iter().position(|o| o.eq_ignore_ascii_case(&input)) {
+            return options[pos].to_string();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,604 @@
+//! Raw-mode prompt widgets
+//!
+//! Arrow-key lists (single choice, checkboxes, type-to-filter) and a line
+//! editor, used by the prompt functions in `interactive`. Each widget
+//! returns `None` when stdin can't be put in raw mode, so the caller can fall
+//! back to line input. Esc and Ctrl-D cancel and keep the default, as does
+//! stdin closing (a hung-up terminal); Ctrl-C restores the terminal and
+//! exits with `INTERRUPT_STATUS`.
+//!
+//! The timed variants take a `Countdown`: they wait on `poll` rather than a
+//! blocking read, redraw the seconds left, and give up with `TimedOut` when
+//! it runs out, so no reader is left behind on stdin.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use super::term::{read_key, stdin_ready, terminal_size, Key, RawMode};
+use crate::utils::expand_colors_unified;
+
+/// Exit status after Ctrl-C in a prompt (as a shell reports SIGINT).
+pub const INTERRUPT_STATUS: i32 = 130;
+
+/// Most list rows shown at once; longer lists scroll.
+const MAX_VISIBLE: usize = 10;
+
+/// How typed text is echoed by `edit_line`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Echo {
+    Plain,
+    Mask(char),
+    Hidden,
+}
+
+struct Interrupted;
+
+/// A timed prompt ran out of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TimedOut;
+
+/// A prompt's time limit and the answer it falls back to, shown as
+/// `(<action> in 9s)`.
+pub(crate) struct Countdown<'a> {
+    pub deadline: Instant,
+    pub action: &'a str,
+}
+
+impl Countdown<'_> {
+    fn left(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    pub(crate) fn hint(&self) -> String {
+        let left = self.left();
+        let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+        match self.action.is_empty() {
+            true => format!("{{grey}}({}s){{reset}}", secs),
+            false => format!("{{grey}}({} in {}s){{reset}}", self.action, secs),
+        }
+    }
+}
+
+enum Input {
+    Key(Key),
+    /// The countdown moved to the next second
+    Tick,
+    Expired,
+    /// Stdin is closed; no more keys will come
+    Closed,
+}
+
+fn key_input() -> Input {
+    match read_key() {
+        Some(key) => Input::Key(key),
+        None => Input::Closed,
+    }
+}
+
+/// The next key, or with a countdown a tick each second until it expires.
+fn next_input(countdown: Option<&Countdown>) -> Input {
+    let Some(countdown) = countdown else {
+        return key_input();
+    };
+    let left = countdown.left();
+    if left.is_zero() {
+        return Input::Expired;
+    }
+    let to_tick = match left.subsec_nanos() {
+        0 => Duration::from_secs(1),
+        nanos => Duration::from_nanos(nanos as u64),
+    };
+    match stdin_ready(to_tick) {
+        true => key_input(),
+        false => Input::Tick,
+    }
+}
+
+/// The lines drawn last, so the next frame can replace them.
+struct Frame {
+    out: io::Stdout,
+    /// Row of the cursor within the frame
+    cursor_row: usize,
+}
+
+impl Frame {
+    fn new() -> Self {
+        Frame {
+            out: io::stdout(),
+            cursor_row: 0,
+        }
+    }
+
+    fn erase(&mut self) {
+        if self.cursor_row > 0 {
+            let _ = write!(self.out, "\x1b[{}A", self.cursor_row);
+        }
+        let _ = write!(self.out, "\r\x1b[J");
+        self.cursor_row = 0;
+    }
+
+    /// Replaces the frame. With `cursor_col` the cursor goes back to that
+    /// column of the first line (for text input), otherwise it is hidden.
+    fn draw(&mut self, lines: &[String], cursor_col: Option<usize>) {
+        self.erase();
+        let body = lines
+            .iter()
+            .map(|line| expand_colors_unified(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let _ = write!(self.out, "{}", body);
+        self.cursor_row = lines.len().saturating_sub(1);
+        match cursor_col {
+            Some(col) => {
+                if self.cursor_row > 0 {
+                    let _ = write!(self.out, "\x1b[{}A", self.cursor_row);
+                    self.cursor_row = 0;
+                }
+                let _ = write!(self.out, "\r\x1b[?25h");
+                if col > 0 {
+                    let _ = write!(self.out, "\x1b[{}C", col);
+                }
+            }
+            None => {
+                let _ = write!(self.out, "\x1b[?25l");
+            }
+        }
+        let _ = self.out.flush();
+    }
+
+    /// Replaces the frame with a one-line summary and moves past it.
+    fn finish(&mut self, summary: &str) {
+        self.erase();
+        let _ = write!(self.out, "{}\n\x1b[?25h", expand_colors_unified(summary));
+        let _ = self.out.flush();
+    }
+}
+
+/// Runs a widget in raw mode. `None` when raw mode is unavailable.
+fn run_raw<T>(widget: impl FnOnce(&mut Frame) -> Result<T, Interrupted>) -> Option<T> {
+    let raw = RawMode::enable()?;
+    let mut frame = Frame::new();
+    let result = widget(&mut frame);
+    drop(raw);
+    match result {
+        Ok(value) => Some(value),
+        Err(Interrupted) => {
+            frame.finish("");
+            std::process::exit(INTERRUPT_STATUS);
+        }
+    }
+}
+
+/// Plain width of a rendered line (color tags and ANSI codes skipped).
+fn visible_width(text: &str) -> usize {
+    let expanded = expand_colors_unified(text);
+    let mut width = 0;
+    let mut chars = expanded.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for nc in chars.by_ref() {
+                if nc.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        width += 1;
+    }
+    width
+}
+
+/// Cuts `text` to `max` characters, marking the cut with `…`.
+fn clip(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(max.saturating_sub(1)).collect();
+    clipped.push('…');
+    clipped
+}
+
+fn header(prefix: &str, message: &str, hint: &str) -> String {
+    format!("{} {} {{grey}}{}{{reset}}", prefix, message, hint)
+}
+
+fn summary(prefix: &str, message: &str, answer: &str) -> String {
+    format!("{} {} {{cyan}}{}{{reset}}", prefix, message, answer)
+}
+
+/// A scrolling window over `len` rows that keeps `cursor` in view.
+struct Window {
+    offset: usize,
+    size: usize,
+}
+
+impl Window {
+    fn new() -> Self {
+        let (_, rows) = terminal_size();
+        Window {
+            offset: 0,
+            size: MAX_VISIBLE.min(rows.saturating_sub(2)).max(1),
+        }
+    }
+
+    fn rows(&mut self, cursor: usize, len: usize) -> std::ops::Range<usize> {
+        if cursor < self.offset {
+            self.offset = cursor;
+        } else if cursor >= self.offset + self.size {
+            self.offset = cursor + 1 - self.size;
+        }
+        self.offset = self.offset.min(len.saturating_sub(self.size));
+        self.offset..(self.offset + self.size).min(len)
+    }
+}
+
+/// Moves a list cursor for navigation keys; `false` if `key` isn't one.
+fn navigate(key: Key, cursor: &mut usize, len: usize, page: usize) -> bool {
+    if len == 0 {
+        return matches!(key, Key::Up | Key::Down | Key::Home | Key::End);
+    }
+    match key {
+        Key::Up | Key::Ctrl('p') => *cursor = (*cursor + len - 1) % len,
+        Key::Down | Key::Ctrl('n') | Key::Tab => *cursor = (*cursor + 1) % len,
+        Key::Home => *cursor = 0,
+        Key::End => *cursor = len - 1,
+        Key::PageUp => *cursor = cursor.saturating_sub(page),
+        Key::PageDown => *cursor = (*cursor + page).min(len - 1),
+        _ => return false,
+    }
+    true
+}
+
+/// Arrow-key single choice. Returns the chosen index.
+pub fn select_list(message: &str, options: &[&str], default: usize) -> Option<usize> {
+    list_select(message, options, default, None).map(|chosen| chosen.unwrap_or(default))
+}
+
+/// `select_list` that gives up when `countdown` runs out.
+pub(crate) fn timed_select_list(
+    message: &str,
+    options: &[&str],
+    default: usize,
+    countdown: &Countdown,
+) -> Option<Result<usize, TimedOut>> {
+    list_select(message, options, default, Some(countdown))
+}
+
+fn list_select(
+    message: &str,
+    options: &[&str],
+    default: usize,
+    countdown: Option<&Countdown>,
+) -> Option<Result<usize, TimedOut>> {
+    let prefix = "{cyan}?{reset}";
+    let default = default.min(options.len().saturating_sub(1));
+    run_raw(|frame| {
+        let (cols, _) = terminal_size();
+        let mut window = Window::new();
+        let mut cursor = default;
+        let chosen = loop {
+            let hint = match countdown {
+                Some(countdown) => format!("(↑/↓, enter) {}", countdown.hint()),
+                None => "(↑/↓, enter)".to_string(),
+            };
+            let mut lines = vec![header(prefix, message, &hint)];
+            for i in window.rows(cursor, options.len()) {
+                let text = clip(options[i], cols.saturating_sub(3));
+                lines.push(match i == cursor {
+                    true => format!("{{cyan}}› {}{{reset}}", text),
+                    false => format!("  {}", text),
+                });
+            }
+            frame.draw(&lines, None);
+
+            let key = match next_input(countdown) {
+                Input::Key(key) => key,
+                Input::Tick => continue,
+                Input::Expired => break Err(TimedOut),
+                Input::Closed => break Ok(default),
+            };
+            if navigate(key, &mut cursor, options.len(), window.size) {
+                continue;
+            }
+            match key {
+                Key::Enter => break Ok(cursor),
+                Key::Char(c @ '1'..='9') if (c as usize - '1' as usize) < options.len() => {
+                    cursor = c as usize - '1' as usize;
+                }
+                Key::Char('k') => cursor = (cursor + options.len() - 1) % options.len(),
+                Key::Char('j') => cursor = (cursor + 1) % options.len(),
+                Key::Esc | Key::Eof => break Ok(default),
+                Key::Interrupt => return Err(Interrupted),
+                _ => {}
+            }
+        };
+        let shown = options[*chosen.as_ref().unwrap_or(&default)];
+        frame.finish(&summary("{cyan}●{reset}", message, shown));
+        Ok(chosen)
+    })
+}
+
+/// Checkbox list: space toggles, `a` toggles all. Returns the checked
+/// indexes in list order.
+pub fn multi_select_list(message: &str, options: &[&str], checked: &[usize]) -> Option<Vec<usize>> {
+    let prefix = "{cyan}?{reset}";
+    run_raw(|frame| {
+        let (cols, _) = terminal_size();
+        let mut window = Window::new();
+        let mut marks: Vec<bool> = (0..options.len()).map(|i| checked.contains(&i)).collect();
+        let mut cursor = 0;
+        loop {
+            let mut lines = vec![header(
+                prefix,
+                message,
+                "(space to toggle, a for all, enter)",
+            )];
+            for i in window.rows(cursor, options.len()) {
+                let text = clip(options[i], cols.saturating_sub(7));
+                let mark = match marks[i] {
+                    true => "{green}[x]{reset}",
+                    false => "[ ]",
+                };
+                lines.push(match i == cursor {
+                    true => format!("{{cyan}}›{{reset}} {} {{cyan}}{}{{reset}}", mark, text),
+                    false => format!("  {} {}", mark, text),
+                });
+            }
+            frame.draw(&lines, None);
+
+            let Input::Key(key) = key_input() else {
+                marks = (0..options.len()).map(|i| checked.contains(&i)).collect();
+                break;
+            };
+            if navigate(key, &mut cursor, options.len(), window.size) {
+                continue;
+            }
+            match key {
+                Key::Char(' ') if !marks.is_empty() => marks[cursor] = !marks[cursor],
+                Key::Char('a') => {
+                    let all = marks.iter().all(|m| *m);
+                    marks.iter_mut().for_each(|m| *m = !all);
+                }
+                Key::Char('k') if !marks.is_empty() => {
+                    cursor = (cursor + options.len() - 1) % options.len()
+                }
+                Key::Char('j') if !marks.is_empty() => cursor = (cursor + 1) % options.len(),
+                Key::Enter => break,
+                Key::Esc | Key::Eof => {
+                    marks = (0..options.len()).map(|i| checked.contains(&i)).collect();
+                    break;
+                }
+                Key::Interrupt => return Err(Interrupted),
+                _ => {}
+            }
+        }
+        let chosen: Vec<usize> = (0..options.len()).filter(|&i| marks[i]).collect();
+        let answer = chosen
+            .iter()
+            .map(|&i| options[i])
+            .collect::<Vec<_>>()
+            .join(", ");
+        frame.finish(&summary("{cyan}●{reset}", message, &answer));
+        Ok(chosen)
+    })
+}
+
+/// Type-to-filter list. Returns the chosen index into `options`.
+pub fn fuzzy_list(message: &str, options: &[&str], default: usize) -> Option<usize> {
+    let prefix = "{cyan}?{reset}";
+    run_raw(|frame| {
+        let (cols, _) = terminal_size();
+        let mut window = Window::new();
+        let mut query = String::new();
+        let mut matches = fuzzy_filter("", options);
+        let mut cursor = default.min(options.len().saturating_sub(1));
+        let chosen = loop {
+            let mut lines = vec![match query.is_empty() {
+                true => header(prefix, message, "(type to filter)"),
+                false => format!("{} {} {{cyan}}{}{{reset}}", prefix, message, query),
+            }];
+            if matches.is_empty() {
+                lines.push("  {grey}no matches{reset}".to_string());
+            }
+            for row in window.rows(cursor, matches.len()) {
+                let text = clip(options[matches[row]], cols.saturating_sub(3));
+                lines.push(match row == cursor {
+                    true => format!("{{cyan}}› {}{{reset}}", text),
+                    false => format!("  {}", text),
+                });
+            }
+            frame.draw(&lines, None);
+
+            let Input::Key(key) = key_input() else {
+                break default;
+            };
+            if navigate(key, &mut cursor, matches.len(), window.size) {
+                continue;
+            }
+            match key {
+                Key::Enter if !matches.is_empty() => break matches[cursor],
+                Key::Char(c) => query.push(c),
+                Key::Backspace => {
+                    query.pop();
+                }
+                Key::Ctrl('u') => query.clear(),
+                Key::Esc | Key::Eof => break default,
+                Key::Interrupt => return Err(Interrupted),
+                _ => continue,
+            }
+            matches = fuzzy_filter(&query, options);
+            cursor = 0;
+        };
+        frame.finish(&summary("{cyan}●{reset}", message, options[chosen]));
+        Ok(chosen)
+    })
+}
+
+/// Line editor starting from `initial` (cursor at the end). Esc, or Ctrl-D
+/// on an empty line, gives back `initial`.
+pub fn edit_line(message: &str, initial: &str, echo: Echo) -> Option<String> {
+    line_editor(message, "", initial, echo, None).map(|answer| answer.unwrap_or_default())
+}
+
+/// An empty line editor with `suffix` after the message (`" [y/n]"`) that
+/// gives up when `countdown` runs out.
+pub(crate) fn timed_line(
+    message: &str,
+    suffix: &str,
+    countdown: &Countdown,
+) -> Option<Result<String, TimedOut>> {
+    line_editor(message, suffix, "", Echo::Plain, Some(countdown))
+}
+
+fn line_editor(
+    message: &str,
+    suffix: &str,
+    initial: &str,
+    echo: Echo,
+    countdown: Option<&Countdown>,
+) -> Option<Result<String, TimedOut>> {
+    let prefix = "{green}›{reset}";
+    run_raw(|frame| {
+        let (cols, _) = terminal_size();
+        let mut text: Vec<char> = initial.chars().collect();
+        let mut pos = text.len();
+        let timed_out = loop {
+            let lead = match countdown {
+                Some(countdown) => {
+                    format!("{} {}{} {} ", prefix, message, suffix, countdown.hint())
+                }
+                None => format!("{} {}{} ", prefix, message, suffix),
+            };
+            let lead_width = visible_width(&lead);
+            let room = cols.saturating_sub(lead_width + 1).max(1);
+            let shown: Vec<char> = match echo {
+                Echo::Plain => text.clone(),
+                Echo::Mask(mask) => vec![mask; text.len()],
+                Echo::Hidden => Vec::new(),
+            };
+            let at = pos.min(shown.len());
+            let start = at.saturating_sub(room);
+            let end = (start + room).min(shown.len());
+            let visible: String = shown[start..end].iter().collect();
+            frame.draw(
+                &[format!("{}{}", lead, visible)],
+                Some(lead_width + at - start),
+            );
+
+            let key = match next_input(countdown) {
+                Input::Key(key) => key,
+                Input::Tick => continue,
+                Input::Expired => break true,
+                Input::Closed => {
+                    text = initial.chars().collect();
+                    break false;
+                }
+            };
+            match key {
+                Key::Enter => break false,
+                Key::Char(c) => {
+                    text.insert(pos, c);
+                    pos += 1;
+                }
+                Key::Backspace if pos > 0 => {
+                    pos -= 1;
+                    text.remove(pos);
+                }
+                Key::Delete if pos < text.len() => {
+                    text.remove(pos);
+                }
+                Key::Left => pos = pos.saturating_sub(1),
+                Key::Right => pos = (pos + 1).min(text.len()),
+                Key::Home | Key::Ctrl('a') => pos = 0,
+                Key::End | Key::Ctrl('e') => pos = text.len(),
+                Key::Ctrl('u') => {
+                    text.drain(..pos);
+                    pos = 0;
+                }
+                Key::Ctrl('k') => text.truncate(pos),
+                Key::Ctrl('w') => {
+                    let mut from = pos;
+                    while from > 0 && text[from - 1] == ' ' {
+                        from -= 1;
+                    }
+                    while from > 0 && text[from - 1] != ' ' {
+                        from -= 1;
+                    }
+                    text.drain(from..pos);
+                    pos = from;
+                }
+                Key::Esc => {
+                    text = initial.chars().collect();
+                    break false;
+                }
+                Key::Eof if text.is_empty() => {
+                    text = initial.chars().collect();
+                    break false;
+                }
+                Key::Interrupt => return Err(Interrupted),
+                _ => {}
+            }
+        };
+        if let (true, Some(countdown)) = (timed_out, countdown) {
+            frame.finish(&summary(prefix, message, countdown.action));
+            return Ok(Err(TimedOut));
+        }
+        let answer: String = text.iter().collect();
+        let shown = match echo {
+            Echo::Plain => answer.clone(),
+            Echo::Mask(mask) => mask.to_string().repeat(text.len()),
+            Echo::Hidden => String::new(),
+        };
+        frame.finish(&summary(prefix, message, &shown));
+        Ok(Ok(answer))
+    })
+}
+
+/// How well `query` matches `candidate` (case-insensitive): `None` unless
+/// every query character appears in order; lower is better. A contiguous
+/// match scores its position, a scattered one also pays for the gaps.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let hay: Vec<char> = candidate.to_lowercase().chars().collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let contiguous = hay
+        .windows(query.len())
+        .position(|window| window == query.as_slice());
+
+    let mut scattered = None;
+    let mut next = 0;
+    let mut last: Option<usize> = None;
+    let mut gaps = 0;
+    for (i, c) in hay.iter().enumerate() {
+        if next < query.len() && *c == query[next] {
+            match last {
+                Some(prev) => gaps += i - prev - 1,
+                None => scattered = Some(i),
+            }
+            last = Some(i);
+            next += 1;
+        }
+    }
+    if next < query.len() {
+        return None;
+    }
+    let scattered = scattered.map(|first| first + gaps * 2 + 1);
+    match (contiguous, scattered) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Indexes of `options` matching `query`, best first (ties keep list
+/// order).
+pub fn fuzzy_filter(query: &str, options: &[&str]) -> Vec<usize> {
+    let mut scored: Vec<(usize, usize)> = options
+        .iter()
+        .enumerate()
+        .filter_map(|(i, option)| fuzzy_score(query, option).map(|score| (score, i)))
+        .collect();
+    scored.sort_by_key(|(score, _)| *score);
+    scored.into_iter().map(|(_, i)| i).collect()
+}
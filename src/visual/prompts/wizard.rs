@@ -0,0 +1,374 @@
+//! Multi-step setup wizards
+//!
+//! A `Wizard` asks a list of `Step`s in order, shows a summary to confirm,
+//! then writes the answers into the global store (and, with `save_to`, a
+//! config file). Each step has a key, a prompt type, a default, optional
+//! validation and an optional `when` condition on earlier answers.
+//!
+//! ```rust,ignore
+//! let answers = Wizard::new("Project setup")
+//!     .step(Step::text("app_name", "Project name").default("my-app"))
+//!     .step(Step::select("app_kind", "Template", &["cli", "web"]))
+//!     .step(Step::text("app_port", "Port").default("8080").when(|a| a.get("app_kind") == Some("web")))
+//!     .step(Step::path("app_root", "Install to", PathCheck::CreateDir).default("~/.local/app"))
+//!     .save_to("~/.config/app/app.conf")
+//!     .run()?;
+//! ```
+//!
+//! Defaults come from `default_from(key, default)`, so a loaded config
+//! pre-fills a re-run. Entering `<` (or picking `‹ back` in a list) returns
+//! to the previous step with its answer pre-filled. Answers supplied with
+//! `--<key>=value` or an answers file are used without asking, as for the
+//! typed prompts; quiet and non‑TTY runs take the defaults and skip the
+//! summary.
+
+use std::path::PathBuf;
+
+use super::interactive::{ask, default_from, select, stdin_is_tty};
+use super::typed::{answer_for, check_path, create_dir, report, PathCheck};
+use crate::com::is_true_val;
+use crate::global::{is_true, set_var, try_save_config_values};
+use crate::utils::expand_colors_unified;
+
+/// What the user enters to return to the previous step.
+pub const WIZARD_BACK: &str = "<";
+
+const BACK_OPTION: &str = "‹ back";
+
+type Validator = Box<dyn Fn(&str) -> Result<(), String>>;
+type Condition = Box<dyn Fn(&WizardAnswers) -> bool>;
+
+enum StepKind {
+    Text,
+    Confirm,
+    Select(Vec<String>),
+    Path(PathCheck),
+}
+
+/// One question of a `Wizard`.
+pub struct Step {
+    key: String,
+    message: String,
+    kind: StepKind,
+    default: Option<String>,
+    validate: Option<Validator>,
+    when: Option<Condition>,
+}
+
+impl Step {
+    fn new(key: &str, message: &str, kind: StepKind) -> Self {
+        Step {
+            key: key.to_string(),
+            message: message.to_string(),
+            kind,
+            default: None,
+            validate: None,
+            when: None,
+        }
+    }
+
+    /// A line of text.
+    pub fn text(key: &str, message: &str) -> Self {
+        Self::new(key, message, StepKind::Text)
+    }
+
+    /// Yes or no, stored as `true`/`false`.
+    pub fn confirm(key: &str, message: &str) -> Self {
+        Self::new(key, message, StepKind::Confirm)
+    }
+
+    /// One of `options`.
+    pub fn select(key: &str, message: &str, options: &[&str]) -> Self {
+        let options = options.iter().map(|o| o.to_string()).collect();
+        Self::new(key, message, StepKind::Select(options))
+    }
+
+    /// A path, stored expanded. A `CreateDir` directory is created when the
+    /// wizard's answers are applied.
+    pub fn path(key: &str, message: &str, check: PathCheck) -> Self {
+        Self::new(key, message, StepKind::Path(check))
+    }
+
+    /// The answer offered when none is given (a set global `key` wins).
+    pub fn default(mut self, value: &str) -> Self {
+        self.default = Some(value.to_string());
+        self
+    }
+
+    /// Extra check on the answer; the error is shown and the step asked
+    /// again.
+    pub fn validate(mut self, check: impl Fn(&str) -> Result<(), String> + 'static) -> Self {
+        self.validate = Some(Box::new(check));
+        self
+    }
+
+    /// Only ask this step when `condition` holds for the answers so far.
+    pub fn when(mut self, condition: impl Fn(&WizardAnswers) -> bool + 'static) -> Self {
+        self.when = Some(Box::new(condition));
+        self
+    }
+
+    fn visible(&self, answers: &WizardAnswers) -> bool {
+        self.when.as_ref().is_none_or(|when| when(answers))
+    }
+
+    /// The value to offer: an earlier answer to this step, else the global
+    /// `key`, else the declared default.
+    fn offered(&self, answers: &WizardAnswers) -> Option<String> {
+        if let Some(previous) = answers.get(&self.key) {
+            return Some(previous.to_string());
+        }
+        let value = default_from(&self.key, self.default.as_deref().unwrap_or(""));
+        (!value.is_empty()).then_some(value)
+    }
+
+    /// Normalizes and validates an answer.
+    fn check(&self, input: &str) -> Result<String, String> {
+        let input = input.trim();
+        let value = match &self.kind {
+            StepKind::Text => input.to_string(),
+            StepKind::Confirm => match input.to_lowercase().as_str() {
+                "y" => "true".to_string(),
+                other if is_true_val(other) => "true".to_string(),
+                "n" | "no" | "false" | "off" | "0" => "false".to_string(),
+                _ => return Err("is not yes or no".to_string()),
+            },
+            StepKind::Select(options) => options
+                .iter()
+                .find(|o| o.eq_ignore_ascii_case(input))
+                .cloned()
+                .ok_or_else(|| format!("is not one of: {}", options.join(", ")))?,
+            StepKind::Path(check) => check_path(input, *check)?.display().to_string(),
+        };
+        if let Some(validate) = &self.validate {
+            validate(&value)?;
+        }
+        Ok(value)
+    }
+
+    /// Asks interactively until the answer checks out; `None` means back.
+    fn ask(&self, answers: &WizardAnswers, can_go_back: bool) -> Option<String> {
+        let offered = self.offered(answers);
+        loop {
+            let input = match &self.kind {
+                StepKind::Select(options) => {
+                    let mut choices: Vec<&str> = options.iter().map(String::as_str).collect();
+                    if can_go_back {
+                        choices.push(BACK_OPTION);
+                    }
+                    let index = offered
+                        .as_deref()
+                        .and_then(|o| options.iter().position(|option| option == o));
+                    select(&self.message, &choices, index)
+                }
+                StepKind::Confirm => {
+                    let offered = offered.as_deref().map(|o| match is_true_val(o) {
+                        true => "y",
+                        false => "n",
+                    });
+                    ask(&format!("{} (y/n)", self.message), offered)
+                }
+                StepKind::Text | StepKind::Path(_) => ask(&self.message, offered.as_deref()),
+            };
+            if can_go_back && (input == WIZARD_BACK || input == BACK_OPTION) {
+                return None;
+            }
+            match self.check(&input) {
+                Ok(value) => return Some(value),
+                Err(e) => report(&format!("'{}' {}", input, e)),
+            }
+        }
+    }
+
+    fn shown(&self, value: &str) -> String {
+        match self.kind {
+            StepKind::Confirm if is_true_val(value) => "yes".to_string(),
+            StepKind::Confirm => "no".to_string(),
+            _ => value.to_string(),
+        }
+    }
+}
+
+/// Answers collected by a `Wizard`, in step order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WizardAnswers {
+    values: Vec<(String, String)>,
+}
+
+impl WizardAnswers {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether `key` holds a true value (a confirmed step).
+    pub fn is_true(&self, key: &str) -> bool {
+        self.get(key).is_some_and(is_true_val)
+    }
+
+    pub fn keys(&self) -> Vec<&str> {
+        self.values.iter().map(|(k, _)| k.as_str()).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn set(&mut self, key: &str, value: String) {
+        match self.values.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((key.to_string(), value)),
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.values.retain(|(k, _)| k != key);
+    }
+
+    /// Puts answers back in step order after going back and forth.
+    fn sort_by_steps(&mut self, steps: &[Step]) {
+        self.values
+            .sort_by_key(|(k, _)| steps.iter().position(|s| &s.key == k));
+    }
+}
+
+/// A sequence of `Step`s with a confirmation summary.
+pub struct Wizard {
+    title: String,
+    steps: Vec<Step>,
+    save_path: Option<String>,
+}
+
+impl Wizard {
+    pub fn new(title: &str) -> Self {
+        Wizard {
+            title: title.to_string(),
+            steps: Vec::new(),
+            save_path: None,
+        }
+    }
+
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Also save the answers to this config file (see `save_config_file`).
+    pub fn save_to(mut self, path: &str) -> Self {
+        self.save_path = Some(path.to_string());
+        self
+    }
+
+    /// Runs the wizard and applies the answers. Fails when the user cancels
+    /// at the summary, a supplied or default answer doesn't check out, or
+    /// the config file can't be written; nothing is applied then. The file
+    /// is saved before `CreateDir` directories are made, so a directory
+    /// that can't be created fails the run with the file already written.
+    pub fn run(self) -> Result<WizardAnswers, String> {
+        let interactive = !is_true("opt_quiet") && stdin_is_tty();
+        if interactive {
+            println!(
+                "{}",
+                expand_colors_unified(&format!(
+                    "{{bold}}{}{{reset}} {{grey}}(enter {} to go back){{reset}}",
+                    self.title, WIZARD_BACK
+                ))
+            );
+        }
+
+        let mut answers = WizardAnswers::default();
+        // Steps answered, most recent last, for going back
+        let mut asked: Vec<usize> = Vec::new();
+        let mut index = 0;
+        loop {
+            while let Some(step) = self.steps.get(index) {
+                if !step.visible(&answers) {
+                    answers.remove(&step.key);
+                    index += 1;
+                    continue;
+                }
+                let value = if let Some(supplied) = answer_for(&step.key)? {
+                    step.check(&supplied)
+                        .map_err(|e| format!("{}: '{}' {}", step.key, supplied, e))?
+                } else if !interactive {
+                    let offered = step.offered(&answers).unwrap_or_default();
+                    step.check(&offered)
+                        .map_err(|e| format!("{}: default '{}' {}", step.key, offered, e))?
+                } else {
+                    match step.ask(&answers, !asked.is_empty()) {
+                        Some(value) => {
+                            asked.push(index);
+                            value
+                        }
+                        None => {
+                            index = asked.pop().unwrap_or(0);
+                            continue;
+                        }
+                    }
+                };
+                answers.set(&step.key, value);
+                index += 1;
+            }
+            answers.sort_by_steps(&self.steps);
+            if !interactive {
+                break;
+            }
+
+            self.print_summary(&answers);
+            let choice = select("Apply these settings?", &["yes", "back", "cancel"], Some(0));
+            match choice.as_str() {
+                "yes" => break,
+                "back" => index = asked.pop().unwrap_or(0),
+                _ => return Err(format!("{}: cancelled", self.title)),
+            }
+        }
+
+        self.apply(&answers)?;
+        Ok(answers)
+    }
+
+    fn print_summary(&self, answers: &WizardAnswers) {
+        let mut lines = vec![format!("{{bold}}{}{{reset}}", self.title)];
+        for step in &self.steps {
+            if let Some(value) = answers.get(&step.key) {
+                lines.push(format!(
+                    "  {}: {{cyan}}{}{{reset}}",
+                    step.message,
+                    step.shown(value)
+                ));
+            }
+        }
+        println!("{}", expand_colors_unified(&lines.join("\n")));
+    }
+
+    /// Saves first, from the answers themselves, so a failed save leaves
+    /// the global store and the filesystem as they were.
+    fn apply(&self, answers: &WizardAnswers) -> Result<(), String> {
+        if let Some(path) = &self.save_path {
+            let values: Vec<(&str, &str)> = answers.iter().collect();
+            try_save_config_values(path, &values).map_err(|e| e.to_string())?;
+        }
+        for step in &self.steps {
+            if let (StepKind::Path(PathCheck::CreateDir), Some(path)) =
+                (&step.kind, answers.get(&step.key))
+            {
+                create_dir(&PathBuf::from(path))
+                    .map_err(|e| format!("{}: '{}' {}", step.key, path, e))?;
+            }
+        }
+        for (key, value) in answers.iter() {
+            set_var(key, value);
+        }
+        Ok(())
+    }
+}
@@ -25,6 +25,9 @@ mod host_env;
 #[path = "sanity/host_paths.rs"]
 mod host_paths;
 
+#[path = "sanity/host_command.rs"]
+mod host_command;
+
 #[path = "sanity/options_cleanup.rs"]
 mod options_cleanup;
 
@@ -94,6 +97,18 @@ mod colors;
 #[path = "sanity/progress.rs"]
 mod progress;
 
+// Streams module sanity tests (lazy sources, chain ops, sinks)
+#[path = "sanity/streams.rs"]
+mod streams;
+
+// JSON module sanity tests (native query engine, set/delete, flattening)
+#[path = "sanity/json.rs"]
+mod json;
+
+// Log module sanity tests (filters, sinks, fields, spans, rotation)
+#[path = "sanity/log.rs"]
+mod log;
+
 // String module sanity tests (MODERN - freshly written)
 #[path = "sanity/string.rs"]
 mod string;
@@ -149,3 +164,7 @@ mod flags;
 // REPL module sanity tests (v2.0+ - Interactive command processing)
 #[path = "sanity/repl.rs"]
 mod repl;
+
+// Prompt widget sanity tests (raw-mode select/multi/fuzzy/password/edit under a PTY)
+#[path = "sanity/prompts.rs"]
+mod prompts;
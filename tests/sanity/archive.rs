@@ -0,0 +1,313 @@
+// RSB Sanity Tests - Archive Module Core Functionality Verification
+// Tests create/list/extract tar, tar.gz and zip natively (no tar/zip binaries)
+
+use assert_fs::TempDir;
+use rsb::fs::archive::{
+    create_archive, extract_archive, list_archive, ArchiveFormat, ArchiveOptions, EntryKind,
+};
+use rsb::prelude::*;
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+fn set_mtime(path: &Path, secs: u64) {
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(UNIX_EPOCH + Duration::from_secs(secs))
+        .unwrap();
+}
+
+/// A tree with spaces in names, an executable, a symlink and a nested dir.
+fn sample_tree(root: &Path) {
+    let src = root.join("my project");
+    fs::create_dir_all(src.join("nested dir")).unwrap();
+    fs::write(src.join("read me.txt"), "hello archive\n").unwrap();
+    fs::write(src.join("run.sh"), "#!/bin/sh\necho hi\n").unwrap();
+    fs::set_permissions(src.join("run.sh"), fs::Permissions::from_mode(0o750)).unwrap();
+    set_mtime(&src.join("run.sh"), 1_600_000_000);
+    // Large enough to span several deflate buffers
+    let big: String = (0..20_000)
+        .map(|i| format!("line {}\n", i * 7919 % 1000))
+        .collect();
+    fs::write(src.join("nested dir/big.log"), big).unwrap();
+    std::os::unix::fs::symlink("read me.txt", src.join("link")).unwrap();
+}
+
+fn roundtrip(name: &str) {
+    let tmp = TempDir::new().unwrap();
+    sample_tree(tmp.path());
+    let archive = tmp.path().join(name).to_string_lossy().to_string();
+    let opts = ArchiveOptions::new().base_dir(&tmp.path().to_string_lossy());
+
+    let written = create_archive(&archive, &["my project"], &opts).unwrap();
+    assert!(written.iter().any(|e| e.path == "my project/read me.txt"));
+
+    let listed = list_archive(&archive, &ArchiveOptions::new()).unwrap();
+    assert_eq!(listed.len(), written.len());
+    let link = listed.iter().find(|e| e.path == "my project/link").unwrap();
+    assert_eq!(link.kind, EntryKind::Symlink("read me.txt".into()));
+
+    let out = tmp.path().join("out");
+    extract_archive(&archive, &out.to_string_lossy(), &ArchiveOptions::new()).unwrap();
+    let root = out.join("my project");
+    assert_eq!(
+        fs::read_to_string(root.join("read me.txt")).unwrap(),
+        "hello archive\n"
+    );
+    assert_eq!(
+        fs::read(root.join("nested dir/big.log")).unwrap(),
+        fs::read(tmp.path().join("my project/nested dir/big.log")).unwrap()
+    );
+    let meta = fs::metadata(root.join("run.sh")).unwrap();
+    assert_eq!(meta.permissions().mode() & 0o777, 0o750, "{}", name);
+    assert_eq!(meta.mtime(), 1_600_000_000, "{}", name);
+    assert_eq!(
+        fs::read_link(root.join("link")).unwrap(),
+        Path::new("read me.txt")
+    );
+}
+
+#[test]
+fn test_archive_roundtrip_tar_tgz_zip() {
+    roundtrip("bundle.tar");
+    roundtrip("bundle.tar.gz");
+    roundtrip("bundle.zip");
+}
+
+#[test]
+fn test_archive_include_exclude() {
+    let tmp = TempDir::new().unwrap();
+    sample_tree(tmp.path());
+    let base = tmp.path().to_string_lossy().to_string();
+    let archive = format!("{}/filtered.tgz", base);
+
+    let opts = ArchiveOptions::new()
+        .base_dir(&base)
+        .exclude("nested dir")
+        .exclude("link");
+    create_archive(&archive, &["my project"], &opts).unwrap();
+    let paths: Vec<String> = list_archive(&archive, &ArchiveOptions::new())
+        .unwrap()
+        .iter()
+        .map(|e| e.display_path())
+        .collect();
+    assert_eq!(
+        paths,
+        vec!["my project/", "my project/read me.txt", "my project/run.sh"]
+    );
+
+    // Include on extract: only *.sh comes out (directories are recreated as needed)
+    let out = format!("{}/out", base);
+    let extracted =
+        extract_archive(&archive, &out, &ArchiveOptions::new().include("*.sh")).unwrap();
+    assert!(extracted
+        .iter()
+        .all(|e| e.is_dir() || e.path.ends_with(".sh")));
+    assert!(Path::new(&out).join("my project/run.sh").exists());
+    assert!(!Path::new(&out).join("my project/read me.txt").exists());
+}
+
+/// A one-entry ustar archive with an arbitrary (possibly hostile) name.
+fn raw_tar(name: &str, typeflag: u8, link: &str) -> Vec<u8> {
+    let mut header = [0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(b"00000000004\0");
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = typeflag;
+    header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[148..156].fill(b' ');
+    let sum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+    let mut data = header.to_vec();
+    let mut body = [0u8; 512];
+    body[..4].copy_from_slice(b"evil");
+    data.extend_from_slice(&body);
+    data.extend_from_slice(&[0u8; 1024]);
+    data
+}
+
+#[test]
+fn test_archive_extract_refuses_path_traversal() {
+    let tmp = TempDir::new().unwrap();
+    let out = tmp.path().join("out");
+    let out_str = out.to_string_lossy().to_string();
+    let cases = [
+        ("dotdot.tar", raw_tar("../escaped.txt", b'0', "")),
+        (
+            "absolute.tar",
+            raw_tar("/tmp/rsb_archive_abs_escape.txt", b'0', ""),
+        ),
+        ("link.tar", raw_tar("sneaky", b'2', "../../etc")),
+    ];
+    for (name, bytes) in cases {
+        let archive = tmp.path().join(name);
+        fs::write(&archive, bytes).unwrap();
+        let err = extract_archive(&archive.to_string_lossy(), &out_str, &ArchiveOptions::new())
+            .unwrap_err();
+        assert!(err.contains("Refusing"), "{}: {}", name, err);
+    }
+    assert!(!tmp.path().join("escaped.txt").exists());
+    assert!(!Path::new("/tmp/rsb_archive_abs_escape.txt").exists());
+    assert!(fs::symlink_metadata(out.join("sneaky")).is_err());
+
+    // A directory symlinked out of the destination can't be written through
+    let outside = tmp.path().join("outside");
+    fs::create_dir_all(&outside).unwrap();
+    std::os::unix::fs::symlink(&outside, out.join("dir")).unwrap();
+    let archive = tmp.path().join("through.tar");
+    fs::write(&archive, raw_tar("dir/file.txt", b'0', "")).unwrap();
+    assert!(extract_archive(&archive.to_string_lossy(), &out_str, &ArchiveOptions::new()).is_err());
+    assert!(!outside.join("file.txt").exists());
+}
+
+#[test]
+fn test_archive_interop_with_system_tools() {
+    let tmp = TempDir::new().unwrap();
+    sample_tree(tmp.path());
+    let base = tmp.path().to_string_lossy().to_string();
+    let has = |tool: &str| {
+        std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("command -v {}", tool))
+            .output()
+            .is_ok_and(|o| o.status.success())
+    };
+
+    if has("tar") {
+        // Ours -> system tar
+        let ours = format!("{}/ours.tgz", base);
+        create_archive(
+            &ours,
+            &["my project"],
+            &ArchiveOptions::new().base_dir(&base),
+        )
+        .unwrap();
+        let listing = std::process::Command::new("tar")
+            .args(["-tzf", &ours])
+            .output()
+            .unwrap();
+        assert!(listing.status.success());
+        assert!(String::from_utf8_lossy(&listing.stdout).contains("my project/nested dir/big.log"));
+
+        // System tar -> ours
+        let theirs = format!("{}/theirs.tar.gz", base);
+        let status = std::process::Command::new("tar")
+            .args(["-czf", &theirs, "-C", &base, "my project"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let out = format!("{}/from_tar", base);
+        extract_archive(&theirs, &out, &ArchiveOptions::new()).unwrap();
+        assert_eq!(
+            fs::read_to_string(format!("{}/my project/read me.txt", out)).unwrap(),
+            "hello archive\n"
+        );
+    }
+    if has("unzip") {
+        let ours = format!("{}/ours.zip", base);
+        create_archive(
+            &ours,
+            &["my project"],
+            &ArchiveOptions::new().base_dir(&base),
+        )
+        .unwrap();
+        let out = format!("{}/from_unzip", base);
+        let status = std::process::Command::new("unzip")
+            .args(["-q", &ours, "-d", &out])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let meta = fs::metadata(format!("{}/my project/run.sh", out)).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o777, 0o750);
+        assert_eq!(
+            fs::read_to_string(format!("{}/my project/read me.txt", out)).unwrap(),
+            "hello archive\n"
+        );
+    }
+}
+
+#[test]
+fn test_archive_macros_handle_spaces() {
+    let tmp = TempDir::new().unwrap();
+    sample_tree(tmp.path());
+    let src = tmp.path().join("my project").to_string_lossy().to_string();
+    let archive = tmp
+        .path()
+        .join("with space.tar.gz")
+        .to_string_lossy()
+        .to_string();
+
+    pack!(archive.as_str(), src.as_str());
+    assert_eq!(ArchiveFormat::sniff(&archive), Some(ArchiveFormat::TarGz));
+    let listing = tar!(list: &archive);
+    assert!(
+        listing
+            .lines()
+            .any(|l| l.ends_with("my project/read me.txt")),
+        "{}",
+        listing
+    );
+
+    let out = tmp
+        .path()
+        .join("unpacked here")
+        .to_string_lossy()
+        .to_string();
+    unpack!(&archive, to: &out);
+    let extracted = list_archive(&archive, &ArchiveOptions::new()).unwrap();
+    let file = extracted
+        .iter()
+        .find(|e| e.path.ends_with("read me.txt"))
+        .unwrap();
+    assert!(Path::new(&out).join(&file.path).exists());
+}
+
+#[cfg(feature = "progress")]
+#[test]
+fn test_archive_progress_task() {
+    use rsb::progress::{
+        create_with_task, extract_with_task, ProgressManager, ProgressState, ProgressStyle,
+    };
+
+    let tmp = TempDir::new().unwrap();
+    sample_tree(tmp.path());
+    let base = tmp.path().to_string_lossy().to_string();
+    let archive = format!("{}/progress.zip", base);
+    let manager = ProgressManager::new();
+
+    let task = manager.start_task("pack", ProgressStyle::Spinner);
+    let entries = create_with_task(
+        &archive,
+        &["my project"],
+        &ArchiveOptions::new().base_dir(&base),
+        &task,
+    )
+    .unwrap();
+    assert_eq!(task.current_progress(), entries.len() as u64);
+    assert_eq!(task.state(), ProgressState::Complete);
+
+    let task = manager.start_task("unpack", ProgressStyle::Spinner);
+    extract_with_task(
+        &archive,
+        &format!("{}/out", base),
+        &ArchiveOptions::new(),
+        &task,
+    )
+    .unwrap();
+    assert_eq!(task.current_progress(), entries.len() as u64);
+
+    let task = manager.start_task("missing", ProgressStyle::Spinner);
+    assert!(extract_with_task(
+        &format!("{}/nope.zip", base),
+        &base,
+        &ArchiveOptions::new(),
+        &task
+    )
+    .is_err());
+    assert_eq!(task.state(), ProgressState::Failed);
+}
@@ -270,3 +270,313 @@ fn test_complex_scenarios() {
     assert_eq!(args.has_val("--env"), Some("production".to_string()));
     assert!(args.has("--dry-run"));
 }
+
+fn build_spec() -> rsb::cli::CommandSpec {
+    use rsb::cli::{ArgSpec, CommandSpec, OptSpec};
+    use rsb::global::ValueType;
+
+    CommandSpec::new("build")
+        .about("Build the project")
+        .alias("b")
+        .arg(ArgSpec::new("target").required().about("What to build"))
+        .arg(ArgSpec::new("files").repeatable())
+        .opt(OptSpec::flag("release").short('r').about("Optimized build"))
+        .opt(
+            OptSpec::value("jobs")
+                .short('j')
+                .value_type(ValueType::Int)
+                .default("4"),
+        )
+        .opt(OptSpec::value("feature").repeatable())
+        .opt(OptSpec::value("out-dir").required())
+        .example("build --release app")
+}
+
+#[test]
+fn test_command_spec_parse_and_apply() {
+    let spec = build_spec();
+    let mut args = Args::from_strs(&[
+        "prog", "build", "app", "-rj8", "--feature=a", "--feature", "b", "--out-dir",
+        "dist", "--", "-x.rs",
+    ]);
+    args.has_pop("build");
+    let matches = spec.parse(&args).unwrap();
+    assert_eq!(matches.positionals, vec!["app", "-x.rs"]);
+    assert_eq!(matches.value("jobs").unwrap(), "8");
+    assert!(matches.has("release"));
+
+    spec.apply(&matches);
+    assert_eq!(get_var("opt_jobs"), "8");
+    assert_eq!(get_var("opt_release"), "true");
+    assert_eq!(get_var("opt_out_dir"), "dist");
+    assert_eq!(rsb::global::get_array("opt_feature"), vec!["a", "b"]);
+
+    // Defaults fill in missing options
+    let mut args = Args::from_strs(&["prog", "build", "app", "--out-dir=x"]);
+    args.has_pop("build");
+    spec.apply(&spec.parse(&args).unwrap());
+    assert_eq!(get_var("opt_jobs"), "4");
+}
+
+#[test]
+fn test_command_spec_reports_all_errors() {
+    let spec = build_spec();
+    let args = Args::from_strs(&["prog", "--jobs", "many", "--bogus", "-r", "-r", "-j"]);
+    let errors = spec.parse(&args).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            "unknown option '--bogus'",
+            "option '-j' requires a value",
+            "option '--release' given more than once",
+            "option '--jobs': expected an int, got 'many'",
+            "missing required option '--out-dir'",
+            "missing required argument <target>",
+        ]
+    );
+
+    let no_files = rsb::cli::CommandSpec::new("x").arg(rsb::cli::ArgSpec::new("one"));
+    let errors = no_files
+        .parse(&Args::from_strs(&["prog", "a", "b"]))
+        .unwrap_err();
+    assert_eq!(errors, vec!["unexpected argument 'b'"]);
+}
+
+#[test]
+fn test_command_spec_runs_validators() {
+    use rsb::cli::{CommandSpec, OptSpec, Validator};
+    let spec = CommandSpec::new("deploy")
+        .opt(OptSpec::value("region").validate(Validator::one_of(&["eu", "us"])))
+        .opt(OptSpec::value("replicas").validate(Validator::range(1.0, 9.0)));
+
+    let ok = spec.parse(&Args::from_strs(&["prog", "--region", "eu", "--replicas=3"]));
+    assert!(ok.is_ok());
+
+    let args = Args::from_strs(&["prog", "--region", "asia", "--replicas=12"]);
+    assert_eq!(
+        spec.parse(&args).unwrap_err(),
+        vec![
+            "option '--region': 'asia' is not one of: eu, us",
+            "option '--replicas': 12 is out of range 1..9",
+        ]
+    );
+}
+
+#[test]
+fn test_command_help_rendering() {
+    use rsb::cli::{find_command, register_command, render_command_help, render_help};
+
+    set_var("SCRIPT_NAME", "mytool");
+    register_command(build_spec());
+    assert_eq!(find_command("b").unwrap().name, "build");
+
+    let page = render_command_help(&build_spec());
+    assert!(page.contains("mytool build [OPTIONS] <target> [files]..."));
+    assert!(page.contains("Build the project"));
+    assert!(page.contains("-j, --jobs <int>"));
+    assert!(page.contains("[default: 4]"));
+    assert!(page.contains("--feature <string>..."));
+    assert!(page.contains("(required)"));
+    assert!(page.contains("mytool build --release app"));
+
+    let general = render_help();
+    assert!(general.contains("build (b)"));
+    assert!(general.contains("Build the project"));
+}
+
+fn cmd_vault_add(args: Args) -> i32 {
+    set_var("VAULT_ADDED", args.get(1));
+    let frames: Vec<String> = rsb::global::get_call_stack()
+        .iter()
+        .map(|frame| frame.function.clone())
+        .collect();
+    set_var("VAULT_FRAMES", frames.join(">"));
+    0
+}
+
+fn cmd_vault_remove(args: Args) -> i32 {
+    set_var("VAULT_REMOVED", args.get(1));
+    0
+}
+
+fn cmd_vault_rotate(_args: Args) -> i32 {
+    set_var("VAULT_ROTATED", "yes");
+    0
+}
+
+fn vault_pre(_args: &Args) -> i32 {
+    set_var("VAULT_HOOK", "ran");
+    0
+}
+
+fn vault_key_pre(_args: &Args) -> i32 {
+    3
+}
+
+#[test]
+fn test_nested_subcommand_dispatch() {
+    use rsb::cli::{register_command, resolve_path, subcommands, ArgSpec, CommandSpec};
+
+    register_command(CommandSpec::new("vault").about("Manage the vault").pre(vault_pre));
+    register_command(CommandSpec::new("vault key").pre(vault_key_pre));
+    let run = |line: &[&str]| {
+        rsb::pre_dispatch!(&Args::from_strs(line), {
+            "vault add" => cmd_vault_add, desc: "Add a secret",
+            "vault remove" => cmd_vault_remove, spec: CommandSpec::new("vault remove").alias("rm").arg(ArgSpec::new("name")),
+            "vault key rotate" => cmd_vault_rotate
+        })
+    };
+
+    assert!(run(&["prog", "vault", "add", "alpha"]));
+    assert_eq!(get_var("VAULT_ADDED"), "alpha");
+    assert_eq!(get_var("VAULT_HOOK"), "ran");
+    assert!(get_var("VAULT_FRAMES").ends_with("vault>vault add"));
+
+    // Aliases resolve per level
+    assert!(run(&["prog", "vault", "rm", "beta"]));
+    assert_eq!(get_var("VAULT_REMOVED"), "beta");
+    let words: Vec<String> = ["vault", "rm", "beta"].iter().map(|w| w.to_string()).collect();
+    assert_eq!(resolve_path(&words), vec!["vault", "remove"]);
+
+    // A failing group hook stops dispatch before the handler
+    assert!(run(&["prog", "vault", "key", "rotate"]));
+    assert!(!has_var("VAULT_ROTATED"));
+
+    // Groups alone and unknown subcommands don't dispatch
+    assert!(!run(&["prog", "vault"]));
+    assert!(!run(&["prog", "vault", "ad"]));
+    assert_eq!(subcommands("vault"), vec!["add", "key", "remove"]);
+}
+
+#[test]
+fn test_group_help_rendering() {
+    use rsb::cli::{register_command, render_group_help, render_help, CommandSpec};
+
+    rsb::global::register_function("depot push", "Push an artifact");
+    rsb::global::register_function("depot pull", "Pull an artifact");
+    register_command(CommandSpec::new("depot").about("Artifact depot"));
+    register_command(CommandSpec::new("depot ls").alias("list").about("List artifacts"));
+
+    let page = render_group_help("depot");
+    assert!(page.contains("depot <command> [options]"));
+    assert!(page.contains("Artifact depot"));
+    assert!(page.contains("ls (list)"));
+    assert!(page.contains("Push an artifact"));
+
+    let general = render_help();
+    assert!(general.contains("depot <command>"));
+    assert!(!general.contains("depot push"));
+}
+
+fn ship_regions(_partial: &str) -> Vec<String> {
+    vec!["eu-west".to_string(), "us-east".to_string()]
+}
+
+#[test]
+fn test_completion_candidates() {
+    use rsb::cli::{complete_words, register_command, ArgSpec, CommandSpec, OptSpec};
+    use rsb::global::ValueType;
+
+    register_command(
+        CommandSpec::new("shipit")
+            .about("Ship a release")
+            .alias("shp")
+            .arg(ArgSpec::new("region").complete_with(ship_regions))
+            .opt(OptSpec::value("mode").value_type(ValueType::Enum(vec![
+                "fast".to_string(),
+                "safe".to_string(),
+            ])))
+            .opt(OptSpec::flag("dry-run").about("Plan only")),
+    );
+    rsb::global::register_function("shipyard dock", "Dock a ship");
+    let complete = |words: &[&str]| -> Vec<String> {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        complete_words(&words).into_iter().map(|(value, _)| value).collect()
+    };
+
+    assert_eq!(complete(&["shi"]), vec!["shipit", "shipyard"]);
+    assert_eq!(complete(&["sh"]), vec!["shipit", "shipyard", "shp"]);
+    assert_eq!(complete(&["shipyard", ""]), vec!["dock"]);
+    assert_eq!(complete(&["shipit", ""]), vec!["eu-west", "us-east"]);
+    assert_eq!(complete(&["shp", "us"]), vec!["us-east"]);
+    assert_eq!(complete(&["shipit", "--mode", ""]), vec!["fast", "safe"]);
+    assert_eq!(complete(&["shipit", "--mode=s"]), vec!["--mode=safe"]);
+    assert_eq!(complete(&["shipit", "--d"]), vec!["--dry-run"]);
+    assert_eq!(complete(&["help", "shipy"]), vec!["shipyard"]);
+    assert_eq!(complete(&["completions", "z"]), vec!["zsh"]);
+    assert!(complete(&[""]).contains(&"completions".to_string()));
+
+    let described = complete_words(&["shipit".to_string(), "--dry".to_string()]);
+    assert_eq!(described[0].1, "Plan only");
+}
+
+#[test]
+fn test_completion_scripts() {
+    use rsb::cli::{render_completions, Shell};
+
+    let bash = render_completions(Shell::from_name("bash").unwrap(), "my-tool");
+    assert!(bash.contains("complete -o default -F _my_tool_complete my-tool"));
+    assert!(bash.contains("\"my-tool\" __complete"));
+    let zsh = render_completions(Shell::Zsh, "my-tool");
+    assert!(zsh.starts_with("#compdef my-tool"));
+    assert!(zsh.contains("compdef _my_tool my-tool"));
+    let fish = render_completions(Shell::Fish, "my-tool");
+    assert!(fish.contains("complete -c my-tool -f -a '(__my_tool_complete)'"));
+    assert_eq!(Shell::from_name("tcsh"), None);
+}
+
+#[test]
+fn test_command_suggestions() {
+    use rsb::cli::{register_alias, register_command, suggest_commands, CommandSpec};
+
+    rsb::global::register_function("suggdeploy", "Deploy");
+    rsb::global::register_function("suggstatus", "Status");
+    rsb::global::register_function("suggrepo clone", "Clone");
+    register_command(CommandSpec::new("suggrepo fetch").alias("pull"));
+    register_alias("suggdep", "suggdeploy --env prod");
+
+    assert_eq!(suggest_commands("", "suggdeploi")[0], "suggdeploy");
+    assert_eq!(suggest_commands("", "suggstauts"), vec!["suggstatus"]);
+    assert!(suggest_commands("", "suggde").contains(&"suggdep".to_string()));
+    assert_eq!(suggest_commands("", "halp"), vec!["help"]);
+    assert_eq!(suggest_commands("suggrepo", "clon"), vec!["clone"]);
+    assert_eq!(suggest_commands("suggrepo", "pul"), vec!["pull"]);
+    assert!(suggest_commands("", "qqqqqqqq").is_empty());
+}
+
+fn cmd_alias_target(args: Args) -> i32 {
+    set_var("ALIAS_TARGET_ARGS", args.remaining().join("|"));
+    0
+}
+
+#[test]
+fn test_command_aliases() {
+    use rsb::cli::{expand_alias, find_alias, register_alias, try_load_aliases, unregister_alias};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let rc = tmp.path().join("aliastool.conf");
+    std::fs::write(
+        &rc,
+        "# aliases\nalias_at='aliastarget --mode \"dry run\"'\nalias_at2=aliastarget\nother=1\n",
+    )
+    .unwrap();
+    assert_eq!(try_load_aliases(&rc.to_string_lossy()).unwrap(), 2);
+    assert_eq!(find_alias("at2"), Some("aliastarget".to_string()));
+    assert!(!has_var("other"));
+    assert!(try_load_aliases("/no/such/aliastool.conf").is_err());
+
+    let run = |line: &[&str]| {
+        rsb::pre_dispatch!(&Args::from_strs(line), {
+            "aliastarget" => cmd_alias_target
+        })
+    };
+    assert!(run(&["prog", "at", "extra"]));
+    assert_eq!(get_var("ALIAS_TARGET_ARGS"), "--mode|dry run|extra");
+
+    // Real commands win over aliases, and only the command word expands
+    register_alias("aliastarget", "stack");
+    let args = Args::from_strs(&["prog", "aliastarget", "at"]);
+    assert_eq!(expand_alias(&args).all(), args.all());
+    unregister_alias("aliastarget");
+    assert!(!run(&["prog", "nope-at"]));
+}
@@ -2,8 +2,10 @@
 #![cfg(feature = "colors-core")]
 
 use rsb::colors::{
-    color, color_enable_with, color_mode, colorize, get_all_colors,
+    color, color_enable_with, color_mode, colored, colorize, get_all_colors, parse_rgb,
+    rgb_to_16, rgb_to_256,
 };
+use rsb::hosts::{refresh_term_caps, set_term_caps, ColorDepth, TermCaps};
 
 #[test]
 fn sanity_colors_simple_palette_is_available() {
@@ -49,3 +51,68 @@ fn sanity_colors_registry_reports_entries() {
     let all = get_all_colors();
     assert!(all.len() >= 8, "expected registry to contain at least the basic palette");
 }
+
+#[test]
+fn sanity_colors_rgb_names_parse() {
+    assert_eq!(parse_rgb("#ff8800"), Some((255, 136, 0)));
+    assert_eq!(parse_rgb("#f80"), Some((255, 136, 0)));
+    assert_eq!(parse_rgb("rgb(255, 136, 0)"), Some((255, 136, 0)));
+    assert_eq!(parse_rgb("#ff88"), None);
+    assert_eq!(parse_rgb("rgb(256,0,0)"), None);
+    assert_eq!(parse_rgb("red"), None);
+
+    assert_eq!(rgb_to_256((255, 136, 0)), 208);
+    assert_eq!(rgb_to_256((128, 128, 128)), 244);
+    assert_eq!(rgb_to_16((255, 0, 0)), 9);
+    assert_eq!(rgb_to_16((0, 0, 0)), 0);
+}
+
+// The only test that changes the detected capabilities, so the depths
+// don't race each other
+#[test]
+fn sanity_colors_fit_the_terminal_depth() {
+    color_mode("always");
+    color_enable_with("simple,named");
+    let caps = |color| TermCaps {
+        color,
+        unicode: false,
+        width: 80,
+        is_tty: false,
+    };
+
+    set_term_caps(caps(ColorDepth::TrueColor));
+    assert_eq!(color("#ff8800"), "\x1B[38;2;255;136;0m");
+    assert_eq!(color("RGB(255, 136, 0)"), color("#ff8800"));
+    assert_eq!(
+        colored("{#ff8800}hot{reset}"),
+        "\x1B[38;2;255;136;0mhot\x1B[0m"
+    );
+    assert_eq!(color("crimson"), "\x1B[38;5;196m");
+
+    set_term_caps(caps(ColorDepth::Ansi256));
+    assert_eq!(color("#ff8800"), "\x1B[38;5;208m");
+    assert_eq!(color("crimson"), "\x1B[38;5;196m");
+
+    set_term_caps(caps(ColorDepth::Basic));
+    assert_eq!(color("#ff0000"), "\x1B[91m");
+    assert_eq!(color("crimson"), "\x1B[91m");
+    assert_eq!(color("red"), "\x1B[31m");
+
+    // "always" still colors a terminal reported as colorless
+    set_term_caps(caps(ColorDepth::None));
+    assert_eq!(color("red"), "\x1B[31m");
+
+    #[cfg(feature = "glyphs")]
+    {
+        use rsb::visual::glyphs::{glyph, glyph_enable};
+        glyph_enable();
+        assert_eq!(glyph("pass"), "+");
+        assert_eq!(glyph("alpha"), "");
+        assert_eq!(colored("{g:alpha}{g:bullet} x"), "* x");
+    }
+
+    color_mode("never");
+    assert_eq!(colored("{#ff8800}plain{reset} {nope}"), "plain {nope}");
+    color_mode("always");
+    refresh_term_caps();
+}
@@ -64,7 +64,7 @@ fn test_pty_session_lifecycle() {
         .expect("Should be able to wait for completion");
 
     // Command should complete successfully
-    assert!(exit_status.success());
+    assert_eq!(exit_status, 0);
 }
 
 #[cfg(feature = "dev-pty")]
@@ -0,0 +1,124 @@
+//! Sanity tests for dry-run and trace modes (hosts::effects)
+
+use assert_fs::TempDir;
+use rsb::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+// The modes are global; serialize the tests that flip them
+static TEST_MUTEX: Mutex<()> = Mutex::new(());
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_dry_run_skips_fs_helpers() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join("keep me");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("data.txt"), "original").unwrap();
+    let data = path_str(&dir.join("data.txt"));
+
+    set_var("opt_dry_run", "true");
+    assert!(rsb::hosts::is_dry_run());
+    write_file(&data, "clobbered");
+    append_file(&data, "more");
+    write_file(&path_str(&tmp.path().join("new/file.txt")), "x");
+    assert!(mkdir_p(&path_str(&tmp.path().join("made"))));
+    assert!(touch(&path_str(&tmp.path().join("touched"))));
+    assert!(cp(&data, &path_str(&tmp.path().join("copy.txt"))));
+    assert!(cp_r(&path_str(&dir), &path_str(&tmp.path().join("tree"))));
+    assert!(mv(&data, &path_str(&tmp.path().join("moved.txt"))));
+    assert!(chmod(&data, "000").is_ok());
+    assert_eq!(backup_file(&data, ".bak").unwrap(), format!("{}.bak", data));
+    assert!(rm(&data));
+    assert!(rm_rf(&path_str(&dir)));
+    // Stream sinks are side effects too
+    Stream::from_string("a\nb").to_file(&data);
+    Stream::from_string("c").append_to_file(&data);
+    Stream::from_string("d").to_file(&path_str(&tmp.path().join("streamed.txt")));
+    unset_var("opt_dry_run");
+
+    assert_eq!(fs::read_to_string(&data).unwrap(), "original");
+    let mut left: Vec<String> = fs::read_dir(tmp.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    left.sort();
+    assert_eq!(left, vec!["keep me"]);
+    assert!(!Path::new(&format!("{}.bak", data)).exists());
+}
+
+#[test]
+fn test_dry_run_skips_commands() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let tmp = TempDir::new().unwrap();
+    let marker = path_str(&tmp.path().join("marker"));
+
+    set_var("DRY_RUN_MODE", "true");
+    let result = run_cmd_with_status(&format!("touch '{}' && echo done", marker));
+    assert_eq!(result.status, 0);
+    assert_eq!(result.output, "");
+    let piped = rsb::hosts::Pipeline::new()
+        .argv(&["touch", &marker])
+        .argv(&["cat"])
+        .run();
+    assert_eq!(piped.status, 0);
+
+    // Mocks still answer in dry-run mode
+    set_mock_cmds(&[("echo mocked-effects", "canned")]);
+    assert_eq!(run_cmd_with_status("echo mocked-effects").output, "canned");
+    clear_mock_cmds();
+    unset_var("DRY_RUN_MODE");
+
+    assert!(!Path::new(&marker).exists());
+    assert_eq!(run_cmd("echo live").trim(), "live");
+}
+
+#[test]
+fn test_trace_still_runs_operations() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let tmp = TempDir::new().unwrap();
+    let file = path_str(&tmp.path().join("traced.txt"));
+
+    set_var("opt_trace", "true");
+    assert!(rsb::hosts::is_trace());
+    write_file(&file, "traced");
+    let result = run_cmd_with_status("echo traced");
+    let traced = rsb::hosts::traced("custom op", || 42);
+    unset_var("opt_trace");
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "traced");
+    assert_eq!(result.output.trim(), "traced");
+    assert_eq!(traced, 42);
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_dry_run_archives() {
+    use rsb::fs::archive::{create_archive, extract_archive, ArchiveOptions};
+
+    let _guard = TEST_MUTEX.lock().unwrap();
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join("src")).unwrap();
+    fs::write(tmp.path().join("src/a.txt"), "a").unwrap();
+    let opts = ArchiveOptions::new().base_dir(&path_str(tmp.path()));
+    let archive = path_str(&tmp.path().join("out.tar.gz"));
+
+    set_var("opt_dry_run", "true");
+    let planned = create_archive(&archive, &["src"], &opts).unwrap();
+    unset_var("opt_dry_run");
+    assert_eq!(planned.len(), 2);
+    assert!(!Path::new(&archive).exists());
+
+    create_archive(&archive, &["src"], &opts).unwrap();
+    let dest = tmp.path().join("dest");
+    set_var("opt_dry_run", "true");
+    let listed = extract_archive(&archive, &path_str(&dest), &ArchiveOptions::new()).unwrap();
+    unset_var("opt_dry_run");
+    assert_eq!(listed.len(), 2);
+    assert!(!dest.exists());
+}
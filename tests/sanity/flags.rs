@@ -109,4 +109,50 @@ fn sanity_version_with_inf_copyright() {
     assert_eq!(result.unwrap(), 0);
     // Cleanup
     global::unset_var("inf_copyright");
-}
\ No newline at end of file
+}
+fn user_help(args: Args) -> i32 {
+    global::set_var("flags_user_help_topic", &args.remaining().join(" "));
+    0
+}
+
+fn user_version(_args: Args) -> i32 {
+    global::set_var("flags_user_version_called", "yes");
+    0
+}
+
+#[test]
+#[serial]
+fn sanity_help_flag_routes_to_user_handler() {
+    global::register_handler("help", user_help);
+
+    let result = check_flag_commands(&Args::from_strs(&["prog", "--help"]));
+    assert_eq!(result, Some(0));
+    assert_eq!(global::get_var("flags_user_help_topic"), "");
+
+    // prog <topic> --help becomes prog help <topic>
+    global::register_function("flags-topic", "");
+    let result = check_flag_commands(&Args::from_strs(&["prog", "flags-topic", "--help"]));
+    assert_eq!(result, Some(0));
+    assert_eq!(global::get_var("flags_user_help_topic"), "flags-topic");
+
+    global::unregister_handler("help");
+    global::unset_var("flags_user_help_topic");
+    let result = check_flag_commands(&Args::from_strs(&["prog", "--help"]));
+    assert_eq!(result, Some(0));
+    assert!(!global::has_var("flags_user_help_topic"));
+}
+
+#[test]
+#[serial]
+fn sanity_version_flag_routes_to_user_handler() {
+    global::register_handler("version", user_version);
+    let result = check_flag_commands(&Args::from_strs(&["prog", "--version"]));
+    assert_eq!(result, Some(0));
+    assert_eq!(global::get_var("flags_user_version_called"), "yes");
+
+    global::unregister_handler("version");
+    global::unset_var("flags_user_version_called");
+    let result = check_flag_commands(&Args::from_strs(&["prog", "-v"]));
+    assert_eq!(result, Some(0));
+    assert!(!global::has_var("flags_user_version_called"));
+}
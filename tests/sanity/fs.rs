@@ -331,6 +331,83 @@ fn test_edge_cases() {
     let _ = std::fs::remove_file(empty_file);
 }
 
+#[test]
+fn test_transaction_commit_and_uninstall() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = tmp.path();
+    let p = |name: &str| root.join(name).to_string_lossy().to_string();
+    std::fs::write(root.join("rc"), "alias ll='ls -l'\n").unwrap();
+    std::fs::write(root.join("app.conf"), "port=1\n").unwrap();
+    std::fs::write(root.join("old.txt"), "old").unwrap();
+    std::fs::write(root.join("tool"), "#!/bin/sh\n").unwrap();
+
+    let tx = || Transaction::new("sanity-install").state_dir(&p("state"));
+    let manifest = tx()
+        .write(&p("app.conf"), "port=2\n")
+        .write(&p("share/mytool/data.txt"), "data")
+        .insert_block(&p("rc"), "mytool", "export MYTOOL=1")
+        .cp(&p("tool"), &p("bin/tool"))
+        .chmod(&p("bin/tool"), "755")
+        .mv(&p("old.txt"), &p("archive/old.txt"))
+        .rm(&p("app.conf.bak"))
+        .commit()
+        .unwrap();
+    assert!(!manifest.entries.is_empty());
+    assert_eq!(std::fs::read_to_string(root.join("app.conf")).unwrap(), "port=2\n");
+    assert_eq!(
+        std::fs::read_to_string(root.join("rc")).unwrap(),
+        "alias ll='ls -l'\n# >>> mytool >>>\nexport MYTOOL=1\n# <<< mytool <<<\n"
+    );
+    let mode = std::fs::metadata(root.join("bin/tool")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+    assert!(root.join("archive/old.txt").exists() && !root.join("old.txt").exists());
+    assert_eq!(Manifest::load(&p("state/manifest.json")).unwrap(), manifest);
+
+    // Re-inserting replaces the block instead of adding another
+    tx().insert_block(&p("rc"), "mytool", "export MYTOOL=2").commit().unwrap();
+    let rc = std::fs::read_to_string(root.join("rc")).unwrap();
+    assert_eq!(rc.matches(">>> mytool >>>").count(), 1);
+    assert!(rc.contains("MYTOOL=2"));
+
+    // Edits outside the block survive the uninstall
+    std::fs::write(root.join("rc"), format!("{}export EDITOR=vi\n", rc)).unwrap();
+    tx().uninstall().unwrap();
+    assert_eq!(
+        std::fs::read_to_string(root.join("rc")).unwrap(),
+        "alias ll='ls -l'\nexport EDITOR=vi\n"
+    );
+    assert_eq!(std::fs::read_to_string(root.join("app.conf")).unwrap(), "port=1\n");
+    assert_eq!(std::fs::read_to_string(root.join("old.txt")).unwrap(), "old");
+    for gone in ["share", "bin", "archive", "state"] {
+        assert!(!root.join(gone).exists(), "{} left behind", gone);
+    }
+}
+
+#[test]
+fn test_transaction_rolls_back_on_failure() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = tmp.path();
+    let p = |name: &str| root.join(name).to_string_lossy().to_string();
+    std::fs::write(root.join("keep.txt"), "keep").unwrap();
+    std::fs::write(root.join("doomed.txt"), "doomed").unwrap();
+
+    let err = Transaction::new("sanity-rollback")
+        .state_dir(&p("state"))
+        .write(&p("keep.txt"), "changed")
+        .write(&p("new/dir/file.txt"), "new")
+        .rm(&p("doomed.txt"))
+        .cp(&p("missing-source"), &p("copy.txt"))
+        .commit()
+        .unwrap_err();
+    assert!(err.contains("cp ") && err.contains("rolled back"), "{}", err);
+
+    assert_eq!(std::fs::read_to_string(root.join("keep.txt")).unwrap(), "keep");
+    assert_eq!(std::fs::read_to_string(root.join("doomed.txt")).unwrap(), "doomed");
+    assert!(!root.join("new").exists());
+    assert!(!root.join("state").exists());
+}
+
 #[test]
 #[ignore]
 fn fs_fatal_read_file_probe() {
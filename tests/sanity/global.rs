@@ -83,6 +83,227 @@ ARRAY=(item1 item2 "item 3")
     assert!(has_var("ARRAY_2"));
 }
 
+#[test]
+fn sanity_global_config_bash_idioms() {
+    set_var("CFGB_HOME", "/home/rebel");
+    let config_content = r#"
+export CFGB_NAME="Rebel App"   # trailing comment
+readonly CFGB_ESC="say \"hi\" \$HOME"
+CFGB_DATA=${CFGB_HOME}/data
+CFGB_PORT=${CFGB_UNSET_PORT:-8080}
+CFGB_RAW='no $expansion here'
+CFGB_MULTI="line one
+line two"
+CFGB_CONT=abc\
+def
+CFGB_LIST=(
+  one          # first
+  "two words"
+  '$three'
+)
+CFGB_LIST+=(four)
+CFGB_TAB=$'a\tb'
+"#;
+
+    let keys = rsb::global::try_parse_config_content(config_content).unwrap();
+    assert!(keys.contains(&"CFGB_LIST".to_string()));
+    assert_eq!(get_var("CFGB_NAME"), "Rebel App");
+    assert_eq!(get_var("CFGB_ESC"), "say \"hi\" $HOME");
+    assert_eq!(get_var("CFGB_DATA"), "/home/rebel/data");
+    assert_eq!(get_var("CFGB_PORT"), "8080");
+    assert_eq!(get_var("CFGB_RAW"), "no $expansion here");
+    assert_eq!(get_var("CFGB_MULTI"), "line one\nline two");
+    assert_eq!(get_var("CFGB_CONT"), "abcdef");
+    assert_eq!(get_var("CFGB_LIST_LENGTH"), "4");
+    assert_eq!(get_var("CFGB_LIST_1"), "two words");
+    assert_eq!(get_var("CFGB_LIST_2"), "$three");
+    assert_eq!(get_var("CFGB_LIST_3"), "four");
+    assert_eq!(get_var("CFGB_TAB"), "a\tb");
+}
+
+#[test]
+fn sanity_global_config_errors_and_source() {
+    let err = rsb::global::try_parse_config_content("CFGE_OK=1\nCFGE_BAD=two words\n").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert!(err.to_string().starts_with("line 2:"), "{}", err);
+    // Strict parsing applies nothing on error
+    assert!(!has_var("CFGE_OK"));
+
+    let err = rsb::global::try_parse_config_content("CFGE_A=1\n\nCFGE_Q=\"open\nmore\n").unwrap_err();
+    assert_eq!(err.line, 3);
+    assert!(err.message.contains("unterminated"));
+
+    // Lenient parsing keeps the good lines
+    parse_config_content("CFGE_GOOD=yes\nnot an assignment\n");
+    assert_eq!(get_var("CFGE_GOOD"), "yes");
+
+    // `source` resolves relative to the including file
+    let dir = std::env::temp_dir().join(format!("rsb_cfg_src_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("base.conf"), "CFGE_BASE=from-base\n").unwrap();
+    std::fs::write(dir.join("main.conf"), "source base.conf\nCFGE_MAIN=${CFGE_BASE}+main\n").unwrap();
+    let keys = rsb::global::try_load_config_file(&dir.join("main.conf").to_string_lossy()).unwrap();
+    assert_eq!(keys, vec!["CFGE_BASE", "CFGE_MAIN"]);
+    assert_eq!(get_var("CFGE_MAIN"), "from-base+main");
+
+    std::fs::write(dir.join("broken.conf"), "CFGE_X=1\nCFGE_Y=$(date)\n").unwrap();
+    let path = dir.join("broken.conf").to_string_lossy().to_string();
+    let err = rsb::global::try_load_config_file(&path).unwrap_err();
+    assert_eq!(err.to_string(), format!("{}:2: command substitution is not supported", path));
+
+    // Lenient loads report bad sourced lines (and missing files) and go on
+    std::fs::write(dir.join("bad_base.conf"), "CFGE_SRC_OK=1
+CFGE_SRC_BAD=$(date)
+CFGE_SRC_LATE=2
+").unwrap();
+    std::fs::write(
+        dir.join("lenient.conf"),
+        "source bad_base.conf
+source missing.conf
+CFGE_PARENT=after
+",
+    )
+    .unwrap();
+    let path = dir.join("lenient.conf").to_string_lossy().to_string();
+    assert!(rsb::global::try_load_config_file(&path).is_err());
+    rsb::global::load_config_file(&path);
+    assert_eq!(get_var("CFGE_SRC_OK"), "1");
+    assert_eq!(get_var("CFGE_SRC_LATE"), "2");
+    assert!(!has_var("CFGE_SRC_BAD"));
+    assert_eq!(get_var("CFGE_PARENT"), "after");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn sanity_global_config_save_round_trip() {
+    let dir = std::env::temp_dir().join(format!("rsb_cfg_save_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.conf");
+    let original = "# App settings\nexport CFGS_NAME=old   # the name\n\n# ports\nCFGS_PORT=80\n";
+    std::fs::write(&path, original).unwrap();
+
+    set_var("CFGS_NAME", "it's new");
+    set_var("CFGS_EXTRA", "plain");
+    rsb::global::set_array("CFGS_TAGS", &["a", "b c"]);
+    save_config_file(&path.to_string_lossy(), &["CFGS_NAME", "CFGS_EXTRA", "CFGS_TAGS", "CFGS_MISSING"]);
+
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        saved,
+        "# App settings\nexport CFGS_NAME=\"it's new\"   # the name\n\n# ports\nCFGS_PORT=80\nCFGS_EXTRA=plain\nCFGS_TAGS=(a 'b c')\n"
+    );
+
+    // Saving again with the same values is a no-op on the text
+    save_config_file(&path.to_string_lossy(), &["CFGS_NAME", "CFGS_EXTRA", "CFGS_TAGS"]);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), saved);
+
+    unset_var("CFGS_NAME");
+    load_config_file(&path.to_string_lossy());
+    assert_eq!(get_var("CFGS_NAME"), "it's new");
+    assert_eq!(get_var("CFGS_TAGS_1"), "b c");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn sanity_global_config_save_replaces_appends() {
+    let dir = std::env::temp_dir().join(format!("rsb_cfg_append_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.conf");
+    let original = "CFGA_NAME=base\nCFGA_NAME+=_x\nCFGA_LIST=(a)\nCFGA_LIST+=(b)\n# end\n";
+    std::fs::write(&path, original).unwrap();
+    load_config_file(&path.to_string_lossy());
+    assert_eq!(get_var("CFGA_NAME"), "base_x");
+
+    set_var("CFGA_NAME", "fresh");
+    rsb::global::set_array("CFGA_LIST", &["c"]);
+    save_config_file(&path.to_string_lossy(), &["CFGA_NAME", "CFGA_LIST"]);
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(saved, "CFGA_NAME=fresh\nCFGA_LIST=(c)\n# end\n");
+
+    unset_var("CFGA_NAME");
+    load_config_file(&path.to_string_lossy());
+    assert_eq!(get_var("CFGA_NAME"), "fresh");
+    assert_eq!(get_var("CFGA_LIST_LENGTH"), "1");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn sanity_global_schema_validation() {
+    use rsb::global::{KeySpec, Schema};
+
+    let schema = Schema::new()
+        .key(KeySpec::int("SCH_PORT").default("8080").describe("listen port"))
+        .key(KeySpec::bool("SCH_VERBOSE"))
+        .key(KeySpec::one_of("SCH_MODE", &["dev", "prod"]).required())
+        .key(KeySpec::float("SCH_RATIO"))
+        .key(KeySpec::path("SCH_ROOT").required());
+
+    // All problems are collected in one pass
+    set_var("SCH_VERBOSE", "maybe");
+    set_var("SCH_RATIO", "1.5x");
+    schema.apply_defaults();
+    let report = schema.validate().unwrap_err();
+    let keys: Vec<&str> = report.issues.iter().map(|i| i.key.as_str()).collect();
+    assert_eq!(keys, vec!["SCH_VERBOSE", "SCH_MODE", "SCH_RATIO", "SCH_ROOT"]);
+    assert!(report.to_string().contains("SCH_MODE: required but not set"));
+    assert!(report.summary().contains("4 problems"));
+    assert_eq!(get_var("SCH_PORT"), "8080");
+
+    set_var("SCH_VERBOSE", "yes");
+    set_var("SCH_RATIO", "1.5");
+    set_var("SCH_MODE", "prod");
+    set_var("SCH_ROOT", "~/data");
+    assert!(schema.validate().is_ok());
+    set_var("SCH_MODE", "staging");
+    assert!(schema.validate().unwrap_err().issues[0]
+        .message
+        .contains("dev|prod"));
+}
+
+#[test]
+fn sanity_global_typed_getters() {
+    use rsb::global::{
+        clear_schema, get_bool, get_float, get_int, get_list, get_path, register_schema,
+        KeySpec, Schema,
+    };
+
+    set_var("TYP_INT", " 42 ");
+    set_var("TYP_FLOAT", "0.25");
+    set_var("TYP_BOOL", "off");
+    set_var("TYP_CSV", "a, b,,c");
+    set_var("TYP_HOME_PATH", "~/cache");
+    parse_config_content("TYP_ARR=(one 'two three')");
+    assert_eq!(get_int("TYP_INT"), Some(42));
+    assert_eq!(get_int("TYP_FLOAT"), None);
+    assert_eq!(get_float("TYP_FLOAT"), Some(0.25));
+    assert_eq!(get_bool("TYP_BOOL"), Some(false));
+    // Bools agree with `is_true`, including its pass/fail words
+    let words = [("pass", true), ("SUCCESS", true), ("fail", false), ("error", false), ("-3", true)];
+    for (word, truth) in words {
+        set_var("TYP_BOOL", word);
+        assert_eq!(get_bool("TYP_BOOL"), Some(truth), "{}", word);
+        assert_eq!(is_true("TYP_BOOL"), truth, "{}", word);
+    }
+    set_var("TYP_BOOL", "maybe");
+    assert_eq!(get_bool("TYP_BOOL"), None);
+    assert_eq!(get_list("TYP_CSV"), vec!["a", "b", "c"]);
+    assert_eq!(get_list("TYP_ARR"), vec!["one", "two three"]);
+    let home = std::env::var("HOME").unwrap();
+    assert_eq!(
+        get_path("TYP_HOME_PATH").unwrap(),
+        std::path::PathBuf::from(format!("{}/cache", home))
+    );
+    assert_eq!(get_int("TYP_UNSET"), None);
+
+    // Unset keys fall back to registered defaults
+    register_schema(Schema::new().key(KeySpec::int("TYP_UNSET").default("7")));
+    assert_eq!(get_int("TYP_UNSET"), Some(7));
+    assert!(!has_var("TYP_UNSET"));
+    assert!(rsb::global::validate_schema());
+    assert_eq!(get_var("TYP_UNSET"), "7");
+    clear_schema();
+}
+
 #[test]
 fn sanity_global_function_registry() {
     // Test function registration and listing
@@ -0,0 +1,167 @@
+// RSB Sanity Tests - hosts::command pipelines
+// Argv-style pipelines, stdin feeding, PIPESTATUS and pipefail semantics
+
+use rsb::prelude::*;
+
+#[test]
+fn test_pipeline_connects_stages() {
+    let res = Pipeline::new()
+        .argv(&["printf", "b\\na\\nc\\n"])
+        .argv(&["sort"])
+        .argv(&["head", "-n", "2"])
+        .run();
+    assert!(res.success());
+    assert_eq!(res.output, "a\nb\n");
+    assert_eq!(res.statuses, vec![0, 0, 0]);
+}
+
+#[test]
+fn test_pipeline_args_are_not_shell_parsed() {
+    let res = Pipeline::new()
+        .cmd(PipeCmd::new("echo").arg("$HOME; rm -rf /tmp/nothing").arg("a b"))
+        .run();
+    assert_eq!(res.output, "$HOME; rm -rf /tmp/nothing a b\n");
+}
+
+#[test]
+fn test_pipeline_stdin_env_and_cwd() {
+    let res = Pipeline::new()
+        .argv(&["grep", "x"])
+        .stdin_str("x1\ny2\nx3\n")
+        .run();
+    assert_eq!(res.output, "x1\nx3\n");
+
+    let from_stream = Pipeline::new()
+        .argv(&["wc", "-l"])
+        .stdin_stream(Stream::from_string("a\nb\nc"))
+        .run();
+    assert_eq!(from_stream.output.trim(), "3");
+
+    let env = Pipeline::new()
+        .cmd(PipeCmd::new("sh").args(&["-c", "echo $RSB_PIPE_VAR"]).env("RSB_PIPE_VAR", "set"))
+        .run();
+    assert_eq!(env.output, "set\n");
+
+    let cwd = Pipeline::new().cmd(PipeCmd::new("pwd").cwd("/")).run();
+    assert_eq!(cwd.output, "/\n");
+}
+
+#[test]
+fn test_pipeline_pipestatus_and_pipefail() {
+    let plain = Pipeline::new()
+        .argv(&["sh", "-c", "echo out; exit 3"])
+        .argv(&["cat"])
+        .run();
+    assert_eq!(plain.statuses, vec![3, 0]);
+    assert_eq!(plain.status, 0);
+    assert_eq!(plain.output, "out\n");
+
+    let failing = Pipeline::new()
+        .argv(&["sh", "-c", "exit 3"])
+        .argv(&["sh", "-c", "cat >/dev/null; exit 4"])
+        .argv(&["cat"])
+        .pipefail(true)
+        .run();
+    assert_eq!(failing.statuses, vec![3, 4, 0]);
+    assert_eq!(failing.status, 4);
+    assert!(!failing.success());
+}
+
+#[test]
+fn test_pipeline_missing_program_reports_127() {
+    let res = Pipeline::new()
+        .argv(&["rsb-definitely-not-a-command"])
+        .argv(&["cat"])
+        .pipefail(true)
+        .run();
+    assert_eq!(res.statuses, vec![127, 0]);
+    assert_eq!(res.status, 127);
+    assert!(res.error.contains("rsb-definitely-not-a-command"));
+}
+
+#[test]
+fn test_pipeline_stream_integration() {
+    let out = cmd!(argv: "printf", "3\\n1\\n2\\n").sort().to_vec();
+    assert_eq!(out, vec!["1", "2", "3"]);
+
+    let piped = Stream::from_string("beta\nalpha")
+        .pipe_to(Pipeline::new().argv(&["sort"]))
+        .to_string();
+    assert_eq!(piped, "alpha\nbeta");
+
+    let child = Pipeline::new().argv(&["sleep", "0"]).spawn();
+    assert_eq!(child.pids().len(), 1);
+    assert!(child.wait().success());
+}
+
+#[test]
+fn test_pipeline_dropped_child_is_killed() {
+    let child = Pipeline::new().argv(&["sleep", "30"]).argv(&["cat"]).spawn();
+    let pids = child.pids();
+    assert_eq!(pids.len(), 2);
+    let started = std::time::Instant::now();
+    drop(child);
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    for pid in pids {
+        // Killed and reaped: the pid no longer exists
+        let proc_dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+        assert!(!proc_dir.exists(), "{} still exists", pid);
+    }
+}
+
+#[test]
+fn test_pipeline_background_job() {
+    let jid = job!(pipeline: Pipeline::new().argv(&["sh", "-c", "exit 5"]));
+    assert_eq!(job!(wait: jid), 5);
+}
+
+#[test]
+fn test_pipeline_live_lines_and_tee() {
+    let log = std::env::temp_dir().join(format!("rsb_live_{}.log", std::process::id()));
+    let log_s = log.to_string_lossy().to_string();
+
+    let mut seen = Vec::new();
+    let res = Pipeline::new()
+        .argv(&["sh", "-c", "echo one; echo warn >&2; echo two"])
+        .tee(&log_s)
+        .run_live(|line| seen.push(line.clone()));
+
+    assert!(res.success());
+    assert_eq!(res.output, "one\ntwo\n");
+    assert_eq!(res.error, "warn\n");
+    assert!(seen.contains(&OutputLine::Stdout("one".to_string())));
+    assert!(seen.contains(&OutputLine::Stderr("warn".to_string())));
+    assert_eq!(seen.len(), 3);
+
+    let logged = std::fs::read_to_string(&log).unwrap();
+    assert!(logged.contains("one\n") && logged.contains("warn\n") && logged.contains("two\n"));
+    let _ = std::fs::remove_file(&log);
+}
+
+#[test]
+fn test_pipeline_timeout_kills_process_group() {
+    let started = std::time::Instant::now();
+    let res = Pipeline::new()
+        .argv(&["sh", "-c", "sleep 30 & sleep 30; echo never"])
+        .timeout(std::time::Duration::from_millis(200))
+        .run();
+    assert!(res.timed_out);
+    assert_eq!(res.status, TIMEOUT_STATUS);
+    assert!(res.output.is_empty());
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+    let quick = run_cmd_with_timeout("echo fast", std::time::Duration::from_secs(5));
+    assert_eq!(quick.status, 0);
+    assert_eq!(quick.output, "fast\n");
+}
+
+#[test]
+fn test_run_cmd_live_reports_lines_as_they_arrive() {
+    let mut count = 0;
+    let res = run_cmd_live("printf 'a\\nb\\nc\\n'", |line| {
+        assert!(!line.is_stderr());
+        count += 1;
+    });
+    assert_eq!(res.status, 0);
+    assert_eq!(count, 3);
+}
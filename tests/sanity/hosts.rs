@@ -286,3 +286,36 @@ fn test_edge_cases() {
     // Should not panic or corrupt state
     assert!(!get_var("SCRIPT_NAME").is_empty());
 }
+
+#[test]
+fn test_terminal_color_depth_detection() {
+    use rsb::hosts::{color_depth_from, locale_is_utf8, ColorDepth};
+
+    assert_eq!(
+        color_depth_from("xterm-256color", "truecolor"),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(color_depth_from("screen", "24bit"), ColorDepth::TrueColor);
+    assert_eq!(color_depth_from("xterm-kitty", ""), ColorDepth::TrueColor);
+    assert_eq!(color_depth_from("xterm-direct", ""), ColorDepth::TrueColor);
+    assert_eq!(color_depth_from("xterm-256color", ""), ColorDepth::Ansi256);
+    assert_eq!(color_depth_from("screen-256color", ""), ColorDepth::Ansi256);
+    assert_eq!(color_depth_from("xterm", ""), ColorDepth::Basic);
+    assert_eq!(color_depth_from("vt100", ""), ColorDepth::Basic);
+    assert_eq!(color_depth_from("dumb", ""), ColorDepth::None);
+    assert_eq!(color_depth_from("", ""), ColorDepth::None);
+    assert!(ColorDepth::TrueColor > ColorDepth::Ansi256 && ColorDepth::Basic > ColorDepth::None);
+
+    assert!(locale_is_utf8("en_US.UTF-8"));
+    assert!(locale_is_utf8("C.utf8"));
+    assert!(!locale_is_utf8("C"));
+    assert!(!locale_is_utf8(""));
+}
+
+#[test]
+fn test_terminal_caps_are_cached() {
+    let caps = rsb::hosts::term_caps();
+    assert_eq!(rsb::hosts::term_caps(), caps);
+    assert!(caps.width > 0);
+    assert!(rsb::hosts::term_width() > 0);
+}
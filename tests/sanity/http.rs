@@ -0,0 +1,166 @@
+// RSB Sanity Tests - HTTP Module Core Functionality Verification
+// Tests run the native client against the rsb::dev loopback TestServer
+
+use rsb::dev::{MockResponse, TestServer};
+use rsb::http::HttpError;
+use rsb::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn test_http_status_headers_body() {
+    let server = TestServer::start();
+    server.respond(
+        "GET",
+        "/hello?x=1",
+        MockResponse::new(200).header("X-Demo", "yes").body("hi there"),
+    );
+    server.respond("GET", "/chunked", MockResponse::new(200).chunked().body("a chunked body!"));
+
+    let resp = rsb::http::get(&server.url("/hello?x=1")).send().unwrap();
+    assert_eq!(resp.status, 200);
+    assert!(resp.is_success());
+    assert_eq!(resp.header("x-demo"), Some("yes"));
+    assert_eq!(resp.text(), "hi there");
+
+    let resp = rsb::http::get(&server.url("/chunked")).send().unwrap();
+    assert_eq!(resp.text(), "a chunked body!");
+
+    let resp = rsb::http::get(&server.url("/missing")).send().unwrap();
+    assert_eq!(resp.status, 404);
+    assert!(!resp.is_success());
+
+    let resp = rsb::http::head(&server.url("/hello?x=1")).send().unwrap();
+    assert!(resp.body.is_empty());
+    assert_eq!(server.requests()[0].header("Host"), Some(server.addr().to_string().as_str()));
+}
+
+#[test]
+fn test_http_auth_and_json_bodies() {
+    let server = TestServer::start();
+    server.respond(
+        "POST",
+        "/api",
+        MockResponse::new(201).json(&serde_json::json!({"id": 7})),
+    );
+
+    let resp = rsb::http::post(&server.url("/api"))
+        .basic_auth("alice", "s3cret")
+        .json(&serde_json::json!({"name": "widget"}))
+        .send()
+        .unwrap();
+    assert_eq!(resp.status, 201);
+    assert_eq!(resp.json().unwrap()["id"], 7);
+
+    rsb::http::get(&server.url("/api")).bearer("tok123").send().unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("Authorization"), Some("Basic YWxpY2U6czNjcmV0"));
+    assert_eq!(requests[0].header("Content-Type"), Some("application/json"));
+    assert_eq!(requests[0].text(), r#"{"name":"widget"}"#);
+    assert_eq!(requests[1].header("Authorization"), Some("Bearer tok123"));
+}
+
+#[test]
+fn test_http_redirects() {
+    let server = TestServer::start();
+    server.respond("POST", "/old", MockResponse::redirect(303, "/new"));
+    server.respond("GET", "/new", MockResponse::new(200).body("moved"));
+    server.respond("GET", "/loop", MockResponse::redirect(302, "loop"));
+
+    let resp = rsb::http::post(&server.url("/old")).body("x").send().unwrap();
+    assert_eq!(resp.text(), "moved");
+    assert_eq!(resp.url, server.url("/new"));
+    assert_eq!(server.requests()[1].method, "GET");
+
+    let resp = rsb::http::post(&server.url("/old")).max_redirects(0).send().unwrap();
+    assert_eq!(resp.status, 303);
+    assert_eq!(resp.header("Location"), Some("/new"));
+
+    let err = rsb::http::get(&server.url("/loop")).send().unwrap_err();
+    assert_eq!(err, HttpError::TooManyRedirects(5));
+}
+
+#[test]
+fn test_http_timeouts_and_errors() {
+    let server = TestServer::start();
+    server.respond("GET", "/slow", MockResponse::new(200).delay(Duration::from_millis(500)));
+
+    let err = rsb::http::get(&server.url("/slow"))
+        .timeout(Duration::from_millis(100))
+        .send()
+        .unwrap_err();
+    assert_eq!(err, HttpError::Timeout);
+    assert_eq!(err.curl_code(), 28);
+
+    let err = rsb::http::get("https://example.com").send().unwrap_err();
+    assert!(matches!(err, HttpError::InvalidUrl(_)));
+
+    // Port 1 on loopback is closed
+    let err = rsb::http::get("http://127.0.0.1:1/").send().unwrap_err();
+    assert!(matches!(err, HttpError::Connect(_)));
+}
+
+#[test]
+fn test_http_download_with_progress() {
+    let server = TestServer::start();
+    let payload = vec![b'z'; 40_000];
+    server.respond("GET", "/file.bin", MockResponse::new(200).body(payload.clone()));
+    server.respond("GET", "/gone.bin", MockResponse::new(404).body("nope"));
+
+    let dir = assert_fs::TempDir::new().unwrap();
+    let path = dir.path().join("file.bin");
+    let path = path.to_str().unwrap();
+    let mut seen = Vec::new();
+    let resp = rsb::http::get(&server.url("/file.bin"))
+        .download_with_progress(path, |done, total| seen.push((done, total)))
+        .unwrap();
+    assert!(resp.body.is_empty());
+    assert_eq!(std::fs::read(path).unwrap(), payload);
+    assert_eq!(seen.last(), Some(&(40_000, Some(40_000))));
+
+    let missing = dir.path().join("gone.bin");
+    let resp = rsb::http::get(&server.url("/gone.bin"))
+        .download(missing.to_str().unwrap())
+        .unwrap();
+    assert_eq!(resp.status, 404);
+    assert_eq!(resp.text(), "nope");
+    assert!(!missing.exists());
+}
+
+#[test]
+fn test_curl_macros_use_native_client() {
+    let server = TestServer::start();
+    server.respond("GET", "/plain", MockResponse::new(200).body("native"));
+    server.respond("POST", "/form", MockResponse::new(200).body("posted"));
+
+    assert_eq!(curl!(&server.url("/plain")), "native");
+    assert_eq!(get!(&server.url("/plain")), "native");
+    assert_eq!(curl!(post: &server.url("/form"), data: "a=1&b=2"), "posted");
+    let requests = server.requests();
+    assert_eq!(requests[2].text(), "a=1&b=2");
+    assert_eq!(requests[2].header("User-Agent"), Some(concat!("rsb/", env!("CARGO_PKG_VERSION"))));
+}
+
+#[cfg(feature = "progress")]
+#[test]
+fn test_download_with_task_reports_bytes() {
+    use rsb::progress::{download_with_task, ProgressManager, ProgressState, ProgressStyle};
+
+    let server = TestServer::start();
+    server.respond("GET", "/data", MockResponse::new(200).body(vec![1u8; 5000]));
+    server.respond("GET", "/missing", MockResponse::new(404));
+    let dir = std::env::temp_dir().join(format!("rsb_http_task_{}", std::process::id()));
+    let path = dir.to_string_lossy().to_string();
+
+    let manager = ProgressManager::new();
+    let task = manager.start_task("download", ProgressStyle::Spinner);
+    let resp = download_with_task(&rsb::http::get(&server.url("/data")), &path, &task).unwrap();
+    assert!(resp.is_success());
+    assert_eq!(task.current_progress(), 5000);
+    assert_eq!(task.state(), ProgressState::Complete);
+    let _ = std::fs::remove_file(&path);
+
+    let task = manager.start_task("download", ProgressStyle::Spinner);
+    download_with_task(&rsb::http::get(&server.url("/missing")), &path, &task).unwrap();
+    assert_eq!(task.state(), ProgressState::Failed);
+}
@@ -0,0 +1,118 @@
+// RSB Sanity Tests - JSON Module Core Functionality Verification
+// Tests verify the native query engine, set/delete, file write-back and
+// flattening into the global store
+
+use rsb::prelude::*;
+
+const DOC: &str = r#"{
+  "name": "demo",
+  "users": [
+    {"name": "Alice", "age": 31, "admin": true, "tags": ["a", "b"]},
+    {"name": "Bob", "age": 25, "admin": false},
+    {"name": "Cara", "age": 40}
+  ],
+  "meta": {"content-type": "json", "count": 3}
+}"#;
+
+#[test]
+fn test_json_paths_and_indexes() {
+    assert_eq!(json_get!(DOC, ".name"), "demo");
+    assert_eq!(json_get!(DOC, "name"), "demo");
+    assert_eq!(json_get!(DOC, ".users[0].name"), "Alice");
+    assert_eq!(json_get!(DOC, ".users[-1].name"), "Cara");
+    assert_eq!(json_get!(DOC, ".users[0].tags"), r#"["a","b"]"#);
+    assert_eq!(json_get!(DOC, r#".meta."content-type""#), "json");
+    assert_eq!(json_get!(DOC, r#".meta["count"]"#), "3");
+    assert_eq!(json_get!(DOC, ".missing.deeper"), "null");
+    assert_eq!(json_get!(DOC, ".users[9]"), "null");
+}
+
+#[test]
+fn test_json_wildcards_select_length_keys() {
+    assert_eq!(json_get!(DOC, ".users[].name"), "Alice\nBob\nCara");
+    assert_eq!(json_get!(DOC, ".users[*].age"), "31\n25\n40");
+    assert_eq!(json_get!(DOC, ".meta.*"), "json\n3");
+    assert_eq!(
+        json_get!(DOC, ".users[] | select(.age >= 30) | .name"),
+        "Alice\nCara"
+    );
+    assert_eq!(
+        json_get!(DOC, r#".users[] | select(.admin == false or .name == "Cara") | .name"#),
+        "Bob\nCara"
+    );
+    assert_eq!(json_get!(DOC, ".users[] | select(.admin) | .name"), "Alice");
+    assert_eq!(json_get!(DOC, ".users | length"), "3");
+    assert_eq!(json_get!(DOC, ".name | length"), "4");
+    assert_eq!(json_get!(DOC, ".meta | keys"), r#"["content-type","count"]"#);
+}
+
+#[test]
+fn test_json_unicode_keys_and_whitespace() {
+    let doc = r#"{"café": {"名前": "ok"}, "aaaaaé": 1, "list": [1, 2]}"#;
+    assert_eq!(json_get!(doc, ".café"), r#"{"名前":"ok"}"#);
+    assert_eq!(json_get!(doc, r#".café."名前""#), "ok");
+    // Keyword checks (`length`, `keys`) must not cut into the key
+    assert_eq!(json_get!(doc, "aaaaaé"), "1");
+    // No-break space and ideographic space around the pipe
+    assert_eq!(json_get!(doc, ".list\u{a0}|\u{3000}length"), "2");
+    assert_eq!(json_get!(doc, "\u{3000}.aaaaaé"), "1");
+}
+
+#[test]
+fn test_json_errors_are_reported() {
+    assert!(rsb::json::json_get("{not json", ".a").is_err());
+    assert!(rsb::json::json_get(DOC, ".users[").is_err());
+    assert!(rsb::json::json_get(DOC, ".name.first").is_err());
+    assert_eq!(json_get!(DOC, ".users[0"), "");
+}
+
+#[test]
+fn test_json_set_and_delete() {
+    let doc = json_set!(DOC, ".users[1].age", "26");
+    assert_eq!(json_get!(&doc, ".users[1].age"), "26");
+
+    let doc = json_set!(&doc, ".users[].active", "true");
+    assert_eq!(json_get!(&doc, ".users[] | select(.active) | .name"), "Alice\nBob\nCara");
+
+    let doc = json_set!(&doc, ".new.list[2]", "hello world");
+    assert_eq!(json_get!(&doc, ".new.list"), r#"[null,null,"hello world"]"#);
+
+    let doc = json_del!(&doc, ".users[0]");
+    assert_eq!(json_get!(&doc, ".users[0].name"), "Bob");
+    let doc = json_del!(&doc, ".users[].age");
+    assert_eq!(json_get!(&doc, ".users[0] | keys"), r#"["active","admin","name"]"#);
+
+    // Non-path queries can't be assigned to
+    assert!(rsb::json::json_set(DOC, ".users | length", "1").is_err());
+}
+
+#[test]
+fn test_json_file_write_back() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let path = dir.path().join("config.json");
+    let path = path.to_str().unwrap();
+    std::fs::write(path, DOC).unwrap();
+
+    json_set!(file: path, ".meta.count", "4");
+    json_del!(file: path, ".users");
+    assert_eq!(json_get_file!(path, ".meta.count"), "4");
+    assert_eq!(json_get_file!(path, ".users"), "null");
+    assert!(std::fs::read_to_string(path).unwrap().ends_with("}\n"));
+}
+
+#[test]
+fn test_json_flatten_into_global() {
+    let keys = rsb::json::json_to_global(DOC, "jsn").unwrap();
+    assert!(keys.contains(&"jsn_users_LENGTH".to_string()));
+    assert_eq!(get_var("jsn_name"), "demo");
+    assert_eq!(get_var("jsn_users_LENGTH"), "3");
+    assert_eq!(get_var("jsn_users_1_name"), "Bob");
+    assert_eq!(get_var("jsn_users_0_tags_1"), "b");
+    assert_eq!(get_var("jsn_users_0_tags"), "a b");
+    assert_eq!(get_var("jsn_meta_content_type"), "json");
+}
+
+#[test]
+fn test_jq_get_is_native() {
+    assert_eq!(rsb::bash::jq::jq_get(DOC, ".users[1].name"), "Bob");
+}
@@ -0,0 +1,166 @@
+// RSB Sanity Tests - Log Module Core Functionality Verification
+// Tests verify filter specs, record formatting, file/JSON sinks with fields
+// and spans, and size-based rotation. Sinks use their own filters so the
+// shared logger's stderr output is left alone.
+
+use rsb::log::{FileSink, Level, LogFilter, Record};
+
+fn read_json_lines(path: &std::path::Path) -> Vec<serde_json::Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_log_filter_specs() {
+    let filter = LogFilter::parse("warn,app::db=debug,noisy=off").unwrap();
+    assert!(filter.enabled(Level::Debug, "app::db"));
+    assert!(filter.enabled(Level::Debug, "app::db::pool"));
+    assert!(!filter.enabled(Level::Debug, "app::dbx"));
+    assert!(!filter.enabled(Level::Info, "app"));
+    assert!(filter.enabled(Level::Warn, "app"));
+    assert!(!filter.enabled(Level::Fatal, "noisy::inner"));
+    assert!(filter.enabled(Level::Okay, "") == filter.enabled(Level::Info, ""));
+
+    let off = LogFilter::parse("off,only=trace").unwrap();
+    assert!(!off.enabled(Level::Error, "other"));
+    assert!(off.enabled(Level::Trace, "only"));
+    assert_eq!(LogFilter::parse(""), Ok(LogFilter::level(Level::Info)));
+    assert!(LogFilter::parse("verbose")
+        .unwrap_err()
+        .contains("unknown log level"));
+    assert!(LogFilter::parse("=debug").is_err());
+    assert_eq!(Level::from_name("think"), Some(Level::Trace));
+}
+
+#[test]
+fn test_log_record_formats() {
+    let record = Record {
+        timestamp: chrono::Local::now(),
+        level: Level::Warn,
+        target: "app::net".to_string(),
+        message: "{red}link down{reset}".to_string(),
+        fields: vec![
+            ("iface".to_string(), "eth0".to_string()),
+            ("note".to_string(), "two words".to_string()),
+        ],
+        span: vec!["deploy".to_string(), "deploy push".to_string()],
+    };
+    let text = record.to_text();
+    assert!(
+        text.ends_with(
+            " WARN  app::net [deploy>deploy push] link down iface=eth0 note=\"two words\""
+        ),
+        "{}",
+        text
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&record.to_json()).unwrap();
+    assert_eq!(json["level"], "warn");
+    assert_eq!(json["msg"], "link down");
+    assert_eq!(json["fields"]["note"], "two words");
+    assert_eq!(json["span"][1], "deploy push");
+}
+
+#[test]
+fn test_log_file_sink_fields_and_spans() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let path = tmp.path().join("logs/run.jsonl");
+    let filter = LogFilter::parse("off,sanitylog=debug,rsb::span=trace").unwrap();
+    rsb::log::add_sink_with_filter("sanity-json", FileSink::new(&path).json(), filter);
+
+    {
+        let _span = rsb::log::span("sanity_log_span");
+        rsb::log!(warn, target: "sanitylog", "disk {} low", "/var"; free_mb = 12, host = "a b");
+        rsb::log!(trace, target: "sanitylog", "filtered out");
+    }
+    rsb::log!(info, target: "sanitylog::sub", "after span");
+    assert!(rsb::log::remove_sink("sanity-json"));
+    rsb::log!(info, target: "sanitylog", "not written");
+
+    let records = read_json_lines(&path);
+    let ours: Vec<&serde_json::Value> = records
+        .iter()
+        .filter(|r| r["target"].as_str().unwrap().starts_with("sanitylog"))
+        .collect();
+    assert_eq!(ours.len(), 2);
+    assert_eq!(ours[0]["level"], "warn");
+    assert_eq!(ours[0]["msg"], "disk /var low");
+    assert_eq!(ours[0]["fields"]["free_mb"], "12");
+    assert_eq!(ours[0]["fields"]["host"], "a b");
+    let span: Vec<&str> = ours[0]["span"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s.as_str().unwrap())
+        .collect();
+    assert!(span.contains(&"sanity_log_span"));
+    assert_eq!(ours[1]["msg"], "after span");
+
+    let messages: Vec<&str> = records.iter().map(|r| r["msg"].as_str().unwrap()).collect();
+    assert!(messages.contains(&"enter sanity_log_span"));
+    assert!(messages.contains(&"exit sanity_log_span"));
+    assert!(!rsb::log::sink_names().contains(&"sanity-json".to_string()));
+}
+
+#[test]
+fn test_log_file_rotation() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let path = tmp.path().join("rot.log");
+    let sink = FileSink::new(&path).max_bytes(300).keep(2);
+    rsb::log::add_sink_with_filter(
+        "sanity-rotate",
+        sink,
+        LogFilter::parse("off,sanityrot=info").unwrap(),
+    );
+    for i in 0..20 {
+        rsb::log!(info, target: "sanityrot", "rotation line {}", i);
+    }
+    rsb::log::remove_sink("sanity-rotate");
+
+    let rotated = |n: usize| std::path::PathBuf::from(format!("{}.{}", path.display(), n));
+    assert!(rotated(1).exists());
+    assert!(rotated(2).exists());
+    assert!(!rotated(3).exists());
+    for file in [path.clone(), rotated(1), rotated(2)] {
+        assert!(std::fs::metadata(&file).unwrap().len() <= 300);
+    }
+    let latest = std::fs::read_to_string(&path).unwrap();
+    assert!(latest.trim_end().ends_with("rotation line 19"));
+}
+
+#[test]
+fn test_log_stderrx_unknown_levels_always_print() {
+    // Without a filter, info is hidden unless a debug/dev/trace mode is on;
+    // level names the logger doesn't know print regardless, as they always have
+    let binary = std::env::current_exe().expect("resolve test binary path");
+    let probe = std::process::Command::new(&binary)
+        .args([
+            "--ignored",
+            "--exact",
+            "--nocapture",
+            "log::log_stderrx_probe",
+        ])
+        .env_remove("RSB_LOG")
+        .output()
+        .expect("spawn stderrx probe");
+    assert!(probe.status.success());
+    let stderr = String::from_utf8_lossy(&probe.stderr);
+    assert!(stderr.contains("probe note shown"), "{}", stderr);
+    assert!(!stderr.contains("probe info hidden"), "{}", stderr);
+    assert!(stderr.contains("probe error shown"), "{}", stderr);
+}
+
+#[test]
+#[ignore]
+fn log_stderrx_probe() {
+    // Exercised via subprocess in test_log_stderrx_unknown_levels_always_print
+    for mode in ["DEBUG_MODE", "DEV_MODE", "TRACE_MODE", "QUIET_MODE"] {
+        rsb::global::unset_var(mode);
+    }
+    rsb::utils::stderrx("note", "probe note shown");
+    rsb::utils::stderrx("info", "probe info hidden");
+    rsb::utils::stderrx("error", "probe error shown");
+}
@@ -1,7 +1,7 @@
 //! Sanity tests for Options Cleanup functionality
 
 use rsb::prelude::*;
-use rsb::cli::{Args, OptionsStrategy, options};
+use rsb::cli::{Args, OptSpec, OptionsStrategy, options};
 
 // Helper to create string vec from string literals
 fn vec_str(items: &[&str]) -> Vec<String> {
@@ -67,13 +67,131 @@ fn test_options_with_values() {
 }
 
 #[test]
-fn test_flag_boundary_detection() {
-    // This pattern could be problematic: --flag value (space-separated)
-    let args = Args::new(&vec_str(&["prog", "--flag", "value", "--safe=value"]));
+fn test_undeclared_space_value_stays_positional() {
+    // Undeclared long options are flags: the next word stays positional
+    let mut args = Args::new(&vec_str(&["prog", "--boundary-flag", "value", "--safe-value=v"]));
     let context = options(&args);
+    assert!(context.errors.is_empty());
+    assert_eq!(get_var("opt_boundary_flag"), "true");
 
-    // Should detect potential boundary issue
-    assert!(context.has_boundary_issues);
+    args.apply_options_strategy(OptionsStrategy::Remove, &context);
+    assert_eq!(args.all(), vec_str(&["prog", "value"]).as_slice());
+}
+
+#[test]
+fn test_gnu_style_declared_options() {
+    let specs = [
+        OptSpec::value("gnu-config").short('c'),
+        OptSpec::value("gnu-out").short('o'),
+        OptSpec::value("gnu-tag").repeatable(),
+        OptSpec::value("gnu-jobs").short('j').value_type(ValueType::Int).default("2"),
+        OptSpec::flag("gnu-verbose").short('V'),
+    ];
+    let mut args = Args::new(&vec_str(&[
+        "prog", "--gnu-config", "app.conf", "in.txt", "-Vxyo", "out.txt", "--gnu-tag", "a",
+        "--gnu-tag=b", "-5", "--", "--not-an-option", "-z",
+    ]));
+    let context = rsb::cli::options_with_specs(&args, &specs);
+    assert!(context.errors.is_empty(), "{:?}", context.errors);
+
+    assert_eq!(get_var("opt_gnu_config"), "app.conf");
+    assert_eq!(get_var("opt_gnu_out"), "out.txt");
+    assert_eq!(get_var("opt_gnu_verbose"), "true");
+    assert_eq!(get_var("opt_x"), "true");
+    assert_eq!(get_var("opt_y"), "true");
+    assert_eq!(rsb::cli::get_option_values("gnu_tag"), vec!["a", "b"]);
+    assert_eq!(rsb::cli::get_option_int("gnu_jobs"), Some(2));
+    assert_eq!(rsb::cli::get_option_bool("gnu_verbose"), Some(true));
+    assert!(!has_var("opt_not_an_option"));
+    assert!(!has_var("opt_z"));
+
+    args.apply_options_strategy(OptionsStrategy::Remove, &context);
+    let expected = vec_str(&["prog", "in.txt", "-5", "--not-an-option", "-z"]);
+    assert_eq!(args.all(), expected.as_slice());
+}
+
+#[test]
+fn test_gnu_style_errors_and_sort() {
+    let specs = [
+        OptSpec::value("err-jobs").short('j').value_type(ValueType::Int),
+        OptSpec::value("err-name").required(),
+        OptSpec::value("err-last"),
+    ];
+    let args = Args::new(&vec_str(&["prog", "-j", "many", "--err-last"]));
+    let context = rsb::cli::options_with_specs(&args, &specs);
+    assert_eq!(context.errors.len(), 3, "{:?}", context.errors);
+    assert!(context.errors[0].contains("'--err-last' requires a value"));
+    assert!(context.errors[1].contains("'--err-jobs'"));
+    assert!(context.errors[2].contains("missing required option '--err-name'"));
+
+    // Sort keeps values with their options and words after `--` last
+    let mut args = Args::new(&vec_str(&["prog", "-j", "4", "file", "--", "-x"]));
+    let context = rsb::cli::options_with_specs(&args, &specs);
+    args.apply_options_strategy(OptionsStrategy::Sort, &context);
+    assert_eq!(args.all(), vec_str(&["prog", "file", "-j", "4", "--", "-x"]).as_slice());
+}
+
+#[test]
+fn test_option_validators_collect_errors() {
+    use rsb::cli::Validator;
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let dir = tmp.path().to_string_lossy().to_string();
+    let existing = tmp.path().join("in.txt");
+    std::fs::write(&existing, "data").unwrap();
+    let existing = existing.to_string_lossy().to_string();
+
+    let specs = [
+        OptSpec::value("val-input-file").validate(Validator::Exists).validate(Validator::Readable),
+        OptSpec::value("val-output-file").validate(Validator::Creatable),
+        OptSpec::value("val-dir").validate(Validator::Dir),
+        OptSpec::value("val-name").validate(Validator::regex("^[a-z]+$")),
+        OptSpec::value("val-level").validate(Validator::range(1.0, 5.0)),
+        OptSpec::value("val-mode").validate(Validator::one_of(&["fast", "safe"])),
+    ];
+
+    // A path-like name no longer exits: a new output file is fine
+    let new_file = format!("{}/new.txt", dir);
+    let args = Args::new(&vec_str(&[
+        "prog", "--val-input-file", &existing, "--val-output-file", &new_file, "--val-dir", &dir,
+        "--val-name=abc", "--val-level", "3", "--val-mode", "safe",
+    ]));
+    let context = rsb::cli::options_with_specs(&args, &specs);
+    assert!(context.errors.is_empty(), "{:?}", context.errors);
+    assert_eq!(get_var("opt_val_output_file"), new_file);
+
+    // Every failure is reported, not just the first
+    let missing_dir = format!("{}/nope/out.txt", dir);
+    let args = Args::new(&vec_str(&[
+        "prog", "--val-input-file", "/no/such/file", "--val-output-file", &missing_dir,
+        "--val-dir", &existing, "--val-name=ABC", "--val-level", "9", "--val-mode", "slow",
+    ]));
+    let context = rsb::cli::options_with_specs(&args, &specs);
+    assert_eq!(context.errors.len(), 7, "{:?}", context.errors);
+    assert!(context.errors[0].contains("path does not exist"));
+    assert!(context.errors[1].contains("cannot read"));
+    assert!(context.errors[2].contains("does not exist"));
+    assert!(context.errors[3].contains("not a directory"));
+    assert!(context.errors[4].contains("does not match"));
+    assert!(context.errors[5].contains("out of range"));
+    assert!(context.errors[6].contains("not one of: fast, safe"));
+}
+
+#[test]
+fn test_try_options_returns_errors() {
+    use rsb::cli::{add_option_validator, try_options, Validator};
+    add_option_validator("try-port", Validator::range(1.0, 65535.0));
+
+    let args = Args::new(&vec_str(&["prog", "--try-port", "8080"]));
+    let context = try_options(&args).unwrap();
+    assert_eq!(rsb::cli::get_option_int("try_port"), Some(8080));
+    // Kept for compatibility; `--key value` is never flagged any more
+    #[allow(deprecated)]
+    let boundary = context.has_boundary_issues;
+    assert!(!boundary);
+
+    let args = Args::new(&vec_str(&["prog", "--try-port=0"]));
+    let errors = try_options(&args).unwrap_err();
+    assert_eq!(errors, vec!["option '--try-port': 0 is out of range 1..65535"]);
 }
 
 #[test]
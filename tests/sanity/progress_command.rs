@@ -0,0 +1,30 @@
+// RSB Sanity Tests - progress + hosts::command
+// `run_with_task` tails a pipeline's output into a progress task and
+// finishes the task from the pipeline status.
+
+use rsb::hosts::command::Pipeline;
+use rsb::progress::{run_with_task, ProgressManager, ProgressState, ProgressStyle};
+
+#[test]
+fn test_run_with_task_tails_output() {
+    let manager = ProgressManager::new();
+    let task = manager.start_task("build", ProgressStyle::Spinner);
+    let result = run_with_task(
+        Pipeline::new().argv(&["printf", "compiling\\nlinking\\n"]),
+        &task,
+    );
+    assert!(result.success());
+    assert_eq!(task.state(), ProgressState::Complete);
+}
+
+#[test]
+fn test_run_with_task_fails_on_error() {
+    let manager = ProgressManager::new();
+    let task = manager.start_task("build", ProgressStyle::Spinner);
+    let result = run_with_task(
+        Pipeline::new().argv(&["sh", "-c", "echo oops; exit 2"]),
+        &task,
+    );
+    assert_eq!(result.status, 2);
+    assert_eq!(task.state(), ProgressState::Failed);
+}
@@ -0,0 +1,425 @@
+// RSB Sanity Tests - Prompts
+// Typed prompts are answered through flags and answers files; the raw-mode
+// widgets (feature `dev-pty`) run the ignored probe below inside a pseudo
+// terminal, type keys at it and read back the answer it prints.
+#![cfg(feature = "prompts")]
+
+use rsb::prelude::*;
+use rsb::visual::prompts::{
+    answer_for, ask_choice, ask_int, ask_path, ask_regex, ask_with, fuzzy_filter, fuzzy_score,
+    PathCheck, Step, Wizard,
+};
+#[cfg(feature = "dev-pty")]
+use rsb::{
+    dev::{spawn_pty, PtyOptions, PtySession},
+    visual::prompts::utils::{ask_with_timeout, select_with_timeout},
+    visual::prompts::{ask, ask_edit, fuzzy_select, multi_select, password, select},
+};
+#[cfg(feature = "dev-pty")]
+use std::time::Duration;
+
+#[cfg(feature = "dev-pty")]
+const WAIT: Duration = Duration::from_secs(10);
+#[cfg(feature = "dev-pty")]
+const OPTIONS: &[&str] = &["alpha", "beta", "gamma"];
+
+#[cfg(feature = "dev-pty")]
+#[test]
+#[ignore]
+fn prompt_probe() {
+    let answer = match std::env::var("PROMPT_PROBE").unwrap_or_default().as_str() {
+        "select" => select("Pick", OPTIONS, None),
+        "multi" => multi_select("Pick", OPTIONS, &[1]).join(","),
+        "fuzzy" => fuzzy_select("Find", OPTIONS, None),
+        "password" => password("Secret", Some('*')),
+        "edit" => ask_edit("Path", "/usr/local"),
+        "timeout" => {
+            let first = ask_with_timeout("First", Some("dflt"), Some(1));
+            format!("{},{}", first, ask("Second", None))
+        }
+        "timed-select" => select_with_timeout("Pick", OPTIONS, Some(2), Some(30)),
+        "long" => select("Pick", &["short", "a rather long option name"], None),
+        "wizard" => {
+            let answers = setup_wizard("wz").run().unwrap();
+            let pairs: Vec<String> = answers
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect();
+            format!("{};global={}", pairs.join(","), get_var("wz_port"))
+        }
+        "int" => ask_int("tpi_port", "Port", None, 1..=65535)
+            .unwrap()
+            .to_string(),
+        other => panic!("unknown probe '{}'", other),
+    };
+    println!("ANSWER=[{}]END", answer);
+}
+
+/// Name, kind, a port only for web projects, and a git flag; keys start
+/// with `prefix`.
+fn setup_wizard(prefix: &str) -> Wizard {
+    let kind_key = format!("{}_kind", prefix);
+    Wizard::new("Project setup")
+        .step(
+            Step::text(&format!("{}_name", prefix), "Project name")
+                .default("demo")
+                .validate(|v| match v.contains(' ') {
+                    true => Err("has spaces".to_string()),
+                    false => Ok(()),
+                }),
+        )
+        .step(Step::select(&kind_key, "Kind", &["cli", "web"]))
+        .step(
+            Step::text(&format!("{}_port", prefix), "Port")
+                .default("8080")
+                .validate(|v| v.parse::<u16>().map(|_| ()).map_err(|e| e.to_string()))
+                .when(move |answers| answers.get(&kind_key) == Some("web")),
+        )
+        .step(Step::confirm(&format!("{}_git", prefix), "Init git").default("yes"))
+}
+
+#[cfg(feature = "dev-pty")]
+fn start(kind: &str, ready: &str) -> PtySession {
+    start_after("", kind, ready)
+}
+
+/// `start`, running the shell commands in `setup` before the probe.
+#[cfg(feature = "dev-pty")]
+fn start_after(setup: &str, kind: &str, ready: &str) -> PtySession {
+    start_in(&PtyOptions::default(), setup, kind, ready)
+}
+
+/// `start_after` in a terminal of the given size.
+#[cfg(feature = "dev-pty")]
+fn start_in(options: &PtyOptions, setup: &str, kind: &str, ready: &str) -> PtySession {
+    let binary = std::env::current_exe().expect("resolve test binary path");
+    let command = format!(
+        "{}PROMPT_PROBE={} '{}' --ignored --exact prompts::prompt_probe --nocapture",
+        setup,
+        kind,
+        binary.display()
+    );
+    let mut session = spawn_pty(&command, options).expect("spawn probe");
+    session.read_until(ready, WAIT).expect("prompt drawn");
+    session
+}
+
+#[cfg(feature = "dev-pty")]
+fn answer(session: &mut PtySession) -> String {
+    let out = session.read_until("]END", WAIT).expect("probe answered");
+    let _ = session.wait();
+    let start = out.rfind("ANSWER=[").expect("answer line") + "ANSWER=[".len();
+    let end = out.rfind("]END").expect("answer end");
+    out[start..end].to_string()
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn select_moves_with_arrow_keys() {
+    let mut session = start("select", "enter)");
+    session.write_all(b"\x1b[B\x1b[B\x1b[A").unwrap();
+    session.write_all(b"\r").unwrap();
+    assert_eq!(answer(&mut session), "beta");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn multi_select_toggles_with_space() {
+    let mut session = start("multi", "enter)");
+    // beta starts checked: check alpha, uncheck beta, check gamma
+    session.write_all(b" \x1b[B \x1b[B ").unwrap();
+    session.write_all(b"\r").unwrap();
+    assert_eq!(answer(&mut session), "alpha,gamma");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn fuzzy_select_filters_as_you_type() {
+    let mut session = start("fuzzy", "filter)");
+    session.write_all(b"gm").unwrap();
+    session.write_all(b"\r").unwrap();
+    assert_eq!(answer(&mut session), "gamma");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn select_clips_options_to_the_terminal_width() {
+    let narrow = PtyOptions {
+        cols: 20,
+        ..PtyOptions::default()
+    };
+    let mut session = start_in(&narrow, "", "long", "enter)");
+    let drawn = session.read_until("…", WAIT).expect("clipped option");
+    assert!(drawn.contains("a rather long op…"), "{}", drawn);
+    assert!(!drawn.contains("option name"), "{}", drawn);
+    // The answer is still the whole option
+    session.write_all(b"\x1b[B\r").unwrap();
+    assert_eq!(answer(&mut session), "a rather long option name");
+}
+
+#[test]
+fn fuzzy_matches_rank_contiguous_first() {
+    assert_eq!(fuzzy_score("", "anything"), Some(0));
+    assert_eq!(fuzzy_score("DEP", "deploy"), Some(0));
+    assert_eq!(fuzzy_score("xyz", "deploy"), None);
+    assert!(fuzzy_score("dpy", "deploy").unwrap() > fuzzy_score("ploy", "deploy").unwrap());
+
+    let options = ["status", "start", "restart", "stop"];
+    assert_eq!(fuzzy_filter("sta", &options), vec![0, 1, 2]);
+    assert_eq!(fuzzy_filter("srt", &options), vec![1, 2]);
+    assert_eq!(fuzzy_filter("", &options), vec![0, 1, 2, 3]);
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn password_is_masked() {
+    let mut session = start("password", "Secret");
+    session.write_all(b"s3cret").unwrap();
+    let echoed = session.read_until("******", WAIT).expect("mask drawn");
+    assert!(!echoed.contains("s3cret"));
+    session.write_all(b"\r").unwrap();
+    assert_eq!(answer(&mut session), "s3cret");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn ask_edit_starts_from_the_initial_value() {
+    let mut session = start("edit", "/usr/local");
+    // Delete "local", jump home, insert a leading dot
+    session.write_all(b"\x7f\x7f\x7f\x7f\x7fopt").unwrap();
+    session.write_all(b"\x1b[H.").unwrap();
+    session.write_all(b"\r").unwrap();
+    assert_eq!(answer(&mut session), "./usr/opt");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn hung_up_terminal_ends_an_edit() {
+    // SIGHUP is ignored (as under nohup), so only the closed stdin can end
+    // the edit, with text still in the buffer
+    let mut session = start_after("trap '' HUP; ", "edit", "/usr/local");
+    session.write_all(b"/bin").unwrap();
+    session.read_until("/usr/local/bin", WAIT).unwrap();
+    session.hang_up();
+    let status = session.wait_timeout(WAIT).unwrap();
+    if status.is_none() {
+        let _ = session.kill();
+    }
+    assert!(status.is_some(), "edit kept running after the hangup");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn typed_prompt_reasks_until_valid() {
+    let mut session = start("int", "Port");
+    session.write_all(b"eighty\r").unwrap();
+    let shown = session.read_until("whole number", WAIT).unwrap();
+    assert!(
+        shown.contains("'eighty' is not a whole number"),
+        "{}",
+        shown
+    );
+    session.write_all(b"70000\r").unwrap();
+    session.read_until("between 1 and 65535", WAIT).unwrap();
+    session.write_all(b"8080\r").unwrap();
+    assert_eq!(answer(&mut session), "8080");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn timed_out_prompt_leaves_stdin_to_the_next_prompt() {
+    let mut session = start("timeout", "(dflt in 1s)");
+    session
+        .read_until("Second", WAIT)
+        .expect("timed out to the next prompt");
+    session.write_all(b"typed\r").unwrap();
+    assert_eq!(answer(&mut session), "dflt,typed");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn timed_select_counts_down_and_takes_keys() {
+    let mut session = start("timed-select", "(gamma in 30s)");
+    session.write_all(b"\x1b[A\r").unwrap();
+    assert_eq!(answer(&mut session), "beta");
+}
+
+#[cfg(feature = "dev-pty")]
+#[test]
+fn wizard_goes_back_and_confirms_a_summary() {
+    let mut session = start("wizard", "Project name");
+    session.write_all(b"my app\r").unwrap();
+    session.read_until("has spaces", WAIT).unwrap();
+    session.write_all(b"app\r").unwrap();
+    session.read_until("enter)", WAIT).unwrap();
+    session.write_all(b"\x1b[B\r").unwrap();
+    session.read_until("Port", WAIT).unwrap();
+    // Back to the list, which offers the previous answer again
+    session.write_all(b"<\r").unwrap();
+    session.read_until("enter)", WAIT).unwrap();
+    session.write_all(b"\r").unwrap();
+    session.read_until("Port", WAIT).unwrap();
+    session.write_all(b"9000\r").unwrap();
+    session.read_until("Init git", WAIT).unwrap();
+    session.write_all(b"n\r").unwrap();
+    let summary = session.read_until("enter)", WAIT).unwrap();
+    assert!(summary.contains("Apply these settings?"), "{}", summary);
+    assert!(summary.contains("Port: "), "{}", summary);
+    assert!(summary.contains("9000"), "{}", summary);
+    session.write_all(b"\r").unwrap();
+    assert_eq!(
+        answer(&mut session),
+        "wz_name=app,wz_kind=web,wz_port=9000,wz_git=false;global=9000"
+    );
+}
+
+#[test]
+fn wizard_runs_unattended_and_saves_its_answers() {
+    let dir = std::env::temp_dir().join(format!("rsb_wizard_{}", std::process::id()));
+    let config = dir.join("setup.conf");
+    set_var("opt_wzq_kind", "WEB");
+    set_var("wzq_port", "9090");
+
+    let answers = setup_wizard("wzq")
+        .save_to(config.to_str().unwrap())
+        .run()
+        .unwrap();
+    // Supplied (normalized), default from the global store, declared defaults
+    assert_eq!(answers.get("wzq_kind"), Some("web"));
+    assert_eq!(answers.get("wzq_port"), Some("9090"));
+    assert_eq!(answers.get("wzq_name"), Some("demo"));
+    assert!(answers.is_true("wzq_git"));
+    assert_eq!(
+        answers.keys(),
+        vec!["wzq_name", "wzq_kind", "wzq_port", "wzq_git"]
+    );
+    assert_eq!(get_var("wzq_git"), "true");
+    let saved = std::fs::read_to_string(&config).unwrap();
+    assert!(saved.contains("wzq_kind=web"), "{}", saved);
+
+    // Hidden steps are skipped; a bad supplied answer fails the run
+    set_var("opt_wzq_kind", "cli");
+    let answers = setup_wizard("wzq").run().unwrap();
+    assert_eq!(answers.get("wzq_port"), None);
+    set_var("opt_wzq_kind", "desktop");
+    let err = setup_wizard("wzq").run().unwrap_err();
+    assert!(err.contains("is not one of: cli, web"), "{}", err);
+
+    for key in [
+        "opt_wzq_kind",
+        "wzq_port",
+        "wzq_name",
+        "wzq_kind",
+        "wzq_git",
+    ] {
+        unset_var(key);
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn wizard_applies_nothing_when_the_save_fails() {
+    let dir = std::env::temp_dir().join(format!("rsb_wizard_fail_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // The config's parent is a regular file, so the save can't succeed
+    let blocker = dir.join("blocker");
+    std::fs::write(&blocker, "").unwrap();
+    let root = dir.join("root");
+    set_var("opt_wzf_kind", "cli");
+
+    let err = setup_wizard("wzf")
+        .step(Step::path("wzf_root", "Root", PathCheck::CreateDir).default(root.to_str().unwrap()))
+        .save_to(blocker.join("setup.conf").to_str().unwrap())
+        .run()
+        .unwrap_err();
+    assert!(err.contains("setup.conf"), "{}", err);
+    assert!(!root.exists());
+    for key in ["wzf_name", "wzf_kind", "wzf_git", "wzf_root"] {
+        assert!(!has_var(key), "{} was set", key);
+    }
+    unset_var("opt_wzf_kind");
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn typed_prompts_take_answers_from_flags() {
+    set_var("opt_tp_port", "8080");
+    set_var("opt_tp_env", "PROD");
+    set_var("opt_tp_tag", "v1.2.0");
+    assert_eq!(ask_int("tp-port", "Port", None, 1..=65535), Ok(8080));
+    assert_eq!(
+        ask_choice("tp_env", "Env", &["dev", "prod"], None),
+        Ok("prod".to_string())
+    );
+    assert_eq!(
+        ask_regex("tp_tag", "Tag", None, r"v\d+\.\d+\.\d+"),
+        Ok("v1.2.0".to_string())
+    );
+
+    // A bad supplied answer is an error, not a re-prompt
+    set_var("opt_tp_port", "99999");
+    let err = ask_int("tp-port", "Port", None, 1..=65535).unwrap_err();
+    assert!(err.contains("must be between 1 and 65535"), "{}", err);
+    set_var("opt_tp_env", "d");
+    assert_eq!(
+        ask_choice("tp_env", "Env", &["dev", "prod"], None),
+        Ok("dev".to_string())
+    );
+    set_var("opt_tp_tag", "latest");
+    assert!(ask_regex("tp_tag", "Tag", None, r"v\d+").is_err());
+
+    for key in ["opt_tp_port", "opt_tp_env", "opt_tp_tag"] {
+        unset_var(key);
+    }
+}
+
+#[test]
+fn typed_prompts_read_an_answers_file_and_check_paths() {
+    let dir = std::env::temp_dir().join(format!("rsb_prompt_answers_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let answers = dir.join("answers.conf");
+    let target = dir.join("install/bin");
+    std::fs::write(
+        &answers,
+        format!(
+            "# installer answers\ntpf_root=\"{}\"\ntpf_user=deploy\n",
+            target.display()
+        ),
+    )
+    .unwrap();
+    set_var("opt_answers", answers.to_str().unwrap());
+
+    assert_eq!(answer_for("tpf-user"), Ok(Some("deploy".to_string())));
+    assert_eq!(answer_for("tpf_missing"), Ok(None));
+    assert!(ask_path("tpf_root", "Root", None, PathCheck::Dir).is_err());
+    assert_eq!(
+        ask_path("tpf_root", "Root", None, PathCheck::CreateDir),
+        Ok(target.clone())
+    );
+    assert!(target.is_dir());
+    let user = ask_with("tpf_user", "User", None, |s| {
+        match s.chars().all(char::is_alphanumeric) {
+            true => Ok(s.to_uppercase()),
+            false => Err("has odd characters".to_string()),
+        }
+    });
+    assert_eq!(user, Ok("DEPLOY".to_string()));
+
+    set_var("opt_answers", dir.join("absent.conf").to_str().unwrap());
+    assert!(answer_for("tpf_user").is_err());
+
+    unset_var("opt_answers");
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn typed_prompts_fall_back_to_valid_defaults() {
+    // Not a terminal under the test harness (or quiet): defaults are used
+    set_var("opt_quiet", "true");
+    assert_eq!(ask_int("tpd_count", "Count", Some(3), 1..=5), Ok(3));
+    assert!(ask_int("tpd_count", "Count", Some(9), 1..=5).is_err());
+    let err = ask_regex("tpd_name", "Name", None, "[a-z]+").unwrap_err();
+    assert!(err.contains("--tpd-name"), "{}", err);
+    assert!(ask_regex("tpd_name", "Name", None, "([a-z]").is_err());
+    unset_var("opt_quiet");
+}
@@ -445,4 +445,30 @@ fn sanity_repl_dispatch_macro_compiles() {
 
     // If we get here, the macro compiled successfully
     assert!(true);
-}
\ No newline at end of file
+}
+// REPL-07: shared command table with dispatch
+#[test]
+fn sanity_repl_runs_shared_handlers() {
+    fn shared_deploy(args: Args) -> i32 {
+        set_var("repl_shared_target", &args.get(1));
+        3
+    }
+
+    rsb::global::register_handler("repl-shared-deploy", shared_deploy);
+    assert!(rsb::global::list_handlers().contains(&"repl-shared-deploy".to_string()));
+
+    // Same routing as the command line: argv[0] + command + args
+    let code = rsb::cli::run_command(&Args::from_strs(&["prog", "repl-shared-deploy", "prod"]));
+    assert_eq!(code, Some(3));
+    assert_eq!(get_var("repl_shared_target"), "prod");
+
+    // From the REPL, the line has no program name
+    let repl = Repl::new();
+    let code = repl.run_command(&Args::from_line("repl-shared-deploy staging"));
+    assert_eq!(code, Some(3));
+    assert_eq!(get_var("repl_shared_target"), "staging");
+
+    assert_eq!(repl.run_command(&Args::from_line("repl-shared-missing")), None);
+    rsb::global::unregister_handler("repl-shared-deploy");
+    assert_eq!(repl.run_command(&Args::from_line("repl-shared-deploy")), None);
+}
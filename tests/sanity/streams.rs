@@ -0,0 +1,127 @@
+// RSB Sanity Tests - Streams Module Core Functionality Verification
+// Tests verify lazy Stream sources, chain operations and sinks
+
+use rsb::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_stream_chain_operations() {
+    let out = Stream::from_string("z,2\ny,1\nz,2\nx,3")
+        .grep("z")
+        .sed("z", "Z")
+        .cut(1, ",")
+        .uniq()
+        .to_string();
+    assert_eq!(out, "Z");
+
+    let lines = Stream::from_string("c\na\nb\na").sort().unique().to_vec();
+    assert_eq!(lines, vec!["a", "b", "c"]);
+
+    let tail = Stream::from_string("1\n2\n3\n4\n5").tail(2).to_vec();
+    assert_eq!(tail, vec!["4", "5"]);
+
+    let window = Stream::from_string("1\n2\n3\n4\n5").sed_lines(2, 3).to_vec();
+    assert_eq!(window, vec!["2", "3"]);
+}
+
+#[test]
+fn test_stream_head_stops_pulling_source() {
+    let pulled = Arc::new(AtomicUsize::new(0));
+    let counter = pulled.clone();
+    let source = (1..=1_000_000).map(move |n| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        format!("line {}", n)
+    });
+
+    let out = Stream::from_lines(source)
+        .grep("7")
+        .head(2)
+        .to_vec();
+    assert_eq!(out, vec!["line 7", "line 17"]);
+    assert_eq!(pulled.load(Ordering::SeqCst), 17);
+}
+
+#[test]
+fn test_stream_lazy_until_materialized() {
+    let stream = Stream::from_lines(vec!["a".to_string(), "b".to_string()]);
+    assert!(!stream.is_buffered());
+    assert_eq!(stream.first().map(String::as_str), Some("a"));
+    assert!(stream.is_buffered());
+    assert_eq!(stream.last().map(String::as_str), Some("b"));
+    assert_eq!(stream.count(), 2);
+}
+
+#[test]
+fn test_stream_from_file_and_to_file() {
+    let dir = std::env::temp_dir().join(format!("rsb_streams_{}", std::process::id()));
+    let src = dir.join("in.log");
+    let dst = dir.join("out.log");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&src, "INFO start\r\nERROR one\nINFO mid\nERROR two\n").unwrap();
+
+    let first_error = Stream::from_file(&src.to_string_lossy())
+        .grep("ERROR")
+        .head(1)
+        .to_string();
+    assert_eq!(first_error, "ERROR one");
+
+    Stream::from_file(&src.to_string_lossy())
+        .grep("INFO")
+        .to_file(&dst.to_string_lossy());
+    assert_eq!(std::fs::read_to_string(&dst).unwrap(), "INFO start\nINFO mid");
+
+    Stream::from_string("INFO end").append_to_file(&dst.to_string_lossy());
+    assert_eq!(
+        std::fs::read_to_string(&dst).unwrap(),
+        "INFO start\nINFO midINFO end\n"
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_stream_from_cmd_head_stops_child() {
+    let started = Instant::now();
+    let out = Stream::from_cmd("yes rsb").head(3).to_vec();
+    assert_eq!(out, vec!["rsb", "rsb", "rsb"]);
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let seq = Stream::from_cmd("printf 'b\\na\\nc\\n'").sort().to_string();
+    assert_eq!(seq, "a\nb\nc");
+}
+
+#[test]
+fn test_stream_pipe_to_cmd() {
+    let out = Stream::from_string("one\ntwo\nthree")
+        .pipe_to_cmd("grep t")
+        .to_vec();
+    assert_eq!(out, vec!["two", "three"]);
+}
+
+#[test]
+fn test_stream_pipe_to_writes_input_lazily() {
+    // An endless source only works if it is fed as the command reads
+    let endless = Stream::from_lines((1..).map(|n: u64| n.to_string()));
+    let out = endless
+        .pipe_to(Pipeline::new().argv(&["head", "-n", "2"]))
+        .to_vec();
+    assert_eq!(out, vec!["1", "2"]);
+}
+
+#[test]
+fn test_stream_clone_and_iterate() {
+    let stream = Stream::from_lines(vec!["x".to_string(), "y".to_string()]);
+    let copy = stream.clone();
+    let collected: Vec<String> = stream.into_iter().collect();
+    assert_eq!(collected, copy.to_vec());
+}
+
+#[test]
+fn test_stream_moves_to_another_thread() {
+    // Lazy streams (commands, readers, filters) can be handed to a worker
+    let stream = Stream::from_cmd("printf 'a\\nb\\nc\\n'").grep("b");
+    let worker = std::thread::spawn(move || stream.to_vec());
+    assert_eq!(worker.join().unwrap(), vec!["b"]);
+}
@@ -50,7 +50,7 @@ fn test_colors_basic_functionality() {
     assert!(!enabled_red.is_empty());
 
     // Reset to safe state
-    color_mode("auto");
+    color_mode("always");
 }
 
 #[test]
@@ -0,0 +1,5 @@
+//! Sanity test wrapper for the native archive subsystem (fs::archive)
+#![cfg(feature = "archive")]
+
+#[path = "sanity/archive.rs"]
+mod archive;
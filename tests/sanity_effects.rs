@@ -0,0 +1,4 @@
+//! Wrapper for dry-run/trace sanity tests (own binary: the modes are global)
+
+#[path = "sanity/effects.rs"]
+mod effects;
@@ -0,0 +1,5 @@
+//! Sanity test wrapper for the http module (native client + loopback server)
+#![cfg(feature = "dev-http")]
+
+#[path = "sanity/http.rs"]
+mod http;
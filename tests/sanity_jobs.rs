@@ -21,3 +21,170 @@ fn sanity_jobs_background_wait_list() {
     // Listing jobs should not panic
     let _ = rsb::jobs::list_jobs();
 }
+
+#[test]
+fn sanity_jobs_real_pid_status_and_kill() {
+    let jid = rsb::jobs::start_background("sleep 5");
+    let pids = rsb::jobs::job_pids(jid);
+    assert_eq!(pids.len(), 1);
+    assert!(rsb::jobs::process_exists_by_pid(&pids[0].to_string()));
+    assert_eq!(job!(status: jid), Some(rsb::jobs::JobStatus::Running));
+    assert!(rsb::jobs::list_job_status()
+        .iter()
+        .any(|(id, status, _)| *id == jid && status.is_running()));
+
+    assert!(job!(kill: jid));
+    let status = rsb::jobs::wait(jid, Some(2)).unwrap();
+    assert_eq!(status, 128 + 15);
+    assert_eq!(job!(status: jid), None);
+}
+
+#[test]
+fn sanity_jobs_captured_output() {
+    let jid = job!(background: "echo out; echo err >&2; exit 3");
+    let result = rsb::jobs::wait_output(jid, Some(2)).unwrap();
+    assert_eq!(result.status, 3);
+    assert_eq!(result.output.trim(), "out");
+    assert_eq!(result.error.trim(), "err");
+
+    // Finished jobs keep their output until reaped
+    let jid = job!(background: "echo kept");
+    while job!(status: jid).unwrap().is_running() {
+        rsb::jobs::sleep_ms(5);
+    }
+    assert_eq!(rsb::jobs::job_output(jid).unwrap().output.trim(), "kept");
+    assert_eq!(job!(output: jid).trim(), "kept");
+}
+
+#[test]
+fn sanity_jobs_wait_any_and_all() {
+    let slow = rsb::jobs::start_background("sleep 0.5; echo slow");
+    let fast = rsb::jobs::start_background("echo fast");
+    let (id, result) = rsb::jobs::wait_any(&[slow, fast]).unwrap();
+    assert_eq!(id, fast);
+    assert_eq!(result.output.trim(), "fast");
+
+    let rest = rsb::jobs::wait_all(&[slow, fast]);
+    assert_eq!(rest.len(), 1);
+    assert_eq!(rest[0].0, slow);
+    assert!(rsb::jobs::wait_any(&[slow, fast]).is_none());
+}
+
+#[test]
+fn sanity_jobs_pool_bounds_concurrency() {
+    let start = std::time::Instant::now();
+    let results = rsb::jobs::run_parallel(
+        &["sleep 0.2; echo a", "sleep 0.2; echo b", "sleep 0.2; echo c", "echo d"],
+        2,
+    );
+    let outputs: Vec<String> = results.iter().map(|r| r.output.trim().to_string()).collect();
+    assert_eq!(outputs, vec!["a", "b", "c", "d"]);
+    assert!(start.elapsed() >= std::time::Duration::from_millis(400));
+
+    let mut pool = rsb::jobs::JobPool::new(1);
+    pool.submit("sleep 0.1");
+    pool.submit("true");
+    assert!(pool.running() <= 1);
+    assert_eq!(pool.wait_all().len(), 2);
+}
+
+// --- trap! dispatch (runs in a re-executed child so signals and exit hooks
+// don't touch the test harness) ---
+
+fn run_trap_child(mode: &str, dir: &std::path::Path) -> std::process::Output {
+    std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "trap_child", "--nocapture", "--test-threads=1"])
+        // validate! panics instead of exiting when it thinks it's under test
+        .env_remove("CARGO_TEST")
+        .env("RSB_TRAP_CHILD", mode)
+        .env("RSB_TRAP_DIR", dir)
+        .output()
+        .unwrap()
+}
+
+fn append_marker(dir: &str, text: &str) {
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(format!("{}/markers", dir))
+        .unwrap();
+    writeln!(file, "{}", text).unwrap();
+}
+
+#[test]
+fn trap_child() {
+    let Ok(mode) = std::env::var("RSB_TRAP_CHILD") else {
+        return;
+    };
+    let dir = std::env::var("RSB_TRAP_DIR").unwrap();
+    let d = dir.clone();
+    trap!(move |_: &EventData| append_marker(&d, "exit"), on: "EXIT");
+    let d = dir.clone();
+    trap!(move |e: &EventData| append_marker(&d, &e.event_type), on: "TERM");
+    let d = dir.clone();
+    trap!(move |e: &EventData| append_marker(&d, &e.event_type), on: "usr1");
+
+    set_var("XDG_TMP", &dir);
+    let tmp = tmp!();
+    std::fs::write(&tmp, "scratch").unwrap();
+    std::fs::write(format!("{}/tmp_path", dir), &tmp).unwrap();
+    rsb::jobs::create_lock(&format!("{}/app.lock", dir)).unwrap();
+
+    let pid = std::process::id() as i32;
+    match mode.as_str() {
+        "exit" => std::process::exit(0),
+        "term" => {
+            unsafe { libc::kill(pid, libc::SIGTERM) };
+            std::thread::sleep(std::time::Duration::from_secs(5));
+        }
+        "usr1" => {
+            unsafe { libc::kill(pid, libc::SIGUSR1) };
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            append_marker(&dir, "alive");
+            std::process::exit(0);
+        }
+        "validate" => {
+            validate!(false, "bad input");
+        }
+        _ => {}
+    }
+}
+
+fn check_trap_child(mode: &str, code: i32, markers: &[&str]) {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let out = run_trap_child(mode, dir.path());
+    assert_eq!(out.status.code(), Some(code), "{:?}", out);
+    let got = std::fs::read_to_string(dir.path().join("markers")).unwrap_or_default();
+    assert_eq!(got.lines().collect::<Vec<_>>(), markers);
+    let tmp = std::fs::read_to_string(dir.path().join("tmp_path")).unwrap();
+    assert!(!std::path::Path::new(&tmp).exists(), "tmp! file not cleaned");
+    assert!(!dir.path().join("app.lock").exists(), "lock not released");
+}
+
+#[test]
+fn sanity_jobs_trap_exit_on_normal_exit() {
+    check_trap_child("exit", 0, &["exit"]);
+}
+
+#[test]
+fn sanity_jobs_trap_sigterm_runs_handlers_then_exit() {
+    check_trap_child("term", 128 + 15, &["SIGTERM", "exit"]);
+}
+
+#[test]
+fn sanity_jobs_trap_usr1_continues() {
+    check_trap_child("usr1", 0, &["SIGUSR1", "alive", "exit"]);
+}
+
+#[test]
+fn sanity_jobs_trap_exit_on_validate_failure() {
+    check_trap_child("validate", 1, &["exit"]);
+}
+
+#[test]
+fn sanity_jobs_trap_names() {
+    assert_eq!(rsb::jobs::signal_number("sigterm"), Some(15));
+    assert_eq!(rsb::jobs::signal_name(libc::SIGHUP).as_deref(), Some("SIGHUP"));
+    assert_eq!(rsb::jobs::install_trap("command_error"), "COMMAND_ERROR");
+}
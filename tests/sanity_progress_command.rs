@@ -0,0 +1,5 @@
+//! Sanity test wrapper for the progress/command adapter (progress::run_with_task)
+#![cfg(feature = "progress")]
+
+#[path = "sanity/progress_command.rs"]
+mod progress_command;
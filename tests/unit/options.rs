@@ -11,14 +11,14 @@ fn long_boolean_flag_sets_true() {
 }
 
 #[test]
-fn long_with_value_sets_value_and_validates_paths() {
+fn long_with_value_sets_value_for_path_like_names() {
     use assert_fs::prelude::*;
     let tmp = assert_fs::TempDir::new().unwrap();
     let file = tmp.child("config.yml");
     file.touch().unwrap();
     let p = file.path().to_string_lossy().to_string();
 
-    // Name contains "file" to trigger path existence validation
+    // Path-like names are plain values; validation is opt-in via validators
     let args = vec!["rsb-test".to_string(), format!("--input-file={}", p)];
     let args = rsb::cli::Args::new(&args);
     options!(&args);
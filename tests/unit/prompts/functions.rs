@@ -1,6 +1,9 @@
 #![cfg(feature = "prompts")]
 use rsb::prelude::*;
-use rsb::visual::prompts::{ask, confirm, confirm_default, default_from, select};
+use rsb::visual::prompts::{
+    ask, ask_edit, confirm, confirm_default, default_from, fuzzy_select, multi_select, password,
+    select,
+};
 
 fn setup_clean_context() {
     unset_var("opt_yes");
@@ -127,3 +130,21 @@ fn test_opt_yes_overrides_opt_quiet() {
     unset_var("opt_yes");
     unset_var("opt_quiet");
 }
+
+#[test]
+fn test_widget_prompts_with_opt_quiet() {
+    setup_clean_context();
+    set_var("opt_quiet", "true");
+
+    let options = ["alpha", "beta", "gamma"];
+    // Defaults come back sorted, deduplicated and in range
+    assert_eq!(
+        multi_select("Features?", &options, &[2, 0, 2, 9]),
+        vec!["alpha".to_string(), "gamma".to_string()]
+    );
+    assert_eq!(fuzzy_select("Find?", &options, Some(1)), "beta");
+    assert_eq!(password("Token?", Some('*')), "");
+    assert_eq!(ask_edit("Path?", "/usr/local"), "/usr/local");
+
+    unset_var("opt_quiet");
+}